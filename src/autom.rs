@@ -3,16 +3,34 @@ use std::convert::From;
 use std::convert::Infallible;
 use std::hash::Hash;
 
+use crate::cancel;
 use crate::error::NautyError;
+#[cfg(feature = "traces")]
+use crate::kill::Traces;
+use crate::kill::{self, densenauty, sparsenauty};
+use crate::nauty_graph::borrowed;
 use crate::nauty_graph::DenseGraph;
 use crate::nauty_graph::SparseGraph;
+use crate::perm::Permutation;
+use crate::stats::NautyStats;
+#[cfg(feature = "traces")]
+use crate::stats::TracesSearchStats;
+use crate::verbosity::capture_nauty;
+#[cfg(feature = "traces")]
+use crate::verbosity::capture_traces;
+use crate::workspace::NautyWorkspace;
 
 use nauty_Traces_sys::{
-    densenauty, optionblk, statsblk, FALSE, MTOOBIG, NTOOBIG, TRUE,
+    optionblk, statsblk, FALSE, MTOOBIG, NAUKILLED, NTOOBIG, TRUE,
 };
-use nauty_Traces_sys::{sparsenauty, Traces, TracesOptions, TracesStats};
+#[cfg(feature = "traces")]
+use nauty_Traces_sys::{TracesOptions, TracesStats};
 use petgraph::{
-    graph::{Graph, IndexType},
+    csr::Csr,
+    graph::{DefaultIx, Graph, IndexType},
+    graphmap::{GraphMap, NodeTrait},
+    matrix_graph::{MatrixGraph, Nullable},
+    stable_graph::StableGraph,
     EdgeType,
 };
 
@@ -33,8 +51,22 @@ impl Autom {
     pub fn grpsize(&self) -> f64 {
         self.grpsize_base * 10f64.powi(self.grpsize_exp as i32)
     }
+
+    /// Check whether the identity is the only automorphism
+    pub fn is_asymmetric(&self) -> bool {
+        self.grpsize_base == 1. && self.grpsize_exp == 0
+    }
+
+    /// Check whether all vertices are in the same orbit
+    ///
+    /// The graph without vertices has no orbits and is not vertex
+    /// transitive.
+    pub fn is_vertex_transitive(&self) -> bool {
+        self.num_orbits == 1
+    }
 }
 
+#[cfg(feature = "traces")]
 impl From<TracesStats> for Autom {
     fn from(o: TracesStats) -> Self {
         Self {
@@ -58,6 +90,22 @@ impl From<statsblk> for Autom {
 }
 
 /// Analyse a graph's automorphism group
+///
+/// This and the other automorphism traits are also implemented for
+/// references to graphs, which avoids cloning the node and edge
+/// weights.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::prelude::*;
+///
+/// let g = UnGraph::<String, ()>::from_edges([(0, 1), (1, 2)]);
+/// let autom = (&g).try_into_autom().unwrap();
+/// assert_eq!(autom.grpsize(), 2.);
+/// assert_eq!(autom, g.try_into_autom().unwrap());
+/// ```
 pub trait TryIntoAutom {
     type Error;
 
@@ -79,12 +127,142 @@ pub trait TryIntoAutomNautyDense {
 }
 
 /// Analyse a graph's automorphism group using Traces
+///
+/// Traces only supports undirected graphs. For directed graphs, the
+/// error [NautyError::TracesDigraphUnsupported] is returned, and
+/// nauty has to be used instead.
+#[cfg(feature = "traces")]
 pub trait TryIntoAutomTraces {
     type Error;
 
     fn try_into_autom_traces(self) -> Result<Autom, Self::Error>;
 }
 
+/// Algorithm used to analyse an automorphism group
+///
+/// The variant `Traces` only exists with the `traces` feature, so
+/// matches on a [Backend] need a wildcard arm.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    #[default]
+    NautyDense,
+    NautySparse,
+    #[cfg(feature = "traces")]
+    Traces,
+}
+
+/// Thresholds for the automatic choice of a [Backend]
+///
+/// Small graphs and dense graphs are analysed with dense nauty.
+/// Large sparse graphs are analysed with sparse nauty, or with
+/// Traces if they are undirected and very large.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AutoOptions {
+    /// Graphs with at most this many nodes always use dense nauty
+    pub max_dense_nodes: usize,
+    /// Larger graphs with at least this fraction of all possible
+    /// edges use dense nauty
+    pub min_dense_density: f64,
+    /// Sparse undirected graphs with at least this many nodes use
+    /// Traces
+    ///
+    /// Ignored without the `traces` feature.
+    pub min_traces_nodes: usize,
+}
+
+impl Default for AutoOptions {
+    fn default() -> Self {
+        Self {
+            max_dense_nodes: 64,
+            min_dense_density: 0.1,
+            min_traces_nodes: 1000,
+        }
+    }
+}
+
+impl AutoOptions {
+    /// The backend chosen for `g`
+    ///
+    /// Edges are counted with multiplicity, including self-loops.
+    pub fn backend<N, E, Ty, Ix>(&self, g: &Graph<N, E, Ty, Ix>) -> Backend
+    where
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let n = g.node_count();
+        if n <= self.max_dense_nodes {
+            return Backend::NautyDense;
+        }
+        let mut max_edges = (n * (n - 1)) as f64;
+        if !g.is_directed() {
+            max_edges /= 2.;
+        }
+        let density = g.edge_count() as f64 / max_edges;
+        if density >= self.min_dense_density {
+            return Backend::NautyDense;
+        }
+        #[cfg(feature = "traces")]
+        if !g.is_directed() && n >= self.min_traces_nodes {
+            return Backend::Traces;
+        }
+        Backend::NautySparse
+    }
+}
+
+/// Analyse a graph's automorphism group with an automatically
+/// chosen backend
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::{AutoOptions, Backend, TryIntoAutomAuto};
+///
+/// // a long cycle is too sparse for dense nauty
+/// let g = UnGraph::<(), ()>::from_edges((0..100).map(|i| (i, (i + 1) % 100)));
+/// assert_eq!(AutoOptions::default().backend(&g), Backend::NautySparse);
+/// let autom = g.try_into_autom_auto().unwrap();
+/// assert_eq!(autom.grpsize(), 200.);
+/// ```
+pub trait TryIntoAutomAuto: Sized {
+    type Error;
+
+    /// Choose the backend with the default [AutoOptions]
+    fn try_into_autom_auto(self) -> Result<Autom, Self::Error> {
+        self.try_into_autom_auto_with(&AutoOptions::default())
+    }
+
+    fn try_into_autom_auto_with(
+        self,
+        options: &AutoOptions,
+    ) -> Result<Autom, Self::Error>;
+}
+
+/// Analyse a graph's automorphism group and return its generators
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::prelude::*;
+///
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+/// let (autom, generators) = g.try_into_autom_with_generators().unwrap();
+/// assert_eq!(autom.grpsize(), 2.);
+/// // the reflection exchanging the end points
+/// assert_eq!(generators.len(), 1);
+/// let v = generators[0].node_image(NodeIndex::<u32>::new(0));
+/// assert_eq!(v, NodeIndex::new(2));
+/// ```
+pub trait TryIntoAutomWithGenerators {
+    type Error;
+
+    fn try_into_autom_with_generators(
+        self,
+    ) -> Result<(Autom, Vec<Permutation>), Self::Error>;
+}
+
 impl<N, E, Ty, Ix> TryIntoAutom for Graph<N, E, Ty, Ix>
 where
     N: Ord,
@@ -99,6 +277,25 @@ where
     }
 }
 
+impl<N, E, Ty, Ix> TryIntoAutomWithGenerators for Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = Infallible;
+
+    fn try_into_autom_with_generators(
+        self,
+    ) -> Result<(Autom, Vec<Permutation>), Self::Error> {
+        let (generators, _, autom) =
+            cancel::unwrap(analyse_with_generators(self));
+        let generators = generators.into_iter().map(Permutation::new);
+        Ok((autom, generators.collect()))
+    }
+}
+
 impl<N, E, Ty, Ix> TryIntoAutomNautySparse for Graph<N, E, Ty, Ix>
 where
     N: Ord,
@@ -109,26 +306,9 @@ where
     type Error = Infallible;
 
     fn try_into_autom_nauty_sparse(self) -> Result<Autom, Self::Error> {
-        let mut options = optionblk::default_sparse();
-        options.getcanon = FALSE;
-        options.defaultptn = FALSE;
-        options.digraph = if self.is_directed() { TRUE } else { FALSE };
-        let mut stats = statsblk::default();
-        let mut sg = SparseGraph::from(self);
-        let mut orbits = vec![0; sg.g.v.len()];
-        unsafe {
-            sparsenauty(
-                &mut (&mut sg.g).into(),
-                sg.nodes.lab.as_mut_ptr(),
-                sg.nodes.ptn.as_mut_ptr(),
-                orbits.as_mut_ptr(),
-                &mut options,
-                &mut stats,
-                std::ptr::null_mut(),
-            );
-        }
-        debug_assert_eq!(stats.errstatus, 0);
-        Ok(stats.into())
+        let is_directed = self.is_directed();
+        let autom = sparse_autom(SparseGraph::from(self), is_directed);
+        Ok(cancel::unwrap(autom))
     }
 }
 
@@ -142,41 +322,214 @@ where
     type Error = NautyError;
 
     fn try_into_autom_nauty_dense(self) -> Result<Autom, Self::Error> {
-        use ::std::os::raw::c_int;
-        use NautyError::*;
+        let is_directed = self.is_directed();
+        dense_autom(DenseGraph::from(self), is_directed)
+    }
+}
 
-        let mut options = optionblk {
-            getcanon: FALSE,
-            defaultptn: FALSE,
-            digraph: if self.is_directed() { TRUE } else { FALSE },
-            ..Default::default()
-        };
-        let mut stats = statsblk::default();
-        let mut dg = DenseGraph::from(self);
-        let mut orbits = vec![0; dg.n];
-        unsafe {
-            densenauty(
-                dg.g.as_mut_ptr(),
-                dg.nodes.lab.as_mut_ptr(),
-                dg.nodes.ptn.as_mut_ptr(),
-                orbits.as_mut_ptr(),
-                &mut options,
-                &mut stats,
-                dg.m as c_int,
-                dg.n as c_int,
-                std::ptr::null_mut(),
-            );
-        }
-        match stats.errstatus {
-            0 => Ok(stats.into()),
-            MTOOBIG => Err(MTooBig),
-            NTOOBIG => Err(NTooBig),
-            _ => unreachable!(),
+impl<N, E, Ty, Ix> TryIntoAutomAuto for Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom_auto_with(
+        self,
+        options: &AutoOptions,
+    ) -> Result<Autom, Self::Error> {
+        let is_directed = self.is_directed();
+        match options.backend(&self) {
+            Backend::NautyDense => {
+                dense_autom(DenseGraph::from(self), is_directed)
+            }
+            Backend::NautySparse => {
+                sparse_autom(SparseGraph::from(self), is_directed)
+            }
+            #[cfg(feature = "traces")]
+            Backend::Traces => self.try_into_autom_traces(),
         }
     }
 }
 
+/// Analyse the automorphism group of a graph in nauty's sparse format
+pub(crate) fn sparse_autom<N, E, Ty>(
+    sg: SparseGraph<N, E, Ty>,
+    is_directed: bool,
+) -> Result<Autom, NautyError> {
+    let options = optionblk::default_sparse();
+    Ok(sparse_autom_with(sg, is_directed, options)?.autom)
+}
+
+/// Analyse the automorphism group of a graph in nauty's sparse
+/// format with the given options
+pub(crate) fn sparse_autom_with<N, E, Ty>(
+    mut sg: SparseGraph<N, E, Ty>,
+    is_directed: bool,
+    mut options: optionblk,
+) -> Result<NautyStats, NautyError> {
+    options.getcanon = FALSE;
+    options.defaultptn = FALSE;
+    options.digraph = if is_directed { TRUE } else { FALSE };
+    let mut stats = statsblk::default();
+    let mut orbits = vec![0; sg.g.v.len()];
+    let output = capture_nauty(&mut options);
+    unsafe {
+        sparsenauty(
+            &mut (&mut sg.g).into(),
+            sg.nodes.lab.as_mut_ptr(),
+            sg.nodes.ptn.as_mut_ptr(),
+            orbits.as_mut_ptr(),
+            &mut options,
+            &mut stats,
+            std::ptr::null_mut(),
+        );
+    }
+    output.finish();
+    // the search may have been stopped by a user-defined procedure or
+    // a cancellation
+    kill::check(stats.errstatus)?;
+    debug_assert_eq!(stats.errstatus, 0);
+    Ok(stats.into())
+}
+
+/// Analyse the automorphism group of a graph in nauty's dense format
+pub(crate) fn dense_autom<N, E, Ty>(
+    mut dg: DenseGraph<N, E, Ty>,
+    is_directed: bool,
+) -> Result<Autom, NautyError> {
+    let options = optionblk::default();
+    let mut ws = NautyWorkspace::default();
+    let stats = dense_autom_in(&mut dg, is_directed, options, &mut ws)?;
+    Ok(stats.autom)
+}
+
+/// Analyse the automorphism group of a graph in nauty's dense
+/// format, using the given options and the buffers of the given
+/// workspace
+pub(crate) fn dense_autom_in<N, E, Ty>(
+    dg: &mut DenseGraph<N, E, Ty>,
+    is_directed: bool,
+    mut options: optionblk,
+    ws: &mut NautyWorkspace,
+) -> Result<NautyStats, NautyError> {
+    use ::std::os::raw::c_int;
+    use NautyError::*;
+
+    options.getcanon = FALSE;
+    options.defaultptn = FALSE;
+    options.digraph = if is_directed { TRUE } else { FALSE };
+    let mut stats = statsblk::default();
+    ws.orbits.clear();
+    ws.orbits.resize(dg.n, 0);
+    let output = capture_nauty(&mut options);
+    unsafe {
+        densenauty(
+            dg.g.as_mut_ptr(),
+            dg.nodes.lab.as_mut_ptr(),
+            dg.nodes.ptn.as_mut_ptr(),
+            ws.orbits.as_mut_ptr(),
+            &mut options,
+            &mut stats,
+            dg.m as c_int,
+            dg.n as c_int,
+            std::ptr::null_mut(),
+        );
+    }
+    output.finish();
+    match stats.errstatus {
+        0 => Ok(stats.into()),
+        MTOOBIG => Err(MTooBig),
+        NTOOBIG => Err(NTooBig),
+        NAUKILLED => Err(Cancelled),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(feature = "traces")]
 impl<N, E, Ty, Ix> TryIntoAutomTraces for Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom_traces(self) -> Result<Autom, Self::Error> {
+        if self.is_directed() {
+            return Err(NautyError::TracesDigraphUnsupported);
+        }
+        let options = TracesOptions::default();
+        Ok(traces_autom(SparseGraph::from(self), options)?.autom)
+    }
+}
+
+/// Analyse the automorphism group of an undirected graph in nauty's
+/// sparse format with Traces and the given options
+#[cfg(feature = "traces")]
+pub(crate) fn traces_autom<N, E, Ty>(
+    mut sg: SparseGraph<N, E, Ty>,
+    mut options: TracesOptions,
+) -> Result<TracesSearchStats, NautyError> {
+    options.getcanon = FALSE;
+    options.defaultptn = FALSE;
+    options.digraph = FALSE;
+    let mut stats = TracesStats::default();
+    let mut orbits = vec![0; sg.g.v.len()];
+    let output = capture_traces(&mut options);
+    unsafe {
+        Traces(
+            &mut (&mut sg.g).into(),
+            sg.nodes.lab.as_mut_ptr(),
+            sg.nodes.ptn.as_mut_ptr(),
+            orbits.as_mut_ptr(),
+            &mut options,
+            &mut stats,
+            std::ptr::null_mut(),
+        );
+    }
+    output.finish();
+    // the search may have been stopped by a cancellation
+    kill::check(stats.errstatus)?;
+    debug_assert_eq!(stats.errstatus, 0);
+    Ok(stats.into())
+}
+
+impl<N, E, Ty, Ix> TryIntoAutom for &Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom(self) -> Result<Autom, Self::Error> {
+        borrowed(self).try_into_autom()
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoAutomAuto for &Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom_auto_with(
+        self,
+        options: &AutoOptions,
+    ) -> Result<Autom, Self::Error> {
+        borrowed(self).try_into_autom_auto_with(options)
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoAutomWithGenerators for &Graph<N, E, Ty, Ix>
 where
     N: Ord,
     E: Hash + Ord,
@@ -185,36 +538,386 @@ where
 {
     type Error = Infallible;
 
+    fn try_into_autom_with_generators(
+        self,
+    ) -> Result<(Autom, Vec<Permutation>), Self::Error> {
+        borrowed(self).try_into_autom_with_generators()
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoAutomNautySparse for &Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = Infallible;
+
+    fn try_into_autom_nauty_sparse(self) -> Result<Autom, Self::Error> {
+        borrowed(self).try_into_autom_nauty_sparse()
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoAutomNautyDense for &Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom_nauty_dense(self) -> Result<Autom, Self::Error> {
+        borrowed(self).try_into_autom_nauty_dense()
+    }
+}
+
+#[cfg(feature = "traces")]
+impl<N, E, Ty, Ix> TryIntoAutomTraces for &Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
     fn try_into_autom_traces(self) -> Result<Autom, Self::Error> {
-        let mut options = TracesOptions {
-            getcanon: FALSE,
-            defaultptn: FALSE,
-            digraph: TRUE,
+        borrowed(self).try_into_autom_traces()
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoAutom for StableGraph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom(self) -> Result<Autom, Self::Error> {
+        Graph::from(self).try_into_autom()
+    }
+}
+
+/// The generators act on the node indices of the [StableGraph]. They
+/// map each unused index to itself.
+impl<N, E, Ty, Ix> TryIntoAutomWithGenerators for StableGraph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = Infallible;
+
+    fn try_into_autom_with_generators(
+        self,
+    ) -> Result<(Autom, Vec<Permutation>), Self::Error> {
+        // converting to a `Graph` keeps the relative order of the nodes
+        let indices = Vec::from_iter(self.node_indices().map(|v| v.index()));
+        let node_bound = self.node_bound();
+        let (generators, _, autom) =
+            cancel::unwrap(analyse_with_generators(self.into()));
+        let generators = generators.into_iter().map(|gen| {
+            let mut images = Vec::from_iter(0..node_bound);
+            for (&v, w) in indices.iter().zip(gen) {
+                images[v] = indices[w];
+            }
+            Permutation::new(images)
+        });
+        Ok((autom, generators.collect()))
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoAutomNautySparse for StableGraph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = Infallible;
+
+    fn try_into_autom_nauty_sparse(self) -> Result<Autom, Self::Error> {
+        Graph::from(self).try_into_autom_nauty_sparse()
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoAutomNautyDense for StableGraph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom_nauty_dense(self) -> Result<Autom, Self::Error> {
+        Graph::from(self).try_into_autom_nauty_dense()
+    }
+}
+
+#[cfg(feature = "traces")]
+impl<N, E, Ty, Ix> TryIntoAutomTraces for StableGraph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom_traces(self) -> Result<Autom, Self::Error> {
+        Graph::from(self).try_into_autom_traces()
+    }
+}
+
+/// The node keys are used as vertex colours. Since they are
+/// distinct, the automorphism group is always trivial unless the
+/// keys are removed first.
+impl<N, E, Ty> TryIntoAutom for GraphMap<N, E, Ty>
+where
+    N: NodeTrait,
+    E: Hash + Ord,
+    Ty: EdgeType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom(self) -> Result<Autom, Self::Error> {
+        self.into_graph::<DefaultIx>().try_into_autom()
+    }
+}
+
+/// The adjacency matrix is converted directly into nauty's dense
+/// format, without building an intermediate [Graph].
+impl<N, E, Ty, Null, Ix> TryIntoAutom for &MatrixGraph<N, E, Ty, Null, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Null: Nullable<Wrapped = E>,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom(self) -> Result<Autom, Self::Error> {
+        dense_autom(DenseGraph::from(self), Ty::is_directed())
+    }
+}
+
+impl<N, E, Ty, Null, Ix> TryIntoAutom for MatrixGraph<N, E, Ty, Null, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Null: Nullable<Wrapped = E>,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom(self) -> Result<Autom, Self::Error> {
+        (&self).try_into_autom()
+    }
+}
+
+/// The graph is converted into nauty's sparse format without
+/// building an intermediate [Graph].
+impl<N, E, Ty, Ix> TryIntoAutom for &Csr<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom(self) -> Result<Autom, Self::Error> {
+        sparse_autom(SparseGraph::from(self), Ty::is_directed())
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoAutom for Csr<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom(self) -> Result<Autom, Self::Error> {
+        (&self).try_into_autom()
+    }
+}
+
+/// Orbits of the automorphism group acting on the vertices
+///
+/// Each vertex is mapped to the smallest vertex index in its orbit.
+pub(crate) fn vertex_orbits<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Result<Vec<usize>, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    if g.node_count() == 0 {
+        return Ok(Vec::new());
+    }
+    let mut options = optionblk::default_sparse();
+    options.getcanon = FALSE;
+    options.defaultptn = FALSE;
+    options.digraph = if g.is_directed() { TRUE } else { FALSE };
+    let mut stats = statsblk::default();
+    let mut sg = SparseGraph::from(g);
+    let mut orbits = vec![0; sg.g.v.len()];
+    let output = capture_nauty(&mut options);
+    unsafe {
+        sparsenauty(
+            &mut (&mut sg.g).into(),
+            sg.nodes.lab.as_mut_ptr(),
+            sg.nodes.ptn.as_mut_ptr(),
+            orbits.as_mut_ptr(),
+            &mut options,
+            &mut stats,
+            std::ptr::null_mut(),
+        );
+    }
+    output.finish();
+    // the search may have been stopped by a cancellation
+    kill::check(stats.errstatus)?;
+    debug_assert_eq!(stats.errstatus, 0);
+    Ok(orbit_reps(&orbits, &sg.nodes.relabel))
+}
+
+/// Generators of the automorphism group and orbits of the vertices
+///
+/// Each generator maps the `i`th vertex to the vertex with index
+/// `generator[i]`. The orbits are given as in [vertex_orbits].
+pub(crate) fn autom_generators<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> (Vec<Vec<usize>>, Vec<usize>)
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let (generators, orbits, _) = cancel::unwrap(analyse_with_generators(g));
+    (generators, orbits)
+}
+
+// generators and orbits as in `autom_generators`, together with the
+// statistics
+fn analyse_with_generators<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Result<(Vec<Vec<usize>>, Vec<usize>, Autom), NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    if g.node_count() == 0 {
+        let trivial = Autom {
+            grpsize_base: 1.,
             ..Default::default()
         };
-        let mut stats = TracesStats::default();
-        let mut sg = SparseGraph::from(self);
-        let mut orbits = vec![0; sg.g.v.len()];
-        unsafe {
-            Traces(
-                &mut (&mut sg.g).into(),
-                sg.nodes.lab.as_mut_ptr(),
-                sg.nodes.ptn.as_mut_ptr(),
-                orbits.as_mut_ptr(),
-                &mut options,
-                &mut stats,
-                std::ptr::null_mut(),
-            );
-        }
-        debug_assert_eq!(stats.errstatus, 0);
-        Ok(stats.into())
+        return Ok((Vec::new(), Vec::new(), trivial));
     }
+    let mut options = optionblk::default_sparse();
+    options.getcanon = FALSE;
+    options.defaultptn = FALSE;
+    options.digraph = if g.is_directed() { TRUE } else { FALSE };
+    options.userautomproc = Some(collect_generator);
+    let mut stats = statsblk::default();
+    let mut sg = SparseGraph::from(g);
+    let mut orbits = vec![0; sg.g.v.len()];
+    let clear = || GENERATORS.with(|gens| gens.borrow_mut().clear());
+    clear();
+    let output = capture_nauty(&mut options);
+    // generators found before a repetition would be collected twice
+    kill::with_reset(clear, || unsafe {
+        sparsenauty(
+            &mut (&mut sg.g).into(),
+            sg.nodes.lab.as_mut_ptr(),
+            sg.nodes.ptn.as_mut_ptr(),
+            orbits.as_mut_ptr(),
+            &mut options,
+            &mut stats,
+            std::ptr::null_mut(),
+        );
+    });
+    output.finish();
+    // the search may have been stopped by a cancellation
+    kill::check(stats.errstatus)?;
+    debug_assert_eq!(stats.errstatus, 0);
+    let nauty_gens =
+        GENERATORS.with(|gens| std::mem::take(&mut *gens.borrow_mut()));
+
+    // restrict to the original vertices, which are never mapped to
+    // auxiliary vertices
+    let relabel = &sg.nodes.relabel;
+    let mut orig = vec![usize::MAX; orbits.len()];
+    for (v, &nauty_idx) in relabel.iter().enumerate() {
+        orig[nauty_idx] = v;
+    }
+    let generators = nauty_gens
+        .into_iter()
+        .map(|perm| {
+            Vec::from_iter(
+                relabel
+                    .iter()
+                    .map(|&nauty_idx| orig[perm[nauty_idx] as usize]),
+            )
+        })
+        .filter(|perm| perm.iter().enumerate().any(|(v, &w)| v != w))
+        .collect();
+    Ok((generators, orbit_reps(&orbits, relabel), stats.into()))
+}
+
+thread_local! {
+    static GENERATORS: std::cell::RefCell<Vec<Vec<std::os::raw::c_int>>> =
+        Default::default();
+}
+
+extern "C" fn collect_generator(
+    _count: std::os::raw::c_int,
+    perm: *mut std::os::raw::c_int,
+    _orbits: *mut std::os::raw::c_int,
+    _numorbits: std::os::raw::c_int,
+    _stabvertex: std::os::raw::c_int,
+    n: std::os::raw::c_int,
+) {
+    // SAFETY: nauty passes a permutation of its `n` vertices
+    let perm = unsafe { std::slice::from_raw_parts(perm, n as usize) };
+    GENERATORS.with(|gens| gens.borrow_mut().push(perm.to_vec()));
+}
+
+// translate nauty orbits back to the original vertex indices
+fn orbit_reps(orbits: &[std::os::raw::c_int], relabel: &[usize]) -> Vec<usize> {
+    let mut reps = vec![usize::MAX; orbits.len()];
+    Vec::from_iter(relabel.iter().enumerate().map(|(v, &nauty_idx)| {
+        let rep = &mut reps[orbits[nauty_idx] as usize];
+        if *rep == usize::MAX {
+            *rep = v;
+        }
+        *rep
+    }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use petgraph::{graph::DiGraph, Undirected};
+    use petgraph::{
+        graph::{DiGraph, NodeIndex, UnGraph},
+        Directed, Undirected,
+    };
+    use testing::GraphIter;
 
     fn log_init() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -240,6 +943,294 @@ mod tests {
         assert_eq!(autom.grpsize_exp, 0);
     }
 
+    #[test]
+    fn symmetry() {
+        log_init();
+
+        let path = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2)]);
+        let autom = path.clone().try_into_autom().unwrap();
+        assert!(!autom.is_asymmetric());
+        assert!(!autom.is_vertex_transitive());
+        let directed = path.into_edge_type::<petgraph::Directed>();
+        let autom = directed.try_into_autom().unwrap();
+        assert!(autom.is_asymmetric());
+        let triangle = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        let autom = triangle.try_into_autom().unwrap();
+        assert!(autom.is_vertex_transitive());
+    }
+
+    #[cfg(feature = "traces")]
+    #[test]
+    fn traces() {
+        log_init();
+
+        let g =
+            UnGraph::<u8, u8>::from_edges([(0, 1, 0), (1, 2, 1), (2, 3, 0)]);
+        let autom = g.clone().try_into_autom_traces().unwrap();
+        assert_eq!(
+            autom.grpsize(),
+            g.clone().try_into_autom().unwrap().grpsize()
+        );
+        assert_eq!(autom.grpsize(), 2.);
+        let g = g.into_edge_type::<petgraph::Directed>();
+        assert!(matches!(
+            g.try_into_autom_traces(),
+            Err(NautyError::TracesDigraphUnsupported)
+        ));
+    }
+
+    #[test]
+    fn auto() {
+        log_init();
+
+        let options = AutoOptions::default();
+        let cycle =
+            |n| UnGraph::<(), ()>::from_edges((0..n).map(|i| (i, (i + 1) % n)));
+        assert_eq!(options.backend(&cycle(10)), Backend::NautyDense);
+        assert_eq!(options.backend(&cycle(100)), Backend::NautySparse);
+        #[cfg(feature = "traces")]
+        assert_eq!(options.backend(&cycle(1000)), Backend::Traces);
+        let g = cycle(1000).into_edge_type::<Directed>();
+        assert_eq!(options.backend(&g), Backend::NautySparse);
+        let complete = UnGraph::<(), ()>::from_edges(
+            (0..100).flat_map(|i| (0..i).map(move |j| (i, j))),
+        );
+        assert_eq!(options.backend(&complete), Backend::NautyDense);
+
+        // force each backend in turn
+        let dense = AutoOptions {
+            max_dense_nodes: usize::MAX,
+            ..Default::default()
+        };
+        let sparse = AutoOptions {
+            max_dense_nodes: 0,
+            min_dense_density: f64::INFINITY,
+            min_traces_nodes: usize::MAX,
+        };
+        let traces = AutoOptions {
+            min_traces_nodes: 0,
+            ..sparse
+        };
+        for g in GraphIter::<Undirected>::default().take(100) {
+            let expected = (&g).try_into_autom().unwrap();
+            for options in [dense, sparse, traces] {
+                let autom = (&g).try_into_autom_auto_with(&options).unwrap();
+                assert_eq!(autom.grpsize(), expected.grpsize());
+                assert_eq!(autom.num_orbits, expected.num_orbits);
+            }
+        }
+        for g in GraphIter::<Directed>::default().take(100) {
+            let expected = (&g).try_into_autom().unwrap();
+            let autom = (&g).try_into_autom_auto_with(&traces).unwrap();
+            assert_eq!(autom.grpsize(), expected.grpsize());
+        }
+    }
+
+    #[test]
+    fn by_reference() {
+        log_init();
+
+        let g =
+            DiGraph::<u8, u8>::from_edges([(0, 1, 0), (1, 2, 0), (2, 0, 0)]);
+        let expected = g.clone().try_into_autom().unwrap();
+        assert_eq!((&g).try_into_autom().unwrap(), expected);
+        assert_eq!((&g).try_into_autom_nauty_dense().unwrap(), expected);
+        let sparse = (&g).try_into_autom_nauty_sparse().unwrap();
+        assert_eq!(sparse.grpsize(), expected.grpsize());
+        let (autom, generators) =
+            (&g).try_into_autom_with_generators().unwrap();
+        assert_eq!(autom.grpsize(), 3.);
+        assert_eq!(generators.len(), 1);
+        #[cfg(feature = "traces")]
+        {
+            assert!((&g).try_into_autom_traces().is_err());
+            let g = g.into_edge_type::<Undirected>();
+            let traces = (&g).try_into_autom_traces().unwrap();
+            assert_eq!(traces.grpsize(), 6.);
+        }
+    }
+
+    #[test]
+    fn stable_graph() {
+        log_init();
+
+        // path 1 - 3 - 4 with a hole at 0 and 2
+        let mut g = StableGraph::<u8, (), Undirected>::default();
+        let v = Vec::from_iter((0..5).map(|_| g.add_node(0)));
+        g.add_edge(v[1], v[3], ());
+        g.add_edge(v[3], v[4], ());
+        g.remove_node(v[0]);
+        g.remove_node(v[2]);
+        let autom = g.clone().try_into_autom().unwrap();
+        assert_eq!(autom.grpsize(), 2.);
+        let sparse = g.clone().try_into_autom_nauty_sparse().unwrap();
+        assert_eq!(sparse.grpsize(), 2.);
+        #[cfg(feature = "traces")]
+        {
+            let traces = g.clone().try_into_autom_traces().unwrap();
+            assert_eq!(traces.grpsize(), 2.);
+        }
+        let (_, generators) = g.try_into_autom_with_generators().unwrap();
+        assert_eq!(generators, [Permutation::new(vec![0, 4, 2, 3, 1])]);
+    }
+
+    #[test]
+    fn graph_map() {
+        log_init();
+
+        use petgraph::graphmap::UnGraphMap;
+        let g = UnGraphMap::<u8, ()>::from_edges([(0, 1), (1, 2)]);
+        let autom = g.try_into_autom().unwrap();
+        assert!(autom.is_asymmetric());
+        assert_eq!(autom.num_orbits, 3);
+    }
+
+    #[test]
+    fn matrix_graph() {
+        log_init();
+
+        use petgraph::matrix_graph::{DiMatrix, UnMatrix};
+        let g =
+            UnMatrix::<u8, u8>::from_edges([(0, 1, 0), (1, 2, 0), (2, 0, 0)]);
+        assert_eq!(g.try_into_autom().unwrap().grpsize(), 6.);
+        let mut g =
+            DiMatrix::<u8, u8>::from_edges([(0, 1, 0), (1, 2, 0), (2, 0, 0)]);
+        let v = g.add_node(0);
+        g.add_edge(v, v, 1);
+        g.remove_node(NodeIndex::new(0));
+        let autom = (&g).try_into_autom().unwrap();
+        let expected = DiGraph::<u8, u8>::from_edges([(0, 1, 0), (2, 2, 1)]);
+        assert_eq!(autom, expected.try_into_autom().unwrap());
+        assert_eq!(autom.num_orbits, 3);
+    }
+
+    #[test]
+    fn csr() {
+        log_init();
+
+        use petgraph::csr::Csr;
+        // four-cycle with a loop
+        let mut g = Csr::<u8, u8, Undirected>::with_nodes(4);
+        for (s, t) in [(0, 1), (1, 2), (2, 3), (3, 0), (0, 0)] {
+            g.add_edge(s, t, 0);
+        }
+        assert_eq!(g.edge_count(), 5);
+        let autom = (&g).try_into_autom().unwrap();
+        assert_eq!(autom.grpsize(), 2.);
+        assert_eq!(autom.num_orbits, 3);
+
+        let g = Csr::<(), u8>::from_sorted_edges(&[(0, 1, 1), (1, 0, 1)]);
+        let autom = g.unwrap().try_into_autom().unwrap();
+        assert_eq!(autom.grpsize(), 2.);
+        let g = Csr::<(), u8>::from_sorted_edges(&[(0, 1, 1), (1, 0, 2)]);
+        let autom = g.unwrap().try_into_autom().unwrap();
+        assert_eq!(autom.grpsize(), 1.);
+
+        // undirected edges stored in one or both directions
+        let path = UnGraph::<(), u8>::from_edges([(0, 1, 0), (1, 2, 0)]);
+        let expected = path.try_into_autom().unwrap();
+        for edges in [
+            &[(1, 0, 0), (2, 1, 0)][..],
+            &[(0, 1, 0), (1, 0, 0), (2, 1, 0)],
+        ] {
+            let g = Csr::<(), u8, Undirected>::from_sorted_edges(edges);
+            let autom = g.unwrap().try_into_autom().unwrap();
+            assert_eq!(autom, expected);
+        }
+    }
+
+    #[test]
+    fn orbits() {
+        log_init();
+
+        let g = DiGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 0), (3, 0)]);
+        assert_eq!(vertex_orbits(g).unwrap(), [0, 1, 2, 3]);
+        let g = UnGraph::<u8, ()>::from_edges([(3, 1), (1, 0), (0, 2)]);
+        assert_eq!(vertex_orbits(g).unwrap(), [0, 0, 2, 2]);
+        let mut g = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        g[NodeIndex::new(3)] = 1;
+        assert_eq!(vertex_orbits(g).unwrap(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn with_generators() {
+        log_init();
+
+        let g = UnGraph::<u8, ()>::from_edges([(3, 1), (1, 0), (0, 2)]);
+        let (autom, generators) =
+            g.clone().try_into_autom_with_generators().unwrap();
+        let expected = g.try_into_autom().unwrap();
+        assert_eq!(autom.grpsize(), expected.grpsize());
+        assert_eq!(autom.num_orbits, expected.num_orbits);
+        assert_eq!(generators, [Permutation::new(vec![1, 0, 3, 2])]);
+
+        let g = UnGraph::<u8, ()>::default();
+        let (autom, generators) = g.try_into_autom_with_generators().unwrap();
+        assert_eq!(autom.grpsize(), 1.);
+        assert!(generators.is_empty());
+    }
+
+    #[test]
+    fn generators() {
+        log_init();
+
+        let g = UnGraph::<u8, ()>::from_edges([(3, 1), (1, 0), (0, 2)]);
+        let (gens, orbits) = autom_generators(g);
+        assert_eq!(gens, [[1, 0, 3, 2]]);
+        assert_eq!(orbits, [0, 0, 2, 2]);
+
+        // edge weights break the symmetry of the square
+        let g = UnGraph::<(), u8>::from_edges([
+            (0, 1, 0),
+            (1, 2, 1),
+            (2, 3, 0),
+            (3, 0, 1),
+        ]);
+        let (gens, orbits) = autom_generators(g);
+        assert_eq!(gens.len(), 2);
+        assert_eq!(orbits, [0, 0, 0, 0]);
+        for gen in gens {
+            let mut sorted = gen.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, [0, 1, 2, 3]);
+            // the weighted edges are permuted among themselves
+            let e = (gen[1].min(gen[2]), gen[1].max(gen[2]));
+            assert!(e == (1, 2) || e == (0, 3));
+        }
+
+        let g = DiGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 0), (3, 0)]);
+        let (gens, _) = autom_generators(g);
+        assert!(gens.is_empty());
+    }
+
+    #[test]
+    fn generators_repeated() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let g = UnGraph::<(), ()>::from_edges(
+            (0..8u32).flat_map(|i| (0..i).map(move |j| (i, j))),
+        );
+        let (expected, _) = autom_generators(g.clone());
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            // keep stopping the calls in the other thread
+            s.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    crate::kill::request();
+                    std::thread::yield_now();
+                    crate::kill::withdraw();
+                }
+            });
+            for _ in 0..100 {
+                let (gens, _) = autom_generators(g.clone());
+                assert_eq!(gens, expected);
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+    }
+
     #[test]
     fn triangle() {
         log_init();
@@ -0,0 +1,320 @@
+//! Cancelling long computations
+//!
+//! For some graphs, nauty and Traces take a very long time. The calls
+//! to nauty and Traces made inside [CancellationToken::run] stop as
+//! soon as the token is cancelled, for example from another thread.
+//!
+//! nauty's mechanism for stopping a search is shared by all threads.
+//! Calls in other threads that are stopped by a cancellation are
+//! repeated, so they still return the correct results.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::thread;
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::prelude::*;
+//! use nauty_pet::cancel::CancellationToken;
+//! use nauty_pet::error::NautyError;
+//!
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+//! let token = CancellationToken::new();
+//! let autom = token.run(|| (&g).try_into_autom()).unwrap().unwrap();
+//! assert_eq!(autom.grpsize(), 2.);
+//!
+//! let other = token.clone();
+//! thread::spawn(move || other.cancel()).join().unwrap();
+//! let res = token.run(|| (&g).try_into_autom());
+//! assert!(matches!(res, Err(NautyError::Cancelled)));
+//! ```
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+use crate::error::NautyError;
+use crate::kill;
+
+/// A token for cancelling computations
+///
+/// Clones of a token share their state, so cancelling a clone
+/// cancels the original.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    /// A new token that has not been cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel all current and future computations run with this
+    /// token
+    pub fn cancel(&self) {
+        let mut state = self.inner.lock();
+        state.cancelled = true;
+        if state.running > 0 && !state.requested {
+            kill::request();
+            state.requested = true;
+        }
+    }
+
+    /// Whether the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    /// Run `f`, stopping the calls to nauty and Traces it makes in
+    /// the current thread once the token is cancelled
+    ///
+    /// Fallible functions like
+    /// [try_into_canon](crate::canon::TryIntoCanon::try_into_canon)
+    /// return [NautyError::Cancelled] when they are stopped. Functions
+    /// that cannot return an error instead unwind to the end of `f`.
+    ///
+    /// If the token is cancelled before `run` returns, the result of
+    /// `f` is discarded and [NautyError::Cancelled] is returned
+    /// instead. If the token has already been cancelled, `f` is not
+    /// called.
+    pub fn run<T>(&self, f: impl FnOnce() -> T) -> Result<T, NautyError> {
+        if self.is_cancelled() {
+            return Err(NautyError::Cancelled);
+        }
+        let prev = CURRENT.with(|c| c.replace(Some(self.inner.clone())));
+        let res = panic::catch_unwind(AssertUnwindSafe(f));
+        CURRENT.with(|c| *c.borrow_mut() = prev);
+        match res {
+            Ok(_) if self.is_cancelled() => Err(NautyError::Cancelled),
+            Ok(res) => Ok(res),
+            Err(payload) if payload.is::<Stopped>() => {
+                Err(NautyError::Cancelled)
+            }
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Inner {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    cancelled: bool,
+    // number of running calls to nauty and Traces
+    running: usize,
+    // whether running calls were asked to stop
+    requested: bool,
+}
+
+impl Inner {
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.lock().cancelled
+    }
+
+    /// Register a call to nauty or Traces
+    ///
+    /// Returns `false` if the token has been cancelled, in which case
+    /// the call should not be made.
+    pub(crate) fn begin_call(&self) -> bool {
+        let mut state = self.lock();
+        if state.cancelled {
+            return false;
+        }
+        state.running += 1;
+        true
+    }
+
+    /// Register the end of a call started with [begin_call](Self::begin_call)
+    pub(crate) fn end_call(&self) {
+        let mut state = self.lock();
+        state.running -= 1;
+        if state.running == 0 && state.requested {
+            kill::withdraw();
+            state.requested = false;
+        }
+    }
+}
+
+thread_local! {
+    // the token of the computation running in this thread
+    static CURRENT: RefCell<Option<Arc<Inner>>> = RefCell::new(None);
+}
+
+/// The token of the computation running in the current thread
+pub(crate) fn current() -> Option<Arc<Inner>> {
+    CURRENT.with(|c| c.borrow().clone())
+}
+
+// payload of the unwinding started by `unwrap`
+struct Stopped;
+
+/// Unwrap the result of a call to nauty or Traces
+///
+/// If the call was stopped because the computation running in the
+/// current thread was cancelled, this unwinds to
+/// [CancellationToken::run] without running the panic hook.
+/// Otherwise, errors cause a panic as with [Result::unwrap].
+#[track_caller]
+pub(crate) fn unwrap<T, E: Debug>(res: Result<T, E>) -> T {
+    let cancelled = || current().map_or(false, |t| t.is_cancelled());
+    if res.is_err() && cancelled() {
+        panic::resume_unwind(Box::new(Stopped));
+    }
+    res.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autom::TryIntoAutom;
+    use crate::canon::{IntoCanon, TryIntoCanon};
+    use crate::generate::GraphGenerator;
+    use petgraph::graph::UnGraph;
+    use std::time::Duration;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // a graph on which nauty takes some time
+    fn hard() -> UnGraph<(), ()> {
+        // Cayley graph of Z_n x Z_n that is not vertex-transitive on
+        // its own due to the missing vertex
+        let n = 40u32;
+        let mut edges = Vec::new();
+        for v in 1..n * n {
+            let (x, y) = (v / n, v % n);
+            for (dx, dy) in [(1, 0), (0, 1), (1, 1)] {
+                let w = n * ((x + dx) % n) + (y + dy) % n;
+                if w != 0 {
+                    edges.push((v - 1, w - 1));
+                }
+            }
+        }
+        UnGraph::from_edges(edges)
+    }
+
+    #[test]
+    fn cancelled() {
+        log_init();
+
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let token = CancellationToken::new();
+        let res = token.run(|| g.clone().try_into_canon());
+        assert!(res.unwrap().is_ok());
+        token.cancel();
+        assert!(token.is_cancelled());
+        let mut called = false;
+        let res = token.run(|| called = true);
+        assert!(matches!(res, Err(NautyError::Cancelled)));
+        assert!(!called);
+
+        // cancel from inside the computation
+        let token = CancellationToken::new();
+        let res = token.run(|| {
+            token.cancel();
+            (&g).try_into_autom()
+        });
+        assert!(matches!(res, Err(NautyError::Cancelled)));
+        assert_eq!((&g).try_into_autom().unwrap().grpsize(), 2.);
+
+        // functions without errors unwind
+        let token = CancellationToken::new();
+        let res = token.run(|| {
+            token.cancel();
+            g.clone().into_canon()
+        });
+        assert!(matches!(res, Err(NautyError::Cancelled)));
+        // other panics are resumed
+        let token = CancellationToken::new();
+        let res = std::panic::catch_unwind(|| {
+            let _ = token.run(|| panic!("other"));
+        });
+        assert_eq!(*res.unwrap_err().downcast::<&str>().unwrap(), "other");
+    }
+
+    #[test]
+    fn cancel_generation() {
+        log_init();
+
+        let mut graphs = GraphGenerator::new(5).into_iter();
+        let token = CancellationToken::new();
+        assert!(token.run(|| graphs.next()).unwrap().is_some());
+        let res = token.run(|| {
+            token.cancel();
+            graphs.next()
+        });
+        assert!(matches!(res, Err(NautyError::Cancelled)));
+        // no graph is lost or repeated
+        assert_eq!(graphs.count(), 33);
+    }
+
+    #[test]
+    fn cancel_waiting() {
+        log_init();
+        let _lock = kill::test_lock();
+
+        let g = hard();
+        // stop all calls until the request is withdrawn
+        kill::request();
+        let token = CancellationToken::new();
+        let res = std::thread::scope(|s| {
+            let call = s.spawn(|| token.run(|| (&g).try_into_autom()));
+            while token.inner.lock().running == 0 {
+                std::thread::yield_now();
+            }
+            std::thread::sleep(Duration::from_millis(10));
+            token.cancel();
+            call.join().unwrap()
+        });
+        kill::withdraw();
+        assert!(matches!(res, Err(NautyError::Cancelled)));
+    }
+
+    #[test]
+    fn cancel_threads() {
+        log_init();
+        let _lock = kill::test_lock();
+
+        let g = hard();
+        let expected = (&g).try_into_autom().unwrap();
+        std::thread::scope(|s| {
+            for _ in 0..3 {
+                s.spawn(|| {
+                    let token = CancellationToken::new();
+                    let other = token.clone();
+                    let g = &g;
+                    std::thread::scope(|s| {
+                        s.spawn(move || {
+                            std::thread::sleep(Duration::from_millis(1));
+                            other.cancel()
+                        });
+                        let res = token.run(|| {
+                            while !token.is_cancelled() {
+                                let _ = g.try_into_autom();
+                            }
+                        });
+                        assert!(matches!(res, Err(NautyError::Cancelled)));
+                    });
+                });
+            }
+            // computations without a token are unaffected
+            for _ in 0..3 {
+                s.spawn(|| {
+                    for _ in 0..3 {
+                        let autom = (&g).try_into_autom().unwrap();
+                        assert_eq!(autom, expected);
+                    }
+                });
+            }
+        });
+    }
+}
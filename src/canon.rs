@@ -1,6 +1,24 @@
+use crate::autom::Autom;
+use crate::cancel;
+use crate::cmp::IsIdentical;
 use crate::error::NautyError;
+use crate::graph::CanonGraph;
+use crate::ignore::map_owned;
+use crate::invariant::may_be_isomorphic;
+#[cfg(feature = "traces")]
+use crate::kill::Traces;
+use crate::kill::{self, densenauty, sparsenauty};
+use crate::nauty_graph::borrowed;
 use crate::nauty_graph::DenseGraph;
 use crate::nauty_graph::SparseGraph;
+use crate::perm::Permutation;
+use crate::stats::NautyStats;
+#[cfg(feature = "traces")]
+use crate::stats::TracesSearchStats;
+use crate::verbosity::capture_nauty;
+#[cfg(feature = "traces")]
+use crate::verbosity::capture_traces;
+use crate::workspace::NautyWorkspace;
 
 use std::cmp::Ord;
 use std::convert::Infallible;
@@ -8,14 +26,18 @@ use std::fmt::Debug;
 use std::hash::Hash;
 
 use nauty_Traces_sys::{
-    densenauty, empty_graph, optionblk, statsblk, FALSE, MTOOBIG, NTOOBIG, TRUE,
+    empty_graph, optionblk, statsblk, FALSE, MTOOBIG, NAUABORTED, NAUKILLED,
+    NTOOBIG, TRUE,
 };
-use nauty_Traces_sys::{
-    sparsegraph, sparsenauty, Traces, TracesOptions, TracesStats, SG_FREE,
-};
-use petgraph::graph::UnGraph;
+use nauty_Traces_sys::{sparsegraph, SG_FREE};
+#[cfg(feature = "traces")]
+use nauty_Traces_sys::{TracesOptions, TracesStats};
+use petgraph::stable_graph::StableGraph;
+#[cfg(feature = "traces")]
+use petgraph::{graph::UnGraph, Undirected};
 use petgraph::{
-    graph::{Graph, IndexType},
+    graph::{EdgeReference, Graph, IndexType, NodeIndex},
+    visit::EdgeRef,
     EdgeType,
 };
 
@@ -62,11 +84,13 @@ pub trait TryIntoCanonNautyDense {
 }
 
 /// Use Traces to find the canonical labelling
+#[cfg(feature = "traces")]
 pub trait IntoCanonTraces {
     fn into_canon_traces(self) -> Self;
 }
 
 /// Use Traces to find the canonical labelling
+#[cfg(feature = "traces")]
 pub trait TryIntoCanonTraces {
     type Error;
 
@@ -75,13 +99,110 @@ pub trait TryIntoCanonTraces {
         Self: Sized;
 }
 
+/// Find the canonical form together with the canonical labelling
+///
+/// The labelling maps each original node to its position in the
+/// canonical form, so results computed on the canonical form can be
+/// transferred back to the original graph. The canonical form is the
+/// same as for [TryIntoCanon].
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::prelude::*;
+///
+/// let g = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2)]);
+/// let (canon, labelling) = g.clone().try_into_canon_with_labelling().unwrap();
+/// for v in g.node_indices() {
+///     assert_eq!(g[v], canon[labelling.node_image(v)]);
+/// }
+/// ```
+pub trait TryIntoCanonWithLabelling {
+    type Error;
+
+    fn try_into_canon_with_labelling(
+        self,
+    ) -> Result<(Self, Permutation), Self::Error>
+    where
+        Self: Sized;
+}
+
+type CanonWithAutom<N, E, Ty, Ix> = (CanonGraph<N, E, Ty, Ix>, Autom);
+
+/// Find the canonical form and analyse the automorphism group in a
+/// single run of dense nauty
+///
+/// The canonical form is the same as for [TryIntoCanon] and the
+/// statistics are the same as for
+/// [TryIntoAutom](crate::autom::TryIntoAutom), but nauty only has to
+/// be called once.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::prelude::*;
+///
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+/// let (canon, autom) = g.clone().try_into_canon_with_autom().unwrap();
+/// assert_eq!(canon, CanonGraph::from(g));
+/// assert_eq!(autom.grpsize(), 2.);
+/// ```
+pub trait TryIntoCanonWithAutom<N, E, Ty: EdgeType, Ix: IndexType> {
+    type Error;
+
+    fn try_into_canon_with_autom(
+        self,
+    ) -> Result<CanonWithAutom<N, E, Ty, Ix>, Self::Error>;
+}
+
+type RootedWithAutom<N, E, Ty, Ix> = (Graph<N, E, Ty, Ix>, Autom);
+
+/// Find the canonical form of a rooted graph
+///
+/// The root is treated as having a colour of its own and becomes the
+/// vertex with index 0 in the canonical form. Two rooted graphs have
+/// identical canonical forms if and only if there is an isomorphism
+/// mapping one root to the other. The returned statistics describe
+/// the stabiliser of the root in the automorphism group.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::prelude::*;
+///
+/// // a star rooted at its centre and at a leaf
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+/// let (centre, autom) =
+///     g.clone().try_into_canon_rooted(NodeIndex::new(0)).unwrap();
+/// assert_eq!(autom.grpsize(), 6.);
+/// let (leaf, autom) = g.try_into_canon_rooted(NodeIndex::new(3)).unwrap();
+/// assert_eq!(autom.grpsize(), 2.);
+/// assert!(!centre.is_identical(&leaf));
+/// assert_eq!(leaf.edges(NodeIndex::new(0)).count(), 1);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `root` is not a vertex of the graph.
+pub trait TryIntoCanonRooted<N, E, Ty: EdgeType, Ix: IndexType> {
+    type Error;
+
+    fn try_into_canon_rooted(
+        self,
+        root: NodeIndex<Ix>,
+    ) -> Result<RootedWithAutom<N, E, Ty, Ix>, Self::Error>;
+}
+
 impl<N, E, Ty: EdgeType, Ix: IndexType> IntoCanon for Graph<N, E, Ty, Ix>
 where
     Graph<N, E, Ty, Ix>: TryIntoCanon,
     <Graph<N, E, Ty, Ix> as TryIntoCanon>::Error: Debug,
 {
     fn into_canon(self) -> Self {
-        self.try_into_canon().unwrap()
+        cancel::unwrap(self.try_into_canon())
     }
 }
 
@@ -109,38 +230,55 @@ where
         if self.node_count() == 0 {
             return Ok(self);
         }
-        let mut options = optionblk::default_sparse();
-        options.getcanon = TRUE;
-        options.defaultptn = FALSE;
-        options.digraph = if self.is_directed() { TRUE } else { FALSE };
-        let mut stats = statsblk::default();
-        let mut sg = SparseGraph::from(self);
-        let mut orbits = vec![0; sg.g.v.len()];
-        let mut cg = sparsegraph::default();
-        unsafe {
-            sparsenauty(
-                &mut (&mut sg.g).into(),
-                sg.nodes.lab.as_mut_ptr(),
-                sg.nodes.ptn.as_mut_ptr(),
-                orbits.as_mut_ptr(),
-                &mut options,
-                &mut stats,
-                &mut cg,
-            );
-            SG_FREE(&mut cg);
-        }
-        debug_assert_eq!(stats.errstatus, 0);
+        let options = optionblk::default_sparse();
+        let res = sparse_graph_canon(SparseGraph::from(self), options);
+        let (sg, _) = cancel::unwrap(res);
         Ok(sg.into())
     }
 }
 
+/// Run sparse nauty with the given options on a graph that is
+/// already in nauty's sparse format
+pub(crate) fn sparse_graph_canon<N, E, Ty>(
+    mut sg: SparseGraph<(N, Vec<E>), E, Ty>,
+    mut options: optionblk,
+) -> Result<(SparseGraph<(N, Vec<E>), E, Ty>, NautyStats), NautyError>
+where
+    Ty: EdgeType,
+{
+    options.getcanon = TRUE;
+    options.defaultptn = FALSE;
+    options.digraph = if Ty::is_directed() { TRUE } else { FALSE };
+    let mut stats = statsblk::default();
+    let mut orbits = vec![0; sg.g.v.len()];
+    let mut cg = sparsegraph::default();
+    let output = capture_nauty(&mut options);
+    unsafe {
+        sparsenauty(
+            &mut (&mut sg.g).into(),
+            sg.nodes.lab.as_mut_ptr(),
+            sg.nodes.ptn.as_mut_ptr(),
+            orbits.as_mut_ptr(),
+            &mut options,
+            &mut stats,
+            &mut cg,
+        );
+        SG_FREE(&mut cg);
+    }
+    output.finish();
+    // the search may have been stopped by a cancellation
+    kill::check(stats.errstatus)?;
+    debug_assert_eq!(stats.errstatus, 0);
+    Ok((sg, stats.into()))
+}
+
 impl<N, E, Ty, Ix> IntoCanonNautySparse for Graph<N, E, Ty, Ix>
 where
     Graph<N, E, Ty, Ix>: TryIntoCanonNautySparse,
     <Graph<N, E, Ty, Ix> as TryIntoCanonNautySparse>::Error: Debug,
 {
     fn into_canon_nauty_sparse(self) -> Self {
-        self.try_into_canon_nauty_sparse().unwrap()
+        cancel::unwrap(self.try_into_canon_nauty_sparse())
     }
 }
 
@@ -169,6 +307,7 @@ where
         let mut dg = DenseGraph::from(self);
         let mut orbits = vec![0; dg.n];
         let mut cg = empty_graph(dg.m, dg.n);
+        let output = capture_nauty(&mut options);
         unsafe {
             densenauty(
                 dg.g.as_mut_ptr(),
@@ -182,10 +321,12 @@ where
                 cg.as_mut_ptr(),
             );
         }
+        output.finish();
         match stats.errstatus {
             0 => Ok(dg.into()),
             MTOOBIG => Err(MTooBig),
             NTOOBIG => Err(NTooBig),
+            NAUKILLED => Err(Cancelled),
             _ => unreachable!(),
         }
     }
@@ -197,10 +338,11 @@ where
     <Graph<N, E, Ty, Ix> as TryIntoCanonNautyDense>::Error: Debug,
 {
     fn into_canon_nauty_dense(self) -> Self {
-        self.try_into_canon_nauty_dense().unwrap()
+        cancel::unwrap(self.try_into_canon_nauty_dense())
     }
 }
 
+#[cfg(feature = "traces")]
 impl<N, E, Ix: IndexType> TryIntoCanonTraces for UnGraph<N, E, Ix>
 where
     N: Ord,
@@ -212,50 +354,709 @@ where
         if self.node_count() == 0 {
             return Ok(self);
         }
-        let mut options = TracesOptions {
-            getcanon: TRUE,
-            defaultptn: FALSE,
-            digraph: FALSE,
-            ..Default::default()
-        };
-        let mut stats = TracesStats::default();
-        let mut sg = SparseGraph::from(self);
-        let mut orbits = vec![0; sg.g.v.len()];
-        let mut cg = sparsegraph::default();
-        unsafe {
-            Traces(
-                &mut (&mut sg.g).into(),
-                sg.nodes.lab.as_mut_ptr(),
-                sg.nodes.ptn.as_mut_ptr(),
-                orbits.as_mut_ptr(),
-                &mut options,
-                &mut stats,
-                &mut cg,
-            );
-            SG_FREE(&mut cg);
-        }
-        debug_assert_eq!(stats.errstatus, 0);
+        let options = TracesOptions::default();
+        let res = traces_graph_canon(SparseGraph::from(self), options);
+        let (sg, _) = cancel::unwrap(res);
         Ok(sg.into())
     }
 }
 
+/// Run Traces with the given options on an undirected graph that is
+/// already in nauty's sparse format
+#[cfg(feature = "traces")]
+pub(crate) fn traces_graph_canon<N, E>(
+    mut sg: SparseGraph<(N, Vec<E>), E, Undirected>,
+    mut options: TracesOptions,
+) -> Result<
+    (SparseGraph<(N, Vec<E>), E, Undirected>, TracesSearchStats),
+    NautyError,
+> {
+    options.getcanon = TRUE;
+    options.defaultptn = FALSE;
+    options.digraph = FALSE;
+    let mut stats = TracesStats::default();
+    let mut orbits = vec![0; sg.g.v.len()];
+    let mut cg = sparsegraph::default();
+    let output = capture_traces(&mut options);
+    unsafe {
+        Traces(
+            &mut (&mut sg.g).into(),
+            sg.nodes.lab.as_mut_ptr(),
+            sg.nodes.ptn.as_mut_ptr(),
+            orbits.as_mut_ptr(),
+            &mut options,
+            &mut stats,
+            &mut cg,
+        );
+        SG_FREE(&mut cg);
+    }
+    output.finish();
+    // the search may have been stopped by a cancellation
+    kill::check(stats.errstatus)?;
+    debug_assert_eq!(stats.errstatus, 0);
+    Ok((sg, stats.into()))
+}
+
+#[cfg(feature = "traces")]
 impl<N, E, Ix> IntoCanonTraces for UnGraph<N, E, Ix>
 where
     UnGraph<N, E, Ix>: TryIntoCanonTraces,
     <UnGraph<N, E, Ix> as TryIntoCanonTraces>::Error: Debug,
 {
     fn into_canon_traces(self) -> Self {
-        self.try_into_canon_traces().unwrap()
+        cancel::unwrap(self.try_into_canon_traces())
+    }
+}
+
+impl<N, E, Ty: EdgeType, Ix: IndexType> IntoCanon for StableGraph<N, E, Ty, Ix>
+where
+    StableGraph<N, E, Ty, Ix>: TryIntoCanon,
+    <StableGraph<N, E, Ty, Ix> as TryIntoCanon>::Error: Debug,
+{
+    fn into_canon(self) -> Self {
+        cancel::unwrap(self.try_into_canon())
+    }
+}
+
+/// The canonical form of a [StableGraph] is the same as for the
+/// corresponding [Graph]. In particular, it has no holes in its node
+/// or edge indices.
+impl<N, E, Ty: EdgeType, Ix: IndexType> TryIntoCanon
+    for StableGraph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+{
+    type Error = NautyError;
+
+    fn try_into_canon(self) -> Result<Self, Self::Error> {
+        let g = Graph::from(self);
+        Ok(g.try_into_canon()?.into())
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoCanonWithLabelling for Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_canon_with_labelling(
+        self,
+    ) -> Result<(Self, Permutation), Self::Error> {
+        let (canon, labelling) = try_canon_with_labelling(self)?;
+        Ok((canon, Permutation::new(labelling)))
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoCanonWithAutom<N, E, Ty, Ix> for Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_canon_with_autom(
+        self,
+    ) -> Result<CanonWithAutom<N, E, Ty, Ix>, Self::Error> {
+        let (canon, _, autom) = dense_canon(self)?;
+        Ok((CanonGraph::from_canon_unchecked(canon), autom))
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoCanonRooted<N, E, Ty, Ix> for Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_canon_rooted(
+        self,
+        root: NodeIndex<Ix>,
+    ) -> Result<RootedWithAutom<N, E, Ty, Ix>, Self::Error> {
+        assert!(
+            root.index() < self.node_count(),
+            "root {} is not in the graph",
+            root.index()
+        );
+        let mut is_root = vec![false; self.node_count()];
+        is_root[root.index()] = true;
+        let mut is_root = is_root.into_iter();
+        let rooted = map_owned(
+            self,
+            // the root is the only vertex with colour 0
+            |w| (usize::from(!is_root.next().unwrap()), w),
+            |w| w,
+        );
+        let (canon, _, autom) = dense_canon(rooted)?;
+        Ok((map_owned(canon, |(_, w)| w, |w| w), autom))
+    }
+}
+
+/// The canonical labelling of a graph
+///
+/// The labelling maps each vertex to its index in the canonical form
+/// given by [TryIntoCanon], so applying it with
+/// [Permutation::apply] yields the nodes and edges of the canonical
+/// form. To obtain the canonical form at the same time, use
+/// [TryIntoCanonWithLabelling].
+pub fn canonical_labelling<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Result<Permutation, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let (_, labelling) = try_canon_with_labelling(borrowed(g))?;
+    Ok(Permutation::new(labelling))
+}
+
+/// Relabel a graph canonically in place
+///
+/// Afterwards, `g` has the same nodes and edges in the same order as
+/// its canonical form given by [TryIntoCanon]. The node weights are
+/// swapped into their canonical positions and the edge weights are
+/// moved, so no weights are cloned and no second graph is built.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::canon::canonize_in_place;
+/// use nauty_pet::prelude::*;
+///
+/// let mut g = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2)]);
+/// canonize_in_place(&mut g).unwrap();
+/// assert!(g.is_identical(&g.clone().into_canon()));
+/// ```
+pub fn canonize_in_place<N, E, Ty, Ix>(
+    g: &mut Graph<N, E, Ty, Ix>,
+) -> Result<(), NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let (dg, _) = canon_dense_graph(borrowed(g))?;
+    let labelling = canonical_positions(&dg);
+
+    // swap the node weights into place, one cycle at a time
+    let mut pos = labelling.clone();
+    for v in 0..pos.len() {
+        while pos[v] != v {
+            let w = pos[v];
+            let (a, b) =
+                g.index_twice_mut(NodeIndex::new(v), NodeIndex::new(w));
+            std::mem::swap(a, b);
+            pos.swap(v, w);
+        }
+    }
+
+    // remove the edges from the back, which requires no reindexing
+    let mut edges = Vec::with_capacity(g.edge_count());
+    while let Some(e) = g.edge_indices().next_back() {
+        let (source, target) = g.edge_endpoints(e).unwrap();
+        let (source, target) = canonical_end_points::<Ty>(
+            &labelling,
+            source.index(),
+            target.index(),
+        );
+        edges.push((source, target, g.remove_edge(e).unwrap()));
+    }
+    edges.sort_unstable();
+    for (source, target, w) in edges {
+        g.add_edge(NodeIndex::new(source), NodeIndex::new(target), w);
+    }
+    Ok(())
+}
+
+/// Clone `g` into the canonical form given by its canonical
+/// labelling
+pub(crate) fn relabel_cloned<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    labelling: &[usize],
+) -> Graph<N, E, Ty, Ix>
+where
+    N: Clone,
+    E: Clone + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut nodes = vec![None; g.node_count()];
+    for (v, w) in g.node_weights().enumerate() {
+        nodes[labelling[v]] = Some(w);
+    }
+    let mut edges = Vec::from_iter(g.edge_references().map(|e| {
+        let (source, target) = canonical_end_points::<Ty>(
+            labelling,
+            e.source().index(),
+            e.target().index(),
+        );
+        (source, target, e.weight())
+    }));
+    edges.sort_unstable();
+    let mut res = Graph::with_capacity(nodes.len(), edges.len());
+    for w in nodes {
+        res.add_node(w.unwrap().clone());
+    }
+    for (source, target, w) in edges {
+        res.add_edge(NodeIndex::new(source), NodeIndex::new(target), w.clone());
+    }
+    res
+}
+
+// the end points of an edge in the canonical form, with the smaller
+// index first for undirected graphs
+fn canonical_end_points<Ty: EdgeType>(
+    labelling: &[usize],
+    source: usize,
+    target: usize,
+) -> (usize, usize) {
+    let source = labelling[source];
+    let target = labelling[target];
+    if !Ty::is_directed() && source > target {
+        (target, source)
+    } else {
+        (source, target)
+    }
+}
+
+/// Individualise the given vertices
+///
+/// Returns a copy of `g` where the weight of each vertex `v` is
+/// extended to `(i, g[v])`, where `i` is the position of `v` in
+/// `fixed`, or `fixed.len()` if `v` is not fixed. Each fixed vertex
+/// is thereby in a colour class of its own. Canonical forms of the
+/// result are rooted at the fixed vertices, with `fixed[i]` becoming
+/// the vertex with index `i`, and its automorphisms fix each vertex
+/// in `fixed`.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::canon::with_fixed_vertices;
+/// use nauty_pet::prelude::*;
+///
+/// // rooted paths are identical if the roots are at the same end
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+/// let [v0, v1, v2] = [0, 1, 2].map(NodeIndex::new);
+/// let end = CanonGraph::from(with_fixed_vertices(&g, &[v0]));
+/// let other_end = CanonGraph::from(with_fixed_vertices(&g, &[v2]));
+/// let centre = CanonGraph::from(with_fixed_vertices(&g, &[v1]));
+/// assert_eq!(end, other_end);
+/// assert_ne!(end, centre);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a vertex in `fixed` is not in `g` or appears more than
+/// once.
+pub fn with_fixed_vertices<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    fixed: &[NodeIndex<Ix>],
+) -> Graph<(usize, N), E, Ty, Ix>
+where
+    N: Clone,
+    E: Clone,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut position = vec![fixed.len(); g.node_count()];
+    for (i, v) in fixed.iter().enumerate() {
+        assert_eq!(
+            position[v.index()],
+            fixed.len(),
+            "vertex {} is fixed more than once",
+            v.index()
+        );
+        position[v.index()] = i;
+    }
+    g.map(|v, w| (position[v.index()], w.clone()), |_, w| w.clone())
+}
+
+/// Colour the nodes with a function instead of their weights
+///
+/// Returns a copy of `g` where the weight of each node is replaced by
+/// `colour((index, weight))`. The node weights of `g` therefore need
+/// not be ordered. Canonical labellings and automorphisms of the
+/// result respect the colours, and can be transferred back to `g`.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::canon::{canonical_labelling, with_node_colouring};
+/// use nauty_pet::group::AutomorphismGroup;
+///
+/// // floating point weights, coloured by their sign
+/// let g = UnGraph::<f64, ()>::from_edges([(0, 1), (1, 2)]);
+/// let g = g.map(|v, _| [-1.5, 0.5, 2.0][v.index()], |_, &e| e);
+/// let coloured = with_node_colouring(&g, |(_, w)| *w > 0.);
+/// assert_eq!(AutomorphismGroup::from(coloured.clone()).order(), Some(1));
+///
+/// // relabel the original graph canonically
+/// let labelling = canonical_labelling(&coloured).unwrap();
+/// let canon = labelling.apply(&g);
+/// assert_eq!(canon.node_count(), 3);
+/// ```
+pub fn with_node_colouring<N, E, Ty, Ix, C, F>(
+    g: &Graph<N, E, Ty, Ix>,
+    mut colour: F,
+) -> Graph<C, E, Ty, Ix>
+where
+    E: Clone,
+    Ty: EdgeType,
+    Ix: IndexType,
+    F: FnMut((NodeIndex<Ix>, &N)) -> C,
+{
+    g.map(|v, w| colour((v, w)), |_, w| w.clone())
+}
+
+/// Colour the edges with a function instead of their weights
+///
+/// Returns a copy of `g` where the weight of each edge is replaced by
+/// `colour(edge)`. This is useful if only a small part of rich edge
+/// weights should be taken into account. See also
+/// [with_node_colouring].
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use petgraph::visit::EdgeRef;
+/// use nauty_pet::canon::with_edge_colouring;
+/// use nauty_pet::prelude::*;
+///
+/// struct Bond {
+///     order: u8,
+///     length: f64,
+/// }
+///
+/// let mut g = UnGraph::<(), Bond>::default();
+/// let [a, b, c] = [(), (), ()].map(|w| g.add_node(w));
+/// g.add_edge(a, b, Bond { order: 1, length: 1.5 });
+/// g.add_edge(b, c, Bond { order: 1, length: 1.4 });
+///
+/// // only the bond order matters
+/// let coloured = with_edge_colouring(&g, |e| e.weight().order);
+/// let autom = coloured.try_into_autom().unwrap();
+/// assert_eq!(autom.grpsize(), 2.);
+/// ```
+pub fn with_edge_colouring<N, E, Ty, Ix, C, F>(
+    g: &Graph<N, E, Ty, Ix>,
+    mut colour: F,
+) -> Graph<N, C, Ty, Ix>
+where
+    N: Clone,
+    Ty: EdgeType,
+    Ix: IndexType,
+    F: FnMut(EdgeReference<'_, E, Ix>) -> C,
+{
+    let mut res = g.filter_map(|_, w| Some(w.clone()), |_, _| None);
+    for e in g.edge_references() {
+        res.add_edge(e.source(), e.target(), colour(e));
+    }
+    res
+}
+
+/// Options for isomorphism tests
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IsoOptions {
+    /// Compare [cheap invariants](crate::invariant) before running
+    /// nauty
+    ///
+    /// This avoids most calls to nauty if the graphs are usually
+    /// not isomorphic, but is wasted effort if they usually are.
+    pub prefilter: bool,
+}
+
+impl Default for IsoOptions {
+    fn default() -> Self {
+        Self { prefilter: true }
+    }
+}
+
+/// An isomorphism from `g` to `h`, if there is one
+///
+/// The isomorphism maps each vertex of `g` to a vertex of `h` with
+/// the same weight, such that edges and their weights are
+/// preserved. Uses the default [IsoOptions].
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::canon::find_isomorphism;
+///
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+/// let h = UnGraph::<(), ()>::from_edges([(2, 0), (0, 1)]);
+/// let iso = find_isomorphism(&g, &h).unwrap().unwrap();
+/// // the centre of the path is mapped to the centre
+/// assert_eq!(iso.image(1), 0);
+///
+/// let triangle = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+/// assert!(find_isomorphism(&g, &triangle).unwrap().is_none());
+/// ```
+pub fn find_isomorphism<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    h: &Graph<N, E, Ty, Ix>,
+) -> Result<Option<Permutation>, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    find_isomorphism_with(g, h, IsoOptions::default())
+}
+
+/// An isomorphism from `g` to `h`, if there is one
+///
+/// See [find_isomorphism].
+pub fn find_isomorphism_with<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    h: &Graph<N, E, Ty, Ix>,
+    options: IsoOptions,
+) -> Result<Option<Permutation>, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    if !is_candidate_pair(g, h, options) {
+        return Ok(None);
+    }
+    let (canon_g, labelling_g) = try_canon_with_labelling(borrowed(g))?;
+    let (canon_h, labelling_h) = try_canon_with_labelling(borrowed(h))?;
+    if !canon_g.is_identical(&canon_h) {
+        return Ok(None);
+    }
+    let to_canon = Permutation::new(labelling_g);
+    let from_canon = Permutation::new(labelling_h).inverse();
+    Ok(Some(&to_canon * &from_canon))
+}
+
+/// Check whether `g` and `h` are isomorphic
+///
+/// Node and edge weights have to be preserved. Uses the default
+/// [IsoOptions].
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::canon::are_isomorphic;
+///
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+/// let h = UnGraph::<(), ()>::from_edges([(2, 0), (0, 1)]);
+/// assert!(are_isomorphic(&g, &h).unwrap());
+/// ```
+pub fn are_isomorphic<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    h: &Graph<N, E, Ty, Ix>,
+) -> Result<bool, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    are_isomorphic_with(g, h, IsoOptions::default())
+}
+
+/// Check whether `g` and `h` are isomorphic
+///
+/// See [are_isomorphic].
+pub fn are_isomorphic_with<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    h: &Graph<N, E, Ty, Ix>,
+    options: IsoOptions,
+) -> Result<bool, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    if !is_candidate_pair(g, h, options) {
+        return Ok(false);
+    }
+    let canon_g = borrowed(g).try_into_canon()?;
+    let canon_h = borrowed(h).try_into_canon()?;
+    Ok(canon_g.is_identical(&canon_h))
+}
+
+// check whether the graphs can be isomorphic before calling nauty
+fn is_candidate_pair<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    h: &Graph<N, E, Ty, Ix>,
+    options: IsoOptions,
+) -> bool
+where
+    N: Ord,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    if options.prefilter {
+        may_be_isomorphic(g, h)
+    } else {
+        g.node_count() == h.node_count() && g.edge_count() == h.edge_count()
+    }
+}
+
+type Labelled<N, E, Ty, Ix> = (Graph<N, E, Ty, Ix>, Vec<usize>);
+
+/// Canonical form together with the canonical position of each vertex
+///
+/// The canonical form is the same as for [TryIntoCanon]. The `i`th
+/// entry of the returned vector is the index of the `i`th vertex of
+/// `g` in the canonical form.
+pub(crate) fn try_canon_with_labelling<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Result<Labelled<N, E, Ty, Ix>, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let (canon, labelling, _) = dense_canon(g)?;
+    Ok((canon, labelling))
+}
+
+type LabelledWithAutom<N, E, Ty, Ix> = (Graph<N, E, Ty, Ix>, Vec<usize>, Autom);
+
+// canonical form, canonical labelling, and automorphism group
+// statistics from a single run of dense nauty
+fn dense_canon<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Result<LabelledWithAutom<N, E, Ty, Ix>, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let (dg, autom) = canon_dense_graph(g)?;
+    let labelling = canonical_positions(&dg);
+    Ok((dg.into(), labelling, autom))
+}
+
+// the index of each original vertex in the canonical form
+pub(crate) fn canonical_positions<N, E, Ty>(
+    dg: &DenseGraph<N, E, Ty>,
+) -> Vec<usize> {
+    let mut pos = vec![0; dg.nodes.lab.len()];
+    for (new, &old) in dg.nodes.lab.iter().enumerate() {
+        pos[old as usize] = new;
+    }
+    Vec::from_iter(dg.nodes.relabel.iter().map(|&v| pos[v]))
+}
+
+type DenseWithAutom<N, E, Ty> = (DenseGraph<(N, Vec<E>), E, Ty>, Autom);
+type DenseWithStats<N, E, Ty> = (DenseGraph<(N, Vec<E>), E, Ty>, NautyStats);
+
+/// Run dense nauty, leaving the canonical labelling in the `lab`
+/// array of the returned graph
+pub(crate) fn canon_dense_graph<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Result<DenseWithAutom<N, E, Ty>, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    dense_graph_canon(DenseGraph::from(g))
+}
+
+/// Run dense nauty on a graph that is already in nauty's dense
+/// format
+pub(crate) fn dense_graph_canon<N, E, Ty>(
+    dg: DenseGraph<(N, Vec<E>), E, Ty>,
+) -> Result<DenseWithAutom<N, E, Ty>, NautyError>
+where
+    Ty: EdgeType,
+{
+    let options = optionblk::default();
+    let mut ws = NautyWorkspace::default();
+    let (dg, stats) = dense_graph_canon_in(dg, options, &mut ws)?;
+    Ok((dg, stats.autom))
+}
+
+/// Run dense nauty on a graph that is already in nauty's dense
+/// format, using the given options and the buffers of the given
+/// workspace
+pub(crate) fn dense_graph_canon_in<N, E, Ty>(
+    mut dg: DenseGraph<(N, Vec<E>), E, Ty>,
+    mut options: optionblk,
+    ws: &mut NautyWorkspace,
+) -> Result<DenseWithStats<N, E, Ty>, NautyError>
+where
+    Ty: EdgeType,
+{
+    use ::std::os::raw::c_int;
+    use NautyError::*;
+
+    let is_directed = Ty::is_directed();
+    if dg.n == 0 {
+        return Ok((dg, NautyStats::trivial()));
+    }
+    options.getcanon = TRUE;
+    options.defaultptn = FALSE;
+    options.digraph = if is_directed { TRUE } else { FALSE };
+    let mut stats = statsblk::default();
+    ws.orbits.clear();
+    ws.orbits.resize(dg.n, 0);
+    ws.cg.clear();
+    ws.cg.resize(dg.m * dg.n, 0);
+    let output = capture_nauty(&mut options);
+    unsafe {
+        densenauty(
+            dg.g.as_mut_ptr(),
+            dg.nodes.lab.as_mut_ptr(),
+            dg.nodes.ptn.as_mut_ptr(),
+            ws.orbits.as_mut_ptr(),
+            &mut options,
+            &mut stats,
+            dg.m as c_int,
+            dg.n as c_int,
+            ws.cg.as_mut_ptr(),
+        );
+    }
+    output.finish();
+    match stats.errstatus {
+        0 => Ok((dg, stats.into())),
+        MTOOBIG => Err(MTooBig),
+        NTOOBIG => Err(NTooBig),
+        NAUABORTED => Err(Aborted),
+        NAUKILLED => Err(Cancelled),
+        _ => unreachable!(),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::cmp::IsIdentical;
     use super::*;
+    use crate::autom::TryIntoAutom;
     use petgraph::{
         algo::isomorphism::is_isomorphic,
-        graph::{Graph, UnGraph},
+        graph::{DiGraph, Graph, UnGraph},
+        visit::EdgeRef,
         Directed, Undirected,
     };
     use rand::prelude::*;
@@ -301,6 +1102,240 @@ mod tests {
         assert!(g1.is_identical(&g2));
     }
 
+    #[test]
+    fn labelling_permutation() {
+        log_init();
+
+        let g = UnGraph::<u8, u8>::from_edges([(0, 1, 2), (1, 2, 1)]);
+        let (canon, labelling) =
+            g.clone().try_into_canon_with_labelling().unwrap();
+        assert!(canon.is_identical(&g.clone().into_canon()));
+        assert!(labelling.apply(&g).node_weights().eq(canon.node_weights()));
+        assert_eq!(labelling, canonical_labelling(&g).unwrap());
+
+        let graphs = GraphIter::<Directed>::default();
+        for g in graphs.take(200) {
+            let labelling = canonical_labelling(&g).unwrap();
+            let canon = g.clone().try_into_canon_nauty_dense().unwrap();
+            let relabelled = labelling.apply(&g);
+            assert!(relabelled.node_weights().eq(canon.node_weights()));
+            assert!(is_isomorphic(&relabelled, &canon));
+        }
+    }
+
+    #[test]
+    fn in_place() {
+        log_init();
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(100) {
+            let mut gg = with_parallel_edges(g, &mut rng);
+            let canon = gg.clone().into_canon();
+            canonize_in_place(&mut gg).unwrap();
+            assert!(gg.is_identical(&canon));
+        }
+        for g in GraphIter::<Undirected>::default().take(100) {
+            let mut gg = with_parallel_edges(g, &mut rng);
+            let canon = gg.clone().into_canon();
+            canonize_in_place(&mut gg).unwrap();
+            assert!(gg.is_identical(&canon));
+        }
+    }
+
+    // check that `iso` maps `g` onto `h`, including all weights
+    fn check_isomorphism<Ty: EdgeType>(
+        g: &Graph<u8, u8, Ty>,
+        h: &Graph<u8, u8, Ty>,
+        iso: &Permutation,
+    ) {
+        assert!(iso.apply(g).node_weights().eq(h.node_weights()));
+        for e in g.edge_references() {
+            let (v, w) =
+                (iso.node_image(e.source()), iso.node_image(e.target()));
+            assert!(h.edges_connecting(v, w).any(|f| f.weight() == e.weight()));
+        }
+    }
+
+    #[test]
+    fn isomorphism() {
+        log_init();
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let graphs = GraphIter::<Directed>::default();
+        for g in graphs.take(200) {
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let iso = find_isomorphism(&g, &gg).unwrap().unwrap();
+            check_isomorphism(&g, &gg, &iso);
+            let inverse = find_isomorphism(&gg, &g).unwrap().unwrap();
+            check_isomorphism(&gg, &g, &inverse);
+        }
+        let graphs = GraphIter::<Undirected>::default();
+        for g in graphs.take(200) {
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let iso = find_isomorphism(&g, &gg).unwrap().unwrap();
+            check_isomorphism(&g, &gg, &iso);
+        }
+
+        // weights and directions have to match
+        let g = UnGraph::<u8, u8>::from_edges([(0, 1, 2), (1, 2, 1)]);
+        let h = UnGraph::<u8, u8>::from_edges([(0, 1, 1), (1, 2, 1)]);
+        assert!(find_isomorphism(&g, &h).unwrap().is_none());
+        let g = DiGraph::<u8, u8>::from_edges([(0, 1, 0), (1, 2, 0)]);
+        let h = DiGraph::<u8, u8>::from_edges([(0, 1, 0), (2, 1, 0)]);
+        assert!(find_isomorphism(&g, &h).unwrap().is_none());
+        let empty = UnGraph::<u8, u8>::default();
+        let iso = find_isomorphism(&empty, &empty).unwrap().unwrap();
+        assert_eq!(iso.degree(), 0);
+    }
+
+    #[test]
+    fn fixed_vertices() {
+        log_init();
+
+        let g = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        let v = NodeIndex::<u32>::new;
+        let fixed = with_fixed_vertices(&g, &[v(2), v(0)]);
+        assert_eq!(fixed[v(2)], (0, 0));
+        assert_eq!(fixed[v(0)], (1, 0));
+        assert_eq!(fixed[v(1)], (2, 0));
+        assert_eq!(fixed.edge_count(), 3);
+
+        // the fixed vertices come first in the canonical form
+        let canon = fixed.try_into_canon().unwrap();
+        assert_eq!(canon[v(0)].0, 0);
+        assert_eq!(canon[v(1)].0, 1);
+        assert!(canon.contains_edge(v(0), v(2)));
+        assert!(canon.contains_edge(v(0), v(3)));
+
+        // rooted canonical forms
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Undirected>::default().take(100) {
+            if g.node_count() == 0 {
+                continue;
+            }
+            let root = v(rng.gen_range(0..g.node_count()));
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let iso = find_isomorphism(&g, &gg).unwrap().unwrap();
+            let image = iso.node_image(root);
+            let canon = with_fixed_vertices(&g, &[root]).into_canon();
+            let other = with_fixed_vertices(&gg, &[image]).into_canon();
+            assert!(canon.is_identical(&other));
+        }
+    }
+
+    #[test]
+    fn rooted() {
+        log_init();
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(100) {
+            let root = NodeIndex::new(rng.gen_range(0..g.node_count()));
+            let (canon, autom) = g.clone().try_into_canon_rooted(root).unwrap();
+            assert_eq!(canon[NodeIndex::new(0)], g[root]);
+            let (expected, expected_autom) = with_fixed_vertices(&g, &[root])
+                .try_into_canon_with_autom()
+                .unwrap();
+            let expected = expected.map(|_, w| w.1, |_, &w| w);
+            assert!(canon.is_identical(&expected));
+            assert_eq!(autom.grpsize(), expected_autom.grpsize());
+
+            // invariant under relabelling
+            let mut images = Vec::from_iter(0..g.node_count());
+            images.shuffle(&mut rng);
+            let perm = Permutation::new(images);
+            let gg = perm.apply(&g);
+            let root = perm.node_image(root);
+            let (rooted, _) = gg.try_into_canon_rooted(root).unwrap();
+            assert!(canon.is_identical(&rooted));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn root_out_of_bounds() {
+        let g = UnGraph::<(), ()>::from_edges([(0, 1)]);
+        let _ = g.try_into_canon_rooted(NodeIndex::new(2));
+    }
+
+    #[test]
+    fn node_colouring() {
+        log_init();
+
+        let g =
+            UnGraph::<u8, u8>::from_edges([(0, 1, 1), (1, 2, 0), (2, 3, 1)]);
+        let coloured = with_node_colouring(&g, |(v, _)| v.index() % 2);
+        assert!(coloured.node_weights().copied().eq([0, 1, 0, 1]));
+        assert!(coloured.edge_weights().eq(g.edge_weights()));
+
+        // the colours are taken into account instead of the weights
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(100) {
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let parity = |(_, &w): (_, &u8)| w % 2;
+            let canon = with_node_colouring(&g, parity).into_canon();
+            let other = with_node_colouring(&gg, parity).into_canon();
+            assert!(canon.is_identical(&other));
+        }
+    }
+
+    #[test]
+    fn edge_colouring() {
+        log_init();
+
+        let g =
+            DiGraph::<u8, u8>::from_edges([(0, 1, 1), (1, 2, 2), (2, 2, 3)]);
+        let coloured = with_edge_colouring(&g, |e| (e.source(), *e.weight()));
+        assert!(coloured.node_weights().eq(g.node_weights()));
+        for (e, f) in g.edge_references().zip(coloured.edge_references()) {
+            assert_eq!((e.source(), e.target()), (f.source(), f.target()));
+            assert_eq!(*f.weight(), (e.source(), *e.weight()));
+        }
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Undirected>::default().take(100) {
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let parity = |e: EdgeReference<'_, u8>| e.weight() % 2;
+            let canon = with_edge_colouring(&g, parity).into_canon();
+            let other = with_edge_colouring(&gg, parity).into_canon();
+            assert!(canon.is_identical(&other));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn fixed_twice() {
+        let g = UnGraph::<(), ()>::from_edges([(0, 1)]);
+        with_fixed_vertices(&g, &[NodeIndex::new(0), NodeIndex::new(0)]);
+    }
+
+    #[test]
+    fn prefilter() {
+        log_init();
+
+        let no_prefilter = IsoOptions { prefilter: false };
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let graphs =
+            Vec::from_iter(GraphIter::<Undirected>::default().take(50));
+        for (g, h) in graphs.iter().zip(&graphs[1..]) {
+            let expected = are_isomorphic_with(g, h, no_prefilter).unwrap();
+            assert_eq!(are_isomorphic(g, h).unwrap(), expected);
+            let gg = randomize_labels(g.clone(), &mut rng);
+            assert!(are_isomorphic(g, &gg).unwrap());
+            assert!(are_isomorphic_with(g, &gg, no_prefilter).unwrap());
+        }
+
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (2, 3), (3, 4)]);
+        let h = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 4)]);
+        assert!(are_isomorphic(&g, &h).unwrap());
+
+        // same invariants, but not isomorphic
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (4, 5)]);
+        let h = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 4), (4, 5)]);
+        assert!(crate::invariant::may_be_isomorphic(&g, &h));
+        assert!(!are_isomorphic(&g, &h).unwrap());
+        assert!(!are_isomorphic_with(&g, &h, no_prefilter).unwrap());
+    }
+
     #[test]
     fn random_canon_nauty_sparse_undirected() {
         log_init();
@@ -389,6 +1424,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "traces")]
     #[test]
     fn random_canon_traces_undirected() {
         log_init();
@@ -411,6 +1447,159 @@ mod tests {
         }
     }
 
+    // add up to two parallel copies of each edge
+    fn with_parallel_edges<R: Rng, Ty: EdgeType>(
+        mut g: Graph<u8, u8, Ty>,
+        rng: &mut R,
+    ) -> Graph<u8, u8, Ty> {
+        let edges = Vec::from_iter(
+            g.edge_references()
+                .map(|e| (e.source(), e.target(), *e.weight())),
+        );
+        for (source, target, w) in edges {
+            for _ in 0..rng.gen_range(0..=2) {
+                let w = if rng.gen() { w } else { rng.gen_range(0..3) };
+                g.add_edge(source, target, w);
+            }
+        }
+        g
+    }
+
+    #[test]
+    fn multigraph() {
+        log_init();
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(300) {
+            let g = with_parallel_edges(g, &mut rng);
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let dense = g.clone().into_canon_nauty_dense();
+            assert_eq!(dense.edge_count(), g.edge_count());
+            assert!(dense.is_identical(&gg.clone().into_canon_nauty_dense()));
+            let sparse = g.into_canon_nauty_sparse();
+            assert!(sparse.is_identical(&gg.into_canon_nauty_sparse()));
+        }
+        for g in GraphIter::<Undirected>::default().take(300) {
+            let g = with_parallel_edges(g, &mut rng);
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let dense = g.clone().into_canon_nauty_dense();
+            assert_eq!(dense.edge_count(), g.edge_count());
+            assert!(dense.is_identical(&gg.clone().into_canon_nauty_dense()));
+            #[cfg(feature = "traces")]
+            {
+                let traces = g.into_canon_traces();
+                assert!(traces.is_identical(&gg.into_canon_traces()));
+            }
+        }
+
+        // multiplicities are not merged
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 1), (1, 2)]);
+        let h = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 1)]);
+        let k = UnGraph::<(), ()>::from_edges([(0, 1), (0, 1), (0, 1), (1, 2)]);
+        assert!(g.clone().into_canon().is_identical(&h.into_canon()));
+        assert!(!are_isomorphic(&g, &k).unwrap());
+        let autom = g.try_into_autom().unwrap();
+        assert_eq!(autom.grpsize(), 1.);
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 1), (1, 2), (1, 2)]);
+        let autom = g.try_into_autom().unwrap();
+        assert_eq!(autom.grpsize(), 2.);
+        // parallel edges with different weights
+        let g = DiGraph::<(), u8>::from_edges([(0, 1, 0), (0, 1, 1)]);
+        let h = DiGraph::<(), u8>::from_edges([(0, 1, 1), (0, 1, 0)]);
+        let k = DiGraph::<(), u8>::from_edges([(0, 1, 1), (0, 1, 1)]);
+        assert!(are_isomorphic(&g, &h).unwrap());
+        assert!(!are_isomorphic(&g, &k).unwrap());
+    }
+
+    #[test]
+    fn self_loops() {
+        log_init();
+
+        // loops on different vertices
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (0, 0)]);
+        let h = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 2)]);
+        let k = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (1, 1)]);
+        assert!(are_isomorphic(&g, &h).unwrap());
+        assert!(!are_isomorphic(&g, &k).unwrap());
+        assert_eq!(g.clone().try_into_autom().unwrap().grpsize(), 1.);
+        assert_eq!(k.clone().try_into_autom().unwrap().grpsize(), 2.);
+        for (canon, expected) in [
+            (
+                g.clone().into_canon_nauty_dense(),
+                h.clone().into_canon_nauty_dense(),
+            ),
+            (
+                g.clone().into_canon_nauty_sparse(),
+                h.clone().into_canon_nauty_sparse(),
+            ),
+            #[cfg(feature = "traces")]
+            (g.clone().into_canon_traces(), h.clone().into_canon_traces()),
+        ] {
+            assert_eq!(canon.edge_count(), 3);
+            assert!(canon.edge_references().any(|e| e.source() == e.target()));
+            assert!(canon.is_identical(&expected));
+        }
+
+        // number and weights of loops
+        let g = DiGraph::<(), u8>::from_edges([(0, 0, 0), (0, 1, 0)]);
+        let h =
+            DiGraph::<(), u8>::from_edges([(0, 0, 0), (0, 0, 0), (0, 1, 0)]);
+        let k = DiGraph::<(), u8>::from_edges([(0, 0, 1), (0, 1, 0)]);
+        assert!(!are_isomorphic(&g, &h).unwrap());
+        assert!(!are_isomorphic(&g, &k).unwrap());
+        let canon = h.clone().into_canon();
+        assert_eq!(canon.edge_count(), 3);
+        let loop_wts = Vec::from_iter(
+            canon
+                .edge_references()
+                .filter(|e| e.source() == e.target())
+                .map(|e| *e.weight()),
+        );
+        assert_eq!(loop_wts, [0, 0]);
+        // the order in which loops are added does not matter
+        let g =
+            DiGraph::<(), u8>::from_edges([(0, 0, 1), (0, 0, 0), (0, 1, 0)]);
+        let h =
+            DiGraph::<(), u8>::from_edges([(0, 0, 0), (0, 0, 1), (0, 1, 0)]);
+        assert!(g.into_canon().is_identical(&h.into_canon()));
+
+        // a loop distinguishes otherwise equivalent vertices
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0), (0, 0)]);
+        assert_eq!(g.try_into_autom().unwrap().grpsize(), 2.);
+    }
+
+    // copy of `g` with a hole at node index 0
+    fn with_hole<Ty: EdgeType>(
+        g: &Graph<u8, u8, Ty>,
+    ) -> StableGraph<u8, u8, Ty> {
+        let mut stable = StableGraph::default();
+        let hole = stable.add_node(0);
+        for &w in g.node_weights() {
+            stable.add_node(w);
+        }
+        for e in g.edge_references() {
+            let source = NodeIndex::new(e.source().index() + 1);
+            let target = NodeIndex::new(e.target().index() + 1);
+            stable.add_edge(source, target, *e.weight());
+        }
+        stable.remove_node(hole);
+        stable
+    }
+
+    #[test]
+    fn stable_graph() {
+        log_init();
+
+        for g in GraphIter::<Directed>::default().take(100) {
+            let stable = with_hole(&g);
+            assert!(stable.node_weight(NodeIndex::new(0)).is_none());
+            let canon = Graph::from(stable.into_canon());
+            assert!(canon.is_identical(&g.into_canon()));
+        }
+        let g = StableGraph::<(), ()>::default();
+        assert_eq!(g.into_canon().node_count(), 0);
+    }
+
     #[test]
     fn asym() {
         log_init();
@@ -426,4 +1615,28 @@ mod tests {
         let g = Graph::<(), (), _>::new_undirected();
         assert!(g.is_identical(&g.clone().into_canon()));
     }
+
+    #[test]
+    fn labelling() {
+        log_init();
+
+        use petgraph::visit::{EdgeRef, NodeIndexable};
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let graphs = GraphIter::<Undirected>::default();
+        for g in graphs.take(200) {
+            let g = randomize_labels(g, &mut rng);
+            let (canon, labelling) =
+                try_canon_with_labelling(g.clone()).unwrap();
+            assert!(canon.is_identical(&g.clone().into_canon()));
+            for v in g.node_indices() {
+                let w = canon.from_index(labelling[v.index()]);
+                assert_eq!(g[v], canon[w]);
+            }
+            for e in g.edge_references() {
+                let source = canon.from_index(labelling[e.source().index()]);
+                let target = canon.from_index(labelling[e.target().index()]);
+                assert!(canon.contains_edge(source, target));
+            }
+        }
+    }
 }
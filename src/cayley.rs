@@ -0,0 +1,383 @@
+//! Cayley graphs of permutation groups
+//!
+//! The Cayley graph of a group with respect to a set of generators
+//! has one vertex for each group element `g` and an arc from `g` to
+//! `g * s` for each generator `s`. Left multiplication by group
+//! elements gives automorphisms, so Cayley graphs are always vertex
+//! transitive. If the group elements are the only automorphisms of
+//! the underlying undirected graph, the graph is a graphical regular
+//! representation of the group.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nauty_pet::cayley::{cayley_graph, is_graphical_regular_representation, is_vertex_transitive};
+//! use nauty_pet::perm::Permutation;
+//!
+//! // cyclic group of order five, generated by a rotation
+//! let rotation = Permutation::new(vec![1, 2, 3, 4, 0]);
+//! let g = cayley_graph(&[rotation.clone()]);
+//! assert_eq!(g.node_count(), 5);
+//! // node weights are group elements and would break the symmetry
+//! assert!(is_vertex_transitive(&g.map(|_, _| (), |_, &s| s)));
+//!
+//! // the underlying undirected five-cycle has reflections as
+//! // additional symmetries
+//! assert!(!is_graphical_regular_representation(&[rotation]));
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::autom::{autom_generators, vertex_orbits};
+use crate::cancel;
+use crate::group::AutomorphismGroup;
+use crate::perm::Permutation;
+
+use petgraph::{
+    graph::{DiGraph, Graph, IndexType, NodeIndex, UnGraph},
+    EdgeType,
+};
+
+/// The Cayley graph of the group generated by `generators`
+///
+/// Vertices are group elements, with the identity first, and edge
+/// weights are generator indices: there is an arc from `g` to `g * s`
+/// with weight `i` for each group element `g` and each generator
+/// `s = generators[i]`. The number of vertices is the order of the
+/// group.
+///
+/// # Panics
+///
+/// Panics if the generators have different degrees.
+pub fn cayley_graph(generators: &[Permutation]) -> DiGraph<Permutation, usize> {
+    let degree = generators.first().map(|s| s.degree()).unwrap_or_default();
+    let mut g = DiGraph::new();
+    let mut idx = HashMap::new();
+    let identity = Permutation::identity(degree);
+    idx.insert(identity.clone(), g.add_node(identity));
+    let mut next = 0;
+    while next < g.node_count() {
+        let from = NodeIndex::new(next);
+        for (i, s) in generators.iter().enumerate() {
+            let element = &g[from] * s;
+            let to = *idx
+                .entry(element)
+                .or_insert_with_key(|element| g.add_node(element.clone()));
+            g.add_edge(from, to, i);
+        }
+        next += 1;
+    }
+    g
+}
+
+/// Check whether the automorphism group acts transitively on the
+/// vertices
+///
+/// Node weights are taken into account, so only vertices with equal
+/// weights can be in the same orbit. The empty graph is not considered
+/// vertex transitive.
+pub fn is_vertex_transitive<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> bool
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    if g.node_count() == 0 {
+        return false;
+    }
+    let reps = cancel::unwrap(vertex_orbits(g.clone()));
+    reps.iter().all(|&rep| rep == 0)
+}
+
+/// The left regular representation of the group generated by
+/// `generators`
+///
+/// The `i`th permutation describes left multiplication by
+/// `generators[i]` acting on the vertices of the
+/// [cayley_graph]. These permutations are automorphisms of the
+/// Cayley graph.
+///
+/// # Panics
+///
+/// Panics if the generators have different degrees.
+pub fn regular_representation(generators: &[Permutation]) -> Vec<Permutation> {
+    let g = cayley_graph(generators);
+    let idx = HashMap::<_, _>::from_iter(
+        g.node_indices().map(|v| (g[v].clone(), v.index())),
+    );
+    Vec::from_iter(generators.iter().map(|s| {
+        Permutation::new(Vec::from_iter(
+            g.node_weights().map(|element| idx[&(s * element)]),
+        ))
+    }))
+}
+
+/// Check whether the underlying undirected graph of the Cayley graph
+/// is a graphical regular representation
+///
+/// This is the case if the group elements, acting by left
+/// multiplication, are the only automorphisms of the undirected graph
+/// obtained by forgetting arc directions and generator labels.
+///
+/// # Panics
+///
+/// Panics if the generators have different degrees.
+pub fn is_graphical_regular_representation(generators: &[Permutation]) -> bool {
+    let cayley = cayley_graph(generators);
+    let mut g = UnGraph::<u8, ()>::with_capacity(cayley.node_count(), 0);
+    for _ in cayley.node_indices() {
+        g.add_node(0);
+    }
+    for e in cayley.edge_indices() {
+        let (v, w) = cayley.edge_endpoints(e).unwrap();
+        if v != w {
+            g.update_edge(v, w, ());
+        }
+    }
+    // the group acts regularly, so it is the full automorphism group
+    // exactly if the stabiliser of a vertex is trivial
+    g[NodeIndex::new(0)] = 1;
+    autom_generators(g).0.is_empty()
+}
+
+/// A graph as the Cayley graph of a group of automorphisms
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CayleyRepresentation {
+    /// The elements of a regular subgroup of the automorphism group,
+    /// where `elements[v]` is the unique element mapping vertex `v`
+    /// to vertex 0
+    pub elements: Vec<Permutation>,
+    /// The sorted neighbours of vertex 0
+    ///
+    /// Identifying each vertex `v` with `elements[v]`, there is an
+    /// arc from `x` to `x * elements[s]` for each `s` in the
+    /// connection set, as in [cayley_graph].
+    pub connection_set: Vec<usize>,
+}
+
+/// Represent `g` as a Cayley graph, if possible
+///
+/// A graph is a Cayley graph if and only if its automorphism group
+/// has a subgroup acting regularly on the vertices. The search
+/// enumerates all automorphisms and is therefore only feasible for
+/// small graphs. Node and edge weights have to be preserved by the
+/// group elements. The empty graph is not considered a Cayley graph.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::cayley::cayley_representation;
+///
+/// // the four-cycle is the Cayley graph of the cyclic group of order
+/// // four and of the Klein four-group
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+/// let cayley = cayley_representation(&g).unwrap();
+/// assert_eq!(cayley.elements.len(), 4);
+/// assert_eq!(cayley.connection_set, [1, 3]);
+/// ```
+pub fn cayley_representation<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Option<CayleyRepresentation>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    if !is_vertex_transitive(g) {
+        return None;
+    }
+    let n = g.node_count();
+    // all elements of a regular subgroup except for the identity are
+    // fixed-point free
+    let mut candidates = vec![Vec::new(); n];
+    for perm in AutomorphismGroup::from(g.clone()).elements() {
+        if (0..n).all(|v| perm.image(v) != v) {
+            candidates[perm.image(0)].push(perm);
+        }
+    }
+    let subgroup = extend_semiregular(Vec::new(), &candidates)?;
+    let mut elements = vec![Permutation::default(); n];
+    for perm in subgroup {
+        elements[perm.inverse().image(0)] = perm;
+    }
+    let mut connection_set =
+        Vec::from_iter(g.neighbors(NodeIndex::new(0)).map(|v| v.index()));
+    connection_set.sort_unstable();
+    connection_set.dedup();
+    Some(CayleyRepresentation {
+        elements,
+        connection_set,
+    })
+}
+
+/// Check whether `g` is a Cayley graph
+///
+/// See [cayley_representation].
+pub fn is_cayley_graph<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> bool
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    cayley_representation(g).is_some()
+}
+
+// extend the semiregular group generated by `generators` to a regular
+// group, choosing additional generators from `candidates[v]`, the
+// fixed-point-free elements mapping vertex 0 to `v`
+fn extend_semiregular(
+    generators: Vec<Permutation>,
+    candidates: &[Vec<Permutation>],
+) -> Option<Vec<Permutation>> {
+    let n = candidates.len();
+    let group = semiregular_closure(&generators, n)?;
+    if group.len() == n {
+        return Some(group);
+    }
+    // a regular supergroup contains exactly one element mapping 0 to
+    // each vertex outside the current orbit
+    let orbit = HashSet::<_>::from_iter(group.iter().map(|g| g.image(0)));
+    let v = (0..n).find(|v| !orbit.contains(v)).unwrap();
+    candidates[v].iter().find_map(|g| {
+        let mut generators = generators.clone();
+        generators.push(g.clone());
+        extend_semiregular(generators, candidates)
+    })
+}
+
+// all elements of the group generated by `generators`, or `None` if
+// it does not act semiregularly on `0..n`
+fn semiregular_closure(
+    generators: &[Permutation],
+    n: usize,
+) -> Option<Vec<Permutation>> {
+    let identity = Permutation::identity(n);
+    let mut elements = vec![identity.clone()];
+    let mut seen = HashSet::from([identity]);
+    let mut next = 0;
+    while next < elements.len() {
+        for s in generators {
+            let element = &elements[next] * s;
+            if seen.contains(&element) {
+                continue;
+            }
+            // a semiregular group has at most `n` elements, and only
+            // the identity has fixed points
+            if elements.len() == n || (0..n).any(|v| element.image(v) == v) {
+                return None;
+            }
+            seen.insert(element.clone());
+            elements.push(element);
+        }
+        next += 1;
+    }
+    Some(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn transposition(n: usize, i: usize, j: usize) -> Permutation {
+        let mut images = Vec::from_iter(0..n);
+        images.swap(i, j);
+        Permutation::new(images)
+    }
+
+    #[test]
+    fn symmetric_group() {
+        log_init();
+
+        // S4 generated by adjacent transpositions
+        let generators =
+            Vec::from_iter((0..3).map(|i| transposition(4, i, i + 1)));
+        let g = cayley_graph(&generators);
+        assert_eq!(g.node_count(), 24);
+        assert_eq!(g.edge_count(), 72);
+        assert!(g[NodeIndex::new(0)].is_identity());
+        assert!(!is_vertex_transitive(&g));
+        assert!(is_vertex_transitive(&g.map(|_, _| (), |_, &s| s)));
+
+        // left multiplications are automorphisms
+        for perm in regular_representation(&generators) {
+            for e in g.edge_indices() {
+                let (v, w) = g.edge_endpoints(e).unwrap();
+                let v = NodeIndex::new(perm.image(v.index()));
+                let w = NodeIndex::new(perm.image(w.index()));
+                let image = g.find_edge(v, w).unwrap();
+                assert_eq!(g[image], g[e]);
+            }
+        }
+    }
+
+    #[test]
+    fn recognition() {
+        log_init();
+
+        // the cube is the Cayley graph of Z_2^3
+        let cube = UnGraph::<(), ()>::from_edges(
+            (0..8u32)
+                .flat_map(|v| [1, 2, 4].map(|b| (v, v ^ b)))
+                .filter(|(v, w)| v < w),
+        );
+        let cayley = cayley_representation(&cube).unwrap();
+        assert_eq!(cayley.connection_set, [1, 2, 4]);
+        let CayleyRepresentation {
+            elements,
+            connection_set,
+        } = cayley;
+        for (u, x) in elements.iter().enumerate() {
+            assert_eq!(x.image(u), 0);
+            for w in 0..8 {
+                let adjacent = connection_set
+                    .iter()
+                    .any(|&s| x * &elements[s] == elements[w]);
+                let expected =
+                    cube.contains_edge(NodeIndex::new(u), NodeIndex::new(w));
+                assert_eq!(adjacent, expected);
+            }
+        }
+
+        // the Petersen graph is vertex transitive, but not a Cayley
+        // graph
+        #[rustfmt::skip]
+        let petersen = UnGraph::<(), ()>::from_edges([
+            (0, 1), (1, 2), (2, 3), (3, 4), (4, 0),
+            (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+            (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+        ]);
+        assert!(is_vertex_transitive(&petersen));
+        assert!(!is_cayley_graph(&petersen));
+
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        assert!(!is_cayley_graph(&path));
+        assert!(!is_cayley_graph(&UnGraph::<(), ()>::default()));
+        let directed = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        assert!(is_cayley_graph(&directed));
+    }
+
+    #[test]
+    fn grr() {
+        log_init();
+
+        // groups of order at least three that are abelian of exponent
+        // greater than two never admit a GRR
+        let rotation = Permutation::new(vec![1, 2, 3, 4, 5, 6, 0]);
+        assert!(!is_graphical_regular_representation(&[rotation]));
+        let trivial = Permutation::identity(3);
+        assert!(is_graphical_regular_representation(&[trivial]));
+        assert!(!is_vertex_transitive(&UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2)
+        ])));
+    }
+}
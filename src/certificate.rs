@@ -0,0 +1,358 @@
+//! Stable byte certificates of canonical forms
+//!
+//! A certificate is a byte string encoding the canonical form of a
+//! graph, including its node and edge weights. Two graphs have the
+//! same certificate if and only if they are isomorphic. In contrast
+//! to a [canonical hash](crate::hashing), there are no collisions.
+//! Certificates can be persisted, for example in a database or in
+//! the certificate store enabled by the `cert-store` feature.
+//!
+//! # Format
+//!
+//! All integers are stored in little-endian byte order. A certificate
+//! consists of
+//!
+//! 1. the four bytes `NPCF`,
+//! 2. the format version as a single byte, currently
+//!    [FORMAT_VERSION],
+//! 3. a single byte, which is 1 for directed and 0 for undirected
+//!    graphs,
+//! 4. the number of nodes and the number of edges, each as a `u64`,
+//! 5. the [encoded](Encode) weights of the nodes in canonical order,
+//! 6. for each edge in canonical order, the source and target
+//!    indices as `u64` followed by the encoded edge weight.
+//!
+//! In the canonical order, edges are sorted by source, target, and
+//! weight. For undirected graphs, the source is the endpoint with the
+//! smaller index.
+//!
+//! # Stability
+//!
+//! Certificates do not depend on the platform. Any change to the
+//! format or to the canonical labelling computed by this crate
+//! increments the format version, so certificates from different
+//! versions never agree by accident. Certificates with the same
+//! format version can be compared across crate versions.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::certificate::canonical_certificate;
+//!
+//! let g = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2)]);
+//! let h = UnGraph::<u8, ()>::from_edges([(2, 0), (1, 2)]);
+//! assert_eq!(canonical_certificate(&g), canonical_certificate(&h));
+//! assert_eq!(&canonical_certificate(&g)[..5], b"NPCF\x01");
+//! ```
+use std::hash::Hash;
+
+use crate::cancel;
+use crate::canon::canon_dense_graph;
+use crate::graph::CanonGraph;
+use crate::nauty_graph::borrowed;
+
+use petgraph::{
+    graph::{Graph, IndexType},
+    visit::EdgeRef,
+    EdgeType,
+};
+
+/// Version of the certificate format
+pub const FORMAT_VERSION: u8 = 1;
+
+const MAGIC: &[u8; 4] = b"NPCF";
+
+/// Platform-independent encoding of node and edge weights
+///
+/// The encoding must be injective, i.e. different values must have
+/// different encodings, and no encoding may be a proper prefix of
+/// another one of the same type. Values that compare equal must have
+/// the same encoding. The implementations in this crate
+/// use the following encodings:
+///
+/// * Integers: fixed width in little-endian byte order, with `usize`
+///   and `isize` widened to 64 bits.
+/// * `bool`: a single byte, 0 or 1.
+/// * `char`: the code point as a `u32`.
+/// * `()`: no bytes.
+/// * Strings: the length in bytes as a `u64`, followed by the UTF-8
+///   bytes.
+/// * `Option`: a single byte, 0 for `None` and 1 for `Some`, followed
+///   by the encoded value, if any.
+/// * Slices and `Vec`: the length as a `u64`, followed by the encoded
+///   elements.
+/// * Arrays and tuples: the encoded elements.
+pub trait Encode {
+    /// Append the encoding of `self` to `out`
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+macro_rules! impl_encode_int {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl Encode for $t {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes())
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Encode for usize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u64).encode(out)
+    }
+}
+
+impl Encode for isize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as i64).encode(out)
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push((*self).into())
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, out: &mut Vec<u8>) {
+        u32::from(*self).encode(out)
+    }
+}
+
+impl Encode for () {
+    fn encode(&self, _out: &mut Vec<u8>) {}
+}
+
+impl Encode for str {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        out.extend_from_slice(self.as_bytes())
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_str().encode(out)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            None => out.push(0),
+            Some(t) => {
+                out.push(1);
+                t.encode(out)
+            }
+        }
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        for t in self {
+            t.encode(out)
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode(out)
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        for t in self {
+            t.encode(out)
+        }
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (**self).encode(out)
+    }
+}
+
+macro_rules! impl_encode_tuple {
+    ( $( ( $( $t:ident $i:tt ),+ ) ),* ) => {
+        $(
+            impl<$( $t: Encode ),+> Encode for ( $( $t, )+ ) {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    $( self.$i.encode(out); )+
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_tuple!(
+    (A 0),
+    (A 0, B 1),
+    (A 0, B 1, C 2),
+    (A 0, B 1, C 2, D 3)
+);
+
+/// The certificate of the canonical form of `g`
+///
+/// See the [module documentation](self) for the format.
+pub fn canonical_certificate<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> Vec<u8>
+where
+    N: Encode + Ord,
+    E: Encode + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let (dg, _) = cancel::unwrap(canon_dense_graph(borrowed(g)));
+    let (nodes, edges) = dg.canon_parts();
+    write_certificate(Ty::is_directed(), &nodes, &edges)
+}
+
+/// The certificate of a graph that is already in canonical form
+///
+/// The result is the same as for [canonical_certificate] applied to
+/// any graph isomorphic to `g`.
+pub fn certificate<N, E, Ty, Ix>(g: &CanonGraph<N, E, Ty, Ix>) -> Vec<u8>
+where
+    N: Encode,
+    E: Encode,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let nodes = Vec::from_iter(g.node_weights());
+    let edges = Vec::from_iter(
+        g.edge_references()
+            .map(|e| (e.source().index(), e.target().index(), e.weight())),
+    );
+    write_certificate(g.is_directed(), &nodes, &edges)
+}
+
+fn write_certificate<N: Encode, E: Encode>(
+    is_directed: bool,
+    nodes: &[N],
+    edges: &[(usize, usize, E)],
+) -> Vec<u8> {
+    let mut out = Vec::from(*MAGIC);
+    out.push(FORMAT_VERSION);
+    is_directed.encode(&mut out);
+    nodes.len().encode(&mut out);
+    edges.len().encode(&mut out);
+    for w in nodes {
+        w.encode(&mut out);
+    }
+    for (source, target, w) in edges {
+        source.encode(&mut out);
+        target.encode(&mut out);
+        w.encode(&mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::{DiGraph, NodeIndex, UnGraph};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn encoded<T: Encode>(t: T) -> Vec<u8> {
+        let mut out = Vec::new();
+        t.encode(&mut out);
+        out
+    }
+
+    #[test]
+    fn encode() {
+        log_init();
+
+        assert_eq!(encoded(0x0102u16), [2, 1]);
+        assert_eq!(encoded(-2i8), [0xfe]);
+        assert_eq!(encoded(3usize), encoded(3u64));
+        assert_eq!(encoded('a'), [0x61, 0, 0, 0]);
+        assert_eq!(encoded(Some(true)), [1, 1]);
+        assert_eq!(encoded(None::<bool>), [0]);
+        assert_eq!(encoded("ab"), [2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b']);
+        assert_eq!(encoded([1u8, 2]), [1, 2]);
+        assert_eq!(encoded(vec![1u8, 2])[8..], [1, 2]);
+        // no ambiguity for variable-length elements
+        assert_ne!(encoded(("a", "bc")), encoded(("ab", "c")));
+    }
+
+    #[test]
+    fn portable() {
+        log_init();
+
+        let g = UnGraph::<u32, ()>::from_edges([(0, 1)]);
+        #[rustfmt::skip]
+        let expected = [
+            b'N', b'P', b'C', b'F', 1, 0,
+            2, 0, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        assert_eq!(canonical_certificate(&g), expected);
+        let d = DiGraph::<(), ()>::default();
+        let mut expected = b"NPCF\x01\x01".to_vec();
+        expected.extend([0; 16]);
+        assert_eq!(canonical_certificate(&d), expected);
+    }
+
+    #[test]
+    fn invariance() {
+        log_init();
+
+        let g = UnGraph::<String, u8>::from_edges([
+            (0, 1, 1),
+            (1, 2, 0),
+            (2, 3, 1),
+            (3, 3, 2),
+            (0, 1, 0),
+        ]);
+        let mut h = UnGraph::<String, u8>::from_edges([
+            (3, 1, 1),
+            (1, 0, 0),
+            (0, 2, 1),
+            (2, 2, 2),
+            (1, 3, 0),
+        ]);
+        assert_eq!(canonical_certificate(&g), canonical_certificate(&h));
+        assert_eq!(
+            canonical_certificate(&g),
+            certificate(&CanonGraph::from(h.clone()))
+        );
+
+        h[NodeIndex::new(0)] = "C".to_owned();
+        assert_ne!(canonical_certificate(&g), canonical_certificate(&h));
+        assert_eq!(
+            canonical_certificate(&h),
+            certificate(&CanonGraph::from(h.clone()))
+        );
+        let d = DiGraph::<String, u8>::from_edges([
+            (0, 1, 1),
+            (1, 2, 0),
+            (2, 3, 1),
+            (3, 3, 2),
+            (0, 1, 0),
+        ]);
+        assert_ne!(canonical_certificate(&g), canonical_certificate(&d));
+        assert_eq!(
+            canonical_certificate(&d),
+            certificate(&CanonGraph::from(d.clone()))
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Canonical numbering of molecular graphs
+//!
+//! Molecules are represented as undirected graphs with [Atom] node
+//! weights and [Bond] edge weights. Atom types, charges, isotopes,
+//! and hydrogen counts become vertex colours, and bond orders become
+//! edge colours, so that the canonical numbering respects all of
+//! them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nauty_pet::chem::{canonical_ranks, symmetry_classes, Atom, Bond, Molecule};
+//!
+//! // ethanol without explicit hydrogens, written in two different
+//! // atom orders
+//! let mut ethanol = Molecule::default();
+//! let c1 = ethanol.add_node(Atom::new(6).with_hydrogens(3));
+//! let c2 = ethanol.add_node(Atom::new(6).with_hydrogens(2));
+//! let o = ethanol.add_node(Atom::new(8).with_hydrogens(1));
+//! ethanol.add_edge(c1, c2, Bond::Single);
+//! ethanol.add_edge(c2, o, Bond::Single);
+//!
+//! let mut reversed = Molecule::default();
+//! let o = reversed.add_node(Atom::new(8).with_hydrogens(1));
+//! let c2 = reversed.add_node(Atom::new(6).with_hydrogens(2));
+//! let c1 = reversed.add_node(Atom::new(6).with_hydrogens(3));
+//! reversed.add_edge(o, c2, Bond::Single);
+//! reversed.add_edge(c2, c1, Bond::Single);
+//!
+//! let ranks = canonical_ranks(&ethanol);
+//! let reversed_ranks = canonical_ranks(&reversed);
+//! assert_eq!(ranks, [reversed_ranks[2], reversed_ranks[1], reversed_ranks[0]]);
+//!
+//! // the two carbon atoms in ethane are equivalent
+//! let mut ethane = Molecule::default();
+//! let c1 = ethane.add_node(Atom::new(6).with_hydrogens(3));
+//! let c2 = ethane.add_node(Atom::new(6).with_hydrogens(3));
+//! ethane.add_edge(c1, c2, Bond::Single);
+//! assert_eq!(symmetry_classes(&ethane), [0, 0]);
+//! ```
+use std::hash::Hash;
+
+use crate::autom::vertex_orbits;
+use crate::cancel;
+use crate::canon::try_canon_with_labelling;
+use crate::graph::CanonGraph;
+
+use petgraph::graph::{NodeIndex, UnGraph};
+
+/// Molecular graph
+pub type Molecule = UnGraph<Atom, Bond>;
+
+/// An atom in a [Molecule]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Atom {
+    /// The atomic number, i.e. the element
+    pub atomic_number: u8,
+    /// Formal charge
+    pub charge: i8,
+    /// Mass number, or 0 for the natural isotope distribution
+    pub isotope: u16,
+    /// Number of implicit hydrogen atoms
+    pub hydrogens: u8,
+}
+
+impl Atom {
+    /// Neutral atom of the element with the given atomic number
+    pub fn new(atomic_number: u8) -> Self {
+        Self {
+            atomic_number,
+            ..Default::default()
+        }
+    }
+
+    /// Set the formal charge
+    pub fn with_charge(mut self, charge: i8) -> Self {
+        self.charge = charge;
+        self
+    }
+
+    /// Set the mass number
+    pub fn with_isotope(mut self, isotope: u16) -> Self {
+        self.isotope = isotope;
+        self
+    }
+
+    /// Set the number of implicit hydrogen atoms
+    pub fn with_hydrogens(mut self, hydrogens: u8) -> Self {
+        self.hydrogens = hydrogens;
+        self
+    }
+}
+
+/// A bond in a [Molecule]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bond {
+    #[default]
+    Single,
+    Double,
+    Triple,
+    Aromatic,
+}
+
+/// Canonical rank of each atom
+///
+/// The `i`th entry is the position of the `i`th atom in the
+/// canonical numbering. Two molecules are identical if and only if
+/// renumbering their atoms according to their canonical ranks
+/// results in the same graph. Note that the numbering is only
+/// canonical for a fixed version of nauty.
+///
+/// This works for any molecule representation with ordered atom and
+/// bond types, not just [Molecule].
+pub fn canonical_ranks<N, E>(mol: &UnGraph<N, E>) -> Vec<usize>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+{
+    cancel::unwrap(try_canon_with_labelling(mol.clone())).1
+}
+
+/// Atoms in canonical order
+///
+/// This is the inverse of [canonical_ranks].
+pub fn canonical_order<N, E>(mol: &UnGraph<N, E>) -> Vec<NodeIndex>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+{
+    let mut order = vec![NodeIndex::end(); mol.node_count()];
+    for (atom, rank) in canonical_ranks(mol).into_iter().enumerate() {
+        order[rank] = NodeIndex::new(atom);
+    }
+    order
+}
+
+/// Canonical form of a molecule
+///
+/// The `i`th atom of the canonical form is the atom with canonical
+/// rank `i`, see [canonical_ranks].
+pub fn canonical_molecule<N, E>(
+    mol: &UnGraph<N, E>,
+) -> CanonGraph<N, E, petgraph::Undirected>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+{
+    mol.clone().into()
+}
+
+/// Symmetry classes of the atoms
+///
+/// Atoms are in the same class if they can be mapped onto each other
+/// by a symmetry of the molecule. Each atom is mapped to the
+/// smallest atom index in its class.
+pub fn symmetry_classes<N, E>(mol: &UnGraph<N, E>) -> Vec<usize>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+{
+    cancel::unwrap(vertex_orbits(mol.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // benzene with explicit alternating single and double bonds,
+    // starting at the given atom
+    fn kekule_benzene(start: usize) -> Molecule {
+        let mut mol = Molecule::default();
+        for _ in 0..6 {
+            mol.add_node(Atom::new(6).with_hydrogens(1));
+        }
+        for i in 0..6 {
+            let bond = if i % 2 == 0 {
+                Bond::Double
+            } else {
+                Bond::Single
+            };
+            let source = NodeIndex::new((start + i) % 6);
+            let target = NodeIndex::new((start + i + 1) % 6);
+            mol.add_edge(source, target, bond);
+        }
+        mol
+    }
+
+    #[test]
+    fn benzene() {
+        log_init();
+
+        let mol1 = kekule_benzene(0);
+        let mol2 = kekule_benzene(3);
+        assert_eq!(canonical_molecule(&mol1), canonical_molecule(&mol2));
+        assert_eq!(symmetry_classes(&mol1), [0; 6]);
+
+        let order = canonical_order(&mol1);
+        let ranks = canonical_ranks(&mol1);
+        let canon = canonical_molecule(&mol1);
+        for (rank, atom) in order.into_iter().enumerate() {
+            assert_eq!(ranks[atom.index()], rank);
+            assert_eq!(canon[NodeIndex::new(rank)], mol1[atom]);
+        }
+        for e in mol1.edge_indices() {
+            let (source, target) = mol1.edge_endpoints(e).unwrap();
+            let source = NodeIndex::new(ranks[source.index()]);
+            let target = NodeIndex::new(ranks[target.index()]);
+            let e_canon = canon.find_edge(source, target).unwrap();
+            assert_eq!(canon[e_canon], mol1[e]);
+        }
+    }
+
+    #[test]
+    fn charges_and_isotopes() {
+        log_init();
+
+        // hydroxide vs. water vs. heavy water
+        let mut water = Molecule::default();
+        let o = water.add_node(Atom::new(8));
+        let h1 = water.add_node(Atom::new(1));
+        let h2 = water.add_node(Atom::new(1));
+        water.add_edge(o, h1, Bond::Single);
+        water.add_edge(o, h2, Bond::Single);
+        assert_eq!(symmetry_classes(&water), [0, 1, 1]);
+
+        let mut hydroxide = water.clone();
+        hydroxide.remove_node(h2);
+        hydroxide[o] = hydroxide[o].with_charge(-1);
+
+        let mut heavy_water = water.clone();
+        heavy_water[h1] = heavy_water[h1].with_isotope(2);
+        assert_eq!(symmetry_classes(&heavy_water), [0, 1, 2]);
+
+        let canon =
+            [water, hydroxide, heavy_water].map(|m| canonical_molecule(&m));
+        assert_ne!(canon[0], canon[1]);
+        assert_ne!(canon[0], canon[2]);
+        assert_ne!(canon[1], canon[2]);
+    }
+}
@@ -0,0 +1,170 @@
+//! Canonical forms of logic circuits
+//!
+//! A circuit is a directed acyclic graph where each node is a gate,
+//! with the gate type as node weight, and each edge connects the
+//! output of a gate to an input port of another gate, with the port
+//! number as edge weight. Like all edge weights, port numbers are
+//! encoded by auxiliary vertices during canonicalisation, so the
+//! canonical form preserves which signal is connected to which input.
+//!
+//! For gates with interchangeable inputs, such as AND or OR gates,
+//! all input edges should have the same port number.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nauty_pet::circuit::{canonical_circuit, Circuit};
+//!
+//! #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+//! enum Gate {
+//!     Input,
+//!     And,
+//!     // output = if port 0 { port 1 } else { port 2 }
+//!     Mux,
+//! }
+//!
+//! let mut mux = Circuit::new();
+//! let a = mux.add_node(Gate::Input);
+//! let b = mux.add_node(Gate::Input);
+//! let c = mux.add_node(Gate::Input);
+//! let m = mux.add_node(Gate::Mux);
+//! mux.add_edge(a, m, 0);
+//! mux.add_edge(b, m, 1);
+//! mux.add_edge(c, m, 2);
+//!
+//! // the same circuit with different node order
+//! let mut same = Circuit::new();
+//! let m = same.add_node(Gate::Mux);
+//! let c = same.add_node(Gate::Input);
+//! let b = same.add_node(Gate::Input);
+//! let a = same.add_node(Gate::Input);
+//! same.add_edge(b, m, 1);
+//! same.add_edge(a, m, 0);
+//! same.add_edge(c, m, 2);
+//!
+//! let mux = canonical_circuit(&mux).unwrap();
+//! assert_eq!(mux.circuit, canonical_circuit(&same).unwrap().circuit);
+//! ```
+use crate::cancel;
+use crate::canon::try_canon_with_labelling;
+use crate::error::CycleError;
+use crate::graph::CanonGraph;
+
+use petgraph::{algo::toposort, graph::DiGraph, Directed};
+
+/// A logic circuit with gates of type `G`
+///
+/// Edge weights are the input port numbers at the target gate.
+pub type Circuit<G> = DiGraph<G, u32>;
+
+/// Canonical form of a [Circuit]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CanonCircuit<G> {
+    /// Canonically labelled circuit
+    pub circuit: CanonGraph<G, u32, Directed>,
+    /// Canonical labelling
+    ///
+    /// The `i`th gate of the original circuit corresponds to the gate
+    /// with index `labelling[i]` in `circuit`.
+    pub labelling: Vec<usize>,
+}
+
+/// Canonical form of a logic circuit
+///
+/// Two circuits have the same canonical form if and only if they are
+/// related by a relabelling of the gates that preserves gate types
+/// and the port numbers of all connections. The canonical circuit is
+/// the same as the one obtained with [CanonGraph::from].
+///
+/// # Errors
+///
+/// Fails if the circuit contains a cycle.
+pub fn canonical_circuit<G>(
+    circuit: &Circuit<G>,
+) -> Result<CanonCircuit<G>, CycleError>
+where
+    G: Clone + Ord,
+{
+    if let Err(cycle) = toposort(circuit, None) {
+        return Err(CycleError {
+            node: cycle.node_id().index(),
+        });
+    }
+    let res = try_canon_with_labelling(circuit.clone());
+    let (canon, labelling) = cancel::unwrap(res);
+    Ok(CanonCircuit {
+        circuit: CanonGraph::from_canon_unchecked(canon),
+        labelling,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::NodeIndex;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    enum Gate {
+        Input,
+        Not,
+        And,
+        // output = port 0 && !port 1
+        AndNot,
+    }
+
+    // a && !b, with the given port numbers for a and b
+    fn and_not(ports: [u32; 2]) -> Circuit<Gate> {
+        let mut c = Circuit::new();
+        let a = c.add_node(Gate::Input);
+        let b = c.add_node(Gate::Input);
+        let g = c.add_node(Gate::AndNot);
+        c.add_edge(a, g, ports[0]);
+        c.add_edge(b, g, ports[1]);
+        c
+    }
+
+    #[test]
+    fn port_order() {
+        log_init();
+
+        let c = canonical_circuit(&and_not([0, 1])).unwrap();
+        let swapped = canonical_circuit(&and_not([1, 0])).unwrap();
+        // swapping inputs gives the same circuit up to input order
+        assert_eq!(c.circuit, swapped.circuit);
+        assert_eq!(c.circuit, CanonGraph::from(and_not([0, 1])));
+
+        // with a NOT gate in front of one input, port order matters
+        let negated = |not_port: u32| {
+            let mut c = Circuit::new();
+            let x = c.add_node(Gate::Input);
+            let y = c.add_node(Gate::Input);
+            let not = c.add_node(Gate::Not);
+            let g = c.add_node(Gate::AndNot);
+            c.add_edge(x, not, 0);
+            c.add_edge(not, g, not_port);
+            c.add_edge(y, g, 1 - not_port);
+            c
+        };
+        let c = canonical_circuit(&negated(0)).unwrap();
+        let d = canonical_circuit(&negated(1)).unwrap();
+        assert_ne!(c.circuit, d.circuit);
+        assert_eq!(c.circuit[NodeIndex::new(c.labelling[3])], Gate::AndNot);
+    }
+
+    #[test]
+    fn cycle() {
+        log_init();
+
+        let mut c = Circuit::new();
+        let a = c.add_node(Gate::And);
+        let b = c.add_node(Gate::Not);
+        c.add_edge(a, b, 0);
+        c.add_edge(b, a, 0);
+        assert!(canonical_circuit(&c).is_err());
+    }
+}
@@ -10,6 +10,7 @@ impl<N: PartialEq, E: PartialEq, Ty: EdgeType, Ix: IndexType> IsIdentical
 {
     fn is_identical(&self, other: &Self) -> bool {
         self.node_weights().eq(other.node_weights())
+            && self.edge_count() == other.edge_count()
             && self.edge_references().zip(other.edge_references()).all(
                 |(e1, e2)| {
                     e1.source() == e2.source()
@@ -32,6 +33,9 @@ mod tests {
         assert!(g1.is_identical(&g1));
         assert!(g2.is_identical(&g2));
         assert!(!g1.is_identical(&g2));
+        let g3 = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 2)]);
+        assert!(!g1.is_identical(&g3));
+        assert!(!g3.is_identical(&g1));
     }
 
     #[test]
@@ -0,0 +1,189 @@
+//! Colours combined from several attributes
+//!
+//! Nodes and edges are distinguished by their weights. When several
+//! attributes should be taken into account, a [ColorEncoder] packs
+//! them into a single integer colour without collisions. The colours
+//! are ordered lexicographically by the attributes, with the first
+//! attribute being the most significant, and can be decoded again.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::color::ColorEncoder;
+//! use nauty_pet::prelude::*;
+//!
+//! // atoms with element, charge, and isotope
+//! // element: atomic number up to 118
+//! // charge: -4 to 4, stored with an offset of 4
+//! // isotope: mass number up to 300
+//! let encoder = ColorEncoder::new([119, 9, 301]).unwrap();
+//!
+//! let mut g = UnGraph::<(u8, i8, u16), ()>::default();
+//! let c = g.add_node((6, 0, 0));
+//! let o = g.add_node((8, -1, 0));
+//! g.add_edge(c, o, ());
+//!
+//! let g = g.map(
+//!     |_, &(element, charge, isotope)| {
+//!         let values = [element as u64, (charge + 4) as u64, isotope as u64];
+//!         encoder.encode(&values).unwrap()
+//!     },
+//!     |_, &e| e,
+//! );
+//! let g = CanonGraph::from(g);
+//! let atoms = Vec::from_iter(g.node_weights().map(|&c| encoder.decode(c)));
+//! assert_eq!(atoms, [[6, 4, 0], [8, 3, 0]]);
+//! ```
+use crate::error::ColorError;
+
+/// Collision-free packing of several attributes into a single colour
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ColorEncoder {
+    num_values: Vec<u64>,
+}
+
+impl ColorEncoder {
+    /// Encoder for attributes with the given numbers of values
+    ///
+    /// The `i`th attribute can take values from 0 to
+    /// `num_values[i] - 1`. Fails if the total number of colours
+    /// does not fit into a `u64`.
+    pub fn new(
+        num_values: impl IntoIterator<Item = u64>,
+    ) -> Result<Self, ColorError> {
+        let num_values = Vec::from_iter(num_values);
+        let mut num_colors = 1u64;
+        for &n in &num_values {
+            num_colors =
+                num_colors.checked_mul(n).ok_or(ColorError::TooManyColors)?;
+        }
+        Ok(Self { num_values })
+    }
+
+    /// The number of attributes
+    pub fn num_attributes(&self) -> usize {
+        self.num_values.len()
+    }
+
+    /// The number of values each attribute can take
+    pub fn num_values(&self) -> &[u64] {
+        &self.num_values
+    }
+
+    /// The total number of distinct colours
+    pub fn num_colors(&self) -> u64 {
+        self.num_values.iter().product()
+    }
+
+    /// Combine the attribute values into a single colour
+    pub fn encode(&self, values: &[u64]) -> Result<u64, ColorError> {
+        if values.len() != self.num_values.len() {
+            return Err(ColorError::NumAttributes {
+                expected: self.num_values.len(),
+                found: values.len(),
+            });
+        }
+        let mut colour = 0;
+        for (attribute, (&value, &num_values)) in
+            values.iter().zip(&self.num_values).enumerate()
+        {
+            if value >= num_values {
+                return Err(ColorError::ValueOutOfRange {
+                    attribute,
+                    value,
+                    num_values,
+                });
+            }
+            colour = colour * num_values + value;
+        }
+        Ok(colour)
+    }
+
+    /// Recover the attribute values from a colour
+    ///
+    /// # Panics
+    ///
+    /// Panics if the colour is not smaller than [num_colors](Self::num_colors).
+    pub fn decode(&self, mut colour: u64) -> Vec<u64> {
+        assert!(
+            colour < self.num_colors(),
+            "Colour {colour} was not produced by this encoder"
+        );
+        let mut values = vec![0; self.num_values.len()];
+        for (value, &num_values) in
+            values.iter_mut().zip(&self.num_values).rev()
+        {
+            *value = colour % num_values;
+            colour /= num_values;
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn roundtrip() {
+        log_init();
+
+        let encoder = ColorEncoder::new([3, 1, 5, 2]).unwrap();
+        assert_eq!(encoder.num_colors(), 30);
+        let mut colours = Vec::new();
+        for a in 0..3 {
+            for c in 0..5 {
+                for d in 0..2 {
+                    let values = [a, 0, c, d];
+                    let colour = encoder.encode(&values).unwrap();
+                    assert_eq!(encoder.decode(colour), values);
+                    colours.push(colour);
+                }
+            }
+        }
+        // lexicographic order is preserved
+        assert_eq!(colours, Vec::from_iter(0..30));
+    }
+
+    #[test]
+    fn errors() {
+        log_init();
+
+        assert!(ColorEncoder::new([u64::MAX, 2]).is_err());
+        let encoder = ColorEncoder::new([u64::MAX]).unwrap();
+        assert_eq!(encoder.decode(u64::MAX - 1), [u64::MAX - 1]);
+
+        let encoder = ColorEncoder::new([2, 3]).unwrap();
+        assert!(matches!(
+            encoder.encode(&[1]),
+            Err(ColorError::NumAttributes {
+                expected: 2,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            encoder.encode(&[1, 3]),
+            Err(ColorError::ValueOutOfRange {
+                attribute: 1,
+                value: 3,
+                num_values: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn empty() {
+        log_init();
+
+        let encoder = ColorEncoder::new([]).unwrap();
+        assert_eq!(encoder.num_colors(), 1);
+        assert_eq!(encoder.encode(&[]).unwrap(), 0);
+        assert!(encoder.decode(0).is_empty());
+    }
+}
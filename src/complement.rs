@@ -0,0 +1,522 @@
+//! Graph complements and complement-based filters
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::complement::ComplementFilter;
+//!
+//! let graphs = [
+//!     // path with four vertices, which is self-complementary
+//!     UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]),
+//!     // star with four vertices, with a triangle and an isolated
+//!     // vertex as complement
+//!     UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]),
+//! ];
+//! let self_compl = Vec::from_iter(graphs.iter().cloned().self_complementary());
+//! assert_eq!(self_compl.len(), 1);
+//! ```
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::hash::Hash;
+
+use crate::autom::{dense_autom, Autom};
+use crate::error::NautyError;
+use crate::graph::CanonGraph;
+use crate::nauty_graph::DenseGraph;
+
+use petgraph::{
+    graph::{Graph, IndexType},
+    visit::{EdgeRef, NodeIndexable},
+    EdgeType,
+};
+
+/// The complement of a graph
+///
+/// The complement has the same nodes as `g` and an edge between two
+/// distinct nodes if and only if they are not adjacent in `g`. For
+/// directed graphs, this applies to each direction separately. The
+/// new edges have the default weight, self-loops and the weights of
+/// existing edges are discarded.
+pub fn complement<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> Graph<N, E, Ty, Ix>
+where
+    N: Clone,
+    E: Default,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let mut adjacent = vec![false; n * n];
+    for e in g.edge_references() {
+        let source = g.to_index(e.source());
+        let target = g.to_index(e.target());
+        adjacent[source * n + target] = true;
+        if !g.is_directed() {
+            adjacent[target * n + source] = true;
+        }
+    }
+    let mut res = Graph::with_capacity(n, 0);
+    for w in g.node_weights() {
+        res.add_node(w.clone());
+    }
+    for source in 0..n {
+        let start = if g.is_directed() { 0 } else { source + 1 };
+        for target in start..n {
+            if source != target && !adjacent[source * n + target] {
+                res.add_edge(
+                    res.from_index(source),
+                    res.from_index(target),
+                    E::default(),
+                );
+            }
+        }
+    }
+    res
+}
+
+/// Analyse the automorphism group of the complement of `g`
+///
+/// The complement is formed as in [complement], but directly on the
+/// dense adjacency matrix passed to nauty without constructing an
+/// intermediate petgraph graph. Node weights are taken into account, edge
+/// weights are discarded.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::complement::autom_of_complement;
+///
+/// // the complement of the star is a triangle and an isolated vertex
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+/// let autom = autom_of_complement(&g).unwrap();
+/// assert_eq!(autom.grpsize(), 6.);
+/// assert_eq!(autom.num_orbits, 2);
+/// ```
+pub fn autom_of_complement<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Result<Autom, NautyError>
+where
+    N: Clone + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    // without edge weights, multiple edges, and self-loops, no
+    // auxiliary vertices are needed
+    let mut edges = BTreeSet::new();
+    for e in g.edge_references() {
+        let (source, target) = (g.to_index(e.source()), g.to_index(e.target()));
+        if source != target {
+            if g.is_directed() || source < target {
+                edges.insert((source, target));
+            } else {
+                edges.insert((target, source));
+            }
+        }
+    }
+    let nodes = Vec::from_iter(g.node_weights().cloned());
+    let edges = edges.into_iter().map(|edge| (edge, ()));
+    let mut dg = DenseGraph::<_, _, Ty>::from_parts(nodes, edges);
+    dg.complement();
+    dense_autom(dg, g.is_directed())
+}
+
+/// Canonical forms of a graph and its complement
+///
+/// Edge weights, self-loops, and multiple edges are discarded as in
+/// [complement], so only the adjacency relation and the node weights
+/// are compared.
+#[derive(Clone, Debug)]
+pub struct WithComplement<N, E, Ty: EdgeType, Ix: IndexType> {
+    /// The canonically labelled graph, without self-loops and
+    /// multiple edges and with default edge weights
+    pub graph: CanonGraph<N, E, Ty, Ix>,
+    /// The canonically labelled complement
+    pub complement: CanonGraph<N, E, Ty, Ix>,
+}
+
+impl<N, E, Ty, Ix> WithComplement<N, E, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Default + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    /// Compute the canonical forms of a graph and its complement
+    pub fn new(g: Graph<N, E, Ty, Ix>) -> Self {
+        Self::simplified(&g)
+    }
+
+    fn simplified(g: &Graph<N, E, Ty, Ix>) -> Self {
+        let compl = complement(g);
+        // complementing twice gives the simple graph with default weights
+        let simple = complement(&compl);
+        Self {
+            graph: simple.into(),
+            complement: compl.into(),
+        }
+    }
+}
+
+impl<N, E, Ty, Ix> WithComplement<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    /// Check whether the graph is isomorphic to its complement
+    pub fn is_self_complementary(&self) -> bool {
+        self.graph.cmp(&self.complement) == Ordering::Equal
+    }
+
+    /// Check whether the graph is the representative of its
+    /// complement pair
+    ///
+    /// Out of a graph and its complement, the one with the smaller
+    /// canonical form is the representative. Self-complementary
+    /// graphs are their own representatives.
+    pub fn is_pair_representative(&self) -> bool {
+        self.graph <= self.complement
+    }
+
+    /// The canonical form of the complement pair representative
+    pub fn pair_representative(&self) -> &CanonGraph<N, E, Ty, Ix> {
+        if self.is_pair_representative() {
+            &self.graph
+        } else {
+            &self.complement
+        }
+    }
+
+    /// Turn into the canonical form of the complement pair
+    /// representative
+    pub fn into_pair_representative(self) -> CanonGraph<N, E, Ty, Ix> {
+        if self.is_pair_representative() {
+            self.graph
+        } else {
+            self.complement
+        }
+    }
+}
+
+/// Check whether `g` is isomorphic to its complement
+///
+/// Node weights are taken into account. Edge weights, self-loops, and
+/// multiple edges are discarded as in [complement], so only the
+/// adjacency relation is compared. This is the same as
+/// [WithComplement::is_self_complementary].
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::complement::is_self_complementary;
+///
+/// // the five-cycle, with weights and a repeated edge
+/// let g = UnGraph::<(), u8>::from_edges([
+///     (0, 1, 1),
+///     (1, 2, 2),
+///     (2, 3, 3),
+///     (3, 4, 4),
+///     (4, 0, 5),
+///     (4, 0, 6),
+/// ]);
+/// assert!(is_self_complementary(&g));
+/// ```
+pub fn is_self_complementary<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> bool
+where
+    N: Clone + Ord,
+    E: Default + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    WithComplement::simplified(g).is_self_complementary()
+}
+
+/// Canonical form of a graph up to complementation
+///
+/// Returns the lexicographically smaller of the canonical forms of
+/// `g` and its complement, see
+/// [WithComplement::is_pair_representative]. Edge weights,
+/// self-loops, and multiple edges are discarded as in [complement].
+/// Two graphs have the same result if and only if the remaining
+/// simple graphs are isomorphic or one is isomorphic to the
+/// complement of the other.
+pub fn canon_up_to_complement<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> CanonGraph<N, E, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Default + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    WithComplement::simplified(g).into_pair_representative()
+}
+
+type Complements<I, N, E, Ty, Ix> =
+    std::iter::Map<I, fn(Graph<N, E, Ty, Ix>) -> WithComplement<N, E, Ty, Ix>>;
+
+type Filter<I, N, E, Ty, Ix> = std::iter::FilterMap<
+    I,
+    fn(Graph<N, E, Ty, Ix>) -> Option<CanonGraph<N, E, Ty, Ix>>,
+>;
+
+/// Complement-based filters for iterators over graphs
+pub trait ComplementFilter<N, E, Ty, Ix>:
+    Iterator<Item = Graph<N, E, Ty, Ix>> + Sized
+where
+    N: Clone + Ord,
+    E: Default + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    /// Compute the canonical forms of each graph and its complement
+    fn with_complements(self) -> Complements<Self, N, E, Ty, Ix> {
+        self.map(WithComplement::new)
+    }
+
+    /// Only keep graphs isomorphic to their complements
+    ///
+    /// The graphs are returned in canonical form.
+    fn self_complementary(self) -> Filter<Self, N, E, Ty, Ix> {
+        self.filter_map(|g| {
+            let g = WithComplement::new(g);
+            g.is_self_complementary().then_some(g.graph)
+        })
+    }
+
+    /// Keep one graph out of each complement pair
+    ///
+    /// A graph is kept if it is the representative of its
+    /// complement pair, see
+    /// [WithComplement::is_pair_representative]. If each
+    /// isomorphism class appears at most once in the original
+    /// iterator, the result contains exactly one graph from each
+    /// pair of complementary classes. The graphs are returned in
+    /// canonical form.
+    fn complement_representatives(self) -> Filter<Self, N, E, Ty, Ix> {
+        self.filter_map(|g| {
+            let g = WithComplement::new(g);
+            g.is_pair_representative().then_some(g.graph)
+        })
+    }
+}
+
+impl<I, N, E, Ty, Ix> ComplementFilter<N, E, Ty, Ix> for I
+where
+    I: Iterator<Item = Graph<N, E, Ty, Ix>>,
+    N: Clone + Ord,
+    E: Default + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::GraphGenerator;
+    use crate::prelude::*;
+    use petgraph::{
+        graph::{DiGraph, UnGraph},
+        Undirected,
+    };
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn complement_edges() {
+        log_init();
+
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 1), (1, 2), (1, 2)]);
+        let c = complement(&g);
+        assert_eq!(c.node_count(), 3);
+        assert_eq!(c.edge_count(), 1);
+        assert!(c.contains_edge(0.into(), 2.into()));
+
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let c = complement(&g);
+        assert_eq!(c.edge_count(), 4);
+        assert!(!c.contains_edge(0.into(), 1.into()));
+        assert!(c.contains_edge(1.into(), 0.into()));
+    }
+
+    // compare with the automorphisms of the explicit complement
+    fn check_complement_autom<Ty: EdgeType>(g: &Graph<u8, (), Ty>) {
+        let autom = autom_of_complement(g).unwrap();
+        let expected = complement(g).try_into_autom().unwrap();
+        assert_eq!(autom, expected);
+    }
+
+    #[test]
+    fn count_self_complementary() {
+        log_init();
+
+        // OEIS A000171
+        for (n, expected) in [(4, 1), (5, 2), (6, 0), (8, 10)] {
+            let num = GraphGenerator::new(n)
+                .into_iter()
+                .filter(|g| is_self_complementary(g))
+                .count();
+            assert_eq!(num, expected);
+        }
+
+        // node weights have to be preserved
+        let mut path = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        assert!(is_self_complementary(&path));
+        path[petgraph::graph::NodeIndex::new(0)] = 1;
+        assert!(!is_self_complementary(&path));
+        path.add_edge(1.into(), 1.into(), ());
+        path[petgraph::graph::NodeIndex::new(0)] = 0;
+        assert!(is_self_complementary(&path));
+        assert!(WithComplement::new(path).is_self_complementary());
+    }
+
+    #[test]
+    fn complement_autom() {
+        log_init();
+
+        let graphs = [
+            DiGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 0)]),
+            DiGraph::<u8, ()>::from_edges([(0, 1), (0, 2), (2, 3), (3, 3)]),
+            DiGraph::<u8, ()>::from_edges([(0, 1), (1, 0), (1, 2), (1, 2)]),
+        ];
+        for g in graphs {
+            let mut weighted = g.clone();
+            weighted[petgraph::graph::NodeIndex::new(0)] = 1;
+            for g in [g, weighted] {
+                check_complement_autom(&g);
+                check_complement_autom(&g.into_edge_type::<Undirected>());
+            }
+        }
+
+        // edge weights are discarded
+        let g = UnGraph::<(), u8>::from_edges([(0, 1, 0), (1, 2, 1)]);
+        assert_eq!(autom_of_complement(&g).unwrap().grpsize(), 2.);
+    }
+
+    #[test]
+    fn self_complementary() {
+        log_init();
+
+        // self-complementary graphs on five vertices:
+        // the five-cycle and the "bull"
+        let graphs = [
+            UnGraph::<(), ()>::from_edges([
+                (0, 1),
+                (1, 2),
+                (2, 3),
+                (3, 4),
+                (4, 0),
+            ]),
+            UnGraph::<(), ()>::from_edges([
+                (0, 1),
+                (1, 2),
+                (2, 0),
+                (0, 3),
+                (1, 4),
+            ]),
+            UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4)]),
+        ];
+        let self_compl =
+            Vec::from_iter(graphs.into_iter().self_complementary());
+        assert_eq!(self_compl.len(), 2);
+
+        // edge weights, self-loops, and multiple edges are discarded
+        let cycle = UnGraph::<(), u8>::from_edges([
+            (0, 1, 1),
+            (1, 2, 2),
+            (2, 3, 3),
+            (3, 4, 4),
+            (4, 0, 5),
+            (4, 0, 6),
+            (2, 2, 7),
+        ]);
+        let self_compl =
+            Vec::from_iter([cycle].into_iter().self_complementary());
+        assert_eq!(self_compl.len(), 1);
+        assert_eq!(self_compl[0].edge_count(), 5);
+    }
+
+    #[test]
+    fn representatives() {
+        log_init();
+
+        // all graphs with three vertices
+        let graphs = [
+            vec![],
+            vec![(0, 1)],
+            vec![(0, 1), (1, 2)],
+            vec![(0, 1), (1, 2), (2, 0)],
+        ]
+        .map(|edges| {
+            let mut g = UnGraph::<(), ()>::from_edges(edges);
+            while g.node_count() < 3 {
+                g.add_node(());
+            }
+            g
+        });
+        let reps = Vec::from_iter(
+            graphs.clone().into_iter().complement_representatives(),
+        );
+        assert_eq!(reps.len(), 2);
+        for g in graphs {
+            let g = WithComplement::new(g);
+            assert!(!g.is_self_complementary());
+            assert!(reps.contains(g.pair_representative()));
+        }
+
+        // the pairing is symmetric for weighted multigraphs
+        let star =
+            UnGraph::<(), u8>::from_edges([(0, 1, 1), (0, 2, 2), (0, 3, 3)]);
+        let mut multi = star.clone();
+        multi.add_edge(0.into(), 1.into(), 4);
+        multi.add_edge(2.into(), 2.into(), 5);
+        let compl = WithComplement::new(complement(&star));
+        for g in [star, multi] {
+            let g = WithComplement::new(g);
+            assert_eq!(g.graph, compl.complement);
+            assert_eq!(g.complement, compl.graph);
+            assert_eq!(g.pair_representative(), compl.pair_representative());
+        }
+    }
+
+    #[test]
+    fn up_to_complement() {
+        log_init();
+
+        let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        let triangle = UnGraph::<(), ()>::from_edges([(1, 2), (2, 3), (3, 1)]);
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        let canon = canon_up_to_complement(&star);
+        assert_eq!(canon, canon_up_to_complement(&triangle));
+        assert_ne!(canon, canon_up_to_complement(&path));
+        assert!(canon <= CanonGraph::from(star));
+        assert!(canon <= CanonGraph::from(triangle));
+        assert_eq!(canon_up_to_complement(&path), CanonGraph::from(path));
+
+        // edge weights are discarded
+        let weighted = UnGraph::<(), u8>::from_edges([(0, 1, 3)]);
+        let edge = UnGraph::<(), u8>::from_edges([(0, 1, 0)]);
+        assert_eq!(
+            canon_up_to_complement(&weighted),
+            canon_up_to_complement(&edge)
+        );
+
+        // so are self-loops and multiple edges
+        let multi =
+            UnGraph::<(), u8>::from_edges([(0, 1, 1), (0, 1, 2), (0, 0, 3)]);
+        assert_eq!(
+            canon_up_to_complement(&multi),
+            canon_up_to_complement(&complement(&multi))
+        );
+        assert_eq!(
+            canon_up_to_complement(&multi),
+            canon_up_to_complement(&edge)
+        );
+    }
+}
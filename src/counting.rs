@@ -0,0 +1,410 @@
+//! Counting colourings up to automorphisms
+//!
+//! Two colourings of a graph are equivalent if an automorphism maps
+//! one to the other. By Burnside's lemma, the number of equivalence
+//! classes is the average number of colourings fixed by a group
+//! element. A colouring is fixed if it is constant on each cycle of
+//! the element, so with `k` colours an element with `c` cycles fixes
+//! `k^c` colourings.
+//!
+//! More generally, the [CycleIndex] records how many group elements
+//! have each cycle structure, which is the input for Pólya
+//! enumeration.
+//!
+//! The cycle structures are collected from all group elements, so
+//! counting is only feasible for small automorphism groups and fails
+//! if the group order exceeds [DEFAULT_MAX_GROUP_ORDER].
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::counting::{count_edge_colourings, count_vertex_colourings};
+//!
+//! // necklaces with six beads of two colours that can be turned over
+//! let g = UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+//! assert_eq!(count_vertex_colourings(&g, 2).unwrap(), 13);
+//!
+//! // colouring the edges of the complete graph with four vertices
+//! // in two colours gives all graphs with four vertices
+//! let g = UnGraph::<(), ()>::from_edges([
+//!     (0, 1),
+//!     (0, 2),
+//!     (0, 3),
+//!     (1, 2),
+//!     (1, 3),
+//!     (2, 3),
+//! ]);
+//! assert_eq!(count_edge_colourings(&g, 2).unwrap(), 11);
+//! ```
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::hash::Hash;
+
+use crate::error::{CountingError, TooManyAutomorphisms};
+use crate::group::AutomorphismGroup;
+use crate::perm::Permutation;
+
+use petgraph::{
+    graph::{Graph, IndexType},
+    visit::EdgeRef,
+    EdgeType,
+};
+
+/// Limit on the automorphism group order for counting colourings
+pub const DEFAULT_MAX_GROUP_ORDER: usize = 1 << 20;
+
+/// Cycle index of a permutation group
+///
+/// The cycle index is the polynomial `1/|G| sum_g x_1^j_1 x_2^j_2
+/// ...`, where `j_i` is the number of cycles of length `i` of the
+/// group element `g`. Substituting the number of colours for all
+/// variables gives the number of colourings up to the group action.
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CycleIndex {
+    order: u128,
+    terms: BTreeMap<Vec<usize>, u128>,
+}
+
+impl CycleIndex {
+    /// The cycle index of the group of all given elements
+    ///
+    /// Elements must not be repeated.
+    pub fn from_elements<'a>(
+        elements: impl IntoIterator<Item = &'a Permutation>,
+    ) -> Self {
+        let mut res = Self::default();
+        for perm in elements {
+            res.order += 1;
+            *res.terms.entry(cycle_type(perm)).or_default() += 1;
+        }
+        res
+    }
+
+    /// The group order, which is the denominator of the polynomial
+    pub fn order(&self) -> u128 {
+        self.order
+    }
+
+    /// The monomials with their coefficients
+    ///
+    /// A monomial is given by the exponents `[j_1, j_2, ...]` without
+    /// trailing zeros. Its coefficient is the number of group elements
+    /// with `j_i` cycles of length `i`, so the coefficients sum up to
+    /// the group order.
+    pub fn terms(&self) -> &BTreeMap<Vec<usize>, u128> {
+        &self.terms
+    }
+
+    /// Substitute `x` for all variables
+    ///
+    /// This is the number of colourings with `x` colours that are
+    /// distinct up to the group action. Fails if the result or an
+    /// intermediate sum does not fit into a `u128`.
+    pub fn evaluate(&self, x: u128) -> Result<u128, CountingError> {
+        let mut sum = 0u128;
+        for (exponents, &count) in &self.terms {
+            let num_cycles: usize = exponents.iter().sum();
+            sum = u32::try_from(num_cycles)
+                .ok()
+                .and_then(|c| x.checked_pow(c))
+                .and_then(|n| n.checked_mul(count))
+                .and_then(|n| n.checked_add(sum))
+                .ok_or(CountingError::Overflow)?;
+        }
+        Ok(sum / self.order.max(1))
+    }
+}
+
+/// Formats the polynomial as `(x1^3 + 3 x1 x2 + 2 x3) / 6`
+impl Display for CycleIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = Vec::with_capacity(self.terms.len());
+        // the identity first
+        for (exponents, &count) in self.terms.iter().rev() {
+            let mut factors = Vec::new();
+            if count > 1 {
+                factors.push(count.to_string());
+            }
+            for (i, &j) in exponents.iter().enumerate() {
+                match j {
+                    0 => {}
+                    1 => factors.push(format!("x{}", i + 1)),
+                    _ => factors.push(format!("x{}^{j}", i + 1)),
+                }
+            }
+            if factors.is_empty() {
+                factors.push("1".to_string());
+            }
+            terms.push(factors.join(" "));
+        }
+        let poly = terms.join(" + ");
+        if self.order == 1 {
+            write!(f, "{poly}")
+        } else {
+            write!(f, "({poly}) / {}", self.order)
+        }
+    }
+}
+
+/// The cycle index of the automorphism group acting on the vertices
+///
+/// Node weights are taken into account, so only automorphisms
+/// preserving them are included.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::counting::vertex_cycle_index;
+///
+/// let triangle = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+/// let index = vertex_cycle_index(&triangle).unwrap();
+/// assert_eq!(index.to_string(), "(x1^3 + 3 x1 x2 + 2 x3) / 6");
+/// ```
+///
+/// # Errors
+///
+/// Fails if the automorphism group has more than
+/// [DEFAULT_MAX_GROUP_ORDER] elements.
+pub fn vertex_cycle_index<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Result<CycleIndex, TooManyAutomorphisms>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    Ok(CycleIndex::from_elements(&automorphisms(g)?))
+}
+
+/// The cycle index of the automorphism group acting on the edges
+///
+/// Automorphisms act on the edges through their endpoints, in the
+/// same order for directed graphs. Parallel edges with the same
+/// weight cannot be told apart and are treated as a single edge.
+///
+/// # Errors
+///
+/// Fails if the automorphism group has more than
+/// [DEFAULT_MAX_GROUP_ORDER] elements.
+pub fn edge_cycle_index<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Result<CycleIndex, TooManyAutomorphisms>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let elements = automorphisms(g)?;
+    let endpoints = |v: usize, w: usize| {
+        if g.is_directed() || v <= w {
+            (v, w)
+        } else {
+            (w, v)
+        }
+    };
+    let mut edge_idx = BTreeMap::new();
+    for e in g.edge_references() {
+        let key = (
+            endpoints(e.source().index(), e.target().index()),
+            e.weight(),
+        );
+        let next = edge_idx.len();
+        edge_idx.entry(key).or_insert(next);
+    }
+    let edge_action = |perm: &Permutation| {
+        let mut images = vec![0; edge_idx.len()];
+        for (&((v, w), weight), &i) in &edge_idx {
+            let image = endpoints(perm.image(v), perm.image(w));
+            images[i] = edge_idx[&(image, weight)];
+        }
+        Permutation::new(images)
+    };
+    let elements = Vec::from_iter(elements.iter().map(edge_action));
+    Ok(CycleIndex::from_elements(&elements))
+}
+
+/// The number of vertex colourings with `num_colours` colours that
+/// are distinct up to automorphisms
+///
+/// Node weights are taken into account, so only automorphisms
+/// preserving them identify colourings. See [vertex_cycle_index].
+///
+/// # Errors
+///
+/// Fails if the automorphism group has more than
+/// [DEFAULT_MAX_GROUP_ORDER] elements or if the number of colourings
+/// does not fit into a `u128`.
+pub fn count_vertex_colourings<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    num_colours: u128,
+) -> Result<u128, CountingError>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    vertex_cycle_index(g)?.evaluate(num_colours)
+}
+
+/// The number of edge colourings with `num_colours` colours that are
+/// distinct up to automorphisms
+///
+/// Parallel edges with the same weight cannot be told apart and are
+/// counted as a single edge. See [edge_cycle_index].
+///
+/// # Errors
+///
+/// Fails if the automorphism group has more than
+/// [DEFAULT_MAX_GROUP_ORDER] elements or if the number of colourings
+/// does not fit into a `u128`.
+pub fn count_edge_colourings<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    num_colours: u128,
+) -> Result<u128, CountingError>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    edge_cycle_index(g)?.evaluate(num_colours)
+}
+
+// all automorphisms, if there are not too many
+fn automorphisms<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Result<Vec<Permutation>, TooManyAutomorphisms>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let group = AutomorphismGroup::from(g.clone());
+    let order = group.order();
+    if order.map_or(true, |order| order > DEFAULT_MAX_GROUP_ORDER as u128) {
+        return Err(TooManyAutomorphisms {
+            order,
+            max_group_order: DEFAULT_MAX_GROUP_ORDER,
+        });
+    }
+    Ok(group.elements())
+}
+
+// number of cycles of each length, without trailing zeros
+fn cycle_type(perm: &Permutation) -> Vec<usize> {
+    let cycles = perm.cycles();
+    let longest = cycles.iter().map(|c| c.len()).max().unwrap_or(1);
+    let mut exponents = vec![0; longest.min(perm.degree())];
+    let moved: usize = cycles.iter().map(|c| c.len()).sum();
+    if moved < perm.degree() {
+        exponents[0] = perm.degree() - moved;
+    }
+    for cycle in cycles {
+        exponents[cycle.len() - 1] += 1;
+    }
+    exponents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::{DiGraph, UnGraph};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn cycle(n: u32) -> UnGraph<(), ()> {
+        UnGraph::from_edges((0..n).map(|i| (i, (i + 1) % n)))
+    }
+
+    fn complete(n: u32) -> UnGraph<(), ()> {
+        UnGraph::from_edges(
+            (0..n).flat_map(|i| ((i + 1)..n).map(move |j| (i, j))),
+        )
+    }
+
+    #[test]
+    fn bracelets() {
+        log_init();
+
+        // OEIS A000029
+        for (n, expected) in [(3, 4), (4, 6), (5, 8), (6, 13), (7, 18)] {
+            assert_eq!(
+                count_vertex_colourings(&cycle(n), 2).unwrap(),
+                expected
+            );
+        }
+        // OEIS A027671
+        assert_eq!(count_vertex_colourings(&cycle(5), 3).unwrap(), 39);
+        assert_eq!(count_vertex_colourings(&cycle(5), 0).unwrap(), 0);
+        assert_eq!(count_vertex_colourings(&cycle(5), 1).unwrap(), 1);
+
+        // necklaces that cannot be turned over, OEIS A000031
+        let directed =
+            DiGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+        assert_eq!(count_vertex_colourings(&directed, 2).unwrap(), 14);
+    }
+
+    #[test]
+    fn graphs() {
+        log_init();
+
+        // OEIS A000088
+        for (n, expected) in [(2, 2), (3, 4), (4, 11), (5, 34), (6, 156)] {
+            assert_eq!(
+                count_edge_colourings(&complete(n), 2).unwrap(),
+                expected
+            );
+        }
+        assert_eq!(count_edge_colourings(&cycle(3), 3).unwrap(), 10);
+        // the parallel edge only leaves the reflection, but is not
+        // coloured separately
+        let mut g = cycle(3);
+        g.add_edge(0.into(), 1.into(), ());
+        assert_eq!(count_edge_colourings(&g, 3).unwrap(), 18);
+    }
+
+    #[test]
+    fn cycle_index() {
+        log_init();
+
+        let index = vertex_cycle_index(&cycle(4)).unwrap();
+        assert_eq!(index.order(), 8);
+        assert_eq!(index.terms().values().sum::<u128>(), 8);
+        assert_eq!(index.to_string(), "(x1^4 + 2 x1^2 x2 + 3 x2^2 + 2 x4) / 8");
+        assert_eq!(index.evaluate(2).unwrap(), 6);
+
+        // the symmetric group acting on pairs
+        let index = edge_cycle_index(&complete(4)).unwrap();
+        assert_eq!(
+            index.to_string(),
+            "(x1^6 + 9 x1^2 x2^2 + 6 x2 x4 + 8 x3^2) / 24"
+        );
+
+        let path = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2)]);
+        let path = path.map(|v, _| v.index(), |_, _| ());
+        assert_eq!(vertex_cycle_index(&path).unwrap().to_string(), "x1^3");
+        let empty = UnGraph::<(), ()>::default();
+        let index = vertex_cycle_index(&empty).unwrap();
+        assert_eq!(index.to_string(), "1");
+        assert_eq!(index.evaluate(5).unwrap(), 1);
+    }
+
+    #[test]
+    fn errors() {
+        log_init();
+
+        let err = count_vertex_colourings(&complete(10), 2).unwrap_err();
+        assert!(matches!(err, CountingError::TooManyAutomorphisms(_)));
+        let err = count_vertex_colourings(&cycle(3), u128::MAX).unwrap_err();
+        assert!(matches!(err, CountingError::Overflow));
+    }
+}
@@ -0,0 +1,200 @@
+//! Decks of vertex-deleted and edge-deleted subgraphs
+//!
+//! The deck of a graph is the multiset of its subgraphs obtained by
+//! deleting a single vertex. According to the reconstruction
+//! conjecture, a graph with at least three vertices is determined up
+//! to isomorphism by its deck. Analogously, the edge deck is the
+//! multiset of subgraphs obtained by deleting a single edge.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::deck::{deck, edge_deck, same_deck};
+//! use nauty_pet::prelude::*;
+//!
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+//! let cards = deck(&g);
+//! assert_eq!(cards.len(), 3);
+//! assert_eq!(cards.count(&CanonGraph::from_edges([(0, 1)])), 2);
+//!
+//! let h = UnGraph::<(), ()>::from_edges([(0, 2), (2, 1)]);
+//! assert!(same_deck(&g, &h));
+//!
+//! let cards = edge_deck(&g);
+//! assert_eq!(cards.len(), 2);
+//! assert_eq!(cards.num_classes(), 1);
+//! ```
+use std::hash::Hash;
+
+use crate::graph::CanonGraph;
+use crate::iso::IsoMultiset;
+use crate::subgraph::induced_subgraph;
+
+use petgraph::{
+    graph::{EdgeIndex, Graph, IndexType},
+    EdgeType,
+};
+
+/// The canonical forms of all vertex-deleted subgraphs
+pub fn deck<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> IsoMultiset<N, E, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let mut vertices = Vec::with_capacity(n);
+    collect_cards((0..n).map(|deleted| {
+        vertices.clear();
+        vertices.extend((0..n).filter(|&v| v != deleted));
+        induced_subgraph(g, &vertices)
+    }))
+}
+
+/// The canonical forms of all edge-deleted subgraphs
+///
+/// Multiple edges between the same vertices give rise to one card
+/// each.
+pub fn edge_deck<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> IsoMultiset<N, E, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    collect_cards((0..g.edge_count()).map(|deleted| {
+        let mut card = g.clone();
+        card.remove_edge(EdgeIndex::new(deleted));
+        card
+    }))
+}
+
+fn collect_cards<N, E, Ty, Ix>(
+    cards: impl Iterator<Item = Graph<N, E, Ty, Ix>>,
+) -> IsoMultiset<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    cards.map(CanonGraph::from).collect()
+}
+
+/// Check whether two graphs have the same deck
+pub fn same_deck<N, E, Ty, Ix>(
+    g1: &Graph<N, E, Ty, Ix>,
+    g2: &Graph<N, E, Ty, Ix>,
+) -> bool
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    g1.node_count() == g2.node_count()
+        && g1.edge_count() == g2.edge_count()
+        && deck(g1) == deck(g2)
+}
+
+/// Check whether two graphs have the same edge deck
+pub fn same_edge_deck<N, E, Ty, Ix>(
+    g1: &Graph<N, E, Ty, Ix>,
+    g2: &Graph<N, E, Ty, Ix>,
+) -> bool
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    g1.node_count() == g2.node_count()
+        && g1.edge_count() == g2.edge_count()
+        && edge_deck(g1) == edge_deck(g2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::{DiGraph, UnGraph};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn path() {
+        log_init();
+
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let cards = deck(&g);
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards.num_classes(), 2);
+        let mut isolated = UnGraph::<(), ()>::default();
+        isolated.add_node(());
+        isolated.add_node(());
+        assert_eq!(cards.count(&isolated.into()), 1);
+    }
+
+    #[test]
+    fn empty() {
+        log_init();
+
+        let g = UnGraph::<(), ()>::default();
+        assert!(deck(&g).is_empty());
+    }
+
+    #[test]
+    fn edge_cards() {
+        log_init();
+
+        // triangle with a pendant edge
+        let g = UnGraph::<(), u8>::from_edges([
+            (0, 1, 0),
+            (1, 2, 0),
+            (2, 0, 0),
+            (2, 3, 1),
+        ]);
+        let cards = edge_deck(&g);
+        assert_eq!(cards.len(), 4);
+        assert_eq!(cards.num_classes(), 3);
+        let mut triangle =
+            UnGraph::<(), u8>::from_edges([(0, 1, 0), (1, 2, 0), (2, 0, 0)]);
+        triangle.add_node(());
+        assert_eq!(cards.count(&triangle.into()), 1);
+
+        let multi = UnGraph::<(), ()>::from_edges([(0, 1), (0, 1)]);
+        let cards = edge_deck(&multi);
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards.num_classes(), 1);
+    }
+
+    #[test]
+    fn compare_edge_decks() {
+        log_init();
+
+        let g1 = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        let g2 = UnGraph::<(), ()>::from_edges([(3, 1), (1, 0), (0, 2)]);
+        let g3 = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        assert!(same_edge_deck(&g1, &g2));
+        assert!(!same_edge_deck(&g1, &g3));
+    }
+
+    #[test]
+    fn compare() {
+        log_init();
+
+        let g1 = DiGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        let g2 = DiGraph::<u8, ()>::from_edges([(1, 0), (2, 1), (0, 2)]);
+        let g3 = DiGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 1)]);
+        assert!(same_deck(&g1, &g2));
+        assert!(!same_deck(&g1, &g3));
+        let mut g4 = g2.clone();
+        g4[petgraph::graph::NodeIndex::new(0)] = 1;
+        assert!(!same_deck(&g2, &g4));
+    }
+}
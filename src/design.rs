@@ -0,0 +1,200 @@
+//! Canonical forms and automorphisms of incidence structures
+//!
+//! An incidence structure, for example a block design, consists of
+//! points and blocks, where each block is a collection of points. It
+//! is encoded as its bipartite incidence graph, with points and blocks
+//! distinguished by their node weights.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nauty_pet::design::canonical_design;
+//!
+//! // the Fano plane
+//! let blocks = [
+//!     [0, 1, 2],
+//!     [0, 3, 4],
+//!     [0, 5, 6],
+//!     [1, 3, 5],
+//!     [1, 4, 6],
+//!     [2, 3, 6],
+//!     [2, 4, 5],
+//! ];
+//! let canon = canonical_design(7, &blocks).unwrap();
+//! assert_eq!(canon.point_group().order(), Some(168));
+//!
+//! // relabelling the points gives the same canonical form
+//! let relabelled = blocks.map(|block| block.map(|p| (p + 1) % 7));
+//! let other = canonical_design(7, &relabelled).unwrap();
+//! assert_eq!(canon.blocks, other.blocks);
+//! ```
+use crate::autom::autom_generators;
+use crate::cancel;
+use crate::canon::try_canon_with_labelling;
+use crate::error::InvalidPoint;
+use crate::group::AutomorphismGroup;
+use crate::perm::Permutation;
+
+use petgraph::graph::{NodeIndex, UnGraph};
+
+// node weights of the incidence graph, all points come first in the
+// canonical form
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Incidence {
+    Point,
+    Block,
+}
+
+/// Canonical form and automorphisms of an incidence structure
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CanonDesign {
+    /// Canonical point ordering
+    ///
+    /// The `i`th point of the original structure is the point with
+    /// index `point_labelling[i]` in the canonical form.
+    pub point_labelling: Vec<usize>,
+    /// Canonical block ordering
+    ///
+    /// The `i`th block of the original structure is the block with
+    /// index `block_labelling[i]` in the canonical form.
+    pub block_labelling: Vec<usize>,
+    /// Blocks of the canonical form
+    ///
+    /// Each block is given by the sorted canonical indices of its
+    /// points.
+    pub blocks: Vec<Vec<usize>>,
+    /// Generators of the automorphism group of the original structure
+    ///
+    /// Each generator is given by its action on the points and on the
+    /// blocks.
+    pub generators: Vec<(Permutation, Permutation)>,
+}
+
+impl CanonDesign {
+    /// The automorphism group acting on the points
+    ///
+    /// The action is faithful unless there are repeated blocks.
+    pub fn point_group(&self) -> AutomorphismGroup {
+        let gens = Vec::from_iter(self.generators.iter().map(|g| g.0.clone()));
+        AutomorphismGroup::new(self.point_labelling.len(), gens)
+    }
+
+    /// The automorphism group acting on the blocks
+    pub fn block_group(&self) -> AutomorphismGroup {
+        let gens = Vec::from_iter(self.generators.iter().map(|g| g.1.clone()));
+        AutomorphismGroup::new(self.block_labelling.len(), gens)
+    }
+}
+
+/// Canonical form and automorphisms of an incidence structure
+///
+/// The structure has the points `0..num_points` and the given
+/// blocks. Two structures have the same canonical blocks if and only
+/// if they are isomorphic. A point can appear several times in the
+/// same block.
+///
+/// # Errors
+///
+/// Fails if a block contains a point that is not less than
+/// `num_points`.
+pub fn canonical_design<B>(
+    num_points: usize,
+    blocks: &[B],
+) -> Result<CanonDesign, InvalidPoint>
+where
+    B: AsRef<[usize]>,
+{
+    let mut g = UnGraph::<Incidence, ()>::default();
+    for _ in 0..num_points {
+        g.add_node(Incidence::Point);
+    }
+    for (i, block) in blocks.iter().enumerate() {
+        let b = g.add_node(Incidence::Block);
+        for &point in block.as_ref() {
+            if point >= num_points {
+                return Err(InvalidPoint { block: i, point });
+            }
+            g.add_edge(NodeIndex::new(point), b, ());
+        }
+    }
+    let (generators, _) = autom_generators(g.clone());
+    let generators = Vec::from_iter(generators.into_iter().map(|gen| {
+        let (points, blocks) = gen.split_at(num_points);
+        (
+            Permutation::new(points.to_vec()),
+            Permutation::new(Vec::from_iter(
+                blocks.iter().map(|b| b - num_points),
+            )),
+        )
+    }));
+    let (_, labelling) = cancel::unwrap(try_canon_with_labelling(g));
+    let (point_labelling, block_labelling) = labelling.split_at(num_points);
+    let block_labelling =
+        Vec::from_iter(block_labelling.iter().map(|b| b - num_points));
+    let mut canon_blocks = vec![Vec::new(); blocks.len()];
+    for (block, &pos) in blocks.iter().zip(&block_labelling) {
+        let mut points =
+            Vec::from_iter(block.as_ref().iter().map(|&p| point_labelling[p]));
+        points.sort_unstable();
+        canon_blocks[pos] = points;
+    }
+    Ok(CanonDesign {
+        point_labelling: point_labelling.to_vec(),
+        block_labelling,
+        blocks: canon_blocks,
+        generators,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn complete_design() {
+        log_init();
+
+        // all pairs of four points
+        let blocks = [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]];
+        let canon = canonical_design(4, &blocks).unwrap();
+        assert_eq!(canon.point_group().order(), Some(24));
+        assert_eq!(canon.block_group().order(), Some(24));
+        for (block, &pos) in blocks.iter().zip(&canon.block_labelling) {
+            let mut points = block.map(|p| canon.point_labelling[p]);
+            points.sort_unstable();
+            assert_eq!(canon.blocks[pos], points);
+        }
+    }
+
+    #[test]
+    fn isomorphism() {
+        log_init();
+
+        let blocks = vec![vec![0, 1, 2], vec![2, 3], vec![3, 3]];
+        let canon = canonical_design(5, &blocks).unwrap();
+        // only the first two points can be exchanged
+        assert_eq!(canon.point_group().order(), Some(2));
+        assert_eq!(canon.block_group().order(), Some(1));
+
+        let other = vec![vec![4, 4], vec![3, 1, 0], vec![1, 4]];
+        assert_eq!(canon.blocks, canonical_design(5, &other).unwrap().blocks);
+        let different = vec![vec![0, 1, 2], vec![2, 3], vec![3, 2]];
+        assert_ne!(
+            canon.blocks,
+            canonical_design(5, &different).unwrap().blocks
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        log_init();
+
+        let err = canonical_design(3, &[vec![0, 1], vec![1, 3]]).unwrap_err();
+        assert_eq!((err.block, err.point), (1, 3));
+    }
+}
@@ -0,0 +1,272 @@
+//! Distance-regular and distance-transitive graphs
+//!
+//! A connected graph is distance-transitive if for any two pairs of
+//! vertices `(u, v)` and `(x, y)` with the same distance there is an
+//! automorphism mapping `u` to `x` and `v` to `y`. It is
+//! distance-regular if for vertices `u`, `v` at distance `i` the
+//! number of neighbours of `v` at distance `i - 1` and `i + 1` from
+//! `u` only depends on `i`. Every distance-transitive graph is
+//! distance-regular, but not the other way round.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::distance::{intersection_array, is_distance_transitive};
+//!
+//! // the cube
+//! let cube = UnGraph::<(), ()>::from_edges(
+//!     (0..8u32)
+//!         .flat_map(|v| [1, 2, 4].map(|b| (v, v ^ b)))
+//!         .filter(|(v, w)| v < w),
+//! );
+//! assert!(is_distance_transitive(&cube));
+//! let array = intersection_array(&cube).unwrap();
+//! assert_eq!(array.b, [3, 2, 1]);
+//! assert_eq!(array.c, [1, 2, 3]);
+//! ```
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use crate::group::AutomorphismGroup;
+use crate::subgraph::undirected_neighbours;
+
+use petgraph::graph::{IndexType, UnGraph};
+
+/// Intersection array `{b_0, ..., b_{d-1}; c_1, ..., c_d}` of a
+/// distance-regular graph with diameter `d`
+///
+/// For vertices `u` and `v` at distance `i`, `v` has `b[i]`
+/// neighbours at distance `i + 1` from `u` and `c[i - 1]` neighbours
+/// at distance `i - 1` from `u`.
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IntersectionArray {
+    pub b: Vec<usize>,
+    pub c: Vec<usize>,
+}
+
+impl IntersectionArray {
+    /// The diameter of the graph
+    pub fn diameter(&self) -> usize {
+        self.b.len()
+    }
+
+    /// The vertex degree
+    pub fn degree(&self) -> usize {
+        self.b.first().copied().unwrap_or_default()
+    }
+}
+
+/// Check whether the automorphism group acts transitively on the
+/// pairs of vertices at each distance
+///
+/// Node and edge weights are taken into account. Graphs that are
+/// empty or not connected are not distance-transitive.
+pub fn is_distance_transitive<N, E, Ix>(g: &UnGraph<N, E, Ix>) -> bool
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ix: IndexType,
+{
+    let Some(dist) = distances(g) else {
+        return false;
+    };
+    let orbitals = AutomorphismGroup::from(g.clone()).orbitals();
+    let mut reps = Vec::new();
+    for (pair, &d) in dist.iter().enumerate() {
+        if d >= reps.len() {
+            reps.resize(d + 1, None);
+        }
+        match reps[d] {
+            None => reps[d] = Some(orbitals[pair]),
+            Some(rep) if rep != orbitals[pair] => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Check whether a graph is distance-regular
+///
+/// This only depends on the graph structure and is much cheaper than
+/// [is_distance_transitive]. Graphs that are empty or not connected
+/// are not distance-regular.
+pub fn is_distance_regular<N, E, Ix>(g: &UnGraph<N, E, Ix>) -> bool
+where
+    Ix: IndexType,
+{
+    intersection_array(g).is_some()
+}
+
+/// The intersection array of a distance-regular graph
+///
+/// Returns `None` if the graph is empty, not connected, or not
+/// distance-regular. Edge multiplicities and loops are ignored.
+pub fn intersection_array<N, E, Ix>(
+    g: &UnGraph<N, E, Ix>,
+) -> Option<IntersectionArray>
+where
+    Ix: IndexType,
+{
+    let dist = distances(g)?;
+    let n = g.node_count();
+    let neighbours = undirected_neighbours(g);
+    // `(c_i, b_i)` for each distance `i`
+    let mut numbers: Vec<Option<(usize, usize)>> = Vec::new();
+    for u in 0..n {
+        for (v, nbrs) in neighbours.iter().enumerate() {
+            let d = dist[u * n + v];
+            let (mut c, mut b) = (0, 0);
+            for &w in nbrs {
+                let dw = dist[u * n + w];
+                if dw + 1 == d {
+                    c += 1;
+                } else if dw == d + 1 {
+                    b += 1;
+                }
+            }
+            if d >= numbers.len() {
+                numbers.resize(d + 1, None);
+            }
+            match numbers[d] {
+                None => numbers[d] = Some((c, b)),
+                Some(cb) if cb != (c, b) => return None,
+                _ => {}
+            }
+        }
+    }
+    let diameter = numbers.len() - 1;
+    let numbers = Vec::from_iter(numbers.into_iter().flatten());
+    Some(IntersectionArray {
+        b: Vec::from_iter(numbers[..diameter].iter().map(|&(_, b)| b)),
+        c: Vec::from_iter(numbers[1..].iter().map(|&(c, _)| c)),
+    })
+}
+
+// distance between `u` and `v` at index `u * n + v`, or `None` if
+// the graph is empty or not connected
+fn distances<N, E, Ix: IndexType>(g: &UnGraph<N, E, Ix>) -> Option<Vec<usize>> {
+    let n = g.node_count();
+    if n == 0 {
+        return None;
+    }
+    let neighbours = undirected_neighbours(g);
+    let mut dist = Vec::with_capacity(n * n);
+    for u in 0..n {
+        let from_u = distances_from(&neighbours, u);
+        if from_u.contains(&usize::MAX) {
+            return None;
+        }
+        dist.extend(from_u);
+    }
+    Some(dist)
+}
+
+// breadth-first search, unreachable vertices are at distance
+// `usize::MAX`
+fn distances_from(neighbours: &[Vec<usize>], u: usize) -> Vec<usize> {
+    let mut dist = vec![usize::MAX; neighbours.len()];
+    dist[u] = 0;
+    let mut todo = VecDeque::from([u]);
+    while let Some(v) = todo.pop_front() {
+        for &w in &neighbours[v] {
+            if dist[w] == usize::MAX {
+                dist[w] = dist[v] + 1;
+                todo.push_back(w);
+            }
+        }
+    }
+    dist
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[rustfmt::skip]
+    fn petersen() -> UnGraph<(), ()> {
+        UnGraph::from_edges([
+            (0, 1), (1, 2), (2, 3), (3, 4), (4, 0),
+            (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+            (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+        ])
+    }
+
+    // Cayley graph of Z_4 x Z_4 with connection set
+    // {±(1, 0), ±(0, 1), ±(1, 1)}
+    fn shrikhande() -> UnGraph<(), ()> {
+        let idx = |a: u32, b: u32| 4 * (a % 4) + b % 4;
+        UnGraph::from_edges((0..4).flat_map(|a| {
+            (0..4).flat_map(move |b| {
+                [(1, 0), (0, 1), (1, 1)]
+                    .map(|(da, db)| (idx(a, b), idx(a + da, b + db)))
+            })
+        }))
+    }
+
+    #[test]
+    fn transitive() {
+        log_init();
+
+        assert!(is_distance_transitive(&petersen()));
+        let cycle =
+            UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+        assert!(is_distance_transitive(&cycle));
+        // distance-regular, but not distance-transitive
+        assert!(!is_distance_transitive(&shrikhande()));
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        assert!(!is_distance_transitive(&path));
+        // not connected
+        let triangles = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (3, 4),
+            (4, 5),
+            (5, 3),
+        ]);
+        assert!(!is_distance_transitive(&triangles));
+        assert!(!is_distance_transitive(&UnGraph::<(), ()>::default()));
+    }
+
+    #[test]
+    fn regular() {
+        log_init();
+
+        let array = intersection_array(&petersen()).unwrap();
+        assert_eq!(array.b, [3, 2]);
+        assert_eq!(array.c, [1, 1]);
+        assert_eq!(array.diameter(), 2);
+        assert_eq!(array.degree(), 3);
+
+        let array = intersection_array(&shrikhande()).unwrap();
+        assert_eq!(array.b, [6, 3]);
+        assert_eq!(array.c, [1, 2]);
+
+        let mut single = UnGraph::<(), ()>::default();
+        single.add_node(());
+        let array = intersection_array(&single).unwrap();
+        assert_eq!(array.diameter(), 0);
+
+        // regular, but not distance-regular
+        let prism = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (3, 4),
+            (4, 5),
+            (5, 3),
+            (0, 3),
+            (1, 4),
+            (2, 5),
+        ]);
+        assert!(!is_distance_regular(&prism));
+        let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        assert!(!is_distance_regular(&star));
+    }
+}
@@ -0,0 +1,284 @@
+//! Distinguishing colourings
+//!
+//! A vertex colouring is distinguishing if no automorphism except for
+//! the identity preserves it. The distinguishing number of a graph is
+//! the smallest number of colours in a distinguishing colouring.
+//!
+//! The search enumerates all automorphisms with a stabiliser chain.
+//! It is exact, but only feasible for small automorphism groups and
+//! fails if the group order exceeds a limit.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::distinguishing::distinguishing_number;
+//!
+//! // the five-cycle needs three colours
+//! let g = UnGraph::<(), ()>::from_edges((0..5).map(|i| (i, (i + 1) % 5)));
+//! assert_eq!(distinguishing_number(&g).unwrap(), 3);
+//!
+//! // while two colours suffice for the six-cycle
+//! let g = UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+//! assert_eq!(distinguishing_number(&g).unwrap(), 2);
+//! ```
+use std::hash::Hash;
+
+use crate::error::TooManyAutomorphisms;
+use crate::group::AutomorphismGroup;
+use crate::perm::Permutation;
+
+use petgraph::{
+    graph::{Graph, IndexType},
+    EdgeType,
+};
+
+/// Default limit on the automorphism group order for
+/// [distinguishing_colouring]
+pub const DEFAULT_MAX_GROUP_ORDER: usize = 1 << 20;
+
+/// The distinguishing number of `g`
+///
+/// Node weights are taken into account, so only automorphisms
+/// preserving them have to be broken. The graph without vertices has
+/// distinguishing number zero.
+///
+/// Fails if the automorphism group has more than
+/// [DEFAULT_MAX_GROUP_ORDER] elements.
+pub fn distinguishing_number<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Result<usize, TooManyAutomorphisms>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let colouring = distinguishing_colouring(g)?;
+    Ok(colouring.into_iter().max().map_or(0, |c| c + 1))
+}
+
+/// A distinguishing colouring with the smallest number of colours
+///
+/// The `i`th entry is the colour of the `i`th vertex. Colours are
+/// numbered from zero, in the order of their first occurrence.
+///
+/// Fails if the automorphism group has more than
+/// [DEFAULT_MAX_GROUP_ORDER] elements. Use
+/// [distinguishing_colouring_with_limit] for a different limit.
+pub fn distinguishing_colouring<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Result<Vec<usize>, TooManyAutomorphisms>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    distinguishing_colouring_with_limit(g, DEFAULT_MAX_GROUP_ORDER)
+}
+
+/// A distinguishing colouring with the smallest number of colours,
+/// enumerating at most `max_group_order` automorphisms
+///
+/// See [distinguishing_colouring].
+pub fn distinguishing_colouring_with_limit<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    max_group_order: usize,
+) -> Result<Vec<usize>, TooManyAutomorphisms>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let group = AutomorphismGroup::from(g.clone());
+    let order = group.order();
+    if order.map_or(true, |order| order > max_group_order as u128) {
+        return Err(TooManyAutomorphisms {
+            order,
+            max_group_order,
+        });
+    }
+    let n = g.node_count();
+    let elements = Vec::from_iter(
+        group
+            .elements()
+            .into_iter()
+            .filter(|perm| !perm.is_identity())
+            .map(Element::new),
+    );
+    let all = Vec::from_iter(0..elements.len());
+    // with `n` colours, all vertices can be told apart, so this only
+    // fails without vertices
+    for num_colours in 1..=n {
+        let mut search = Search {
+            num_vertices: n,
+            num_colours,
+            elements: &elements,
+            colouring: Vec::with_capacity(n),
+        };
+        if search.extend(0, &all) {
+            return Ok(search.colouring);
+        }
+    }
+    Ok(Vec::new())
+}
+
+// a nontrivial automorphism
+struct Element {
+    perm: Permutation,
+    inverse: Permutation,
+    // the largest vertex that is not fixed
+    last_moved: usize,
+}
+
+impl Element {
+    fn new(perm: Permutation) -> Self {
+        let inverse = perm.inverse();
+        let last_moved = (0..perm.degree())
+            .rev()
+            .find(|&v| perm.image(v) != v)
+            .unwrap();
+        Self {
+            perm,
+            inverse,
+            last_moved,
+        }
+    }
+}
+
+// backtracking search colouring the vertices in order
+struct Search<'a> {
+    num_vertices: usize,
+    num_colours: usize,
+    elements: &'a [Element],
+    colouring: Vec<usize>,
+}
+
+impl Search<'_> {
+    // try to colour the remaining vertices such that none of the
+    // `surviving` elements preserves the colouring
+    fn extend(&mut self, num_used: usize, surviving: &[usize]) -> bool {
+        let v = self.colouring.len();
+        if v == self.num_vertices {
+            return surviving.is_empty();
+        }
+        // colours are interchangeable, so a new colour is only tried
+        // once
+        for colour in 0..self.num_colours.min(num_used + 1) {
+            self.colouring.push(colour);
+            let surviving = Vec::from_iter(
+                surviving.iter().copied().filter(|&i| self.preserves(i, v)),
+            );
+            // an element that only moves coloured vertices can no
+            // longer be broken
+            let dead_end =
+                surviving.iter().any(|&i| self.elements[i].last_moved <= v);
+            let num_used = num_used.max(colour + 1);
+            if !dead_end && self.extend(num_used, &surviving) {
+                return true;
+            }
+            self.colouring.pop();
+        }
+        false
+    }
+
+    // check whether the `i`th element preserves the colours of the
+    // pairs involving the newest vertex `v`, assuming the other pairs
+    // were checked before
+    fn preserves(&self, i: usize, v: usize) -> bool {
+        let element = &self.elements[i];
+        let colour = |u: usize| self.colouring.get(u).copied();
+        let c = colour(v);
+        let image = element.perm.image(v);
+        let preimage = element.inverse.image(v);
+        (image > v || colour(image) == c)
+            && (preimage > v || colour(preimage) == c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::UnGraph;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn cycle(n: u32) -> UnGraph<(), ()> {
+        UnGraph::from_edges((0..n).map(|i| (i, (i + 1) % n)))
+    }
+
+    fn complete(n: u32) -> UnGraph<(), ()> {
+        UnGraph::from_edges(
+            (0..n).flat_map(|i| ((i + 1)..n).map(move |j| (i, j))),
+        )
+    }
+
+    // check that only the identity preserves the colouring
+    fn check_colouring(g: &UnGraph<(), ()>) -> usize {
+        let colouring = distinguishing_colouring(g).unwrap();
+        assert_eq!(colouring.len(), g.node_count());
+        let coloured = g.map(|v, _| colouring[v.index()], |_, _| ());
+        assert_eq!(AutomorphismGroup::from(coloured).order(), Some(1));
+        colouring.into_iter().max().map_or(0, |c| c + 1)
+    }
+
+    #[test]
+    fn cycles() {
+        log_init();
+
+        for (n, expected) in [(3, 3), (4, 3), (5, 3), (6, 2), (7, 2)] {
+            assert_eq!(check_colouring(&cycle(n)), expected);
+        }
+    }
+
+    #[test]
+    fn complete_graphs() {
+        log_init();
+
+        for n in 2..6 {
+            assert_eq!(check_colouring(&complete(n)), n as usize);
+        }
+        assert_eq!(
+            distinguishing_number(&UnGraph::<(), ()>::default()).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn petersen() {
+        log_init();
+
+        #[rustfmt::skip]
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1), (1, 2), (2, 3), (3, 4), (4, 0),
+            (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+            (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+        ]);
+        assert_eq!(check_colouring(&g), 3);
+    }
+
+    #[test]
+    fn weights() {
+        log_init();
+
+        // node weights break the symmetry of the path
+        let mut g = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2)]);
+        assert_eq!(distinguishing_number(&g).unwrap(), 2);
+        g[petgraph::graph::NodeIndex::new(0)] = 1;
+        assert_eq!(distinguishing_number(&g).unwrap(), 1);
+    }
+
+    #[test]
+    fn limit() {
+        log_init();
+
+        let err =
+            distinguishing_colouring_with_limit(&complete(5), 100).unwrap_err();
+        assert_eq!(err.order, Some(120));
+        assert_eq!(err.max_group_order, 100);
+    }
+}
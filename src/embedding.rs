@@ -0,0 +1,470 @@
+//! Combinatorial maps, i.e. graphs embedded in surfaces
+//!
+//! An embedding of a graph into an orientable surface is described by
+//! its rotation system: the cyclic order of the edges around each
+//! vertex. Each edge consists of two darts (half-edges), and an
+//! [Embedding] lists the darts leaving each vertex in cyclic order.
+//!
+//! Embeddings are canonicalised via their flag graph, which has a
+//! vertex for each flag, i.e. each incident triple of a vertex, an
+//! edge, and a face. Two maps are isomorphic if and only if their flag
+//! graphs are isomorphic.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::embedding::{Embedding, MapIsomorphisms};
+//!
+//! // the planar embedding of a square
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+//! let rotation = [[1, 3], [2, 0], [3, 1], [0, 2]].map(Vec::from);
+//! let map = Embedding::from_graph(&g, &rotation);
+//! assert_eq!(map.num_faces(), 2);
+//! assert_eq!(map.genus(), 0);
+//!
+//! // the same map with different labels
+//! let h = UnGraph::<(), ()>::from_edges([(0, 2), (2, 1), (1, 3), (3, 0)]);
+//! let rotation = [[3, 2], [3, 2], [0, 1], [1, 0]].map(Vec::from);
+//! let relabelled = Embedding::from_graph(&h, &rotation);
+//! assert_eq!(
+//!     map.canonical_form(MapIsomorphisms::All),
+//!     relabelled.canonical_form(MapIsomorphisms::All)
+//! );
+//! ```
+use crate::cancel;
+use crate::canon::try_canon_with_labelling;
+use crate::orbits::UnionFind;
+
+use petgraph::{
+    graph::{IndexType, NodeIndex, UnGraph},
+    visit::EdgeRef,
+};
+
+/// Which map isomorphisms to take into account
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MapIsomorphisms {
+    /// Only isomorphisms that preserve the orientation
+    OrientationPreserving,
+    /// Both orientation-preserving and orientation-reversing
+    /// isomorphisms, so that a map and its mirror image are the same
+    #[default]
+    All,
+}
+
+/// A graph embedded in an orientable surface
+///
+/// Darts are numbered such that the `e`th edge consists of the darts
+/// `2 * e` and `2 * e + 1`.
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Embedding {
+    rotation: Vec<Vec<usize>>,
+}
+
+impl Embedding {
+    /// Embedding with the given rotation system
+    ///
+    /// `rotation[v]` lists the darts leaving vertex `v` in
+    /// counterclockwise order.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless each dart from `0` to `2 * m - 1` appears exactly
+    /// once for some number of edges `m`.
+    pub fn new(rotation: Vec<Vec<usize>>) -> Self {
+        let num_darts = rotation.iter().map(|r| r.len()).sum::<usize>();
+        assert_eq!(num_darts % 2, 0, "Odd number of darts");
+        let mut seen = vec![false; num_darts];
+        for &dart in rotation.iter().flatten() {
+            assert!(dart < num_darts, "Dart {dart} out of range");
+            assert!(!seen[dart], "Duplicate dart {dart}");
+            seen[dart] = true;
+        }
+        Self { rotation }
+    }
+
+    /// Embedding of a graph without self-loops and multiple edges
+    ///
+    /// `rotation[v]` lists the neighbours of vertex `v` in
+    /// counterclockwise order. The dart `2 * e` of the `e`th edge
+    /// leaves its source, the dart `2 * e + 1` leaves its target.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `g` has self-loops or multiple edges, or if
+    /// `rotation[v]` is not a permutation of the neighbours of `v`.
+    pub fn from_graph<N, E, Ix: IndexType>(
+        g: &UnGraph<N, E, Ix>,
+        rotation: &[Vec<usize>],
+    ) -> Self {
+        assert_eq!(rotation.len(), g.node_count());
+        let rotation = rotation.iter().enumerate().map(|(v, neighbours)| {
+            let v = NodeIndex::new(v);
+            assert_eq!(neighbours.len(), g.edges(v).count());
+            Vec::from_iter(neighbours.iter().map(|&w| {
+                let mut edges = g.edges_connecting(v, NodeIndex::new(w));
+                let e = edges.next().expect("No edge between neighbours");
+                assert!(edges.next().is_none(), "Multiple edges");
+                let (source, target) = g.edge_endpoints(e.id()).unwrap();
+                assert_ne!(source, target, "Self-loop");
+                2 * e.id().index() + usize::from(source != v)
+            }))
+        });
+        Self::new(Vec::from_iter(rotation))
+    }
+
+    /// The darts leaving vertex `v` in counterclockwise order
+    pub fn rotation(&self, v: usize) -> &[usize] {
+        &self.rotation[v]
+    }
+
+    /// The number of vertices
+    pub fn num_vertices(&self) -> usize {
+        self.rotation.len()
+    }
+
+    /// The number of edges
+    pub fn num_edges(&self) -> usize {
+        self.num_darts() / 2
+    }
+
+    /// The number of darts, i.e. twice the number of edges
+    pub fn num_darts(&self) -> usize {
+        self.rotation.iter().map(|r| r.len()).sum()
+    }
+
+    /// The faces, each given by the darts along its boundary
+    ///
+    /// Each face is traversed by following a dart and then turning
+    /// to the next dart in the rotation at the end vertex.
+    pub fn faces(&self) -> Vec<Vec<usize>> {
+        let sigma = self.sigma();
+        let mut seen = vec![false; sigma.len()];
+        let mut faces = Vec::new();
+        for start in 0..sigma.len() {
+            let mut face = Vec::new();
+            let mut dart = start;
+            while !seen[dart] {
+                seen[dart] = true;
+                face.push(dart);
+                dart = sigma[alpha(dart)];
+            }
+            if !face.is_empty() {
+                faces.push(face);
+            }
+        }
+        faces
+    }
+
+    /// The number of faces
+    pub fn num_faces(&self) -> usize {
+        self.faces().len()
+    }
+
+    /// The genus of the surface
+    ///
+    /// For disconnected maps, this is the sum of the genera of the
+    /// components.
+    pub fn genus(&self) -> usize {
+        let n = self.num_vertices();
+        let vertex = self.vertex_of_dart();
+        let mut components = UnionFind::new(n);
+        for (dart, &v) in vertex.iter().enumerate() {
+            components.union(v, vertex[alpha(dart)]);
+        }
+        let num_components = components.into_classes(0..n).len();
+        // Euler characteristic 2 - 2g for each component
+        (2 * num_components + self.num_edges() - n - self.num_faces()) / 2
+    }
+
+    /// Canonical form of the map
+    ///
+    /// Two embeddings have the same canonical form if and only if
+    /// there is a map isomorphism of the requested kind between them.
+    pub fn canonical_form(&self, isomorphisms: MapIsomorphisms) -> Self {
+        let sigma = self.sigma();
+        let num_darts = sigma.len();
+        let flag = |dart: usize, side: usize| 2 * dart + side;
+
+        // the flag (d, 0) corresponds to the vertex and edge of dart
+        // d and the face between d and the next dart in the rotation,
+        // (d, 1) to the face between the previous dart and d.
+        const CHANGE_VERTEX: u8 = 0;
+        const CHANGE_EDGE: u8 = 1;
+        const CHANGE_FACE: u8 = 2;
+        let mut flags =
+            UnGraph::<usize, u8>::with_capacity(2 * num_darts, 3 * num_darts);
+        for _ in 0..num_darts {
+            let sides = match isomorphisms {
+                MapIsomorphisms::OrientationPreserving => [0, 1],
+                MapIsomorphisms::All => [0, 0],
+            };
+            for side in sides {
+                flags.add_node(side);
+            }
+        }
+        for (dart, &next) in sigma.iter().enumerate() {
+            let f = NodeIndex::new(flag(dart, 0));
+            let change_vertex = NodeIndex::new(flag(alpha(dart), 1));
+            let change_edge = NodeIndex::new(flag(next, 1));
+            let change_face = NodeIndex::new(flag(dart, 1));
+            flags.add_edge(f, change_vertex, CHANGE_VERTEX);
+            flags.add_edge(f, change_edge, CHANGE_EDGE);
+            flags.add_edge(f, change_face, CHANGE_FACE);
+        }
+        let (_, pos) = cancel::unwrap(try_canon_with_labelling(flags));
+
+        // flags are partitioned into two sides per component. Choose
+        // the side of the canonically first flag as the orientation
+        // unless the orientation is fixed.
+        let side_zero = if isomorphisms == MapIsomorphisms::All {
+            let vertex = self.vertex_of_dart();
+            let mut components = UnionFind::new(self.num_vertices());
+            for (dart, &v) in vertex.iter().enumerate() {
+                components.union(v, vertex[alpha(dart)]);
+            }
+            let mut first = vec![usize::MAX; self.num_vertices()];
+            for (f, &p) in pos.iter().enumerate() {
+                let c = components.find(vertex[f / 2]);
+                if first[c] == usize::MAX || p < pos[first[c]] {
+                    first[c] = f;
+                }
+            }
+            Vec::from_iter((0..2 * num_darts).map(|f| {
+                let c = components.find(vertex[f / 2]);
+                f % 2 == first[c] % 2
+            }))
+        } else {
+            Vec::from_iter((0..2 * num_darts).map(|f| f % 2 == 0))
+        };
+
+        // in the canonical map, darts correspond to flags in the
+        // chosen side, with the rotation and the edges given by the
+        // compositions of flag graph involutions
+        let mut sigma_inv = vec![0; num_darts];
+        for (dart, &next) in sigma.iter().enumerate() {
+            sigma_inv[next] = dart;
+        }
+        let tau = |f: usize, kind: u8| match (kind, f % 2) {
+            (CHANGE_VERTEX, side) => flag(alpha(f / 2), 1 - side),
+            (CHANGE_EDGE, 0) => flag(sigma[f / 2], 1),
+            (CHANGE_EDGE, _) => flag(sigma_inv[f / 2], 0),
+            _ => f ^ 1,
+        };
+        let mut darts =
+            Vec::from_iter((0..2 * num_darts).filter(|&f| side_zero[f]));
+        darts.sort_unstable_by_key(|&f| pos[f]);
+        // number edges by their first dart
+        let mut new_dart = vec![usize::MAX; 2 * num_darts];
+        let mut num_edges = 0;
+        for &f in &darts {
+            if new_dart[f] == usize::MAX {
+                let other = tau(tau(f, CHANGE_VERTEX), CHANGE_FACE);
+                new_dart[f] = 2 * num_edges;
+                new_dart[other] = 2 * num_edges + 1;
+                num_edges += 1;
+            }
+        }
+        // number vertices by their first dart
+        let mut rotation = Vec::new();
+        let mut seen = vec![false; 2 * num_darts];
+        for &f in &darts {
+            if seen[f] {
+                continue;
+            }
+            let mut vertex = Vec::new();
+            let mut next = f;
+            while !seen[next] {
+                seen[next] = true;
+                vertex.push(new_dart[next]);
+                next = tau(tau(next, CHANGE_EDGE), CHANGE_FACE);
+            }
+            rotation.push(vertex);
+        }
+        // isolated vertices come last
+        let num_isolated =
+            self.rotation.iter().filter(|r| r.is_empty()).count();
+        rotation.extend(std::iter::repeat_n(Vec::new(), num_isolated));
+        Self { rotation }
+    }
+
+    // the next dart in counterclockwise order around its vertex
+    fn sigma(&self) -> Vec<usize> {
+        let mut sigma = vec![0; self.num_darts()];
+        for darts in &self.rotation {
+            for (i, &dart) in darts.iter().enumerate() {
+                sigma[dart] = darts[(i + 1) % darts.len()];
+            }
+        }
+        sigma
+    }
+
+    fn vertex_of_dart(&self) -> Vec<usize> {
+        let mut vertex = vec![0; self.num_darts()];
+        for (v, darts) in self.rotation.iter().enumerate() {
+            for &dart in darts {
+                vertex[dart] = v;
+            }
+        }
+        vertex
+    }
+}
+
+// the other dart of the same edge
+fn alpha(dart: usize) -> usize {
+    dart ^ 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{seq::SliceRandom, Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // the same map with randomly renumbered vertices, edges, and
+    // darts, and rotations starting at random positions
+    fn relabel(map: &Embedding, rng: &mut impl Rng) -> Embedding {
+        let mut edges = Vec::from_iter(0..map.num_edges());
+        edges.shuffle(rng);
+        let flip = Vec::from_iter(edges.iter().map(|_| rng.gen::<bool>()));
+        let new_dart =
+            |d: usize| 2 * edges[d / 2] + ((d % 2 == 1) ^ flip[d / 2]) as usize;
+        let mut rotation = Vec::from_iter((0..map.num_vertices()).map(|v| {
+            let mut darts =
+                Vec::from_iter(map.rotation(v).iter().map(|&d| new_dart(d)));
+            if !darts.is_empty() {
+                let shift = rng.gen_range(0..darts.len());
+                darts.rotate_left(shift);
+            }
+            darts
+        }));
+        rotation.shuffle(rng);
+        Embedding::new(rotation)
+    }
+
+    fn mirror(map: &Embedding) -> Embedding {
+        Embedding::new(Vec::from_iter(map.rotation.iter().map(|r| {
+            let mut r = r.clone();
+            r.reverse();
+            r
+        })))
+    }
+
+    // planar tree with branches of lengths 1, 2, 3 around the centre
+    fn chiral_tree() -> Embedding {
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (0, 2),
+            (2, 3),
+            (0, 4),
+            (4, 5),
+            (5, 6),
+        ]);
+        let rotation = [
+            vec![1, 2, 4],
+            vec![0],
+            vec![0, 3],
+            vec![2],
+            vec![0, 5],
+            vec![4, 6],
+            vec![5],
+        ];
+        Embedding::from_graph(&g, &rotation)
+    }
+
+    #[test]
+    fn euler() {
+        log_init();
+
+        // K4 in the plane
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (1, 2),
+            (1, 3),
+            (2, 3),
+        ]);
+        let planar =
+            [vec![1, 2, 3], vec![0, 3, 2], vec![0, 1, 3], vec![0, 2, 1]];
+        let map = Embedding::from_graph(&g, &planar);
+        assert_eq!(map.num_faces(), 4);
+        assert_eq!(map.genus(), 0);
+        // K4 on the torus
+        let toroidal =
+            [vec![1, 2, 3], vec![0, 2, 3], vec![0, 1, 3], vec![0, 1, 2]];
+        let map = Embedding::from_graph(&g, &toroidal);
+        assert_eq!(map.num_faces(), 2);
+        assert_eq!(map.genus(), 1);
+
+        // a single loop and an isolated vertex
+        let map = Embedding::new(vec![vec![0, 1], vec![]]);
+        assert_eq!(map.num_faces(), 2);
+        assert_eq!(map.genus(), 0);
+        assert_eq!(map.canonical_form(MapIsomorphisms::All).num_vertices(), 2);
+    }
+
+    #[test]
+    fn canonical_forms() {
+        log_init();
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(223);
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (1, 2),
+            (1, 3),
+            (2, 3),
+        ]);
+        let k4 = Embedding::from_graph(
+            &g,
+            &[vec![1, 2, 3], vec![0, 2, 3], vec![0, 1, 3], vec![0, 1, 2]],
+        );
+        let maps = [
+            k4,
+            chiral_tree(),
+            Embedding::new(vec![vec![0, 2, 1, 3], vec![4, 5]]),
+        ];
+        for map in maps {
+            for isos in
+                [MapIsomorphisms::OrientationPreserving, MapIsomorphisms::All]
+            {
+                let canon = map.canonical_form(isos);
+                assert_eq!(canon.num_faces(), map.num_faces());
+                assert_eq!(canon.genus(), map.genus());
+                assert_eq!(canon.canonical_form(isos), canon);
+                for _ in 0..10 {
+                    let relabelled = relabel(&map, &mut rng);
+                    assert_eq!(relabelled.canonical_form(isos), canon);
+                }
+            }
+            assert_eq!(
+                mirror(&map).canonical_form(MapIsomorphisms::All),
+                map.canonical_form(MapIsomorphisms::All)
+            );
+        }
+    }
+
+    #[test]
+    fn chirality() {
+        log_init();
+
+        let tree = chiral_tree();
+        let mirrored = mirror(&tree);
+        assert_ne!(
+            tree.canonical_form(MapIsomorphisms::OrientationPreserving),
+            mirrored.canonical_form(MapIsomorphisms::OrientationPreserving)
+        );
+        assert_eq!(
+            tree.canonical_form(MapIsomorphisms::All),
+            mirrored.canonical_form(MapIsomorphisms::All)
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::options::Invariant;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -7,6 +9,74 @@ pub enum NautyError {
     MTooBig,
     #[error("Too many nodes")]
     NTooBig,
-    //  #[error("Aborted by user code")]
-    // Aborted,
+    #[error("Traces does not support directed graphs")]
+    TracesDigraphUnsupported,
+    #[error("Invariant {0:?} is not available for sparse nauty")]
+    SparseInvariantUnsupported(Invariant),
+    #[error("Aborted by user code")]
+    Aborted,
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("Time limit exceeded")]
+    Timeout,
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ColorError {
+    #[error("Total number of colours exceeds 2^64")]
+    TooManyColors,
+    #[error("Expected {expected} attributes, found {found}")]
+    NumAttributes { expected: usize, found: usize },
+    #[error(
+        "Value {value} of attribute {attribute} is not less than {num_values}"
+    )]
+    ValueOutOfRange {
+        attribute: usize,
+        value: u64,
+        num_values: u64,
+    },
+}
+
+#[derive(Debug, Error)]
+#[error("Number of subsets {num_subsets} exceeds limit {max_subsets}")]
+pub struct TooManySubsets {
+    pub num_subsets: u128,
+    pub max_subsets: usize,
+}
+
+#[derive(Debug, Error)]
+#[error("Automorphism group order exceeds limit {max_group_order}")]
+pub struct TooManyAutomorphisms {
+    pub order: Option<u128>,
+    pub max_group_order: usize,
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CountingError {
+    #[error(transparent)]
+    TooManyAutomorphisms(#[from] TooManyAutomorphisms),
+    #[error("Number of colourings exceeds 2^128")]
+    Overflow,
+}
+
+#[derive(Debug, Error)]
+#[error("Cycle through node {node}")]
+pub struct CycleError {
+    pub node: usize,
+}
+
+#[derive(Debug, Error)]
+#[error("Arc {from} -> {to} connects two nodes of the same kind")]
+pub struct InvalidArc {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Error)]
+#[error("Block {block} contains point {point}, which is out of range")]
+pub struct InvalidPoint {
+    pub block: usize,
+    pub point: usize,
 }
@@ -0,0 +1,765 @@
+//! Exhaustive generation of simple undirected graphs
+//!
+//! Graphs are generated by canonical augmentation in the style of
+//! nauty's `geng`: each graph is extended by one vertex at a time and
+//! an extension is only accepted if the new vertex is equivalent to
+//! the canonically last vertex. This produces exactly one graph from
+//! each isomorphism class.
+//!
+//! Previous results are not stored globally. To discard isomorphic
+//! extensions of the same graph, the canonical forms of the children
+//! generated so far are kept for each graph on the current branch of
+//! the augmentation tree. The memory needed therefore grows with the
+//! number of non-isomorphic one-vertex extensions of a single graph,
+//! which is at most `2^(n-1)` for `n` vertices, rather than with the
+//! total number of graphs.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nauty_pet::generate::GraphGenerator;
+//!
+//! // There are 11 graphs with four vertices
+//! assert_eq!(GraphGenerator::new(4).into_iter().count(), 11);
+//!
+//! // Exactly one of them is 3-regular
+//! let cubic = GraphGenerator::new(4).min_degree(3).max_degree(3);
+//! assert_eq!(cubic.into_iter().count(), 1);
+//!
+//! // Six of them are connected
+//! let connected = GraphGenerator::new(4).connected();
+//! assert_eq!(connected.into_iter().count(), 6);
+//! ```
+use std::collections::HashSet;
+use std::os::raw::c_int;
+
+use crate::cancel;
+use crate::error::NautyError;
+use crate::kill::{self, densenauty};
+
+use ahash::RandomState;
+use nauty_Traces_sys::{
+    bit, graph, optionblk, statsblk, ADDONEEDGE, FALSE, TRUE, WORDSIZE,
+};
+use petgraph::graph::{NodeIndex, UnGraph};
+
+/// Largest number of vertices supported by [GraphGenerator]
+pub const MAX_GENERATED_VERTICES: usize = WORDSIZE as usize;
+
+/// Generator for all simple undirected graphs with a given number of
+/// vertices, up to isomorphism
+///
+/// Optional degree and structural constraints are enforced while
+/// building up the graphs, so that branches that cannot lead to an
+/// admissible graph are discarded early. The constraints correspond
+/// to the following `geng` switches:
+///
+/// | `geng` | [GraphGenerator]                  |
+/// |--------|-----------------------------------|
+/// | `-c`   | [connected](Self::connected)      |
+/// | `-C`   | [biconnected](Self::biconnected)  |
+/// | `-b`   | [bipartite](Self::bipartite)      |
+/// | `-t`   | [triangle_free](Self::triangle_free) |
+/// | `-d`   | [min_degree](Self::min_degree)    |
+/// | `-D`   | [max_degree](Self::max_degree)    |
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GraphGenerator {
+    num_vertices: usize,
+    min_degree: usize,
+    max_degree: usize,
+    degree_sequence: Option<Vec<usize>>,
+    connectivity: Connectivity,
+    bipartite: bool,
+    triangle_free: bool,
+    split: Option<Split>,
+}
+
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+struct Split {
+    res: usize,
+    modulus: usize,
+    level: usize,
+}
+
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Connectivity {
+    #[default]
+    Any,
+    Connected,
+    Biconnected,
+}
+
+impl GraphGenerator {
+    /// Generator for graphs with `num_vertices` vertices
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_vertices` is larger than [MAX_GENERATED_VERTICES].
+    pub fn new(num_vertices: usize) -> Self {
+        assert!(
+            num_vertices <= MAX_GENERATED_VERTICES,
+            "Can generate graphs with at most {MAX_GENERATED_VERTICES} vertices"
+        );
+        Self {
+            num_vertices,
+            min_degree: 0,
+            max_degree: num_vertices.saturating_sub(1),
+            degree_sequence: None,
+            connectivity: Connectivity::Any,
+            bipartite: false,
+            triangle_free: false,
+            split: None,
+        }
+    }
+
+    /// Only generate part `res` out of `modulus` parts
+    ///
+    /// This corresponds to `geng`'s `res/mod` argument. The graphs
+    /// in the augmentation tree with a certain number of vertices,
+    /// the split level, are numbered in the order in which they are
+    /// generated. Only the descendants of the graphs whose number is
+    /// congruent to `res` modulo `modulus` are generated. The
+    /// numbering is deterministic, so running the generator for each
+    /// `res` from 0 to `modulus - 1` produces each graph exactly once,
+    /// which allows distributing the generation over many machines.
+    /// Results can be merged with
+    /// [IsoMultiset::merge](crate::iso::IsoMultiset::merge).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `res` is not smaller than `modulus`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nauty_pet::generate::GraphGenerator;
+    ///
+    /// let count = (0..3)
+    ///     .map(|res| GraphGenerator::new(5).split(res, 3).into_iter().count())
+    ///     .sum::<usize>();
+    /// assert_eq!(count, 34);
+    /// ```
+    pub fn split(mut self, res: usize, modulus: usize) -> Self {
+        assert!(res < modulus, "Part number has to be less than {modulus}");
+        let level = self.split.map(|s| s.level).unwrap_or_else(|| {
+            self.num_vertices
+                .saturating_sub(3)
+                .max(1)
+                .min(self.num_vertices)
+        });
+        self.split = Some(Split {
+            res,
+            modulus,
+            level,
+        });
+        self
+    }
+
+    /// Set the number of vertices at which generation is split into
+    /// parts
+    ///
+    /// By default, the split level is three less than the number of
+    /// vertices. Larger split levels lead to more evenly sized parts,
+    /// at the cost of each part repeating more of the work at lower
+    /// levels. This setting has no effect without
+    /// [split](Self::split).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level` exceeds the number of vertices.
+    pub fn split_level(mut self, level: usize) -> Self {
+        assert!(
+            level <= self.num_vertices,
+            "Split level cannot exceed {} vertices",
+            self.num_vertices
+        );
+        let split = self.split.get_or_insert(Split {
+            res: 0,
+            modulus: 1,
+            level,
+        });
+        split.level = level;
+        self
+    }
+
+    /// Only generate connected graphs
+    pub fn connected(mut self) -> Self {
+        self.connectivity = self.connectivity.max(Connectivity::Connected);
+        self
+    }
+
+    /// Only generate biconnected graphs
+    ///
+    /// A graph is biconnected if it is connected and stays connected
+    /// after removing any single vertex. Following `geng`, the graphs
+    /// with one and two vertices are considered biconnected if they
+    /// are connected.
+    pub fn biconnected(mut self) -> Self {
+        self.connectivity = Connectivity::Biconnected;
+        self
+    }
+
+    /// Only generate bipartite graphs
+    pub fn bipartite(mut self) -> Self {
+        self.bipartite = true;
+        self
+    }
+
+    /// Only generate graphs without triangles
+    pub fn triangle_free(mut self) -> Self {
+        self.triangle_free = true;
+        self
+    }
+
+    /// Only generate graphs where each vertex has at least degree `d`
+    pub fn min_degree(mut self, d: usize) -> Self {
+        self.min_degree = d;
+        self
+    }
+
+    /// Only generate graphs where each vertex has at most degree `d`
+    pub fn max_degree(mut self, d: usize) -> Self {
+        self.max_degree = d;
+        self
+    }
+
+    /// Only generate graphs with the given degree sequence
+    ///
+    /// The order of the degrees does not matter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the degree sequence differs from the
+    /// number of vertices.
+    pub fn degree_sequence(mut self, mut degrees: Vec<usize>) -> Self {
+        assert_eq!(
+            degrees.len(),
+            self.num_vertices,
+            "Degree sequence length has to match the number of vertices"
+        );
+        degrees.sort_unstable();
+        self.degree_sequence = Some(degrees);
+        self
+    }
+
+    /// The number of vertices of the generated graphs
+    pub fn num_vertices(&self) -> usize {
+        self.num_vertices
+    }
+
+    // check whether a graph with the given vertex degrees can still be
+    // extended to an admissible graph
+    fn is_feasible(&self, degrees: &[usize]) -> bool {
+        let remaining = self.num_vertices - degrees.len();
+        if degrees
+            .iter()
+            .any(|&d| d > self.max_degree || d + remaining < self.min_degree)
+        {
+            return false;
+        }
+        let Some(targets) = &self.degree_sequence else {
+            return true;
+        };
+        // each current vertex needs a distinct target degree between
+        // its current degree and its current degree plus the number
+        // of remaining vertices; since all these intervals have the
+        // same length, greedy matching is optimal
+        let mut degrees = degrees.to_vec();
+        degrees.sort_unstable();
+        let mut targets = targets.iter().copied().peekable();
+        for d in degrees {
+            while targets.next_if(|&t| t < d).is_some() {}
+            match targets.next() {
+                Some(t) if t <= d + remaining => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    // check constraints on a graph obtained by adding a vertex
+    //
+    // Bipartiteness and triangle-freeness are inherited by induced
+    // subgraphs, so they can be checked on each extension. The
+    // connectivity constraints only apply to the final graphs.
+    fn is_admissible(&self, adj: &[graph]) -> bool {
+        let new = adj.len() - 1;
+        if self.triangle_free
+            && adj
+                .iter()
+                .any(|&row| row & adj[new] != 0 && row & bit[new] != 0)
+        {
+            return false;
+        }
+        if self.bipartite && !is_bipartite(adj) {
+            return false;
+        }
+        if adj.len() < self.num_vertices {
+            return true;
+        }
+        match self.connectivity {
+            Connectivity::Any => true,
+            Connectivity::Connected => is_connected(adj, None),
+            Connectivity::Biconnected => {
+                is_connected(adj, None)
+                    && (adj.len() < 3
+                        || (0..adj.len()).all(|v| is_connected(adj, Some(v))))
+            }
+        }
+    }
+}
+
+impl IntoIterator for GraphGenerator {
+    type Item = UnGraph<(), ()>;
+    type IntoIter = GeneratedGraphs;
+
+    fn into_iter(self) -> Self::IntoIter {
+        GeneratedGraphs::new(self)
+    }
+}
+
+/// Iterator over generated graphs
+///
+/// See [GraphGenerator].
+///
+/// # Checkpoints
+///
+/// The iterator stores its complete state, i.e. the current position
+/// in the augmentation tree together with the canonical forms of the
+/// graphs already generated at each level of the current branch. A
+/// clone of the iterator therefore serves as a checkpoint from which
+/// the generation can be resumed. With the `serde-1` feature, the
+/// iterator can be serialised, so that long runs can be saved to disk
+/// and resumed after an interruption.
+///
+/// ```rust
+/// use nauty_pet::generate::GraphGenerator;
+///
+/// let mut graphs = GraphGenerator::new(5).into_iter();
+/// let first = Vec::from_iter(graphs.by_ref().take(10));
+/// let checkpoint = graphs.clone();
+/// // interrupted here
+/// let rest = Vec::from_iter(checkpoint);
+/// assert_eq!(first.len() + rest.len(), 34);
+/// ```
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug)]
+pub struct GeneratedGraphs {
+    generator: GraphGenerator,
+    stack: Vec<Node>,
+    num_split_nodes: usize,
+}
+
+// a node in the augmentation tree together with the state of the
+// iteration over its children
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug)]
+struct Node {
+    adj: Vec<graph>,
+    degrees: Vec<usize>,
+    next_neighbours: u64,
+    children: HashSet<Vec<graph>, RandomState>,
+}
+
+impl Node {
+    fn new(adj: Vec<graph>) -> Self {
+        let degrees =
+            Vec::from_iter(adj.iter().map(|row| row.count_ones() as usize));
+        Self {
+            adj,
+            degrees,
+            next_neighbours: 0,
+            children: HashSet::default(),
+        }
+    }
+
+    fn num_vertices(&self) -> usize {
+        self.adj.len()
+    }
+
+    fn num_extensions(&self) -> u64 {
+        1 << self.num_vertices()
+    }
+
+    // add a new vertex adjacent to the vertices in `neighbours`
+    fn extend(&self, neighbours: u64) -> Vec<graph> {
+        let n = self.num_vertices();
+        let mut adj = self.adj.clone();
+        adj.push(0);
+        for v in (0..n).filter(|v| neighbours & (1 << v) != 0) {
+            ADDONEEDGE(&mut adj, v, n, 1);
+        }
+        adj
+    }
+}
+
+impl GeneratedGraphs {
+    fn new(generator: GraphGenerator) -> Self {
+        let mut res = Self {
+            generator,
+            stack: Vec::new(),
+            num_split_nodes: 0,
+        };
+        if res.generator.is_feasible(&[]) && res.is_in_part(0) {
+            res.stack.push(Node::new(Vec::new()));
+        }
+        res
+    }
+
+    // check whether the next graph with the given number of vertices
+    // belongs to the selected part
+    fn is_in_part(&mut self, num_vertices: usize) -> bool {
+        let Some(split) = self.generator.split else {
+            return true;
+        };
+        if num_vertices != split.level {
+            return true;
+        }
+        let idx = self.num_split_nodes;
+        self.num_split_nodes += 1;
+        idx % split.modulus == split.res
+    }
+
+    // find the next accepted child of the topmost node
+    fn next_child(&mut self) -> Option<Vec<graph>> {
+        let node = self.stack.last_mut()?;
+        while node.next_neighbours < node.num_extensions() {
+            let neighbours = node.next_neighbours;
+            node.next_neighbours += 1;
+            let mut degrees = node.degrees.clone();
+            for (v, d) in degrees.iter_mut().enumerate() {
+                if neighbours & (1 << v) != 0 {
+                    *d += 1;
+                }
+            }
+            degrees.push(neighbours.count_ones() as usize);
+            if !self.generator.is_feasible(&degrees) {
+                continue;
+            }
+            let child = node.extend(neighbours);
+            if !self.generator.is_admissible(&child) {
+                continue;
+            }
+            let canon = canonical_extension(child);
+            if canon.is_err() {
+                // try the same extension again if generation is resumed
+                node.next_neighbours -= 1;
+            }
+            if let Some(canon) = cancel::unwrap(canon) {
+                if node.children.insert(canon.clone()) {
+                    return Some(canon);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Iterator for GeneratedGraphs {
+    type Item = UnGraph<(), ()>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.last() {
+            if node.num_vertices() == self.generator.num_vertices {
+                let node = self.stack.pop().unwrap();
+                return Some(to_petgraph(&node.adj));
+            }
+            match self.next_child() {
+                Some(child) => {
+                    if self.is_in_part(child.len()) {
+                        self.stack.push(Node::new(child))
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+// canonical form of a graph if its last vertex is in the same orbit
+// as the canonically last vertex
+fn canonical_extension(
+    mut adj: Vec<graph>,
+) -> Result<Option<Vec<graph>>, NautyError> {
+    let n = adj.len();
+    let mut options = optionblk {
+        getcanon: TRUE,
+        defaultptn: TRUE,
+        digraph: FALSE,
+        ..Default::default()
+    };
+    let mut stats = statsblk::default();
+    let mut lab = vec![0; n];
+    let mut ptn = vec![0; n];
+    let mut orbits = vec![0; n];
+    let mut canon = vec![0; n];
+    unsafe {
+        densenauty(
+            adj.as_mut_ptr(),
+            lab.as_mut_ptr(),
+            ptn.as_mut_ptr(),
+            orbits.as_mut_ptr(),
+            &mut options,
+            &mut stats,
+            1,
+            n as c_int,
+            canon.as_mut_ptr(),
+        );
+    }
+    // the search may have been stopped by a cancellation
+    kill::check(stats.errstatus)?;
+    debug_assert_eq!(stats.errstatus, 0);
+    let last = lab[n - 1] as usize;
+    Ok((orbits[last] == orbits[n - 1]).then_some(canon))
+}
+
+// check whether the graph is connected after removing `removed`
+fn is_connected(adj: &[graph], removed: Option<usize>) -> bool {
+    let mut remaining: graph = (0..adj.len())
+        .filter(|&v| Some(v) != removed)
+        .map(|v| bit[v])
+        .fold(0, |acc, b| acc | b);
+    let Some(start) = first_element(remaining) else {
+        return true;
+    };
+    let mut frontier = bit[start];
+    remaining &= !frontier;
+    while let Some(v) = first_element(frontier) {
+        frontier &= !bit[v];
+        let new = adj[v] & remaining;
+        remaining &= !new;
+        frontier |= new;
+    }
+    remaining == 0
+}
+
+fn is_bipartite(adj: &[graph]) -> bool {
+    let mut colour = vec![None; adj.len()];
+    let mut stack = Vec::new();
+    for start in 0..adj.len() {
+        if colour[start].is_some() {
+            continue;
+        }
+        colour[start] = Some(false);
+        stack.push(start);
+        while let Some(v) = stack.pop() {
+            let c = colour[v];
+            for w in (0..adj.len()).filter(|&w| adj[v] & bit[w] != 0) {
+                match colour[w] {
+                    None => {
+                        colour[w] = c.map(|c| !c);
+                        stack.push(w);
+                    }
+                    Some(_) if colour[w] == c => return false,
+                    _ => {}
+                }
+            }
+        }
+    }
+    true
+}
+
+// smallest vertex in a set
+fn first_element(set: graph) -> Option<usize> {
+    (set != 0).then(|| set.leading_zeros() as usize)
+}
+
+fn to_petgraph(adj: &[graph]) -> UnGraph<(), ()> {
+    let n = adj.len();
+    let mut g = UnGraph::with_capacity(n, 0);
+    for _ in 0..n {
+        g.add_node(());
+    }
+    for (v, row) in adj.iter().enumerate() {
+        for w in (v + 1..n).filter(|&w| row & bit[w] != 0) {
+            g.add_edge(NodeIndex::new(v), NodeIndex::new(w), ());
+        }
+    }
+    g
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use std::collections::BTreeSet;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // number of graphs with n vertices, OEIS A000088
+    const NUM_GRAPHS: [usize; 8] = [1, 1, 2, 4, 11, 34, 156, 1044];
+
+    #[test]
+    fn count() {
+        log_init();
+
+        for (n, &expected) in NUM_GRAPHS.iter().enumerate() {
+            assert_eq!(GraphGenerator::new(n).into_iter().count(), expected);
+        }
+    }
+
+    #[test]
+    fn distinct() {
+        log_init();
+
+        let graphs = BTreeSet::from_iter(
+            GraphGenerator::new(6).into_iter().map(CanonGraph::from),
+        );
+        assert_eq!(graphs.len(), NUM_GRAPHS[6]);
+    }
+
+    #[test]
+    fn degree_bounds() {
+        log_init();
+
+        // number of 3-regular graphs with 8 vertices, five of them
+        // connected
+        let cubic = GraphGenerator::new(8).min_degree(3).max_degree(3);
+        assert_eq!(cubic.into_iter().count(), 6);
+
+        let generator = GraphGenerator::new(6).min_degree(2).max_degree(3);
+        let expected = GraphGenerator::new(6)
+            .into_iter()
+            .filter(|g| {
+                g.node_indices()
+                    .all(|v| (2..=3).contains(&g.neighbors(v).count()))
+            })
+            .count();
+        assert_eq!(generator.into_iter().count(), expected);
+    }
+
+    #[test]
+    fn resume() {
+        log_init();
+
+        let all = Vec::from_iter(GraphGenerator::new(6).connected());
+        let mut graphs = GraphGenerator::new(6).connected().into_iter();
+        let mut resumed = Vec::new();
+        while let Some(g) = graphs.next() {
+            resumed.push(g);
+            graphs = graphs.clone();
+        }
+        assert_eq!(resumed.len(), all.len());
+        for (g1, g2) in resumed.iter().zip(&all) {
+            assert!(g1.is_identical(g2));
+        }
+    }
+
+    #[cfg(feature = "serde-1")]
+    #[test]
+    fn resume_serialised() {
+        log_init();
+
+        let all = Vec::from_iter(GraphGenerator::new(6).connected());
+        let mut graphs = GraphGenerator::new(6).connected().into_iter();
+        let mut resumed = Vec::new();
+        while let Some(g) = graphs.next() {
+            resumed.push(g);
+            let checkpoint = serde_json::to_string(&graphs).unwrap();
+            graphs = serde_json::from_str(&checkpoint).unwrap();
+        }
+        assert_eq!(resumed.len(), all.len());
+        for (g1, g2) in resumed.iter().zip(&all) {
+            assert!(g1.is_identical(g2));
+        }
+    }
+
+    #[test]
+    fn split() {
+        log_init();
+
+        let all = BTreeSet::from_iter(
+            GraphGenerator::new(7).into_iter().map(CanonGraph::from),
+        );
+        for (modulus, level) in [(1, 4), (3, 4), (7, 5), (100, 3), (5, 7)] {
+            let mut parts = BTreeSet::new();
+            let mut count = 0;
+            for res in 0..modulus {
+                let generator = GraphGenerator::new(7)
+                    .split_level(level)
+                    .split(res, modulus);
+                for g in generator {
+                    count += 1;
+                    parts.insert(CanonGraph::from(g));
+                }
+            }
+            assert_eq!(count, all.len());
+            assert_eq!(parts, all);
+        }
+    }
+
+    #[test]
+    fn connectivity() {
+        log_init();
+
+        // OEIS A001349
+        let num_connected = [1, 1, 2, 6, 21, 112, 853];
+        for (n, &expected) in num_connected.iter().enumerate() {
+            let generator = GraphGenerator::new(n + 1).connected();
+            assert_eq!(generator.into_iter().count(), expected);
+        }
+
+        // OEIS A002218
+        let num_biconnected = [1, 1, 1, 3, 10, 56, 468];
+        for (n, &expected) in num_biconnected.iter().enumerate() {
+            let generator = GraphGenerator::new(n + 1).biconnected();
+            assert_eq!(generator.into_iter().count(), expected);
+        }
+    }
+
+    #[test]
+    fn hereditary() {
+        log_init();
+
+        // OEIS A033995
+        let num_bipartite = [1, 2, 3, 7, 13, 35, 88];
+        for (n, &expected) in num_bipartite.iter().enumerate() {
+            let generator = GraphGenerator::new(n + 1).bipartite();
+            assert_eq!(generator.into_iter().count(), expected);
+        }
+
+        // OEIS A006785
+        let num_triangle_free = [1, 2, 3, 7, 14, 38, 107];
+        for (n, &expected) in num_triangle_free.iter().enumerate() {
+            let generator = GraphGenerator::new(n + 1).triangle_free();
+            assert_eq!(generator.into_iter().count(), expected);
+        }
+
+        // connected bipartite graphs, OEIS A005142
+        let generator = GraphGenerator::new(6).bipartite().connected();
+        assert_eq!(generator.into_iter().count(), 17);
+    }
+
+    #[test]
+    fn degree_sequence() {
+        log_init();
+
+        let mut degrees = vec![1, 1, 2, 2, 3, 3];
+        let generator = GraphGenerator::new(6).degree_sequence(degrees.clone());
+        let graphs = Vec::from_iter(generator);
+        assert!(!graphs.is_empty());
+        let expected = GraphGenerator::new(6)
+            .into_iter()
+            .filter(|g| {
+                let mut d = Vec::from_iter(
+                    g.node_indices().map(|v| g.neighbors(v).count()),
+                );
+                d.sort_unstable();
+                d == degrees
+            })
+            .count();
+        assert_eq!(graphs.len(), expected);
+
+        degrees[0] = 2;
+        let generator = GraphGenerator::new(6).degree_sequence(degrees);
+        assert_eq!(generator.into_iter().count(), 0);
+    }
+}
@@ -3,10 +3,16 @@ use std::convert::From;
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 
+use crate::cancel;
+use crate::canon::dense_graph_canon;
+use crate::nauty_graph::DenseGraph;
 use crate::{IntoCanon, IsIdentical};
 
 use petgraph::{
+    csr::Csr,
     graph::{DefaultIx, Graph, IndexType, NodeIndex},
+    graphmap::{GraphMap, NodeTrait},
+    matrix_graph::{MatrixGraph, Nullable},
     stable_graph::StableGraph,
     visit::EdgeRef,
     Directed, EdgeType, IntoWeightedEdge, Undirected,
@@ -59,6 +65,13 @@ impl<N, E, Ty: EdgeType, Ix: IndexType> AsRef<Graph<N, E, Ty, Ix>> for CanonGrap
     }
 }
 
+impl<N, E, Ty: EdgeType, Ix: IndexType> CanonGraph<N, E, Ty, Ix> {
+    // wrap a graph that is already canonically labelled
+    pub(crate) fn from_canon_unchecked(g: Graph<N, E, Ty, Ix>) -> Self {
+        Self(g)
+    }
+}
+
 impl<N, E, Ty, Ix> CanonGraph<N, E, Ty, Ix>
 where
     Ty: EdgeType,
@@ -119,6 +132,81 @@ where
     }
 }
 
+/// The node keys of the [GraphMap] become the node weights of the
+/// canonical form. Since the keys are distinct, two graph maps have
+/// the same canonical form if and only if they have the same nodes
+/// and edges, irrespective of the order in which they were added.
+/// To compare the structure only, convert the graph map into a
+/// [Graph] with node weights ignoring the keys first.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graphmap::UnGraphMap;
+/// use nauty_pet::prelude::*;
+///
+/// let g = UnGraphMap::<&str, ()>::from_edges([("a", "b"), ("b", "c")]);
+/// let h = UnGraphMap::<&str, ()>::from_edges([("c", "b"), ("b", "a")]);
+/// assert_eq!(CanonGraph::from(g), CanonGraph::from(h));
+/// ```
+impl<N, E, Ty> From<GraphMap<N, E, Ty>> for CanonGraph<N, E, Ty>
+where
+    N: NodeTrait,
+    Graph<N, E, Ty>: IntoCanon,
+    Ty: EdgeType,
+{
+    fn from(g: GraphMap<N, E, Ty>) -> Self {
+        Self(g.into_graph().into_canon())
+    }
+}
+
+/// The adjacency matrix is converted directly into nauty's dense
+/// format. Only the weights are cloned, in canonical order.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use petgraph::matrix_graph::UnMatrix;
+/// use nauty_pet::prelude::*;
+///
+/// let m = UnMatrix::<(), ()>::from_edges([(0, 1), (1, 2)]);
+/// let g = UnGraph::<(), ()>::from_edges([(2, 0), (0, 1)]);
+/// assert_eq!(CanonGraph::from(&m), CanonGraph::from(g));
+/// ```
+impl<N, E, Ty, Null, Ix> From<&MatrixGraph<N, E, Ty, Null, Ix>>
+    for CanonGraph<N, E, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Null: Nullable<Wrapped = E>,
+    Ix: IndexType,
+{
+    fn from(g: &MatrixGraph<N, E, Ty, Null, Ix>) -> Self {
+        let (dg, _) = cancel::unwrap(dense_graph_canon(DenseGraph::from(g)));
+        let canon: Graph<&N, &E, Ty, Ix> = dg.into();
+        Self(canon.map(|_, &w| w.clone(), |_, &w| w.clone()))
+    }
+}
+
+/// The canonical form is computed with dense nauty, to agree with
+/// that of the corresponding [Graph]. Only the weights are cloned, in
+/// canonical order.
+impl<N, E, Ty, Ix> From<&Csr<N, E, Ty, Ix>> for CanonGraph<N, E, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    fn from(g: &Csr<N, E, Ty, Ix>) -> Self {
+        let (dg, _) = cancel::unwrap(dense_graph_canon(DenseGraph::from(g)));
+        let canon: Graph<&N, &E, Ty, Ix> = dg.into();
+        Self(canon.map(|_, &w| w.clone(), |_, &w| w.clone()))
+    }
+}
+
 impl<N, E, Ty, Ix> PartialEq for CanonGraph<N, E, Ty, Ix>
 where
     N: PartialEq,
@@ -227,6 +315,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn graph_map() {
+        log_init();
+
+        use petgraph::graphmap::DiGraphMap;
+        let g = DiGraphMap::<u32, u8>::from_edges([(3, 1, 0), (1, 2, 1)]);
+        let h = DiGraphMap::<u32, u8>::from_edges([(1, 2, 1), (3, 1, 0)]);
+        let g = CanonGraph::from(g);
+        assert_eq!(g, CanonGraph::from(h));
+        assert!(g.node_weights().eq(&[1, 2, 3]));
+        // the keys distinguish otherwise isomorphic graphs
+        let k = DiGraphMap::<u32, u8>::from_edges([(2, 1, 0), (1, 3, 1)]);
+        assert_ne!(g, CanonGraph::from(k));
+    }
+
+    #[test]
+    fn matrix_graph() {
+        log_init();
+
+        use petgraph::matrix_graph::MatrixGraph;
+        for g in GraphIter::<Directed>::default().take(100) {
+            let mut m = MatrixGraph::<u8, u8>::with_capacity(g.node_count());
+            for &w in g.node_weights() {
+                m.add_node(w);
+            }
+            for e in g.edge_references() {
+                m.add_edge(e.source(), e.target(), *e.weight());
+            }
+            assert_eq!(CanonGraph::from(&m), CanonGraph::from(g));
+        }
+    }
+
+    #[test]
+    fn csr() {
+        log_init();
+
+        use petgraph::csr::Csr;
+        for g in GraphIter::<Undirected>::default().take(100) {
+            let mut csr = Csr::<u8, u8, Undirected>::new();
+            for &w in g.node_weights() {
+                csr.add_node(w);
+            }
+            for e in g.edge_references() {
+                let (s, t) = (e.source().index(), e.target().index());
+                csr.add_edge(s as u32, t as u32, *e.weight());
+            }
+            assert_eq!(CanonGraph::from(&csr), CanonGraph::from(g));
+        }
+    }
+
     #[test]
     fn test_eq_ord() {
         assert_eq!(CanonGraph::from(UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)])), CanonGraph::from(UnGraph::<(), ()>::from_edges([(0, 1), (0, 2)])));
@@ -0,0 +1,1136 @@
+//! Automorphism groups as permutation groups
+//!
+//! In contrast to [Autom](crate::autom::Autom), which only holds
+//! statistics, an [AutomorphismGroup] keeps the generators found by
+//! nauty, so that properties of the action on the vertices can be
+//! examined.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::group::AutomorphismGroup;
+//!
+//! // the cycle of length six
+//! let g = UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+//! let group = AutomorphismGroup::from(g);
+//! assert!(group.is_transitive());
+//! // reflections fix vertices
+//! assert!(!group.is_regular());
+//! // opposite vertices form blocks
+//! assert!(!group.is_primitive());
+//! ```
+use std::hash::Hash;
+
+use crate::autom::autom_generators;
+use crate::canon::with_fixed_vertices;
+use crate::orbits::{edge_orbits, UnionFind};
+use crate::perm::Permutation;
+
+use petgraph::{
+    graph::{Graph, IndexType, NodeIndex},
+    visit::EdgeRef,
+    EdgeType,
+};
+
+/// A permutation group acting on the vertices of a graph
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AutomorphismGroup {
+    degree: usize,
+    generators: Vec<Permutation>,
+}
+
+impl AutomorphismGroup {
+    /// The group acting on `0..degree` generated by `generators`
+    ///
+    /// # Panics
+    ///
+    /// Panics if any generator has a different degree.
+    pub fn new(degree: usize, generators: Vec<Permutation>) -> Self {
+        for gen in &generators {
+            assert_eq!(gen.degree(), degree, "Generator of wrong degree");
+        }
+        Self { degree, generators }
+    }
+
+    /// The number of permuted vertices
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// The generators of the group
+    pub fn generators(&self) -> &[Permutation] {
+        &self.generators
+    }
+
+    /// Orbit of each vertex, given by the smallest vertex index in the
+    /// orbit
+    pub fn orbits(&self) -> Vec<usize> {
+        let mut orbits = UnionFind::new(self.degree);
+        for gen in &self.generators {
+            for v in 0..self.degree {
+                orbits.union(v, gen.image(v));
+            }
+        }
+        Vec::from_iter((0..self.degree).map(|v| orbits.find(v)))
+    }
+
+    /// The number of group elements
+    ///
+    /// Returns `None` if the order does not fit into a `u128`.
+    pub fn order(&self) -> Option<u128> {
+        StabilizerChain::new(self.degree, &self.generators).order()
+    }
+
+    // all group elements, only feasible for small groups
+    pub(crate) fn elements(&self) -> Vec<Permutation> {
+        Vec::from_iter(self.iter_elements())
+    }
+
+    /// Iterate over all group elements
+    ///
+    /// Each element is produced exactly once, starting with the
+    /// identity. The elements are generated on demand, but there are
+    /// as many as the [order](Self::order), so iterating over all of
+    /// them is only feasible for small groups.
+    pub fn iter_elements(&self) -> Elements {
+        StabilizerChain::new(self.degree, &self.generators).into_elements()
+    }
+
+    /// Check whether `perm` is an element of the group
+    ///
+    /// Permutations of a different degree are never elements. For
+    /// repeated queries, the [stabilizer_chain](Self::stabilizer_chain)
+    /// should be computed once instead.
+    pub fn contains(&self, perm: &Permutation) -> bool {
+        self.stabilizer_chain().contains(perm)
+    }
+
+    /// The stabiliser chain of the group
+    pub fn stabilizer_chain(&self) -> StabilizerChain {
+        StabilizerChain::new(self.degree, &self.generators)
+    }
+
+    /// Check whether the identity is the only element
+    pub fn is_trivial(&self) -> bool {
+        self.generators.iter().all(|gen| gen.is_identity())
+    }
+
+    /// Orbit of each ordered pair of vertices
+    ///
+    /// The pair `(v, w)` has index `v * degree + w`, and its orbit is
+    /// given by the smallest pair index in the orbit.
+    pub fn orbitals(&self) -> Vec<usize> {
+        let n = self.degree;
+        let mut orbitals = UnionFind::new(n * n);
+        for gen in &self.generators {
+            for v in 0..n {
+                for w in 0..n {
+                    orbitals.union(v * n + w, gen.image(v) * n + gen.image(w));
+                }
+            }
+        }
+        Vec::from_iter((0..n * n).map(|pair| orbitals.find(pair)))
+    }
+
+    /// The number of orbits on ordered pairs of vertices
+    ///
+    /// For a transitive group, this is the rank: the number of orbits
+    /// of a vertex stabiliser, including the fixed vertex itself.
+    pub fn rank(&self) -> usize {
+        self.orbitals()
+            .into_iter()
+            .enumerate()
+            .filter(|&(pair, rep)| pair == rep)
+            .count()
+    }
+
+    /// Check whether there is a single orbit
+    ///
+    /// The group acting on no vertices is not transitive.
+    pub fn is_transitive(&self) -> bool {
+        self.degree > 0 && self.orbits().iter().all(|&rep| rep == 0)
+    }
+
+    /// Check whether the group acts regularly
+    ///
+    /// A group acts regularly if it is transitive and only the
+    /// identity fixes a vertex, or equivalently if for any two
+    /// vertices there is exactly one group element mapping one to the
+    /// other.
+    pub fn is_regular(&self) -> bool {
+        self.is_transitive() && self.order() == Some(self.degree as u128)
+    }
+
+    /// Check whether the group acts primitively
+    ///
+    /// A group acts primitively if it is transitive and there is no
+    /// nontrivial block system, that is no partition of the vertices
+    /// into at least two sets with more than one element each that is
+    /// preserved by the group.
+    pub fn is_primitive(&self) -> bool {
+        self.is_transitive()
+            && (1..self.degree)
+                .all(|v| self.minimal_block_size(0, v) == self.degree)
+    }
+
+    /// An element that fixes no vertex, if there is one
+    ///
+    /// The group elements are enumerated with a stabiliser chain,
+    /// discarding all elements that fix a base point. In the worst
+    /// case, this takes time proportional to the group order.
+    pub fn fixed_point_free_element(&self) -> Option<Permutation> {
+        if self.orbits().iter().enumerate().any(|(v, &rep)| {
+            rep == v && self.generators.iter().all(|gen| gen.image(v) == v)
+        }) {
+            return None;
+        }
+        let chain = StabilizerChain::new(self.degree, &self.generators);
+        chain.fixed_point_free_element(0, &Permutation::identity(self.degree))
+    }
+
+    // size of the smallest block containing `v` and `w`
+    fn minimal_block_size(&self, v: usize, w: usize) -> usize {
+        let mut blocks = UnionFind::new(self.degree);
+        blocks.union(v, w);
+        let mut pairs = vec![(v, w)];
+        while let Some((v, w)) = pairs.pop() {
+            for gen in &self.generators {
+                let (v, w) = (gen.image(v), gen.image(w));
+                if blocks.find(v) != blocks.find(w) {
+                    blocks.union(v, w);
+                    pairs.push((v, w));
+                }
+            }
+        }
+        let root = blocks.find(v);
+        (0..self.degree).filter(|&u| blocks.find(u) == root).count()
+    }
+}
+
+/// The automorphism group of a graph
+impl<N, E, Ty, Ix> From<Graph<N, E, Ty, Ix>> for AutomorphismGroup
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    fn from(g: Graph<N, E, Ty, Ix>) -> Self {
+        let degree = g.node_count();
+        let (generators, _) = autom_generators(g);
+        Self {
+            degree,
+            generators: generators.into_iter().map(Permutation::new).collect(),
+        }
+    }
+}
+
+/// Check whether `perm` is an automorphism of `g`
+///
+/// An automorphism maps each vertex to a vertex with the same weight
+/// and preserves the edges, including their weights and
+/// multiplicities.
+///
+/// # Panics
+///
+/// Panics if the degree of `perm` differs from the number of vertices.
+pub fn is_automorphism<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    perm: &Permutation,
+) -> bool
+where
+    N: PartialEq,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    assert_eq!(perm.degree(), g.node_count(), "Permutation of wrong degree");
+    let node_weights_preserved = g
+        .node_indices()
+        .all(|v| g[v] == g[NodeIndex::new(perm.image(v.index()))]);
+    if !node_weights_preserved {
+        return false;
+    }
+    let edge = |v: usize, w: usize| {
+        if g.is_directed() || v <= w {
+            (v, w)
+        } else {
+            (w, v)
+        }
+    };
+    let mut edges =
+        Vec::from_iter(g.edge_references().map(|e| {
+            (edge(e.source().index(), e.target().index()), e.weight())
+        }));
+    let mut images = Vec::from_iter(
+        edges
+            .iter()
+            .map(|&((v, w), wt)| (edge(perm.image(v), perm.image(w)), wt)),
+    );
+    edges.sort_unstable();
+    images.sort_unstable();
+    edges == images
+}
+
+/// Check whether `g` has an automorphism that fixes no vertex
+///
+/// See [AutomorphismGroup::fixed_point_free_element].
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::group::has_fixed_point_free_automorphism;
+///
+/// let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+/// assert!(has_fixed_point_free_automorphism(&path));
+/// // the middle vertex is always fixed
+/// let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+/// assert!(!has_fixed_point_free_automorphism(&path));
+/// ```
+pub fn has_fixed_point_free_automorphism<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> bool
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    AutomorphismGroup::from(g.clone())
+        .fixed_point_free_element()
+        .is_some()
+}
+
+/// The automorphisms of `g` that map `subset` onto itself
+///
+/// The vertices in `subset` are treated as an additional colour
+/// class, so their automorphisms are computed by running nauty
+/// again. Vertices may appear more than once in `subset`.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::group::setwise_stabilizer;
+///
+/// // the cycle of length six
+/// let g = UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+/// let opposite = [NodeIndex::new(0), NodeIndex::new(3)];
+/// assert_eq!(setwise_stabilizer(&g, &opposite).order(), Some(4));
+/// ```
+///
+/// # Panics
+///
+/// Panics if a vertex in `subset` is not in `g`.
+pub fn setwise_stabilizer<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    subset: &[NodeIndex<Ix>],
+) -> AutomorphismGroup
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut in_subset = vec![false; g.node_count()];
+    for v in subset {
+        in_subset[v.index()] = true;
+    }
+    let coloured =
+        g.map(|v, w| (in_subset[v.index()], w.clone()), |_, w| w.clone());
+    AutomorphismGroup::from(coloured)
+}
+
+/// The automorphisms of `g` that fix each vertex in `fixed`
+///
+/// The fixed vertices are individualised with
+/// [with_fixed_vertices] and the automorphisms are computed by
+/// running nauty again.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::group::pointwise_stabilizer;
+///
+/// // the cycle of length six
+/// let g = UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+/// let opposite = [NodeIndex::new(0), NodeIndex::new(3)];
+/// assert_eq!(pointwise_stabilizer(&g, &opposite).order(), Some(2));
+/// ```
+///
+/// # Panics
+///
+/// Panics if a vertex in `fixed` is not in `g` or appears more than
+/// once.
+pub fn pointwise_stabilizer<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    fixed: &[NodeIndex<Ix>],
+) -> AutomorphismGroup
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    AutomorphismGroup::from(with_fixed_vertices(g, fixed))
+}
+
+/// Check whether the automorphism group acts transitively on the
+/// edges
+///
+/// Edges of directed graphs are arcs, so an edge can only be mapped
+/// to edges in the same direction. Node and edge weights are taken
+/// into account as for [edge_orbits]. Graphs without edges are not
+/// edge transitive.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::group::is_edge_transitive;
+///
+/// let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+/// assert!(is_edge_transitive(&star));
+/// let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+/// assert!(!is_edge_transitive(&path));
+/// ```
+pub fn is_edge_transitive<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> bool
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    edge_orbits(g).len() == 1
+}
+
+/// Check whether the automorphism group acts transitively on the
+/// arcs
+///
+/// The arcs of an undirected graph are the edges taken in both
+/// directions, so in an arc-transitive graph each edge can also be
+/// reversed. For directed graphs, this is the same as
+/// [is_edge_transitive]. Graphs without edges are not arc transitive.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::group::{is_arc_transitive, is_edge_transitive};
+///
+/// // the star is edge transitive, but the centre cannot be mapped to
+/// // a leaf
+/// let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+/// assert!(is_edge_transitive(&star));
+/// assert!(!is_arc_transitive(&star));
+/// ```
+pub fn is_arc_transitive<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> bool
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    if g.is_directed() {
+        return is_edge_transitive(g);
+    }
+    let Some(first) = g.edge_references().next() else {
+        return false;
+    };
+    // reversing an edge preserves its weight, so it is enough to
+    // check the orbits of the endpoints as ordered pairs
+    let n = g.node_count();
+    let orbitals = AutomorphismGroup::from(g.clone()).orbitals();
+    let orbital = |v: usize, w: usize| orbitals[v * n + w];
+    let expected = orbital(first.source().index(), first.target().index());
+    is_edge_transitive(g)
+        && g.edge_references().all(|e| {
+            let (v, w) = (e.source().index(), e.target().index());
+            orbital(v, w) == expected && orbital(w, v) == expected
+        })
+}
+
+/// Check whether two graphs on the same vertices have the same
+/// automorphisms
+///
+/// The groups are compared as permutation groups, not just by their
+/// order: each generator of one group has to be an automorphism of
+/// the other graph. Graphs with different numbers of vertices never
+/// have the same automorphism group.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::group::same_automorphism_group;
+///
+/// // a four-cycle and its complement, a perfect matching
+/// let cycle = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+/// let matching = UnGraph::<(), ()>::from_edges([(0, 2), (1, 3)]);
+/// assert!(same_automorphism_group(&cycle, &matching));
+///
+/// // the same group order, but a different action
+/// let matching = UnGraph::<(), ()>::from_edges([(0, 1), (2, 3)]);
+/// assert!(!same_automorphism_group(&cycle, &matching));
+/// ```
+pub fn same_automorphism_group<N1, E1, Ty1, N2, E2, Ty2, Ix>(
+    g1: &Graph<N1, E1, Ty1, Ix>,
+    g2: &Graph<N2, E2, Ty2, Ix>,
+) -> bool
+where
+    N1: Clone + Ord,
+    E1: Clone + Hash + Ord,
+    Ty1: EdgeType,
+    N2: Clone + Ord,
+    E2: Clone + Hash + Ord,
+    Ty2: EdgeType,
+    Ix: IndexType,
+{
+    if g1.node_count() != g2.node_count() {
+        return false;
+    }
+    let aut1 = AutomorphismGroup::from(g1.clone());
+    if !aut1.generators().iter().all(|gen| is_automorphism(g2, gen)) {
+        return false;
+    }
+    let aut2 = AutomorphismGroup::from(g2.clone());
+    aut2.generators().iter().all(|gen| is_automorphism(g1, gen))
+}
+
+/// Iterator over the elements of an [AutomorphismGroup]
+///
+/// Created by [AutomorphismGroup::iter_elements].
+#[derive(Clone, Debug)]
+pub struct Elements {
+    degree: usize,
+    // transversals of the stabiliser chain levels
+    transversals: Vec<Vec<Permutation>>,
+    // position in each transversal, `None` when exhausted
+    positions: Option<Vec<usize>>,
+}
+
+impl Iterator for Elements {
+    type Item = Permutation;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let positions = self.positions.as_mut()?;
+        // multiply one transversal element from each level, starting
+        // from the deepest level
+        let mut perm = Permutation::identity(self.degree);
+        for (transversal, &pos) in
+            self.transversals.iter().zip(positions.iter()).rev()
+        {
+            perm = &perm * &transversal[pos];
+        }
+        // advance the positions like the digits of a number, the
+        // first level changing fastest
+        let mut exhausted = true;
+        for (transversal, pos) in self.transversals.iter().zip(positions) {
+            *pos += 1;
+            if *pos < transversal.len() {
+                exhausted = false;
+                break;
+            }
+            *pos = 0;
+        }
+        if exhausted {
+            self.positions = None;
+        }
+        Some(perm)
+    }
+}
+
+/// Base and strong generating set of a permutation group
+///
+/// The chain is computed from the generators with the deterministic
+/// Schreier-Sims algorithm. Level `i` has a base point `b_i` and
+/// consists of the elements fixing `b_0, ..., b_{i-1}`. Once
+/// computed, the chain answers membership queries and gives the group
+/// order without further searching.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::group::AutomorphismGroup;
+/// use nauty_pet::perm::Permutation;
+///
+/// // the cycle of length five
+/// let g = UnGraph::<(), ()>::from_edges((0..5).map(|i| (i, (i + 1) % 5)));
+/// let chain = AutomorphismGroup::from(g).stabilizer_chain();
+/// assert_eq!(chain.order(), Some(10));
+/// assert!(chain.contains(&Permutation::new(vec![4, 3, 2, 1, 0])));
+/// assert!(!chain.contains(&Permutation::new(vec![1, 0, 2, 3, 4])));
+/// // only the reflection fixing the first base point remains
+/// assert_eq!(chain.stabilizer(1).order(), Some(2));
+/// ```
+#[derive(Clone, Debug)]
+pub struct StabilizerChain {
+    degree: usize,
+    levels: Vec<Level>,
+}
+
+#[derive(Clone, Debug)]
+struct Level {
+    base: usize,
+    generators: Vec<Permutation>,
+    // `transversal[v]` maps the base point to `v` for each `v` in
+    // its orbit
+    transversal: Vec<Option<Permutation>>,
+}
+
+impl StabilizerChain {
+    /// The chain of the group acting on `0..degree` generated by
+    /// `generators`
+    ///
+    /// # Panics
+    ///
+    /// Panics if any generator has a different degree.
+    pub fn new(degree: usize, generators: &[Permutation]) -> Self {
+        let mut chain = Self {
+            degree,
+            levels: Vec::new(),
+        };
+        for gen in generators {
+            assert_eq!(gen.degree(), degree, "Generator of wrong degree");
+            if !chain.contains_from(gen, 0) {
+                chain.add_generator(0, gen.clone());
+            }
+        }
+        chain
+    }
+
+    /// The number of permuted points
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// The number of levels
+    ///
+    /// This is the length of the base. The chain of the trivial group
+    /// has no levels.
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Check whether the chain has no levels
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// The base points
+    ///
+    /// Only the identity fixes all of them.
+    pub fn base(&self) -> Vec<usize> {
+        Vec::from_iter(self.levels.iter().map(|level| level.base))
+    }
+
+    /// The strong generating set
+    ///
+    /// For each level `i`, the generators fixing the first `i` base
+    /// points generate the stabiliser at that level.
+    pub fn strong_generators(&self) -> Vec<Permutation> {
+        Vec::from_iter(
+            self.levels
+                .iter()
+                .flat_map(|level| level.generators.iter().cloned()),
+        )
+    }
+
+    /// The orbit of the `i`th base point under the stabiliser at level
+    /// `i`, in increasing order
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not less than the number of levels.
+    pub fn basic_orbit(&self, i: usize) -> Vec<usize> {
+        Vec::from_iter(
+            self.levels[i]
+                .transversal
+                .iter()
+                .enumerate()
+                .filter_map(|(v, u)| u.as_ref().map(|_| v)),
+        )
+    }
+
+    /// The number of group elements
+    ///
+    /// Returns `None` if the order does not fit into a `u128`.
+    pub fn order(&self) -> Option<u128> {
+        self.levels.iter().try_fold(1u128, |order, level| {
+            let orbit_len = level.transversal.iter().flatten().count();
+            order.checked_mul(orbit_len as u128)
+        })
+    }
+
+    /// Sift `perm` through the chain
+    ///
+    /// Returns the residue and the number of levels passed. `perm` is
+    /// a group element if and only if all levels are passed and the
+    /// residue is the identity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `perm` has a different degree.
+    pub fn strip(&self, perm: &Permutation) -> (Permutation, usize) {
+        assert_eq!(perm.degree(), self.degree, "Permutation of wrong degree");
+        self.strip_from(perm, 0)
+    }
+
+    /// Check whether `perm` is a group element
+    ///
+    /// Permutations of a different degree are never elements.
+    pub fn contains(&self, perm: &Permutation) -> bool {
+        perm.degree() == self.degree && self.contains_from(perm, 0)
+    }
+
+    /// The pointwise stabiliser of the first `i` base points
+    ///
+    /// For `i` at least the number of levels, this is the trivial
+    /// group.
+    pub fn stabilizer(&self, i: usize) -> AutomorphismGroup {
+        let generators = Vec::from_iter(
+            self.levels
+                .iter()
+                .skip(i)
+                .flat_map(|level| level.generators.iter().cloned()),
+        );
+        AutomorphismGroup::new(self.degree, generators)
+    }
+
+    // each element is the product of one transversal element from
+    // each level
+    fn into_elements(self) -> Elements {
+        let transversals = Vec::from_iter(self.levels.into_iter().map(|l| {
+            // the base point comes first, so the identity is the first
+            // element
+            let mut transversal =
+                Vec::from_iter(l.transversal.into_iter().flatten());
+            let base =
+                transversal.iter().position(|u| u.is_identity()).unwrap();
+            transversal.swap(0, base);
+            transversal
+        }));
+        Elements {
+            degree: self.degree,
+            positions: Some(vec![0; transversals.len()]),
+            transversals,
+        }
+    }
+
+    // search for an element without fixed points, extending the
+    // product `partial` of transversal elements of the previous levels
+    fn fixed_point_free_element(
+        &self,
+        level: usize,
+        partial: &Permutation,
+    ) -> Option<Permutation> {
+        let Some(lvl) = self.levels.get(level) else {
+            let fixes_none = (0..self.degree).all(|v| partial.image(v) != v);
+            return fixes_none.then(|| partial.clone());
+        };
+        // the deeper levels fix the base point, so its image is final
+        for u in lvl.transversal.iter().flatten() {
+            let perm = u * partial;
+            if perm.image(lvl.base) != lvl.base {
+                let res = self.fixed_point_free_element(level + 1, &perm);
+                if res.is_some() {
+                    return res;
+                }
+            }
+        }
+        None
+    }
+
+    // sift `perm` through the levels starting at `start`
+    fn strip_from(
+        &self,
+        perm: &Permutation,
+        start: usize,
+    ) -> (Permutation, usize) {
+        let mut perm = perm.clone();
+        let start = start.min(self.levels.len());
+        for (i, level) in self.levels.iter().enumerate().skip(start) {
+            let Some(u) = &level.transversal[perm.image(level.base)] else {
+                return (perm, i);
+            };
+            perm = &perm * &u.inverse();
+        }
+        (perm, self.levels.len())
+    }
+
+    // check whether `perm` is in the group at the given level
+    fn contains_from(&self, perm: &Permutation, level: usize) -> bool {
+        let (residue, passed) = self.strip_from(perm, level);
+        passed == self.levels.len() && residue.is_identity()
+    }
+
+    fn add_generator(&mut self, level: usize, gen: Permutation) {
+        if level == self.levels.len() {
+            let base = (0..self.degree).find(|&v| gen.image(v) != v).unwrap();
+            let mut transversal = vec![None; self.degree];
+            transversal[base] = Some(Permutation::identity(self.degree));
+            self.levels.push(Level {
+                base,
+                generators: Vec::new(),
+                transversal,
+            });
+        }
+        self.levels[level].generators.push(gen.clone());
+
+        // apply the new generator to the old orbit, and all generators
+        // to new orbit points
+        let mut todo = Vec::from_iter(
+            (0..self.degree)
+                .filter(|&v| self.levels[level].transversal[v].is_some())
+                .map(|v| (v, gen.clone())),
+        );
+        while let Some((v, s)) = todo.pop() {
+            let w = s.image(v);
+            let lvl = &mut self.levels[level];
+            let u = lvl.transversal[v].as_ref().unwrap() * &s;
+            match &lvl.transversal[w] {
+                None => {
+                    lvl.transversal[w] = Some(u);
+                    todo.extend(lvl.generators.iter().map(|s| (w, s.clone())));
+                }
+                Some(uw) => {
+                    let schreier = &u * &uw.inverse();
+                    if !self.contains_from(&schreier, level + 1) {
+                        self.add_generator(level + 1, schreier);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::UnGraph;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn cycle(n: u32) -> UnGraph<(), ()> {
+        UnGraph::from_edges((0..n).map(|i| (i, (i + 1) % n)))
+    }
+
+    #[test]
+    fn order() {
+        log_init();
+
+        let group = AutomorphismGroup::from(cycle(7));
+        assert_eq!(group.order(), Some(14));
+
+        // symmetric group on five points
+        let complete = UnGraph::<(), ()>::from_edges(
+            (0..5).flat_map(|i| ((i + 1)..5).map(move |j| (i, j))),
+        );
+        let group = AutomorphismGroup::from(complete);
+        let chain = StabilizerChain::new(group.degree(), group.generators());
+        assert_eq!(chain.order(), Some(120));
+        let five_cycle = Permutation::new(vec![1, 2, 3, 4, 0]);
+        assert!(chain.contains(&five_cycle));
+
+        let group = AutomorphismGroup::from(cycle(6));
+        let chain = StabilizerChain::new(group.degree(), group.generators());
+        let swap = Permutation::new(vec![1, 0, 2, 3, 4, 5]);
+        assert!(!chain.contains(&swap));
+        let mut elements = group.elements();
+        assert_eq!(elements.len(), 12);
+        elements.sort_unstable();
+        elements.dedup();
+        assert_eq!(elements.len(), 12);
+        assert!(elements.iter().all(|perm| chain.contains(perm)));
+    }
+
+    #[test]
+    fn elements() {
+        log_init();
+
+        let group = AutomorphismGroup::from(cycle(5));
+        assert!(!group.is_trivial());
+        let mut elements = Vec::from_iter(group.iter_elements());
+        assert!(elements[0].is_identity());
+        assert_eq!(elements.len(), 10);
+        assert!(elements.iter().all(|perm| group.contains(perm)));
+        elements.sort_unstable();
+        elements.dedup();
+        assert_eq!(elements.len(), 10);
+
+        let rotation = Permutation::new(vec![1, 2, 3, 4, 0]);
+        assert!(group.contains(&rotation));
+        let swap = Permutation::new(vec![1, 0, 2, 3, 4]);
+        assert!(!group.contains(&swap));
+        assert!(!group.contains(&Permutation::identity(4)));
+
+        let path = UnGraph::<u8, ()>::from_edges([(0, 1), (1, 2)]);
+        let group =
+            AutomorphismGroup::from(path.map(|v, _| v.index(), |_, _| ()));
+        assert!(group.is_trivial());
+        let elements = Vec::from_iter(group.iter_elements());
+        assert_eq!(elements, [Permutation::identity(3)]);
+        assert_eq!(AutomorphismGroup::default().iter_elements().count(), 1);
+    }
+
+    #[test]
+    fn chain() {
+        log_init();
+
+        // the symmetric group on four points
+        let complete = UnGraph::<(), ()>::from_edges(
+            (0..4).flat_map(|i| ((i + 1)..4).map(move |j| (i, j))),
+        );
+        let chain = AutomorphismGroup::from(complete).stabilizer_chain();
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.order(), Some(24));
+        let base = chain.base();
+        for i in 0..chain.len() {
+            assert_eq!(chain.basic_orbit(i).len(), 4 - i);
+            assert!(chain.basic_orbit(i).contains(&base[i]));
+            let stabilizer = chain.stabilizer(i);
+            assert_eq!(stabilizer.order(), Some([24, 6, 2][i]));
+            for gen in stabilizer.generators() {
+                assert!(base[..i].iter().all(|&b| gen.image(b) == b));
+            }
+        }
+        assert!(chain.stabilizer(3).is_trivial());
+        let strong = AutomorphismGroup::new(4, chain.strong_generators());
+        assert_eq!(strong.order(), Some(24));
+
+        // the cycle of length six
+        let chain = AutomorphismGroup::from(cycle(6)).stabilizer_chain();
+        let swap = Permutation::new(vec![1, 0, 2, 3, 4, 5]);
+        let (residue, passed) = chain.strip(&swap);
+        assert!(passed < chain.len() || !residue.is_identity());
+        let rotation = Permutation::new(vec![1, 2, 3, 4, 5, 0]);
+        let (residue, passed) = chain.strip(&rotation);
+        assert_eq!(passed, chain.len());
+        assert!(residue.is_identity());
+        assert!(!chain.contains(&Permutation::identity(5)));
+
+        let chain = AutomorphismGroup::default().stabilizer_chain();
+        assert!(chain.is_empty());
+        assert_eq!(chain.order(), Some(1));
+    }
+
+    #[test]
+    fn transitivity() {
+        log_init();
+
+        #[rustfmt::skip]
+        let petersen = UnGraph::<(), ()>::from_edges([
+            (0, 1), (1, 2), (2, 3), (3, 4), (4, 0),
+            (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+            (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+        ]);
+        assert!(is_edge_transitive(&petersen));
+        assert!(is_arc_transitive(&petersen));
+
+        // complete bipartite graph with parts of different sizes
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+        ]);
+        assert!(is_edge_transitive(&g));
+        assert!(!is_arc_transitive(&g));
+
+        // edge weights distinguish the edges of the cycle
+        let g = UnGraph::<(), u8>::from_edges([
+            (0, 1, 0),
+            (1, 2, 1),
+            (2, 3, 0),
+            (3, 0, 1),
+        ]);
+        assert!(!is_edge_transitive(&g));
+
+        // directed cycles cannot be reversed
+        let g = petgraph::graph::DiGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 0),
+        ]);
+        assert!(is_arc_transitive(&g));
+        assert!(!is_arc_transitive(&UnGraph::<(), ()>::default()));
+    }
+
+    #[test]
+    fn stabilizer() {
+        log_init();
+
+        let g = cycle(6);
+        let subset = |vs: &[usize]| {
+            Vec::from_iter(vs.iter().map(|&v| NodeIndex::<u32>::new(v)))
+        };
+        assert_eq!(setwise_stabilizer(&g, &[]).order(), Some(12));
+        assert_eq!(setwise_stabilizer(&g, &subset(&[0, 1])).order(), Some(2));
+        assert_eq!(
+            setwise_stabilizer(&g, &subset(&[0, 2, 4])).order(),
+            Some(6)
+        );
+        let group = setwise_stabilizer(&g, &subset(&[0, 3, 0]));
+        assert_eq!(group.order(), Some(4));
+        for perm in group.iter_elements() {
+            assert!(is_automorphism(&g, &perm));
+            assert!([0, 3].contains(&perm.image(0)));
+        }
+
+        assert_eq!(pointwise_stabilizer(&g, &[]).order(), Some(12));
+        assert_eq!(pointwise_stabilizer(&g, &subset(&[1])).order(), Some(2));
+        let group = pointwise_stabilizer(&g, &subset(&[0, 3]));
+        assert_eq!(group.order(), Some(2));
+        for perm in group.iter_elements() {
+            assert!(is_automorphism(&g, &perm));
+            assert_eq!((perm.image(0), perm.image(3)), (0, 3));
+        }
+        assert_eq!(pointwise_stabilizer(&g, &subset(&[0, 1])).order(), Some(1));
+        // agrees with the stabiliser chain
+        let chain = AutomorphismGroup::from(g.clone()).stabilizer_chain();
+        for i in 0..=chain.len() {
+            let fixed = subset(&chain.base()[..i]);
+            assert_eq!(
+                pointwise_stabilizer(&g, &fixed).order(),
+                chain.stabilizer(i).order()
+            );
+        }
+    }
+
+    #[test]
+    fn rank() {
+        log_init();
+
+        assert_eq!(AutomorphismGroup::from(cycle(5)).rank(), 3);
+        assert_eq!(AutomorphismGroup::from(cycle(6)).rank(), 4);
+        let complete = UnGraph::<(), ()>::from_edges(
+            (0..5).flat_map(|i| ((i + 1)..5).map(move |j| (i, j))),
+        );
+        assert_eq!(AutomorphismGroup::from(complete).rank(), 2);
+    }
+
+    #[test]
+    fn regular() {
+        log_init();
+
+        let rotation = Permutation::new(vec![1, 2, 3, 4, 0]);
+        assert!(AutomorphismGroup::new(5, vec![rotation]).is_regular());
+        assert!(!AutomorphismGroup::from(cycle(5)).is_regular());
+        assert!(!AutomorphismGroup::new(0, vec![]).is_regular());
+        assert!(AutomorphismGroup::new(1, vec![]).is_regular());
+    }
+
+    #[test]
+    fn fixed_point_free() {
+        log_init();
+
+        for n in 3..8 {
+            let group = AutomorphismGroup::from(cycle(n));
+            let perm = group.fixed_point_free_element().unwrap();
+            assert!((0..n as usize).all(|v| perm.image(v) != v));
+            assert!(is_automorphism(&cycle(n), &perm));
+        }
+        // the product of two reflections with fixed points is a
+        // fixed-point-free rotation
+        let reflections = [
+            Permutation::new(vec![0, 3, 2, 1]),
+            Permutation::new(vec![1, 0, 3, 2]),
+        ];
+        let group = AutomorphismGroup::new(4, reflections[..1].to_vec());
+        assert_eq!(group.fixed_point_free_element(), None);
+        let group = AutomorphismGroup::new(4, reflections.to_vec());
+        assert!(group.fixed_point_free_element().is_some());
+
+        let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        assert!(!has_fixed_point_free_automorphism(&star));
+        let mut g = UnGraph::<(), ()>::default();
+        g.add_node(());
+        assert!(!has_fixed_point_free_automorphism(&g));
+        // the cube with pendant vertices at two antipodal vertices
+        let mut cube = UnGraph::<(), ()>::from_edges(
+            (0..8u32)
+                .flat_map(|v| [1, 2, 4].map(|b| (v, v ^ b)))
+                .filter(|(v, w)| v < w),
+        );
+        cube.extend_with_edges([(0, 8), (7, 9)]);
+        assert!(has_fixed_point_free_automorphism(&cube));
+    }
+
+    #[test]
+    fn automorphisms() {
+        log_init();
+
+        let g = UnGraph::<u8, u8>::from_edges([(0, 1, 0), (1, 2, 1)]);
+        assert!(is_automorphism(&g, &Permutation::identity(3)));
+        let reflection = Permutation::new(vec![2, 1, 0]);
+        assert!(!is_automorphism(&g, &reflection));
+        let g = UnGraph::<u8, u8>::from_edges([(0, 1, 1), (2, 1, 1)]);
+        assert!(is_automorphism(&g, &reflection));
+        let d = petgraph::graph::DiGraph::<u8, u8>::from_edges([
+            (0, 1, 1),
+            (1, 2, 1),
+        ]);
+        assert!(!is_automorphism(&d, &reflection));
+
+        // multiple edges
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 1), (1, 2)]);
+        assert!(!is_automorphism(&g, &reflection));
+    }
+
+    #[test]
+    fn same_group() {
+        log_init();
+
+        // the cycle and its complement, with different edge types
+        let c5 = cycle(5);
+        let complement = petgraph::graph::DiGraph::<(), ()>::from_edges(
+            (0..5).flat_map(|i| [(i, (i + 2) % 5), ((i + 2) % 5, i)]),
+        );
+        assert!(same_automorphism_group(&c5, &complement));
+        // a subgroup
+        let oriented = petgraph::graph::DiGraph::<(), ()>::from_edges(
+            (0..5).map(|i| (i, (i + 1) % 5)),
+        );
+        assert!(!same_automorphism_group(&c5, &oriented));
+        assert!(!same_automorphism_group(&oriented, &c5));
+        assert!(!same_automorphism_group(&c5, &cycle(6)));
+    }
+
+    #[test]
+    fn primitive() {
+        log_init();
+
+        // dihedral groups of prime degree are primitive
+        assert!(AutomorphismGroup::from(cycle(7)).is_primitive());
+        assert!(!AutomorphismGroup::from(cycle(8)).is_primitive());
+        // the Petersen graph
+        #[rustfmt::skip]
+        let petersen = UnGraph::<(), ()>::from_edges([
+            (0, 1), (1, 2), (2, 3), (3, 4), (4, 0),
+            (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+            (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+        ]);
+        assert!(AutomorphismGroup::from(petersen).is_primitive());
+        // the cube preserves pairs of antipodal vertices
+        let cube = UnGraph::<(), ()>::from_edges(
+            (0..8u32)
+                .flat_map(|v| [1, 2, 4].map(|b| (v, v ^ b)))
+                .filter(|(v, w)| v < w),
+        );
+        let group = AutomorphismGroup::from(cube);
+        assert!(group.is_transitive());
+        assert!(!group.is_primitive());
+        // not transitive
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        assert!(!AutomorphismGroup::from(path).is_primitive());
+    }
+}
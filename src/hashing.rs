@@ -0,0 +1,467 @@
+//! Canonical hashes for deduplicating large graph collections
+//!
+//! A canonical hash is a 128-bit key computed from the canonical form
+//! of a graph, so isomorphic graphs always have the same key, and
+//! non-isomorphic graphs have different keys with overwhelming
+//! probability. In contrast to [CanonGraph], only the key has to be
+//! stored.
+//!
+//! Node and edge features should be turned into compact colours
+//! first, for example with a [ColorEncoder](crate::color::ColorEncoder).
+//!
+//! For pre-bucketing very large collections, the
+//! Weisfeiler-Leman hash [wl_hash] is cheaper, since it does not call
+//! nauty. It is also the same for isomorphic graphs, but many
+//! non-isomorphic graphs share the same Weisfeiler-Leman hash.
+//!
+//! # Stability
+//!
+//! Keys do not depend on the order of vertices and edges, the
+//! process, or the number of threads. They are computed with 128-bit
+//! FNV-1a from the canonical form or the colour refinement, and the
+//! [Hash] implementation of the node and edge weights. Integers are
+//! fed to the hash function in little-endian byte order, with
+//! `usize` and `isize` widened to 64 bits, so keys are the same on
+//! all platforms.
+//!
+//! For weights that are primitive integers, machines deduplicating
+//! graphs together only have to agree on the version of this crate.
+//! Other weights are hashed with the bytes their [Hash]
+//! implementations produce. The standard library does not guarantee
+//! that these stay the same between Rust releases, for example for
+//! strings, derived implementations for enums, and collections, so
+//! the machines also have to use the same Rust version.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::hashing::canonical_hash;
+//!
+//! let g = UnGraph::<u64, ()>::from_edges([(0, 1), (1, 2)]);
+//! let h = UnGraph::<u64, ()>::from_edges([(2, 0), (1, 2)]);
+//! assert_eq!(canonical_hash(&g), canonical_hash(&h));
+//! ```
+use std::hash::{Hash, Hasher};
+
+use crate::cancel;
+use crate::canon::canon_dense_graph;
+use crate::nauty_graph::borrowed;
+
+use petgraph::{
+    graph::{Graph, IndexType},
+    visit::EdgeRef,
+    EdgeType,
+};
+
+#[cfg(feature = "rayon")]
+use rayon::iter::ParallelIterator;
+
+/// Canonical 128-bit hash of a graph
+///
+/// See the [module documentation](self) for details.
+pub fn canonical_hash<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> u128
+where
+    N: Hash + Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    canon_hash(borrowed(g))
+}
+
+/// Canonical 128-bit hash of a graph, taking ownership
+///
+/// The result is the same as for [canonical_hash], which has to copy
+/// the structure of `g`, but not its weights. The hash is computed
+/// directly from the output of nauty, without building the canonical
+/// graph.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::hashing::{canon_hash, canonical_hash};
+///
+/// let g = UnGraph::<u64, ()>::from_edges([(0, 1), (1, 2)]);
+/// assert_eq!(canonical_hash(&g), canon_hash(g));
+/// ```
+pub fn canon_hash<N, E, Ty, Ix>(g: Graph<N, E, Ty, Ix>) -> u128
+where
+    N: Hash + Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let (dg, _) = cancel::unwrap(canon_dense_graph(g));
+    let (nodes, edges) = dg.canon_parts();
+    let mut hasher = Fnv128::default();
+    hasher.write_u8(Ty::is_directed().into());
+    hasher.write(&(nodes.len() as u64).to_le_bytes());
+    hasher.write(&(edges.len() as u64).to_le_bytes());
+    for w in nodes {
+        w.hash(&mut hasher);
+    }
+    for (source, target, w) in edges {
+        hasher.write(&(source as u64).to_le_bytes());
+        hasher.write(&(target as u64).to_le_bytes());
+        w.hash(&mut hasher);
+    }
+    hasher.0
+}
+
+/// Canonical 128-bit hashes of many graphs in parallel
+///
+/// The `i`th entry of the result is the [canonical_hash] of the
+/// `i`th graph.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use rayon::prelude::*;
+/// use nauty_pet::hashing::canonical_hashes;
+///
+/// let graphs = vec![
+///     UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]),
+///     UnGraph::<(), ()>::from_edges([(0, 1), (0, 2)]),
+/// ];
+/// let hashes = canonical_hashes(graphs.into_par_iter());
+/// assert_eq!(hashes[0], hashes[1]);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn canonical_hashes<I, N, E, Ty, Ix>(graphs: I) -> Vec<u128>
+where
+    I: ParallelIterator<Item = Graph<N, E, Ty, Ix>>,
+    N: Clone + Hash + Ord + Send,
+    E: Clone + Hash + Ord + Send,
+    Ty: EdgeType + Send,
+    Ix: IndexType + Send,
+{
+    graphs.map(canon_hash).collect()
+}
+
+/// Weisfeiler-Leman hash of a graph
+///
+/// Each vertex starts with the hash of its weight as colour. In each
+/// of the `iterations` rounds of colour refinement, the new colour of
+/// a vertex is the hash of its old colour and the sorted list of its
+/// incident edges, each given by the edge weight, the direction, and
+/// the colour of the other endpoint. The result is the hash of the
+/// sorted final colours.
+///
+/// Isomorphic graphs have the same hash, so graphs with different
+/// hashes are never isomorphic. The converse does not hold, even
+/// with many iterations. For example, all regular graphs with the
+/// same numbers of vertices and edges have the same hash if they
+/// are unweighted.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::hashing::wl_hash;
+///
+/// let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+/// let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+/// assert_ne!(wl_hash(&path, 1), wl_hash(&star, 1));
+///
+/// // two triangles cannot be distinguished from the six-cycle
+/// let cycle = UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+/// let triangles = UnGraph::<(), ()>::from_edges([
+///     (0, 1),
+///     (1, 2),
+///     (2, 0),
+///     (3, 4),
+///     (4, 5),
+///     (5, 3),
+/// ]);
+/// assert_eq!(wl_hash(&cycle, 10), wl_hash(&triangles, 10));
+/// ```
+pub fn wl_hash<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>, iterations: usize) -> u128
+where
+    N: Hash,
+    E: Hash,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut colours = Vec::from_iter(g.node_weights().map(fnv_hash));
+    let edge_colours = Vec::from_iter(g.edge_weights().map(fnv_hash));
+    // for undirected graphs, both endpoints see an outgoing edge
+    let incoming = u8::from(g.is_directed());
+    let mut incident = vec![Vec::new(); g.node_count()];
+    for _ in 0..iterations {
+        for e in g.edge_references() {
+            let (source, target) = (e.source().index(), e.target().index());
+            let colour = edge_colours[e.id().index()];
+            incident[source].push((0, colour, colours[target]));
+            incident[target].push((incoming, colour, colours[source]));
+        }
+        for (colour, incident) in colours.iter_mut().zip(&mut incident) {
+            incident.sort_unstable();
+            let mut hasher = Fnv128::default();
+            hasher.write_u128(*colour);
+            for (direction, edge_colour, nbr_colour) in incident.drain(..) {
+                hasher.write_u8(direction);
+                hasher.write_u128(edge_colour);
+                hasher.write_u128(nbr_colour);
+            }
+            *colour = hasher.0;
+        }
+    }
+    colours.sort_unstable();
+    let mut hasher = Fnv128::default();
+    hasher.write_u8(g.is_directed().into());
+    hasher.write(&(g.node_count() as u64).to_le_bytes());
+    hasher.write(&(g.edge_count() as u64).to_le_bytes());
+    for colour in colours {
+        hasher.write_u128(colour);
+    }
+    hasher.0
+}
+
+fn fnv_hash<T: Hash>(t: &T) -> u128 {
+    let mut hasher = Fnv128::default();
+    t.hash(&mut hasher);
+    hasher.0
+}
+
+// 128-bit FNV-1a
+struct Fnv128(u128);
+
+impl Default for Fnv128 {
+    fn default() -> Self {
+        Self(0x6c62272e07bb014262b821756295c58d)
+    }
+}
+
+impl Hasher for Fnv128 {
+    fn write(&mut self, bytes: &[u8]) {
+        const PRIME: u128 = 0x0000000001000000000000000000013b;
+        for &byte in bytes {
+            self.0 ^= u128::from(byte);
+            self.0 = self.0.wrapping_mul(PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        (self.0 ^ (self.0 >> 64)) as u64
+    }
+
+    // the default implementations use the native byte order and
+    // pointer width
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    // sign-extended, unlike the default implementation
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::{DiGraph, NodeIndex, UnGraph};
+    use petgraph::{Directed, Undirected};
+    use rand::SeedableRng;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn fnv() {
+        log_init();
+
+        // reference values for FNV-1a 128
+        let mut hasher = Fnv128::default();
+        assert_eq!(hasher.0, 0x6c62272e07bb014262b821756295c58d);
+        hasher.write(b"a");
+        assert_eq!(hasher.0, 0xd228cb696f1a8caf78912b704e4a8964);
+    }
+
+    #[test]
+    fn negative() {
+        log_init();
+
+        let mut hasher = Fnv128::default();
+        hasher.write_isize(-2);
+        let mut expected = Fnv128::default();
+        expected.write(&(-2i64).to_le_bytes());
+        assert_eq!(hasher.0, expected.0);
+
+        let g = UnGraph::<isize, isize>::from_edges([(0, 1, -1), (1, 2, 3)]);
+        let h = UnGraph::<i64, i64>::from_edges([(0, 1, -1), (1, 2, 3)]);
+        assert_eq!(canonical_hash(&g), canonical_hash(&h));
+    }
+
+    #[test]
+    fn portable() {
+        log_init();
+
+        let mut g = UnGraph::<u32, ()>::default();
+        g.add_node(1);
+        assert_eq!(canonical_hash(&g), 0xb18336facbd8d854440650dd7226db8f);
+        let d = DiGraph::<(), ()>::default();
+        assert_eq!(canonical_hash(&d), 0x3b824b98c8a437692eaffa8d346ff244);
+
+        // independent of the pointer width
+        let g = UnGraph::<usize, ()>::from_edges([(0, 1)]);
+        let h = UnGraph::<u64, ()>::from_edges([(0, 1)]);
+        assert_eq!(canonical_hash(&g), canonical_hash(&h));
+    }
+
+    #[test]
+    fn invariance() {
+        log_init();
+
+        let g =
+            UnGraph::<u8, u8>::from_edges([(0, 1, 1), (1, 2, 0), (2, 3, 1)]);
+        let mut h =
+            UnGraph::<u8, u8>::from_edges([(3, 2, 1), (0, 2, 0), (1, 0, 1)]);
+        assert_eq!(canonical_hash(&g), canonical_hash(&h));
+
+        // weights, edges and directions make a difference
+        h[NodeIndex::new(0)] = 1;
+        assert_ne!(canonical_hash(&g), canonical_hash(&h));
+        let h =
+            UnGraph::<u8, u8>::from_edges([(0, 1, 1), (1, 2, 1), (2, 3, 1)]);
+        assert_ne!(canonical_hash(&g), canonical_hash(&h));
+        let d =
+            DiGraph::<u8, u8>::from_edges([(0, 1, 1), (1, 2, 0), (2, 3, 1)]);
+        assert_ne!(canonical_hash(&g), canonical_hash(&d));
+    }
+
+    // hash of the canonical graph built by nauty-pet
+    fn hash_canon_graph<N, E, Ty>(g: Graph<N, E, Ty>) -> u128
+    where
+        N: Hash + Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+    {
+        use crate::graph::CanonGraph;
+
+        let g = CanonGraph::from(g);
+        let mut hasher = Fnv128::default();
+        hasher.write_u8(g.is_directed().into());
+        hasher.write(&(g.node_count() as u64).to_le_bytes());
+        hasher.write(&(g.edge_count() as u64).to_le_bytes());
+        for w in g.node_weights() {
+            w.hash(&mut hasher);
+        }
+        for e in g.edge_references() {
+            hasher.write(&(e.source().index() as u64).to_le_bytes());
+            hasher.write(&(e.target().index() as u64).to_le_bytes());
+            e.weight().hash(&mut hasher);
+        }
+        hasher.0
+    }
+
+    #[test]
+    fn canon_graph() {
+        log_init();
+
+        // loops and multiple edges
+        let g = UnGraph::<u8, u8>::from_edges([
+            (0, 1, 2),
+            (1, 0, 1),
+            (1, 1, 3),
+            (2, 3, 0),
+            (3, 3, 1),
+            (3, 3, 0),
+        ]);
+        assert_eq!(canon_hash(g.clone()), hash_canon_graph(g));
+        let d = DiGraph::<u8, u8>::from_edges([
+            (0, 1, 2),
+            (1, 0, 1),
+            (2, 2, 3),
+            (2, 0, 0),
+            (1, 2, 0),
+        ]);
+        assert_eq!(canon_hash(d.clone()), hash_canon_graph(d));
+        let empty = UnGraph::<(), ()>::default();
+        assert_eq!(canon_hash(empty.clone()), hash_canon_graph(empty));
+    }
+
+    #[test]
+    fn weisfeiler_leman() {
+        use testing::{randomize_labels, GraphIter};
+
+        log_init();
+
+        let mut rng = rand_xoshiro::Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(200) {
+            let gg = randomize_labels(g.clone(), &mut rng);
+            assert_eq!(wl_hash(&g, 3), wl_hash(&gg, 3));
+        }
+        for g in GraphIter::<Undirected>::default().take(200) {
+            let gg = randomize_labels(g.clone(), &mut rng);
+            assert_eq!(wl_hash(&g, 3), wl_hash(&gg, 3));
+        }
+
+        // weights and directions make a difference
+        let g = UnGraph::<u8, u8>::from_edges([(0, 1, 1), (1, 2, 0)]);
+        let mut h = UnGraph::<u8, u8>::from_edges([(0, 1, 0), (1, 2, 0)]);
+        assert_ne!(wl_hash(&g, 1), wl_hash(&h, 1));
+        h[NodeIndex::new(0)] = 1;
+        assert_ne!(wl_hash(&g, 1), wl_hash(&h, 1));
+        let d = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let e = DiGraph::<(), ()>::from_edges([(0, 1), (2, 1)]);
+        assert_ne!(wl_hash(&d, 1), wl_hash(&e, 1));
+
+        // without refinement, only the node weights count
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(wl_hash(&path, 0), wl_hash(&star, 0));
+        assert_ne!(wl_hash(&path, 1), wl_hash(&star, 1));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        use rayon::prelude::*;
+
+        log_init();
+
+        let graphs = Vec::from_iter((0..20).map(|n| {
+            UnGraph::<(), ()>::from_edges((0..n).map(|i| (i, i + 1)))
+        }));
+        let expected = Vec::from_iter(graphs.iter().map(canonical_hash));
+        assert_eq!(canonical_hashes(graphs.into_par_iter()), expected);
+    }
+}
@@ -0,0 +1,107 @@
+//! Rust closures as user-defined procedures of nauty
+//!
+//! nauty only passes its own arguments to user-defined procedures,
+//! so the closures are stored in thread-local variables for the
+//! duration of a call. Returning [ControlFlow::Break] from a closure
+//! or panicking stops the search, and panics are resumed once nauty
+//! has returned. The procedures observing the search tree are in
+//! [search](crate::search).
+use std::any::Any;
+use std::cell::Cell;
+use std::ops::ControlFlow;
+use std::os::raw::c_int;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use nauty_Traces_sys::optionblk;
+
+use crate::kill;
+
+thread_local! {
+    // the closure called for each generator, with its type erased
+    static AUTOMPROC: Cell<*mut ()> = Cell::new(ptr::null_mut());
+    // whether a closure stopped the search
+    static STOPPED: Cell<bool> = Cell::new(false);
+    // panic raised by a closure
+    static PANIC: Cell<Option<Box<dyn Any + Send>>> = Cell::new(None);
+}
+
+/// Run nauty with `automproc` called for each generator of the
+/// automorphism group
+///
+/// Each generator is passed as a permutation of nauty's vertices.
+/// Returns `None` if `automproc` stopped the search.
+pub(crate) fn with_automproc<F, R>(
+    mut options: optionblk,
+    automproc: &mut F,
+    run: impl FnOnce(optionblk) -> R,
+) -> Option<R>
+where
+    F: FnMut(&[c_int]) -> ControlFlow<()>,
+{
+    options.userautomproc = Some(automproc_trampoline::<F>);
+    let f = automproc as *mut F as *mut ();
+    let prev = AUTOMPROC.with(|p| p.replace(f));
+    let res = run(options);
+    AUTOMPROC.with(|p| p.set(prev));
+    finish(res)
+}
+
+/// Resume a panic raised by a closure, or return `None` if a closure
+/// stopped the search
+pub(crate) fn finish<R>(res: R) -> Option<R> {
+    if let Some(payload) = PANIC.with(Cell::take) {
+        STOPPED.with(|s| s.set(false));
+        panic::resume_unwind(payload);
+    }
+    if STOPPED.with(|s| s.replace(false)) {
+        None
+    } else {
+        Some(res)
+    }
+}
+
+extern "C" fn automproc_trampoline<F>(
+    _count: c_int,
+    perm: *mut c_int,
+    _orbits: *mut c_int,
+    _numorbits: c_int,
+    _stabvertex: c_int,
+    n: c_int,
+) where
+    F: FnMut(&[c_int]) -> ControlFlow<()>,
+{
+    call(|| {
+        let f = AUTOMPROC.with(Cell::get) as *mut F;
+        // SAFETY: nauty passes a permutation of its `n` vertices, and
+        // `f` was set by `with_automproc` for the duration of the call
+        unsafe { (*f)(slice::from_raw_parts(perm, n as usize)) }
+    })
+}
+
+/// Call a closure from a user-defined procedure, unless the search
+/// is already being stopped
+pub(crate) fn call(f: impl FnOnce() -> ControlFlow<()>) {
+    if try_call(f).is_break() {
+        kill::stop_current();
+    }
+}
+
+/// Call a closure from a user-defined procedure that stops the
+/// search through its return value
+///
+/// Returns [ControlFlow::Break] if the search should stop.
+pub(crate) fn try_call(f: impl FnOnce() -> ControlFlow<()>) -> ControlFlow<()> {
+    if STOPPED.with(Cell::get) {
+        return ControlFlow::Break(());
+    }
+    let flow = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|e| {
+        PANIC.with(|p| p.set(Some(e)));
+        ControlFlow::Break(())
+    });
+    if flow.is_break() {
+        STOPPED.with(|s| s.set(true));
+    }
+    flow
+}
@@ -0,0 +1,523 @@
+//! Canonical forms and automorphisms ignoring weights or directions
+//!
+//! Wrapping the node or edge weights in [Ignored] makes them all
+//! compare equal, so canonical forms and automorphisms only depend on
+//! the structure of the graph. The weights are moved rather than
+//! cloned, and still present in the canonical form. They do not even
+//! have to be ordered.
+//!
+//! Since all weights compare equal, the canonical form with the
+//! weights restored is only unique up to the weights. Two graphs
+//! with the same structure can give canonical forms that differ in
+//! the node weights.
+//!
+//! Similarly, [ignore_direction] reinterprets a directed graph as an
+//! undirected one, and [try_canon_ignoring_direction] computes a
+//! canonical form that is unique up to the orientation of the edges.
+//!
+//! For weights that are not ordered, such as floating-point numbers,
+//! [with_keys] pairs each weight with a key extracted from it. Only
+//! the keys are compared, and [try_canon_by_key] returns a canonical
+//! form with the original weights.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::ignore::{ignore_node_weights, try_canon_ignoring_node_weights};
+//! use nauty_pet::prelude::*;
+//!
+//! let g = UnGraph::<f64, ()>::from_edges([(0, 1), (1, 2)]);
+//! let g = g.map(|v, _| v.index() as f64, |_, &e| e);
+//!
+//! // the path has two automorphisms, whatever the weights
+//! let autom = ignore_node_weights(g.clone()).try_into_autom().unwrap();
+//! assert_eq!(autom.grpsize(), 2.);
+//!
+//! // the centre of the path keeps its weight
+//! let canon = try_canon_ignoring_node_weights(g).unwrap();
+//! let centre = canon.node_indices().find(|&v| canon.edges(v).count() == 2);
+//! assert_eq!(canon[centre.unwrap()], 1.);
+//! ```
+//!
+//! Comparing directed graphs up to orientation:
+//!
+//! ```rust
+//! use petgraph::graph::DiGraph;
+//! use petgraph::visit::EdgeRef;
+//! use nauty_pet::ignore::{ignore_direction, try_canon_ignoring_direction};
+//! use nauty_pet::prelude::*;
+//!
+//! let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+//! let h = DiGraph::<(), ()>::from_edges([(0, 1), (2, 1)]);
+//!
+//! // the canonical forms agree up to the orientation of the edges
+//! let endpoints = |g: DiGraph<(), ()>| {
+//!     let g = try_canon_ignoring_direction(g).unwrap();
+//!     Vec::from_iter(g.edge_references().map(|e| {
+//!         let [s, t] = [e.source(), e.target()].map(|v| v.index());
+//!         (s.min(t), s.max(t))
+//!     }))
+//! };
+//! assert_eq!(endpoints(g.clone()), endpoints(h.clone()));
+//!
+//! let g_autom = g.try_into_autom().unwrap();
+//! let h_autom = ignore_direction(h).try_into_autom().unwrap();
+//! assert_eq!(g_autom.grpsize(), 1.);
+//! assert_eq!(h_autom.grpsize(), 2.);
+//! ```
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use crate::canon::{try_canon_with_labelling, TryIntoCanon};
+use crate::error::NautyError;
+
+use petgraph::{
+    graph::{Graph, IndexType, NodeIndex, UnGraph},
+    EdgeType, Undirected,
+};
+
+/// Weight wrapper comparing equal to all other weights
+///
+/// All instances are equal and have the same hash, irrespective of
+/// the wrapped value.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Ignored<T>(pub T);
+
+impl<T> PartialEq for Ignored<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for Ignored<T> {}
+
+impl<T> PartialOrd for Ignored<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Ignored<T> {
+    fn cmp(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl<T> Hash for Ignored<T> {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Wrap all node weights in [Ignored]
+///
+/// The weights are moved, not cloned.
+pub fn ignore_node_weights<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Graph<Ignored<N>, E, Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    map_owned(g, Ignored, |e| e)
+}
+
+/// Unwrap all node weights
+///
+/// This undoes [ignore_node_weights] without cloning the weights.
+pub fn restore_node_weights<N, E, Ty, Ix>(
+    g: Graph<Ignored<N>, E, Ty, Ix>,
+) -> Graph<N, E, Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    map_owned(g, |n| n.0, |e| e)
+}
+
+/// Canonical form of `g` treating all vertices as having the same
+/// colour
+///
+/// The node weights are carried over to the canonical form. See the
+/// [module documentation](self) for details.
+pub fn try_canon_ignoring_node_weights<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Result<Graph<N, E, Ty, Ix>, NautyError>
+where
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let canon = ignore_node_weights(g).try_into_canon()?;
+    Ok(restore_node_weights(canon))
+}
+
+/// Wrap all edge weights in [Ignored]
+///
+/// The weights are moved, not cloned.
+pub fn ignore_edge_weights<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Graph<N, Ignored<E>, Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    map_owned(g, |n| n, Ignored)
+}
+
+/// Unwrap all edge weights
+///
+/// This undoes [ignore_edge_weights] without cloning the weights.
+pub fn restore_edge_weights<N, E, Ty, Ix>(
+    g: Graph<N, Ignored<E>, Ty, Ix>,
+) -> Graph<N, E, Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    map_owned(g, |n| n, |e| e.0)
+}
+
+/// Canonical form of `g` treating all edges as having the same
+/// colour
+///
+/// The edge weights are carried over to the canonical form. See the
+/// [module documentation](self) for details.
+pub fn try_canon_ignoring_edge_weights<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Result<Graph<N, E, Ty, Ix>, NautyError>
+where
+    N: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let canon = ignore_edge_weights(g).try_into_canon()?;
+    Ok(restore_edge_weights(canon))
+}
+
+/// Reinterpret `g` as an undirected graph
+///
+/// The graph is not rebuilt. An edge from `u` to `v` becomes an
+/// undirected edge between `u` and `v`, so pairs of opposite edges
+/// become parallel edges.
+pub fn ignore_direction<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> UnGraph<N, E, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    g.into_edge_type()
+}
+
+/// Canonical form of `g` ignoring the direction of its edges
+///
+/// The vertices and edges are in the canonical order of
+/// [ignore_direction]`(g)`, but each edge keeps its original
+/// orientation. Two graphs that only differ in the orientation of
+/// their edges therefore give canonical forms that also only differ
+/// in the orientation of their edges.
+pub fn try_canon_ignoring_direction<N, E, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+) -> Result<Graph<N, E, Ty, Ix>, NautyError>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    // remember the original source of each edge
+    let (nodes, edges) = g.into_nodes_edges();
+    let mut undirected =
+        Graph::<_, _, Undirected, Ix>::with_capacity(nodes.len(), edges.len());
+    for node in nodes {
+        undirected.add_node(node.weight);
+    }
+    for edge in edges {
+        let source = edge.source();
+        undirected.add_edge(
+            source,
+            edge.target(),
+            (edge.weight, Ignored(source)),
+        );
+    }
+
+    let (canon, labelling) = try_canon_with_labelling(undirected)?;
+    let (nodes, edges) = canon.into_nodes_edges();
+    let mut res = Graph::with_capacity(nodes.len(), edges.len());
+    for node in nodes {
+        res.add_node(node.weight);
+    }
+    for edge in edges {
+        let (weight, Ignored(source)) = edge.weight;
+        let source = NodeIndex::new(labelling[source.index()]);
+        let target = if source == edge.source() {
+            edge.target()
+        } else {
+            edge.source()
+        };
+        res.add_edge(source, target, weight);
+    }
+    Ok(res)
+}
+
+/// Pair each node and edge weight with a key
+///
+/// The keys are computed with `node_key` and `edge_key`, and the
+/// weights are wrapped in [Ignored], so that only the keys matter
+/// for canonical forms and automorphisms. Use [strip_keys] to
+/// recover the original weights.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::ignore::with_keys;
+/// use nauty_pet::prelude::*;
+///
+/// let g = UnGraph::<f64, f64>::from_edges([(0, 1, 0.5), (1, 2, 0.5)]);
+/// let g = g.map(|v, _| [0.1, 0.3, 0.1][v.index()], |_, &w| w);
+/// // quantise the weights to one decimal place
+/// let quantise = |w: &f64| (w * 10.).round() as i64;
+/// let autom = with_keys(g, quantise, quantise).try_into_autom().unwrap();
+/// assert_eq!(autom.grpsize(), 2.);
+/// ```
+pub fn with_keys<N, E, Ty, Ix, K, L, F, G>(
+    g: Graph<N, E, Ty, Ix>,
+    mut node_key: F,
+    mut edge_key: G,
+) -> Graph<(K, Ignored<N>), (L, Ignored<E>), Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+    F: FnMut(&N) -> K,
+    G: FnMut(&E) -> L,
+{
+    map_owned(
+        g,
+        |n| (node_key(&n), Ignored(n)),
+        |e| (edge_key(&e), Ignored(e)),
+    )
+}
+
+/// Remove the keys added by [with_keys]
+pub fn strip_keys<N, E, Ty, Ix, K, L>(
+    g: Graph<(K, Ignored<N>), (L, Ignored<E>), Ty, Ix>,
+) -> Graph<N, E, Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    map_owned(g, |(_, n)| n.0, |(_, e)| e.0)
+}
+
+/// Canonical form of `g` comparing weights by the given keys
+///
+/// Two vertices have the same colour if `node_key` returns the same
+/// key for their weights, and analogously for edges. The original
+/// weights are carried over to the canonical form, without
+/// requiring them to be ordered or hashable. As for
+/// [try_canon_ignoring_node_weights], the result is only unique up to
+/// weights with the same key.
+pub fn try_canon_by_key<N, E, Ty, Ix, K, L, F, G>(
+    g: Graph<N, E, Ty, Ix>,
+    node_key: F,
+    edge_key: G,
+) -> Result<Graph<N, E, Ty, Ix>, NautyError>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+    K: Ord,
+    L: Hash + Ord,
+    F: FnMut(&N) -> K,
+    G: FnMut(&E) -> L,
+{
+    let canon = with_keys(g, node_key, edge_key).try_into_canon()?;
+    Ok(strip_keys(canon))
+}
+
+// like `Graph::map`, but taking ownership of the weights
+pub(crate) fn map_owned<N, M, E, F, Ty, Ix>(
+    g: Graph<N, E, Ty, Ix>,
+    mut node_map: impl FnMut(N) -> M,
+    mut edge_map: impl FnMut(E) -> F,
+) -> Graph<M, F, Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let (nodes, edges) = g.into_nodes_edges();
+    let mut res = Graph::with_capacity(nodes.len(), edges.len());
+    for node in nodes {
+        res.add_node(node_map(node.weight));
+    }
+    for edge in edges {
+        res.add_edge(edge.source(), edge.target(), edge_map(edge.weight));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autom::TryIntoAutom;
+    use crate::cmp::IsIdentical;
+    use crate::graph::CanonGraph;
+    use petgraph::graph::DiGraph;
+    use petgraph::visit::EdgeRef;
+    use petgraph::Directed;
+    use rand::SeedableRng;
+    use testing::{randomize_labels, GraphIter};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn round_trip() {
+        log_init();
+
+        for g in GraphIter::<Directed>::default().take(50) {
+            let h = restore_node_weights(ignore_node_weights(g.clone()));
+            assert!(g.is_identical(&h));
+        }
+    }
+
+    #[test]
+    fn canon() {
+        log_init();
+
+        let mut rng = rand_xoshiro::Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(100) {
+            let unweighted = g.map(|_, _| (), |_, &w| w);
+            let expected = CanonGraph::from(unweighted);
+
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let canon = try_canon_ignoring_node_weights(gg).unwrap();
+            // same structure as without node weights
+            assert!(canon.map(|_, _| (), |_, &w| w).is_identical(&expected));
+            // the weights are carried along
+            let mut weights = Vec::from_iter(canon.node_weights().copied());
+            let mut expected_weights =
+                Vec::from_iter(g.node_weights().copied());
+            weights.sort_unstable();
+            expected_weights.sort_unstable();
+            assert_eq!(weights, expected_weights);
+        }
+
+        // weights stay with their vertices
+        let g = UnGraph::<&str, ()>::from_edges([(0, 1), (1, 2), (1, 3)]);
+        let g = g.map(|v, _| ["a", "centre", "b", "c"][v.index()], |_, _| ());
+        let canon = try_canon_ignoring_node_weights(g).unwrap();
+        let centre = canon
+            .node_indices()
+            .find(|&v| canon[v] == "centre")
+            .unwrap();
+        assert_eq!(canon.edges(centre).count(), 3);
+        assert!(canon
+            .edge_references()
+            .all(|e| e.source() == centre || e.target() == centre));
+    }
+
+    #[test]
+    fn edge_weights() {
+        log_init();
+
+        let mut rng = rand_xoshiro::Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(100) {
+            let h = restore_edge_weights(ignore_edge_weights(g.clone()));
+            assert!(g.is_identical(&h));
+
+            let unweighted = g.map(|_, &w| w, |_, _| ());
+            let expected = CanonGraph::from(unweighted);
+
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let canon = try_canon_ignoring_edge_weights(gg).unwrap();
+            assert!(canon.map(|_, &w| w, |_, _| ()).is_identical(&expected));
+            let mut weights = Vec::from_iter(canon.edge_weights().copied());
+            let mut expected_weights =
+                Vec::from_iter(g.edge_weights().copied());
+            weights.sort_unstable();
+            expected_weights.sort_unstable();
+            assert_eq!(weights, expected_weights);
+        }
+    }
+
+    #[test]
+    fn direction() {
+        log_init();
+
+        let mut rng = rand_xoshiro::Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(100) {
+            let expected = CanonGraph::from(ignore_direction(g.clone()));
+
+            // reverse some of the edges
+            let (nodes, edges) =
+                randomize_labels(g.clone(), &mut rng).into_nodes_edges();
+            let mut gg = DiGraph::with_capacity(nodes.len(), edges.len());
+            for node in nodes {
+                gg.add_node(node.weight);
+            }
+            for (i, edge) in edges.into_iter().enumerate() {
+                let (s, t) = (edge.source(), edge.target());
+                if i % 2 == 0 {
+                    gg.add_edge(s, t, edge.weight);
+                } else {
+                    gg.add_edge(t, s, edge.weight);
+                }
+            }
+
+            let canon = try_canon_ignoring_direction(gg).unwrap();
+            assert!(canon.node_weights().eq(expected.node_weights()));
+            assert_eq!(canon.edge_count(), expected.edge_count());
+            for (e, f) in
+                canon.edge_references().zip(expected.edge_references())
+            {
+                let (s, t) = (e.source(), e.target());
+                assert_eq!((s.min(t), s.max(t)), (f.source(), f.target()));
+                assert_eq!(e.weight(), f.weight());
+            }
+        }
+
+        // orientations are kept
+        let g = DiGraph::<u8, ()>::from_edges([(0, 1), (2, 1), (2, 3)]);
+        let g = g.map(|v, _| v.index() as u8, |_, _| ());
+        let canon = try_canon_ignoring_direction(g.clone()).unwrap();
+        for e in canon.edge_references() {
+            let (s, t) = (canon[e.source()], canon[e.target()]);
+            let [s, t] = [s, t].map(|w| NodeIndex::new(w as usize));
+            assert!(g.find_edge(s, t).is_some());
+        }
+    }
+
+    #[test]
+    fn keys() {
+        log_init();
+
+        let mut rng = rand_xoshiro::Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(100) {
+            let h = strip_keys(with_keys(g.clone(), |&n| n, |&e| e));
+            assert!(g.is_identical(&h));
+
+            // keys that agree with the weights give the usual
+            // canonical form
+            let expected = CanonGraph::from(g.clone());
+            let gg = randomize_labels(g, &mut rng);
+            let gg = gg.map(|_, &w| f64::from(w), |_, &w| f64::from(w));
+            let canon =
+                try_canon_by_key(gg, |&w| w as u8, |&w| w as u8).unwrap();
+            let canon = canon.map(|_, &w| w as u8, |_, &w| w as u8);
+            assert!(canon.is_identical(&expected));
+        }
+
+        // weights with the same key are equivalent
+        let g = UnGraph::<f64, ()>::from_edges([(0, 1), (1, 2)]);
+        let g = g.map(|v, _| [0.9, 2., 1.1][v.index()], |_, _| ());
+        let canon = try_canon_by_key(g, |w| w.round() as i64, |_| ()).unwrap();
+        let centre = canon.node_indices().find(|&v| canon[v] == 2.).unwrap();
+        assert_eq!(canon.edges(centre).count(), 2);
+        let autom = with_keys(canon, |w| w.round() as i64, |_| ())
+            .try_into_autom()
+            .unwrap();
+        assert_eq!(autom.grpsize(), 2.);
+    }
+}
@@ -0,0 +1,174 @@
+//! Cheap isomorphism invariants
+//!
+//! Computing canonical forms with nauty is comparatively expensive.
+//! The invariants in this module are much cheaper and can rule out
+//! most pairs of non-isomorphic graphs before nauty is called. They
+//! are used by [find_isomorphism](crate::canon::find_isomorphism) and
+//! [are_isomorphic](crate::canon::are_isomorphic) unless disabled
+//! with [IsoOptions](crate::canon::IsoOptions).
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::invariant::{may_be_isomorphic, triangle_count};
+//!
+//! // the six-cycle and two triangles have the same degrees
+//! let cycle = UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+//! let triangles = UnGraph::<(), ()>::from_edges([
+//!     (0, 1),
+//!     (1, 2),
+//!     (2, 0),
+//!     (3, 4),
+//!     (4, 5),
+//!     (5, 3),
+//! ]);
+//! assert_eq!(triangle_count(&cycle), 0);
+//! assert_eq!(triangle_count(&triangles), 2);
+//! assert!(!may_be_isomorphic(&cycle, &triangles));
+//! ```
+use crate::subgraph::undirected_neighbours;
+
+use petgraph::{
+    graph::{Graph, IndexType},
+    visit::EdgeRef,
+    EdgeType,
+};
+
+/// Check whether `g` and `h` agree in a number of cheap invariants
+///
+/// The compared invariants are the numbers of nodes and edges, the
+/// sorted node weights together with the in- and out-degrees, the
+/// sorted edge weights, and the number of triangles. If this returns
+/// `false`, the graphs are not isomorphic. If it returns `true`, they
+/// may or may not be isomorphic.
+pub fn may_be_isomorphic<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    h: &Graph<N, E, Ty, Ix>,
+) -> bool
+where
+    N: Ord,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    g.node_count() == h.node_count()
+        && g.edge_count() == h.edge_count()
+        && node_profile(g) == node_profile(h)
+        && edge_profile(g) == edge_profile(h)
+        && triangle_count(g) == triangle_count(h)
+}
+
+/// The number of triangles in `g`
+///
+/// Edge directions, self-loops, and multiple edges are ignored.
+pub fn triangle_count<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> usize
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let adj = undirected_neighbours(g);
+    let mut count = 0;
+    // each triangle is counted once from its smallest vertex
+    for (u, nbrs) in adj.iter().enumerate() {
+        let larger = &nbrs[nbrs.partition_point(|&v| v <= u)..];
+        for (i, &v) in larger.iter().enumerate() {
+            count += larger[i + 1..]
+                .iter()
+                .filter(|&&w| adj[v].binary_search(&w).is_ok())
+                .count();
+        }
+    }
+    count
+}
+
+// sorted node weights with in- and out-degrees
+//
+// for undirected graphs, the in-degree is always zero and
+// self-loops count twice
+fn node_profile<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Vec<(&N, usize, usize)>
+where
+    N: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut degrees = vec![(0, 0); g.node_count()];
+    for e in g.edge_references() {
+        degrees[e.source().index()].0 += 1;
+        if g.is_directed() {
+            degrees[e.target().index()].1 += 1;
+        } else {
+            degrees[e.target().index()].0 += 1;
+        }
+    }
+    let mut profile = Vec::from_iter(
+        g.node_weights()
+            .zip(degrees)
+            .map(|(w, (out_deg, in_deg))| (w, out_deg, in_deg)),
+    );
+    profile.sort_unstable();
+    profile
+}
+
+fn edge_profile<N, E, Ty, Ix>(g: &Graph<N, E, Ty, Ix>) -> Vec<&E>
+where
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut weights = Vec::from_iter(g.edge_weights());
+    weights.sort_unstable();
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::{DiGraph, NodeIndex, UnGraph};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn triangles() {
+        log_init();
+
+        let complete = UnGraph::<(), ()>::from_edges(
+            (0..5).flat_map(|i| ((i + 1)..5).map(move |j| (i, j))),
+        );
+        assert_eq!(triangle_count(&complete), 10);
+        // directions, loops, and multiple edges are ignored
+        let d = DiGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 0),
+            (1, 2),
+            (0, 2),
+            (2, 2),
+        ]);
+        assert_eq!(triangle_count(&d), 1);
+        assert_eq!(triangle_count(&UnGraph::<(), ()>::default()), 0);
+    }
+
+    #[test]
+    fn prefilter() {
+        log_init();
+
+        let g = UnGraph::<u8, u8>::from_edges([(0, 1, 1), (1, 2, 0)]);
+        let mut h = UnGraph::<u8, u8>::from_edges([(2, 1, 1), (0, 2, 0)]);
+        assert!(may_be_isomorphic(&g, &h));
+        h[NodeIndex::new(0)] = 1;
+        assert!(!may_be_isomorphic(&g, &h));
+        let h = UnGraph::<u8, u8>::from_edges([(2, 1, 1), (0, 2, 1)]);
+        assert!(!may_be_isomorphic(&g, &h));
+        let h = UnGraph::<u8, u8>::from_edges([(0, 1, 1), (0, 0, 0)]);
+        assert!(!may_be_isomorphic(&g, &h));
+
+        // same degrees, but different directions
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let h = DiGraph::<(), ()>::from_edges([(0, 1), (2, 1)]);
+        assert!(!may_be_isomorphic(&g, &h));
+    }
+}
@@ -0,0 +1,370 @@
+//! Collections of graphs up to isomorphism
+use std::collections::btree_map::{self, BTreeMap};
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+
+use crate::graph::CanonGraph;
+use crate::srg::srg_parameters;
+use crate::IntoCanon;
+
+use petgraph::{
+    graph::{DefaultIx, Graph, IndexType},
+    Directed, EdgeType, Undirected,
+};
+
+/// Multiset of graphs up to isomorphism
+///
+/// Each isomorphism class is stored once as a canonically labelled
+/// graph, together with the number of times it was inserted.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::iso::IsoMultiset;
+///
+/// let mut graphs = IsoMultiset::new();
+/// graphs.insert(UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]));
+/// graphs.insert(UnGraph::<(), ()>::from_edges([(0, 1), (0, 2)]));
+/// graphs.insert(UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]));
+///
+/// assert_eq!(graphs.len(), 3);
+/// assert_eq!(graphs.num_classes(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct IsoMultiset<N, E, Ty: EdgeType = Directed, Ix: IndexType = DefaultIx>
+{
+    counts: BTreeMap<CanonGraph<N, E, Ty, Ix>, usize>,
+    len: usize,
+}
+
+impl<N, E, Ty: EdgeType, Ix: IndexType> Default for IsoMultiset<N, E, Ty, Ix> {
+    fn default() -> Self {
+        Self {
+            counts: BTreeMap::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<N, E, Ty, Ix> PartialEq for IsoMultiset<N, E, Ty, Ix>
+where
+    N: PartialEq,
+    E: PartialEq,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.counts == other.counts
+    }
+}
+impl<N: Eq, E: Eq, Ty: EdgeType, Ix: IndexType> Eq
+    for IsoMultiset<N, E, Ty, Ix>
+{
+}
+
+impl<N: Hash, E: Hash, Ty: EdgeType, Ix: IndexType> Hash
+    for IsoMultiset<N, E, Ty, Ix>
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.counts.hash(state)
+    }
+}
+
+impl<N, E, Ty: EdgeType, Ix: IndexType> IsoMultiset<N, E, Ty, Ix> {
+    /// Create an empty multiset
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total number of elements, counting multiplicities
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the multiset is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of distinct isomorphism classes
+    pub fn num_classes(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Iterate over the isomorphism classes and their multiplicities
+    pub fn iter(&self) -> Iter<'_, N, E, Ty, Ix> {
+        Iter(self.counts.iter())
+    }
+}
+
+impl<N, E, Ty, Ix> IsoMultiset<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    /// Insert a graph, returning the new multiplicity of its class
+    pub fn insert(&mut self, g: Graph<N, E, Ty, Ix>) -> usize
+    where
+        Graph<N, E, Ty, Ix>: IntoCanon,
+    {
+        self.insert_canon(g.into())
+    }
+
+    /// Insert a canonically labelled graph, returning the new
+    /// multiplicity of its class
+    pub fn insert_canon(&mut self, g: CanonGraph<N, E, Ty, Ix>) -> usize {
+        self.insert_canon_n(g, 1)
+    }
+
+    /// Insert `n` copies of a canonically labelled graph, returning
+    /// the new multiplicity of its class
+    pub fn insert_canon_n(
+        &mut self,
+        g: CanonGraph<N, E, Ty, Ix>,
+        n: usize,
+    ) -> usize {
+        self.len += n;
+        let count = self.counts.entry(g).or_default();
+        *count += n;
+        *count
+    }
+
+    /// Add all graphs from another multiset
+    ///
+    /// This can be used to combine partial results, for example from
+    /// [GraphGenerator::split](crate::generate::GraphGenerator::split).
+    pub fn merge(&mut self, other: Self) {
+        for (g, n) in other.counts {
+            self.insert_canon_n(g, n);
+        }
+    }
+
+    /// Multiplicity of the isomorphism class of a canonically
+    /// labelled graph
+    pub fn count(&self, g: &CanonGraph<N, E, Ty, Ix>) -> usize {
+        self.counts.get(g).copied().unwrap_or_default()
+    }
+
+    /// Check whether the isomorphism class of a canonically labelled
+    /// graph is contained in the multiset
+    pub fn contains(&self, g: &CanonGraph<N, E, Ty, Ix>) -> bool {
+        self.counts.contains_key(g)
+    }
+}
+
+impl<N, E, Ix: IndexType> IsoMultiset<N, E, Undirected, Ix> {
+    /// Iterate over the strongly regular isomorphism classes, their
+    /// parameters `(n, k, λ, μ)`, and their multiplicities
+    ///
+    /// See [srg_parameters] for the definition of the parameters.
+    pub fn strongly_regular(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            &CanonGraph<N, E, Undirected, Ix>,
+            (usize, usize, usize, usize),
+            usize,
+        ),
+    > + '_ {
+        self.iter().filter_map(|(g, n)| {
+            let params = srg_parameters(g)?;
+            Some((g, params, n))
+        })
+    }
+}
+
+/// Iterator over isomorphism classes and multiplicities of an [IsoMultiset]
+#[derive(Clone, Debug)]
+pub struct Iter<'a, N, E, Ty: EdgeType, Ix: IndexType>(
+    btree_map::Iter<'a, CanonGraph<N, E, Ty, Ix>, usize>,
+);
+
+impl<'a, N, E, Ty: EdgeType, Ix: IndexType> Iterator
+    for Iter<'a, N, E, Ty, Ix>
+{
+    type Item = (&'a CanonGraph<N, E, Ty, Ix>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(g, n)| (g, *n))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// Owning iterator over isomorphism classes and multiplicities of an [IsoMultiset]
+#[derive(Debug)]
+pub struct IntoIter<N, E, Ty: EdgeType, Ix: IndexType>(
+    btree_map::IntoIter<CanonGraph<N, E, Ty, Ix>, usize>,
+);
+
+impl<N, E, Ty: EdgeType, Ix: IndexType> Iterator for IntoIter<N, E, Ty, Ix> {
+    type Item = (CanonGraph<N, E, Ty, Ix>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<N, E, Ty: EdgeType, Ix: IndexType> IntoIterator
+    for IsoMultiset<N, E, Ty, Ix>
+{
+    type Item = (CanonGraph<N, E, Ty, Ix>, usize);
+    type IntoIter = IntoIter<N, E, Ty, Ix>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self.counts.into_iter())
+    }
+}
+
+impl<'a, N, E, Ty: EdgeType, Ix: IndexType> IntoIterator
+    for &'a IsoMultiset<N, E, Ty, Ix>
+{
+    type Item = (&'a CanonGraph<N, E, Ty, Ix>, usize);
+    type IntoIter = Iter<'a, N, E, Ty, Ix>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<N, E, Ty, Ix> Extend<Graph<N, E, Ty, Ix>> for IsoMultiset<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+    Graph<N, E, Ty, Ix>: IntoCanon,
+{
+    fn extend<I: IntoIterator<Item = Graph<N, E, Ty, Ix>>>(&mut self, iter: I) {
+        for g in iter {
+            self.insert(g);
+        }
+    }
+}
+
+impl<N, E, Ty, Ix> Extend<CanonGraph<N, E, Ty, Ix>>
+    for IsoMultiset<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    fn extend<I: IntoIterator<Item = CanonGraph<N, E, Ty, Ix>>>(
+        &mut self,
+        iter: I,
+    ) {
+        for g in iter {
+            self.insert_canon(g);
+        }
+    }
+}
+
+impl<N, E, Ty, Ix> FromIterator<Graph<N, E, Ty, Ix>>
+    for IsoMultiset<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+    Graph<N, E, Ty, Ix>: IntoCanon,
+{
+    fn from_iter<I: IntoIterator<Item = Graph<N, E, Ty, Ix>>>(iter: I) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+
+impl<N, E, Ty, Ix> FromIterator<CanonGraph<N, E, Ty, Ix>>
+    for IsoMultiset<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    fn from_iter<I: IntoIterator<Item = CanonGraph<N, E, Ty, Ix>>>(
+        iter: I,
+    ) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::{DiGraph, UnGraph};
+
+    #[test]
+    fn multiplicities() {
+        let mut graphs = IsoMultiset::new();
+        assert!(graphs.is_empty());
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let triangle = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(graphs.insert(path.clone()), 1);
+        assert_eq!(
+            graphs.insert(UnGraph::<(), ()>::from_edges([(1, 0), (0, 2)])),
+            2
+        );
+        assert_eq!(graphs.insert(triangle.clone()), 1);
+        assert_eq!(graphs.len(), 3);
+        assert_eq!(graphs.num_classes(), 2);
+        assert_eq!(graphs.count(&path.into()), 2);
+        assert_eq!(graphs.count(&triangle.into()), 1);
+        let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        assert!(!graphs.contains(&star.into()));
+    }
+
+    #[test]
+    fn collect() {
+        let graphs = IsoMultiset::from_iter([
+            DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]),
+            DiGraph::<(), ()>::from_edges([(1, 2), (2, 0)]),
+            DiGraph::<(), ()>::from_edges([(0, 1), (2, 1)]),
+        ]);
+        assert_eq!(graphs.len(), 3);
+        assert_eq!(graphs.num_classes(), 2);
+        let counts = Vec::from_iter(graphs.iter().map(|(_, n)| n));
+        assert_eq!(counts.iter().sum::<usize>(), 3);
+        let copies = graphs.clone().into_iter().flat_map(|(g, n)| vec![g; n]);
+        assert_eq!(graphs, copies.collect());
+    }
+
+    #[test]
+    fn merge() {
+        let path = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let cycle = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        let mut graphs = IsoMultiset::from_iter([path.clone(), cycle.clone()]);
+        graphs.merge(IsoMultiset::from_iter([path.clone(), path.clone()]));
+        assert_eq!(graphs.len(), 4);
+        assert_eq!(graphs.count(&path.into()), 3);
+        assert_eq!(graphs.count(&cycle.into()), 1);
+    }
+
+    #[test]
+    fn strongly_regular() {
+        let cycle = |n: u32| {
+            UnGraph::<(), ()>::from_edges((0..n).map(|i| (i, (i + 1) % n)))
+        };
+        let graphs =
+            IsoMultiset::from_iter([cycle(4), cycle(5), cycle(6), cycle(5)]);
+        let srgs = Vec::from_iter(graphs.strongly_regular());
+        assert_eq!(srgs.len(), 2);
+        let five_cycle = CanonGraph::from(cycle(5));
+        assert!(srgs.contains(&(&five_cycle, (5, 2, 0, 1), 2)));
+        let square = CanonGraph::from(cycle(4));
+        assert!(srgs.contains(&(&square, (4, 2, 0, 2), 1)));
+    }
+}
@@ -0,0 +1,255 @@
+//! Stopping running calls to nauty and Traces
+//!
+//! nauty and Traces return early when the variable
+//! `nauty_kill_request` is set. Unlike the rest of nauty's state,
+//! this variable is shared by all threads, so a request to stop one
+//! call also stops the calls running in other threads. The functions
+//! in this module wrap the entry points of nauty and Traces and
+//! repeat every call that was stopped without being asked to, either
+//! from a user-defined procedure or through a
+//! [CancellationToken](crate::cancel::CancellationToken).
+use std::cell::Cell;
+use std::os::raw::c_int;
+use std::ptr::addr_of_mut;
+use std::slice;
+use std::sync::Mutex;
+use std::thread;
+
+use nauty_Traces_sys::{graph, optionblk, sparsegraph, statsblk, NAUKILLED};
+#[cfg(feature = "traces")]
+use nauty_Traces_sys::{TracesOptions, TracesStats};
+
+use crate::cancel;
+use crate::error::NautyError;
+
+extern "C" {
+    // defined in nautil.c, deliberately without thread-local storage
+    static mut nauty_kill_request: c_int;
+}
+
+// number of outstanding kill requests
+static REQUESTS: Mutex<usize> = Mutex::new(0);
+
+thread_local! {
+    // whether a call is running in this thread
+    static RUNNING: Cell<bool> = Cell::new(false);
+    // whether the call running in this thread was asked to stop
+    static STOP: Cell<bool> = Cell::new(false);
+    // initial lab and ptn of the running call
+    static SAVED: Cell<Vec<c_int>> = Cell::new(Vec::new());
+    // resets the state collected by user-defined procedures
+    static RESET: Cell<Option<fn()>> = Cell::new(None);
+}
+
+/// Run `f`, calling `reset` before every repetition of the calls to
+/// nauty and Traces it makes
+///
+/// This discards what user-defined procedures collected during a
+/// call that was stopped on behalf of another thread.
+pub(crate) fn with_reset<R>(reset: fn(), f: impl FnOnce() -> R) -> R {
+    let prev = RESET.with(|r| r.replace(Some(reset)));
+    let res = f();
+    RESET.with(|r| r.set(prev));
+    res
+}
+
+// serialises the tests that stop calls to nauty and Traces
+#[cfg(test)]
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Keep other tests from stopping calls to nauty and Traces
+///
+/// Tests that stop calls, directly or through cancellations, time
+/// limits and closures, take this lock, and so do tests that count
+/// how often user-defined procedures are called, since the calls
+/// stopped by other tests are repeated.
+#[cfg(test)]
+pub(crate) fn test_lock() -> std::sync::MutexGuard<'static, ()> {
+    TEST_LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Stop the call to nauty running in the current thread
+///
+/// Only has an effect when called from a user-defined procedure
+/// during one of the calls in this module. The request is withdrawn
+/// when the call returns.
+pub(crate) fn stop_current() {
+    if !STOP.with(|stop| stop.replace(true)) {
+        request();
+    }
+}
+
+/// Ask all running calls to nauty and Traces to stop
+///
+/// Every request has to be withdrawn.
+pub(crate) fn request() {
+    let mut requests = REQUESTS.lock().unwrap();
+    *requests += 1;
+    unsafe { addr_of_mut!(nauty_kill_request).write_volatile(1) }
+}
+
+/// Withdraw a request made with [request]
+pub(crate) fn withdraw() {
+    let mut requests = REQUESTS.lock().unwrap();
+    *requests -= 1;
+    if *requests == 0 {
+        unsafe { addr_of_mut!(nauty_kill_request).write_volatile(0) }
+    }
+}
+
+/// Turn the status of a call that was stopped into an error
+///
+/// The calls in this module are only stopped for good when the
+/// computation in the current thread is cancelled or when a
+/// user-defined procedure asked them to stop.
+pub(crate) fn check(errstatus: c_int) -> Result<(), NautyError> {
+    if errstatus == NAUKILLED {
+        Err(NautyError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+// Wait until all kill requests are withdrawn
+//
+// Returns `false` if `token` is cancelled in the meantime, since the
+// requests then include the one made on its behalf.
+fn wait_for_withdrawal(token: Option<&cancel::Inner>) -> bool {
+    while *REQUESTS.lock().unwrap() > 0 {
+        if token.map_or(false, |t| t.is_cancelled()) {
+            return false;
+        }
+        thread::yield_now();
+    }
+    true
+}
+
+// Repeat `call` until it is not stopped on behalf of another thread,
+// restoring the `n` entries of `lab` and `ptn` before each repetition
+//
+// Returns `false` without calling nauty if the computation in this
+// thread has already been cancelled. Panics if called from a
+// user-defined procedure, since nauty and Traces are not reentrant.
+unsafe fn run(
+    lab: *mut c_int,
+    ptn: *mut c_int,
+    n: usize,
+    mut call: impl FnMut() -> c_int,
+) -> bool {
+    if RUNNING.with(|r| r.replace(true)) {
+        panic!("reentrant call to nauty or Traces");
+    }
+    let token = cancel::current();
+    if let Some(token) = &token {
+        if !token.begin_call() {
+            RUNNING.with(|r| r.set(false));
+            return false;
+        }
+    }
+    let mut saved = SAVED.with(Cell::take);
+    saved.clear();
+    saved.extend_from_slice(slice::from_raw_parts(lab, n));
+    saved.extend_from_slice(slice::from_raw_parts(ptn, n));
+    loop {
+        let errstatus = call();
+        if STOP.with(|stop| stop.replace(false)) {
+            withdraw();
+            break;
+        }
+        if errstatus != NAUKILLED
+            || token.as_ref().map_or(false, |t| t.is_cancelled())
+        {
+            break;
+        }
+        slice::from_raw_parts_mut(lab, n).copy_from_slice(&saved[..n]);
+        slice::from_raw_parts_mut(ptn, n).copy_from_slice(&saved[n..]);
+        if !wait_for_withdrawal(token.as_deref()) {
+            break;
+        }
+        if let Some(reset) = RESET.with(Cell::get) {
+            reset();
+        }
+    }
+    SAVED.with(|s| s.set(saved));
+    if let Some(token) = token {
+        token.end_call();
+    }
+    RUNNING.with(|r| r.set(false));
+    true
+}
+
+/// nauty's `densenauty`, repeated if stopped by another thread
+///
+/// # Safety
+///
+/// The requirements are the same as for `densenauty`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) unsafe fn densenauty(
+    g: *mut graph,
+    lab: *mut c_int,
+    ptn: *mut c_int,
+    orbits: *mut c_int,
+    options: *mut optionblk,
+    stats: *mut statsblk,
+    m: c_int,
+    n: c_int,
+    h: *mut graph,
+) {
+    let called = run(lab, ptn, n as usize, || {
+        nauty_Traces_sys::densenauty(
+            g, lab, ptn, orbits, options, stats, m, n, h,
+        );
+        (*stats).errstatus
+    });
+    if !called {
+        (*stats).errstatus = NAUKILLED;
+    }
+}
+
+/// nauty's `sparsenauty`, repeated if stopped by another thread
+///
+/// # Safety
+///
+/// The requirements are the same as for `sparsenauty`.
+pub(crate) unsafe fn sparsenauty(
+    g: *mut sparsegraph,
+    lab: *mut c_int,
+    ptn: *mut c_int,
+    orbits: *mut c_int,
+    options: *mut optionblk,
+    stats: *mut statsblk,
+    h: *mut sparsegraph,
+) {
+    let called = run(lab, ptn, (*g).nv as usize, || {
+        nauty_Traces_sys::sparsenauty(g, lab, ptn, orbits, options, stats, h);
+        (*stats).errstatus
+    });
+    if !called {
+        (*stats).errstatus = NAUKILLED;
+    }
+}
+
+/// `Traces`, repeated if stopped by another thread
+///
+/// # Safety
+///
+/// The requirements are the same as for `Traces`.
+#[cfg(feature = "traces")]
+#[allow(non_snake_case)]
+pub(crate) unsafe fn Traces(
+    g: *mut sparsegraph,
+    lab: *mut c_int,
+    ptn: *mut c_int,
+    orbits: *mut c_int,
+    options: *mut TracesOptions,
+    stats: *mut TracesStats,
+    h: *mut sparsegraph,
+) {
+    let called = run(lab, ptn, (*g).nv as usize, || {
+        nauty_Traces_sys::Traces(g, lab, ptn, orbits, options, stats, h);
+        (*stats).errstatus
+    });
+    if !called {
+        (*stats).errstatus = NAUKILLED;
+    }
+}
@@ -31,15 +31,72 @@
 //! assert_eq!(c1, c2);
 //! ```
 //!
+//! # Multigraphs and self-loops
+//!
+//! Graphs may contain parallel edges. Internally, all edges between
+//! the same pair of vertices are combined into a single edge, with
+//! the sorted list of their weights as its colour. Canonical forms
+//! and automorphisms therefore respect the multiplicities and
+//! weights of parallel edges. In the canonical form, parallel edges
+//! are ordered by their weights.
+//!
+//! Self-loops are supported as well. They are not passed to nauty,
+//! but instead combined into the colour of the vertex they are
+//! attached to, again respecting their number and weights.
+//!
+//! # Thread safety
+//!
+//! All functions can be called from several threads at the same time,
+//! for example with [rayon](https://crates.io/crates/rayon). nauty
+//! and Traces are built with thread-local storage for their internal
+//! state, and graphs, canonical forms and automorphism groups are
+//! [Send] and [Sync] whenever their node and edge weights are.
+//!
+//! The only state nauty shares between threads is the request to stop
+//! a search, which is used for [cancellation](cancel),
+//! [time limits](timeout) and closures that end a search early. A
+//! call stopped on behalf of another thread is repeated, so it
+//! returns the same result, but may take longer. Closures passed to
+//! the call, like [search] hooks and the generator callbacks of
+//! [NautyOptions](options::NautyOptions), see the repeated part of
+//! the search again. Settings for the
+//! [diagnostic output](verbosity) apply to all threads.
+//!
 //! # Features
 //!
 //! * `serde-1`: Enables serialisation of
-//!              [CanonGraph](graph::CanonGraph) objects using
+//!              [CanonGraph](graph::CanonGraph) objects and graph
+//!              generator checkpoints using
 //!              [serde](https://crates.io/crates/serde).
 //!
 //! * `stable`: Ensures deterministic behaviour when node or edge
 //!             weights are distinguishable, but compare equal.
 //!
+//! * `rand`: Enables estimating subgraph counts by random sampling with
+//!           [motifs::sample_graphlet_census].
+//!
+//! * `cert-store`: Enables the memory-mapped certificate store
+//!                 [store::CertStore] on Unix-like systems.
+//!
+//! * `rayon`: Enables computing canonical hashes in parallel with
+//!            [hashing::canonical_hashes].
+//!
+//! * `wl2`: Enables two-dimensional Weisfeiler-Leman refinement in
+//!          [refine], which needs memory cubic in the number of
+//!          vertices.
+//!
+//! * `log`: Enables forwarding the diagnostic output of nauty and
+//!          Traces to the [log](https://crates.io/crates/log) crate
+//!          or any writer on Unix-like systems, see [verbosity].
+//!
+//! * `traces`: Enables canonical forms and automorphism groups
+//!             computed with Traces. Enabled by default. The C code
+//!             of Traces is always compiled by `nauty-Traces-sys`,
+//!             so disabling the feature only removes the Rust API
+//!             and does not reduce compile time or binary size.
+//!             Dense and sparse nauty cannot be disabled, since the
+//!             rest of the crate is built on them.
+//!
 //! To enable features `feature1`, `feature2` add the following to
 //! your Cargo.toml:
 //! ```toml
@@ -47,15 +104,88 @@
 //! nauty-pet = { version = "0.8", features = ["feature1", "feature2"] }
 //! ```
 mod autom;
+pub mod cancel;
 pub mod canon;
+pub mod cayley;
+pub mod certificate;
+pub mod chem;
+pub mod circuit;
 mod cmp;
+pub mod color;
+pub mod complement;
+pub mod counting;
+pub mod deck;
+pub mod design;
+pub mod distance;
+pub mod distinguishing;
+pub mod embedding;
 pub mod error;
+pub mod generate;
 pub mod graph;
+pub mod group;
+pub mod hashing;
+mod hooks;
+pub mod ignore;
+pub mod invariant;
+pub mod iso;
+mod kill;
+pub mod line_graph;
+pub mod local_complement;
+pub mod motifs;
 mod nauty_graph;
+pub mod numeric;
+pub mod options;
+pub mod orbital;
+pub mod orbits;
+pub mod periodic;
+pub mod perm;
+pub mod petri;
 pub mod prelude;
+pub mod product;
+pub mod refine;
+pub mod search;
+pub mod signed;
+pub mod srg;
+pub mod stats;
+#[cfg(all(unix, feature = "cert-store"))]
+pub mod store;
+mod subgraph;
+pub mod temporal;
+pub mod timeout;
+pub mod two_graph;
+#[cfg(all(unix, feature = "log"))]
+pub mod verbosity;
+pub mod visit;
+pub mod workspace;
 
+// without the `log` feature, nauty and Traces produce no output
+#[cfg(not(all(unix, feature = "log")))]
+mod verbosity {
+    use nauty_Traces_sys::optionblk;
+    #[cfg(feature = "traces")]
+    use nauty_Traces_sys::TracesOptions;
+
+    pub(crate) struct Output;
+
+    impl Output {
+        pub(crate) fn finish(self) {}
+    }
+
+    pub(crate) fn capture_nauty(_options: &mut optionblk) -> Output {
+        Output
+    }
+
+    #[cfg(feature = "traces")]
+    pub(crate) fn capture_traces(_options: &mut TracesOptions) -> Output {
+        Output
+    }
+}
+
+pub use autom::{AutoOptions, Backend, TryIntoAutomAuto};
 pub use canon::IntoCanon;
-pub use canon::{IntoCanonNautySparse, TryIntoCanonTraces};
+pub use canon::IntoCanonNautySparse;
+#[cfg(feature = "traces")]
+pub use canon::TryIntoCanonTraces;
 pub use cmp::IsIdentical;
 
 #[cfg(test)]
@@ -77,6 +207,40 @@ mod tests {
         g.add_edge(g.from_index(v1), g.from_index(v2), wt)
     }
 
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        assert_send_sync::<UnGraph<(), ()>>();
+        assert_send_sync::<CanonGraph<(), ()>>();
+        assert_send_sync::<crate::autom::Autom>();
+        assert_send_sync::<crate::cancel::CancellationToken>();
+        assert_send_sync::<crate::error::NautyError>();
+        assert_send_sync::<crate::options::NautyOptions>();
+        assert_send_sync::<crate::stats::NautyStats>();
+    }
+
+    #[test]
+    fn concurrent() {
+        let graphs = Vec::from_iter((3..40).map(|n| {
+            UnGraph::<(), ()>::from_edges((0..n).map(|i| (i, (i + 1) % n)))
+        }));
+        let expected = Vec::from_iter(graphs.iter().map(|g| {
+            let autom = g.try_into_autom().unwrap();
+            (g.clone().into_canon(), autom)
+        }));
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for (g, (canon, autom)) in graphs.iter().zip(&expected) {
+                        assert_eq!(&g.try_into_autom().unwrap(), autom);
+                        assert!(g.clone().into_canon().is_identical(canon));
+                    }
+                });
+            }
+        });
+    }
+
     #[test]
     fn nautyex8() {
         let n_range = (2..20).step_by(2);
@@ -0,0 +1,310 @@
+//! Line graphs and their symmetries
+//!
+//! By Whitney's theorem, the automorphisms of the line graph of a
+//! connected graph are induced by the automorphisms of the graph
+//! itself, with a few small exceptions. This allows computing the
+//! symmetries of a line graph without constructing it.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::line_graph::line_graph_autom;
+//!
+//! // the edges of a four-cycle can be rotated and reflected
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+//! let generators = line_graph_autom(&g);
+//! assert!(!generators.is_empty());
+//! for gen in generators {
+//!     assert_eq!(gen.len(), g.edge_count());
+//! }
+//! ```
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::autom::autom_generators;
+use crate::orbits::UnionFind;
+
+use petgraph::{
+    graph::{Graph, IndexType, NodeIndex, UnGraph},
+    visit::EdgeRef,
+    Undirected,
+};
+
+// components with at most this many vertices can have line graph
+// automorphisms that are not induced by graph automorphisms
+const MAX_EXCEPTIONAL_SIZE: usize = 4;
+
+/// The line graph
+///
+/// The `i`th vertex of the line graph corresponds to the `i`th edge
+/// of `g` and has the same weight. Two vertices are adjacent if the
+/// corresponding edges share an end point. Vertex weights of `g` are
+/// discarded.
+pub fn line_graph<N, E, Ix>(
+    g: &Graph<N, E, Undirected, Ix>,
+) -> UnGraph<E, (), Ix>
+where
+    E: Clone,
+    Ix: IndexType,
+{
+    let mut line = UnGraph::with_capacity(g.edge_count(), 0);
+    for e in g.edge_weights() {
+        line.add_node(e.clone());
+    }
+    for v in g.node_indices() {
+        let incident = Vec::from_iter(g.edges(v).map(|e| e.id()));
+        for (i, &e) in incident.iter().enumerate() {
+            for &f in &incident[(i + 1)..] {
+                let (e, f) = (NodeIndex::new(e.index()), f.index());
+                line.update_edge(e, NodeIndex::new(f), ());
+            }
+        }
+    }
+    line
+}
+
+/// Generators of the automorphism group of the line graph
+///
+/// Each generator maps the `i`th edge of `g` to the edge with index
+/// `generator[i]`. Edge weights have to be preserved, whereas vertex
+/// weights are ignored, just as for [line_graph]. The result is
+/// obtained from the automorphisms of `g` and does not require
+/// constructing the line graph. The exceptions to Whitney's theorem
+/// are taken into account: the triangle and the star with three edges
+/// have the same line graph, and for some small connected graphs the
+/// line graph has additional automorphisms.
+///
+/// # Panics
+///
+/// Panics if `g` has self-loops or multiple edges.
+pub fn line_graph_autom<N, E, Ix>(
+    g: &Graph<N, E, Undirected, Ix>,
+) -> Vec<Vec<usize>>
+where
+    E: Clone + Hash + Ord,
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let mut edge_idx = HashMap::with_capacity(g.edge_count());
+    let mut components = UnionFind::new(n);
+    for e in g.edge_references() {
+        let (v, w) = (e.source().index(), e.target().index());
+        assert_ne!(v, w, "Self-loop at node {v}");
+        let prev = edge_idx.insert((v.min(w), v.max(w)), e.id().index());
+        assert!(prev.is_none(), "Multiple edges between {v} and {w}");
+        components.union(v, w);
+    }
+    let components = components.into_classes(0..n);
+
+    // replace each star with three edges by a triangle, which has the
+    // same line graph
+    let mut ends = Vec::from_iter(
+        g.edge_references()
+            .map(|e| (e.source().index(), e.target().index())),
+    );
+    for component in &components {
+        if let Some(centre) = star_centre(g, component) {
+            let leaves = Vec::from_iter(g.neighbors(centre).map(|v| v.index()));
+            for (i, e) in g.edges(centre).enumerate() {
+                ends[e.id().index()] = (leaves[i], leaves[(i + 1) % 3]);
+            }
+        }
+    }
+    let mut reduced = UnGraph::<(), E, Ix>::with_capacity(n, g.edge_count());
+    for _ in 0..n {
+        reduced.add_node(());
+    }
+    for (&(v, w), e) in ends.iter().zip(g.edge_weights()) {
+        reduced.add_edge(NodeIndex::new(v), NodeIndex::new(w), e.clone());
+    }
+    let mut ends_idx = HashMap::with_capacity(ends.len());
+    for (e, &(v, w)) in ends.iter().enumerate() {
+        ends_idx.insert((v.min(w), v.max(w)), e);
+    }
+    let (generators, _) = autom_generators(reduced);
+    let mut edge_generators = Vec::from_iter(generators.iter().map(|gen| {
+        Vec::from_iter(ends.iter().map(|&(v, w)| {
+            let (v, w) = (gen[v], gen[w]);
+            ends_idx[&(v.min(w), v.max(w))]
+        }))
+    }));
+
+    // small components can have additional symmetries, so we compute
+    // them from their line graphs
+    for component in components {
+        if component.len() > MAX_EXCEPTIONAL_SIZE {
+            continue;
+        }
+        let edges = Vec::from_iter(
+            g.edge_references()
+                .filter(|e| component.contains(&e.source().index()))
+                .map(|e| e.id()),
+        );
+        if edges.len() < 2 {
+            continue;
+        }
+        let sub = Graph::<(), E, Undirected, Ix>::from_edges(edges.iter().map(
+            |&e| {
+                let (v, w) = g.edge_endpoints(e).unwrap();
+                (v, w, g[e].clone())
+            },
+        ));
+        let (generators, _) = autom_generators(line_graph(&sub));
+        for gen in generators {
+            let mut edge_gen = Vec::from_iter(0..g.edge_count());
+            for (i, &e) in edges.iter().enumerate() {
+                edge_gen[e.index()] = edges[gen[i]].index();
+            }
+            edge_generators.push(edge_gen);
+        }
+    }
+    edge_generators.retain(|gen| gen.iter().enumerate().any(|(i, &j)| i != j));
+    edge_generators.sort_unstable();
+    edge_generators.dedup();
+    edge_generators
+}
+
+// centre of a component that forms a star with three edges
+fn star_centre<N, E, Ix: IndexType>(
+    g: &Graph<N, E, Undirected, Ix>,
+    component: &[usize],
+) -> Option<NodeIndex<Ix>> {
+    if component.len() != 4 {
+        return None;
+    }
+    component
+        .iter()
+        .map(|&v| NodeIndex::new(v))
+        .find(|&v| g.edges(v).count() == 3)
+        .filter(|&v| g.neighbors(v).all(|w| g.edges(w).count() == 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // order of the group generated by permutations of 0..n
+    fn group_order(n: usize, generators: &[Vec<usize>]) -> usize {
+        let mut elements = std::collections::HashSet::new();
+        let mut todo = vec![Vec::from_iter(0..n)];
+        while let Some(perm) = todo.pop() {
+            if elements.insert(perm.clone()) {
+                for gen in generators {
+                    todo.push(Vec::from_iter(perm.iter().map(|&i| gen[i])));
+                }
+            }
+        }
+        elements.len()
+    }
+
+    fn line_graph_order<E: Clone + Hash + Ord>(g: &UnGraph<(), E>) -> usize {
+        let generators = line_graph_autom(g);
+        for gen in &generators {
+            let mut sorted = gen.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, Vec::from_iter(0..g.edge_count()));
+        }
+        group_order(g.edge_count(), &generators)
+    }
+
+    fn direct_order<E: Clone + Hash + Ord>(g: &UnGraph<(), E>) -> usize {
+        let (generators, _) = autom_generators(line_graph(g));
+        group_order(g.edge_count(), &generators)
+    }
+
+    #[test]
+    fn line_graph_edges() {
+        log_init();
+
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        let line = line_graph(&g);
+        assert_eq!(line.node_count(), 3);
+        assert_eq!(line.edge_count(), 3);
+    }
+
+    #[test]
+    fn whitney() {
+        log_init();
+
+        let graphs = [
+            // triangle and star
+            vec![(0, 1), (1, 2), (2, 0), (3, 4), (3, 5), (3, 6)],
+            // K4
+            vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)],
+            // K4 minus an edge
+            vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3)],
+            // triangle with a pendant edge, twice
+            vec![
+                (0, 1),
+                (1, 2),
+                (2, 0),
+                (0, 3),
+                (4, 5),
+                (5, 6),
+                (6, 4),
+                (4, 7),
+            ],
+            // Petersen graph
+            vec![
+                (0, 1),
+                (1, 2),
+                (2, 3),
+                (3, 4),
+                (4, 0),
+                (0, 5),
+                (1, 6),
+                (2, 7),
+                (3, 8),
+                (4, 9),
+                (5, 7),
+                (7, 9),
+                (9, 6),
+                (6, 8),
+                (8, 5),
+            ],
+            // path, with an isolated edge and vertex
+            vec![(0, 1), (1, 2), (2, 3), (4, 5), (6, 6)],
+        ];
+        for (i, edges) in graphs.into_iter().enumerate() {
+            let mut g = UnGraph::<(), ()>::from_edges(
+                edges.into_iter().filter(|(v, w)| v != w),
+            );
+            if i == 5 {
+                g.add_node(());
+            }
+            assert_eq!(line_graph_order(&g), direct_order(&g));
+        }
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (1, 2),
+            (1, 3),
+            (2, 3),
+        ]);
+        // L(K4) is the octahedron
+        assert_eq!(line_graph_order(&g), 48);
+    }
+
+    #[test]
+    fn weighted() {
+        log_init();
+
+        // star with one distinguished edge next to a triangle
+        let g = UnGraph::<(), u8>::from_edges([
+            (0, 1, 0),
+            (0, 2, 0),
+            (0, 3, 1),
+            (4, 5, 0),
+            (5, 6, 0),
+            (6, 4, 1),
+        ]);
+        assert_eq!(line_graph_order(&g), direct_order(&g));
+        assert_eq!(line_graph_order(&g), 8);
+    }
+}
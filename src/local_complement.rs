@@ -0,0 +1,226 @@
+//! Equivalence under local complementation
+//!
+//! The local complement of a graph at a vertex `v` is obtained by
+//! complementing the subgraph induced by the neighbours of `v`. Two
+//! graphs are locally equivalent if one can be transformed into a
+//! graph isomorphic to the other by a sequence of local
+//! complementations. For graph states, local equivalence corresponds
+//! to equivalence under local Clifford operations.
+//!
+//! The functions in this module explore the whole orbit of a graph
+//! under local complementation, whose size can grow exponentially
+//! with the number of vertices. They are only suitable for small
+//! graphs.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::local_complement::canon_up_to_local_complement;
+//!
+//! // the star and the complete graph with four vertices both
+//! // correspond to the GHZ state
+//! let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+//! let complete = UnGraph::<(), ()>::from_edges([
+//!     (0, 1),
+//!     (0, 2),
+//!     (0, 3),
+//!     (1, 2),
+//!     (1, 3),
+//!     (2, 3),
+//! ]);
+//! assert_eq!(
+//!     canon_up_to_local_complement(&star),
+//!     canon_up_to_local_complement(&complete)
+//! );
+//! ```
+use std::collections::BTreeSet;
+use std::hash::Hash;
+
+use crate::graph::CanonGraph;
+use crate::subgraph::undirected_neighbours;
+
+use petgraph::{
+    graph::{IndexType, NodeIndex, UnGraph},
+    Undirected,
+};
+
+/// The local complement of `g` at vertex `v`
+///
+/// Two distinct neighbours of `v` are adjacent in the result if and
+/// only if they are not adjacent in `g`. All other adjacencies are
+/// unchanged. Edges have the default weight, self-loops, multiple
+/// edges, and the weights of existing edges are discarded.
+pub fn local_complement<N, E, Ix>(
+    g: &UnGraph<N, E, Ix>,
+    v: NodeIndex<Ix>,
+) -> UnGraph<N, E, Ix>
+where
+    N: Clone,
+    E: Default,
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let mut adjacent = adjacency(g);
+    let nbrs = Vec::from_iter((0..n).filter(|&u| adjacent[v.index() * n + u]));
+    for &u in &nbrs {
+        for &w in nbrs.iter().filter(|&&w| w != u) {
+            adjacent[u * n + w] = !adjacent[u * n + w];
+        }
+    }
+    with_adjacency(g, &adjacent)
+}
+
+/// The canonical forms of all graphs that are locally equivalent to
+/// `g`
+///
+/// Edge weights, self-loops, and multiple edges are discarded as in
+/// [local_complement]. Node weights are kept.
+pub fn local_complement_orbit<N, E, Ix>(
+    g: &UnGraph<N, E, Ix>,
+) -> BTreeSet<CanonGraph<N, E, Undirected, Ix>>
+where
+    N: Clone + Ord,
+    E: Default + Hash + Ord,
+    Ix: IndexType,
+{
+    let mut orbit = BTreeSet::new();
+    let mut todo = vec![CanonGraph::from(with_adjacency(g, &adjacency(g)))];
+    while let Some(h) = todo.pop() {
+        if orbit.contains(&h) {
+            continue;
+        }
+        // local complementation commutes with relabelling, so it is
+        // enough to consider canonical forms
+        for v in h.node_indices() {
+            let next = CanonGraph::from(local_complement(&h, v));
+            if !orbit.contains(&next) {
+                todo.push(next);
+            }
+        }
+        orbit.insert(h);
+    }
+    orbit
+}
+
+/// Canonical representative of the local equivalence class of `g`
+///
+/// This is the smallest canonical form in the
+/// [local_complement_orbit], so two graphs have the same
+/// representative if and only if they are locally equivalent.
+pub fn canon_up_to_local_complement<N, E, Ix>(
+    g: &UnGraph<N, E, Ix>,
+) -> CanonGraph<N, E, Undirected, Ix>
+where
+    N: Clone + Ord,
+    E: Default + Hash + Ord,
+    Ix: IndexType,
+{
+    local_complement_orbit(g).into_iter().next().unwrap()
+}
+
+// adjacency matrix without self-loops
+fn adjacency<N, E, Ix: IndexType>(g: &UnGraph<N, E, Ix>) -> Vec<bool> {
+    let n = g.node_count();
+    let mut adjacent = vec![false; n * n];
+    for (v, nbrs) in undirected_neighbours(g).into_iter().enumerate() {
+        for w in nbrs {
+            adjacent[v * n + w] = true;
+        }
+    }
+    adjacent
+}
+
+// graph with the node weights of `g` and the given adjacency matrix
+fn with_adjacency<N, E, Ix>(
+    g: &UnGraph<N, E, Ix>,
+    adjacent: &[bool],
+) -> UnGraph<N, E, Ix>
+where
+    N: Clone,
+    E: Default,
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let mut res = g.filter_map(|_, w| Some(w.clone()), |_, _| None);
+    for v in 0..n {
+        for w in (v + 1)..n {
+            if adjacent[v * n + w] {
+                res.add_edge(
+                    NodeIndex::new(v),
+                    NodeIndex::new(w),
+                    E::default(),
+                );
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::GraphGenerator;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn complement_neighbours() {
+        log_init();
+
+        let g = UnGraph::<(), u8>::from_edges([
+            (0, 1, 1),
+            (0, 2, 2),
+            (0, 3, 3),
+            (1, 2, 4),
+            (1, 1, 5),
+        ]);
+        let h = local_complement(&g, NodeIndex::new(0));
+        assert_eq!(h.node_count(), 4);
+        assert_eq!(h.edge_count(), 5);
+        assert!(!h.contains_edge(1.into(), 2.into()));
+        assert!(h.contains_edge(1.into(), 3.into()));
+        assert!(h.contains_edge(2.into(), 3.into()));
+        assert!(h.edge_weights().all(|&w| w == 0));
+        // local complementation is an involution
+        let g = local_complement(&h, NodeIndex::new(0));
+        assert!(g.contains_edge(1.into(), 2.into()));
+        assert_eq!(g.edge_count(), 4);
+    }
+
+    #[test]
+    fn orbits() {
+        log_init();
+
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        assert_eq!(local_complement_orbit(&path).len(), 2);
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        assert_ne!(
+            canon_up_to_local_complement(&path),
+            canon_up_to_local_complement(&star)
+        );
+        let mut edgeless = UnGraph::<(), ()>::default();
+        edgeless.add_node(());
+        edgeless.add_node(());
+        assert_eq!(local_complement_orbit(&edgeless).len(), 1);
+    }
+
+    #[test]
+    fn num_classes() {
+        log_init();
+
+        // OEIS A090899
+        for (n, num_classes) in [(4, 2), (5, 4), (6, 11)] {
+            let reps = BTreeSet::from_iter(
+                GraphGenerator::new(n)
+                    .connected()
+                    .into_iter()
+                    .map(|g| canon_up_to_local_complement(&g)),
+            );
+            assert_eq!(reps.len(), num_classes);
+        }
+    }
+}
@@ -0,0 +1,691 @@
+//! Network motif census
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::motifs::{graphlet_census, motif_census};
+//! use nauty_pet::prelude::*;
+//!
+//! // square with one diagonal
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)]);
+//! let census = motif_census(&g, 3);
+//!
+//! let triangle = CanonGraph::from_edges([(0, 1), (1, 2), (2, 0)]);
+//! let path = CanonGraph::from_edges([(0, 1), (1, 2)]);
+//! assert_eq!(census.count(&triangle), 2);
+//! assert_eq!(census.count(&path), 2);
+//!
+//! // including disconnected subgraphs
+//! let census = graphlet_census(&g, 3);
+//! assert_eq!(census.len(), 4);
+//! ```
+use std::collections::BTreeMap;
+use std::hash::Hash;
+
+#[cfg(feature = "rand")]
+use crate::autom::vertex_orbits;
+#[cfg(feature = "rand")]
+use crate::cancel;
+use crate::graph::CanonGraph;
+use crate::group::AutomorphismGroup;
+use crate::iso::IsoMultiset;
+use crate::subgraph::{
+    count_embeddings, induced_subgraph, undirected_neighbours,
+};
+
+use itertools::Itertools;
+use petgraph::{
+    graph::{DefaultIx, Graph, IndexType, NodeIndex, UnGraph},
+    Directed, EdgeType, Undirected,
+};
+
+/// Largest supported motif size
+pub const MAX_MOTIF_SIZE: usize = 6;
+
+/// Count connected `k`-vertex subgraphs by isomorphism class
+///
+/// Each connected induced subgraph of `g` with `k` vertices is
+/// enumerated exactly once and added to the result in its canonical
+/// form. Node and edge weights are taken into account. For directed
+/// graphs, subgraphs have to be weakly connected.
+///
+/// # Panics
+///
+/// Panics if `k` is zero or larger than [MAX_MOTIF_SIZE].
+pub fn motif_census<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    k: usize,
+) -> IsoMultiset<N, E, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    assert!(
+        (1..=MAX_MOTIF_SIZE).contains(&k),
+        "Motif size has to be between 1 and {MAX_MOTIF_SIZE}"
+    );
+    let mut census = IsoMultiset::new();
+    for_each_connected_subset(&undirected_neighbours(g), k, |vertices| {
+        census.insert_canon(CanonGraph::from(induced_subgraph(g, vertices)));
+    });
+    census
+}
+
+/// Count induced `k`-vertex subgraphs (graphlets) by isomorphism class
+///
+/// In contrast to [motif_census], all `k`-vertex subsets of `g` are
+/// considered, including those that induce disconnected subgraphs.
+///
+/// # Panics
+///
+/// Panics if `k` is zero or larger than [MAX_MOTIF_SIZE].
+pub fn graphlet_census<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    k: usize,
+) -> IsoMultiset<N, E, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    assert!(
+        (1..=MAX_MOTIF_SIZE).contains(&k),
+        "Graphlet size has to be between 1 and {MAX_MOTIF_SIZE}"
+    );
+    (0..g.node_count())
+        .combinations(k)
+        .map(|vertices| CanonGraph::from(induced_subgraph(g, &vertices)))
+        .collect()
+}
+
+/// Count the embeddings of `pattern` into `host`
+///
+/// An embedding maps the vertices of `pattern` to distinct vertices
+/// of `host` with the same weights, such that each edge of `pattern`
+/// is mapped to an edge of `host` with the same weight and direction.
+/// In contrast to [motif_census], the image does not have to be an
+/// induced subgraph. `pattern` should not have multiple edges.
+pub fn count_labelled_copies<N, E, Ty, Ix>(
+    pattern: &Graph<N, E, Ty, Ix>,
+    host: &Graph<N, E, Ty, Ix>,
+) -> usize
+where
+    N: PartialEq,
+    E: PartialEq,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    count_embeddings(pattern, host)
+}
+
+/// Count the subgraphs of `host` that are isomorphic to `pattern`
+///
+/// This is the number of embeddings as given by
+/// [count_labelled_copies], divided by the number of automorphisms
+/// of `pattern`.
+pub fn count_copies<N, E, Ty, Ix>(
+    pattern: &Graph<N, E, Ty, Ix>,
+    host: &Graph<N, E, Ty, Ix>,
+) -> usize
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let embeddings = count_labelled_copies(pattern, host);
+    if embeddings == 0 {
+        return 0;
+    }
+    // automorphisms act freely on the embeddings, so the group order
+    // divides their number and fits
+    let order = AutomorphismGroup::from(pattern.clone()).order().unwrap();
+    debug_assert_eq!(embeddings as u128 % order, 0);
+    (embeddings as u128 / order) as usize
+}
+
+/// Graphlet counts estimated from a random sample
+#[derive(Clone, Debug)]
+pub struct GraphletEstimate<
+    N,
+    E,
+    Ty: EdgeType = Directed,
+    Ix: IndexType = DefaultIx,
+> {
+    samples: IsoMultiset<N, E, Ty, Ix>,
+    estimates: BTreeMap<CanonGraph<N, E, Ty, Ix>, f64>,
+}
+
+impl<N, E, Ty: EdgeType, Ix: IndexType> GraphletEstimate<N, E, Ty, Ix> {
+    /// The sampled subgraphs
+    pub fn samples(&self) -> &IsoMultiset<N, E, Ty, Ix> {
+        &self.samples
+    }
+
+    /// Iterate over the sampled isomorphism classes and their
+    /// estimated number of occurrences
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&CanonGraph<N, E, Ty, Ix>, f64)> + '_ {
+        self.estimates.iter().map(|(g, &n)| (g, n))
+    }
+}
+
+impl<N, E, Ty, Ix> GraphletEstimate<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    /// Estimated number of induced subgraphs isomorphic to `g`
+    pub fn estimate(&self, g: &CanonGraph<N, E, Ty, Ix>) -> f64 {
+        self.estimates.get(g).copied().unwrap_or(0.)
+    }
+}
+
+/// Estimate induced `k`-vertex subgraph counts from random samples
+///
+/// This is an alternative to [graphlet_census] for graphs that are
+/// too large for exhaustive enumeration. Each sample consists of an
+/// anchor vertex and `k - 1` further vertices chosen uniformly at
+/// random.
+///
+/// Automorphisms of `g` map the samples anchored at one vertex to
+/// the samples anchored at any other vertex in the same orbit, so
+/// anchors are drawn per vertex orbit. If there are at least as many
+/// samples as orbits, each orbit is the anchor of one sample and the
+/// remaining samples are spread over the orbits in proportion to
+/// their sizes. The samples of each orbit are then weighted by its
+/// share of the vertices. With fewer samples, the orbits are chosen
+/// in proportion to their sizes. In both cases, the estimates are
+/// unbiased and vary less than with anchors drawn uniformly from
+/// all vertices.
+///
+/// Requires the `rand` feature.
+///
+/// # Panics
+///
+/// Panics if `k` is zero or larger than [MAX_MOTIF_SIZE].
+#[cfg(feature = "rand")]
+pub fn sample_graphlet_census<N, E, Ty, Ix, R>(
+    g: &Graph<N, E, Ty, Ix>,
+    k: usize,
+    num_samples: usize,
+    rng: &mut R,
+) -> GraphletEstimate<N, E, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+    R: rand::Rng + ?Sized,
+{
+    assert!(
+        (1..=MAX_MOTIF_SIZE).contains(&k),
+        "Graphlet size has to be between 1 and {MAX_MOTIF_SIZE}"
+    );
+    let n = g.node_count();
+    let mut samples = IsoMultiset::new();
+    if n < k || num_samples == 0 {
+        return GraphletEstimate {
+            samples,
+            estimates: BTreeMap::new(),
+        };
+    }
+    let num_subsets = binomial(n, k);
+    // vertex orbits, given by their smallest vertices and their sizes
+    let reps = cancel::unwrap(vertex_orbits(g.clone()));
+    let mut sizes = vec![0; n];
+    for &rep in &reps {
+        sizes[rep] += 1;
+    }
+    let orbits = Vec::from_iter((0..n).filter(|&v| reps[v] == v));
+
+    // one sample per orbit if possible, the others are spread
+    // systematically over the vertices sorted by orbit
+    let base = usize::from(num_samples >= orbits.len());
+    let extra = num_samples - base * orbits.len();
+    let offset: f64 = rng.gen();
+    // number of systematic samples before the `x`th vertex
+    let before = |x: usize| {
+        let pos = x as f64 * extra as f64 / n as f64 - offset;
+        (pos.ceil().max(0.) as usize).min(extra)
+    };
+    let mut estimates = BTreeMap::new();
+    let mut vertices = Vec::with_capacity(k);
+    let mut start = 0;
+    for anchor in orbits {
+        let size = sizes[anchor];
+        let num = base + before(start + size) - before(start);
+        start += size;
+        let share = size as f64 / n as f64;
+        let weight = if base == 1 {
+            num_subsets * share / num as f64
+        } else {
+            // each sample is anchored in this orbit with probability
+            // `share`
+            num_subsets / num_samples as f64
+        };
+        for _ in 0..num {
+            vertices.clear();
+            vertices.push(anchor);
+            let others = rand::seq::index::sample(rng, n - 1, k - 1);
+            vertices.extend(others.into_iter().map(|v| {
+                if v < anchor {
+                    v
+                } else {
+                    v + 1
+                }
+            }));
+            let sample = CanonGraph::from(induced_subgraph(g, &vertices));
+            *estimates.entry(sample.clone()).or_insert(0.) += weight;
+            samples.insert_canon(sample);
+        }
+    }
+    GraphletEstimate { samples, estimates }
+}
+
+#[cfg(feature = "rand")]
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1., |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Call `f` on each connected vertex subset of size `k`
+///
+/// Uses the ESU algorithm by S. Wernicke, IEEE/ACM Transactions on
+/// Computational Biology and Bioinformatics 3 (2006) 347
+pub(crate) fn for_each_connected_subset(
+    adj: &[Vec<usize>],
+    k: usize,
+    mut f: impl FnMut(&[usize]),
+) {
+    let mut subset = Vec::with_capacity(k);
+    for v in 0..adj.len() {
+        subset.push(v);
+        let extension =
+            Vec::from_iter(adj[v].iter().copied().filter(|&w| w > v));
+        extend_subset(adj, k, v, &mut subset, extension, &mut f);
+        subset.pop();
+    }
+}
+
+fn extend_subset(
+    adj: &[Vec<usize>],
+    k: usize,
+    root: usize,
+    subset: &mut Vec<usize>,
+    mut extension: Vec<usize>,
+    f: &mut impl FnMut(&[usize]),
+) {
+    if subset.len() == k {
+        f(subset);
+        return;
+    }
+    while let Some(w) = extension.pop() {
+        // exclusive neighbours of `w`: neither in the subset
+        // nor adjacent to it
+        let mut new_extension = extension.clone();
+        new_extension.extend(adj[w].iter().copied().filter(|&u| {
+            u > root
+                && subset
+                    .iter()
+                    .all(|&s| s != u && adj[s].binary_search(&u).is_err())
+        }));
+        subset.push(w);
+        extend_subset(adj, k, root, subset, new_extension, f);
+        subset.pop();
+    }
+}
+
+/// Largest subgraph size supported by [MotifTable]
+pub const MAX_MOTIF_TABLE_SIZE: usize = 5;
+
+/// Lookup table classifying small undirected subgraphs
+///
+/// The table contains every simple undirected graph with `k`
+/// vertices, with class ids given by the order of the canonical
+/// forms. The ids are therefore stable, i.e. independent of how
+/// the table was built. Classifying a labelled `k`-vertex graph only
+/// requires a single table lookup, without running nauty.
+///
+/// Edge directions, self-loops, multiple edges, and weights are
+/// ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::motifs::MotifTable;
+/// use nauty_pet::prelude::*;
+///
+/// let table = MotifTable::new(3);
+/// assert_eq!(table.num_classes(), 4);
+///
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)]);
+/// let triangle = table.class_id(&CanonGraph::from_edges([(0, 1), (1, 2), (2, 0)]));
+/// let vertices = [0, 1, 2].map(NodeIndex::new);
+/// assert_eq!(Some(table.classify(&g, &vertices)), triangle);
+///
+/// let census = table.census(&g);
+/// assert_eq!(census[triangle.unwrap()], 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MotifTable {
+    k: usize,
+    classes: Vec<CanonGraph<(), (), Undirected>>,
+    // class id for each labelled graph, indexed by adjacency bits
+    ids: Vec<usize>,
+}
+
+impl MotifTable {
+    /// Build the table for subgraphs with `k` vertices
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is larger than [MAX_MOTIF_TABLE_SIZE].
+    pub fn new(k: usize) -> Self {
+        assert!(
+            k <= MAX_MOTIF_TABLE_SIZE,
+            "Motif table size has to be at most {MAX_MOTIF_TABLE_SIZE}"
+        );
+        let pairs = Vec::from_iter((0..k).tuple_combinations::<(_, _)>());
+        let canon = Vec::from_iter((0..1usize << pairs.len()).map(|bits| {
+            let mut g = UnGraph::with_capacity(k, 0);
+            for _ in 0..k {
+                g.add_node(());
+            }
+            for (n, &(i, j)) in pairs.iter().enumerate() {
+                if bits & (1 << n) != 0 {
+                    g.add_edge(NodeIndex::new(i), NodeIndex::new(j), ());
+                }
+            }
+            CanonGraph::from(g)
+        }));
+        let mut classes = canon.clone();
+        classes.sort_unstable();
+        classes.dedup();
+        let ids = Vec::from_iter(
+            canon.iter().map(|g| classes.binary_search(g).unwrap()),
+        );
+        Self { k, classes, ids }
+    }
+
+    /// The number of vertices of the classified subgraphs
+    pub fn size(&self) -> usize {
+        self.k
+    }
+
+    /// The number of isomorphism classes
+    pub fn num_classes(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// The canonical form of the graphs with the given class id
+    pub fn class(&self, id: usize) -> &CanonGraph<(), (), Undirected> {
+        &self.classes[id]
+    }
+
+    /// The class id of a canonical graph
+    ///
+    /// Returns `None` if the graph is not a simple graph with the
+    /// right number of vertices.
+    pub fn class_id(
+        &self,
+        g: &CanonGraph<(), (), Undirected>,
+    ) -> Option<usize> {
+        self.classes.binary_search(g).ok()
+    }
+
+    /// Classify the subgraph induced by the given vertices
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of vertices differs from [size](Self::size).
+    pub fn classify<N, E, Ty, Ix>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+        vertices: &[NodeIndex<Ix>],
+    ) -> usize
+    where
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        assert_eq!(vertices.len(), self.k, "Wrong number of vertices");
+        self.classify_by(|i, j| {
+            let (v, w) = (vertices[i], vertices[j]);
+            g.contains_edge(v, w) || g.contains_edge(w, v)
+        })
+    }
+
+    /// Count the subgraphs induced by all `k`-vertex subsets by class
+    ///
+    /// The `i`th entry of the result is the number of subgraphs with
+    /// class id `i`.
+    pub fn census<N, E, Ty, Ix>(&self, g: &Graph<N, E, Ty, Ix>) -> Vec<usize>
+    where
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let adj = undirected_neighbours(g);
+        let mut census = vec![0; self.num_classes()];
+        for vertices in (0..g.node_count()).combinations(self.k) {
+            let id = self.classify_by(|i, j| {
+                adj[vertices[i]].binary_search(&vertices[j]).is_ok()
+            });
+            census[id] += 1;
+        }
+        census
+    }
+
+    fn classify_by(&self, adjacent: impl Fn(usize, usize) -> bool) -> usize {
+        let mut bits = 0;
+        for (n, (i, j)) in (0..self.k).tuple_combinations().enumerate() {
+            if adjacent(i, j) {
+                bits |= 1 << n;
+            }
+        }
+        self.ids[bits]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::{DiGraph, UnGraph};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn num_subsets(n: usize, k: usize) -> usize {
+        (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+    }
+
+    #[test]
+    fn complete() {
+        log_init();
+
+        for n in 1..8 {
+            let mut g = UnGraph::<(), ()>::default();
+            for _ in 0..n {
+                g.add_node(());
+            }
+            for i in 0..n {
+                for j in (i + 1)..n {
+                    g.add_edge((i as u32).into(), (j as u32).into(), ());
+                }
+            }
+            for k in 1..=n.min(MAX_MOTIF_SIZE) {
+                let census = motif_census(&g, k);
+                assert_eq!(census.num_classes(), 1);
+                assert_eq!(census.len(), num_subsets(n, k));
+            }
+        }
+    }
+
+    #[test]
+    fn star() {
+        log_init();
+
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        let census = motif_census(&g, 3);
+        assert_eq!(census.num_classes(), 1);
+        assert_eq!(census.len(), 3);
+        let census = motif_census(&g, 2);
+        assert_eq!(census.len(), 3);
+        let census = motif_census(&g, 4);
+        assert_eq!(census.len(), 1);
+    }
+
+    #[test]
+    fn weakly_connected() {
+        log_init();
+
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (2, 1), (2, 3)]);
+        let census = motif_census(&g, 3);
+        assert_eq!(census.len(), 2);
+        assert_eq!(census.num_classes(), 2);
+        let into = CanonGraph::from(DiGraph::from_edges([(0, 1), (2, 1)]));
+        let out = CanonGraph::from(DiGraph::from_edges([(1, 0), (1, 2)]));
+        assert_eq!(census.count(&into), 1);
+        assert_eq!(census.count(&out), 1);
+    }
+
+    #[test]
+    fn graphlets() {
+        log_init();
+
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+        let census = graphlet_census(&g, 3);
+        assert_eq!(census.len(), 4);
+        assert_eq!(census.num_classes(), 2);
+        let connected = motif_census(&g, 3);
+        for (g, n) in &connected {
+            assert_eq!(census.count(g), n);
+        }
+        let census = graphlet_census(&g, 2);
+        assert_eq!(census.len(), 6);
+        let edge = CanonGraph::from(UnGraph::<(), ()>::from_edges([(0, 1)]));
+        assert_eq!(census.count(&edge), 3);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn sampled_graphlets() {
+        use rand::SeedableRng;
+        use rand_xoshiro::Xoshiro256Plus;
+
+        log_init();
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (3, 4),
+            (4, 5),
+            (5, 0),
+            (0, 3),
+        ]);
+        let exact = graphlet_census(&g, 3);
+        let estimate = sample_graphlet_census(&g, 3, 20000, &mut rng);
+        assert_eq!(estimate.samples().len(), 20000);
+        for (g, n) in &exact {
+            let est = estimate.estimate(g);
+            assert!((est - n as f64).abs() < 0.1 * n as f64 + 0.5);
+        }
+        let total: f64 = estimate.iter().map(|(_, n)| n).sum();
+        assert!((total - 20.).abs() < 1e-8);
+
+        // fewer samples than the three vertex orbits of a path
+        let path =
+            UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4)]);
+        let estimate = sample_graphlet_census(&path, 2, 2, &mut rng);
+        assert_eq!(estimate.samples().len(), 2);
+        let total: f64 = estimate.iter().map(|(_, n)| n).sum();
+        assert!((total - 10.).abs() < 1e-8);
+    }
+
+    #[test]
+    fn weighted() {
+        log_init();
+
+        let g =
+            UnGraph::<u8, u8>::from_edges([(0, 1, 0), (1, 2, 1), (2, 0, 0)]);
+        let census = motif_census(&g, 2);
+        assert_eq!(census.len(), 3);
+        assert_eq!(census.num_classes(), 2);
+    }
+
+    #[test]
+    fn copies() {
+        log_init();
+
+        let triangle = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        let complete = UnGraph::<(), ()>::from_edges(
+            (0..4).flat_map(|i| ((i + 1)..4).map(move |j| (i, j))),
+        );
+        assert_eq!(count_labelled_copies(&triangle, &complete), 24);
+        assert_eq!(count_copies(&triangle, &complete), 4);
+
+        // copies need not be induced
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        assert_eq!(count_labelled_copies(&path, &complete), 24);
+        assert_eq!(count_copies(&path, &complete), 12);
+        assert_eq!(count_copies(&complete, &path), 0);
+
+        let arc = DiGraph::<(), ()>::from_edges([(0, 1)]);
+        let cycle = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(count_copies(&arc, &cycle), 3);
+        let two_paths = DiGraph::<(), ()>::from_edges([(0, 1), (0, 2)]);
+        assert_eq!(count_labelled_copies(&two_paths, &cycle), 0);
+    }
+
+    #[test]
+    fn weighted_copies() {
+        log_init();
+
+        let mut pattern = UnGraph::<u8, u8>::from_edges([(0, 1, 0)]);
+        pattern[NodeIndex::new(1)] = 1;
+        let mut host =
+            UnGraph::<u8, u8>::from_edges([(0, 1, 0), (1, 2, 1), (2, 0, 0)]);
+        assert_eq!(count_copies(&pattern, &host), 0);
+        host[NodeIndex::new(2)] = 1;
+        // only the edge between 0 and 2 has matching weights
+        assert_eq!(count_copies(&pattern, &host), 1);
+        let edge = UnGraph::<u8, u8>::from_edges([(0, 1, 0)]);
+        assert_eq!(count_labelled_copies(&edge, &host), 2);
+    }
+
+    #[test]
+    fn motif_table() {
+        log_init();
+
+        // OEIS A000088
+        for (k, num_classes) in [1, 1, 2, 4, 11, 34].into_iter().enumerate() {
+            assert_eq!(MotifTable::new(k).num_classes(), num_classes);
+        }
+
+        let table = MotifTable::new(4);
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (3, 0),
+            (0, 4),
+            (4, 5),
+            (5, 2),
+        ]);
+        let census = table.census(&g);
+        let expected = graphlet_census(&g, 4);
+        assert_eq!(census.iter().sum::<usize>(), expected.len());
+        for (id, &count) in census.iter().enumerate() {
+            assert_eq!(expected.count(table.class(id)), count);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::cmp::{Ord, Ordering};
+use std::collections::BTreeMap;
 use std::convert::From;
 use std::hash::Hash;
 use std::marker::PhantomData;
@@ -6,12 +7,18 @@ use std::os::raw::c_int;
 
 use ahash::RandomState;
 use itertools::izip;
-use nauty_Traces_sys::{empty_graph, graph, ADDONEARC, SETWORDSNEEDED};
-use nauty_Traces_sys::{SparseGraph as NautySparse};
+use nauty_Traces_sys::SparseGraph as NautySparse;
+use nauty_Traces_sys::{bit, graph, GRAPHROW, SETBT, SETWD};
+use nauty_Traces_sys::{ADDONEARC, SETWORDSNEEDED};
 
 use petgraph::{
+    csr::Csr,
     graph::{Graph, IndexType},
-    visit::EdgeRef,
+    matrix_graph::{MatrixGraph, Nullable},
+    visit::{
+        Data, EdgeRef, GraphProp, IntoEdgeReferences, IntoNodeReferences,
+        NodeCompactIndexable, NodeRef,
+    },
     EdgeType,
 };
 
@@ -82,6 +89,8 @@ pub(crate) struct Nodes<N> {
     pub(crate) lab: Vec<c_int>,
     pub(crate) ptn: Vec<c_int>,
     pub(crate) weights: Vec<N>,
+    // position of each original node in the nauty graph
+    pub(crate) relabel: Vec<usize>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -131,14 +140,105 @@ where
 {
     fn from(g: Graph<N, E, Ty, Ix>) -> Self {
         use petgraph::visit::NodeIndexable;
-        let is_directed = g.is_directed();
         let edges = Vec::from_iter(
             g.edge_references()
                 .map(|e| (g.to_index(e.source()), g.to_index(e.target()))),
         );
         let (nodes, e) = g.into_nodes_edges();
+        let nodes = Vec::from_iter(nodes.into_iter().map(|n| n.weight));
+        let edges = izip!(edges, e.into_iter().map(|e| e.weight));
+        Self::from_parts(nodes, edges)
+    }
+}
+
+impl<'a, N, E, Ty, Null, Ix> From<&'a MatrixGraph<N, E, Ty, Null, Ix>>
+    for RawGraphData<(&'a N, Vec<&'a E>), &'a E, Ty>
+where
+    Ty: EdgeType,
+    Null: Nullable<Wrapped = E>,
+    Ix: IndexType,
+    N: Ord,
+    E: Hash + Ord,
+{
+    fn from(g: &'a MatrixGraph<N, E, Ty, Null, Ix>) -> Self {
+        use petgraph::visit::NodeIndexable;
+        // skip the indices of removed nodes
+        let mut pos = vec![usize::MAX; g.node_bound()];
+        let mut nodes = Vec::with_capacity(g.node_count());
+        for (v, w) in g.node_references() {
+            pos[v.index()] = nodes.len();
+            nodes.push(w);
+        }
+        let edges = g
+            .edge_references()
+            .map(|(s, t, w)| ((pos[s.index()], pos[t.index()]), w));
+        Self::from_parts(nodes, edges)
+    }
+}
+
+impl<'a, N, E, Ty, Ix> From<&'a Csr<N, E, Ty, Ix>>
+    for RawGraphData<(&'a N, Vec<&'a E>), &'a E, Ty>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+    N: Ord,
+    E: Hash + Ord,
+{
+    fn from(g: &'a Csr<N, E, Ty, Ix>) -> Self {
+        let nodes = Vec::from_iter(g.node_references().map(|(_, w)| w));
+        let edges = g
+            .edge_references()
+            .map(|e| ((e.source().index(), e.target().index()), e.weight()));
+        if Ty::is_directed() {
+            return Self::from_parts(nodes, edges);
+        }
+        // undirected edges are usually stored in both directions, but
+        // `Csr::from_sorted_edges` may store them in only one
+        let mut pairs: BTreeMap<_, [Vec<&E>; 2]> = BTreeMap::new();
+        for ((s, t), w) in edges {
+            let pair = (s.min(t), s.max(t));
+            pairs.entry(pair).or_default()[usize::from(s > t)].push(w);
+        }
+        let edges = pairs.into_iter().flat_map(|(pair, [fwd, bwd])| {
+            max_union(fwd, bwd).into_iter().map(move |w| (pair, w))
+        });
+        Self::from_parts(nodes, edges)
+    }
+}
+
+// union of two multisets, where each element is repeated as often as
+// in the multiset containing it more often
+fn max_union<T: Ord>(mut a: Vec<T>, mut b: Vec<T>) -> Vec<T> {
+    a.sort_unstable();
+    b.sort_unstable();
+    let mut res = Vec::with_capacity(a.len().max(b.len()));
+    let mut b = b.into_iter().peekable();
+    for x in a {
+        while let Some(y) = b.next_if(|y| *y < x) {
+            res.push(y);
+        }
+        b.next_if(|y| *y == x);
+        res.push(x);
+    }
+    res.extend(b);
+    res
+}
+
+impl<N, E, Ty> RawGraphData<(N, Vec<E>), E, Ty>
+where
+    Ty: EdgeType,
+    N: Ord,
+    E: Hash + Ord,
+{
+    // build from the node weights and the edges given by the
+    // positions of their end points
+    fn from_parts(
+        nodes: Vec<N>,
+        edges: impl IntoIterator<Item = ((usize, usize), E)>,
+    ) -> Self {
+        let is_directed = Ty::is_directed();
         let mut node_weights =
-            Vec::from_iter(nodes.into_iter().map(|n| (n.weight, Vec::new())));
+            Vec::from_iter(nodes.into_iter().map(|n| (n, Vec::new())));
 
         // edge weights
         // we combine multiple edges into a single one with an
@@ -147,7 +247,7 @@ where
         // self-loops are removed and their weights instead appended
         // to the corresponding node weight
         let mut edge_weights: HashMap<_, Vec<E>> = HashMap::default();
-        for (mut edge, wt) in izip!(edges, e.into_iter().map(|e| e.weight)) {
+        for (mut edge, wt) in edges {
             if edge.0 == edge.1 {
                 node_weights[edge.0].1.push(wt);
             } else {
@@ -160,6 +260,9 @@ where
         for v in edge_weights.values_mut() {
             sort(v);
         }
+        for (_, loops) in &mut node_weights {
+            sort(loops);
+        }
         let relabel = relabel_to_contiguous_node_weights(&mut node_weights);
         let edge_weights: HashMap<_, _> = edge_weights
             .into_iter()
@@ -269,6 +372,7 @@ where
             weights: node_weights,
             lab,
             ptn,
+            relabel,
         };
         let num_nauty_edges = {
             let num_nauty_edges = edge_weights.len() + total_num_aux;
@@ -337,10 +441,24 @@ where
     E: Hash + Ord,
 {
     fn from(g: RawGraphData<N, E, Ty>) -> Self {
+        Self::from_raw_in(g, Vec::new())
+    }
+}
+
+impl<N, E, Ty> DenseGraph<N, E, Ty>
+where
+    Ty: EdgeType,
+    N: Ord,
+    E: Hash + Ord,
+{
+    // build the adjacency matrix in the given buffer, reusing its
+    // allocation
+    fn from_raw_in(g: RawGraphData<N, E, Ty>, mut dg: Vec<graph>) -> Self {
         let adj = g.adj;
         let n = adj.len();
         let m = SETWORDSNEEDED(n);
-        let mut dg = empty_graph(m, n);
+        dg.clear();
+        dg.resize(m * n, 0);
         for (source, adj) in adj.into_iter().enumerate() {
             for target in adj {
                 ADDONEARC(&mut dg, source, target as usize, m);
@@ -366,11 +484,140 @@ where
     E: Hash + Ord,
 {
     fn from(g: Graph<N, E, Ty, Ix>) -> Self {
+        Self::from_graph_in(g, Vec::new())
+    }
+}
+
+impl<N, E, Ty> DenseGraph<(N, Vec<E>), E, Ty>
+where
+    Ty: EdgeType,
+    N: Ord,
+    E: Hash + Ord,
+{
+    /// Convert a petgraph `Graph`, reusing the allocation of `buf`
+    /// for the adjacency matrix
+    pub(crate) fn from_graph_in<Ix: IndexType>(
+        g: Graph<N, E, Ty, Ix>,
+        buf: Vec<graph>,
+    ) -> Self {
+        Self::from_raw_in(g.into(), buf)
+    }
+
+    // build from the node weights and the edges given by the
+    // positions of their end points
+    pub(crate) fn from_parts(
+        nodes: Vec<N>,
+        edges: impl IntoIterator<Item = ((usize, usize), E)>,
+    ) -> Self {
+        RawGraphData::from_parts(nodes, edges).into()
+    }
+}
+
+impl<'a, N, E, Ty, Null, Ix> From<&'a MatrixGraph<N, E, Ty, Null, Ix>>
+    for DenseGraph<(&'a N, Vec<&'a E>), &'a E, Ty>
+where
+    Ty: EdgeType,
+    Null: Nullable<Wrapped = E>,
+    Ix: IndexType,
+    N: Ord,
+    E: Hash + Ord,
+{
+    fn from(g: &'a MatrixGraph<N, E, Ty, Null, Ix>) -> Self {
+        let g: RawGraphData<_, _, _> = g.into();
+        g.into()
+    }
+}
+
+impl<'a, N, E, Ty, Ix> From<&'a Csr<N, E, Ty, Ix>>
+    for SparseGraph<(&'a N, Vec<&'a E>), &'a E, Ty>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+    N: Ord,
+    E: Hash + Ord,
+{
+    fn from(g: &'a Csr<N, E, Ty, Ix>) -> Self {
         let g: RawGraphData<_, _, _> = g.into();
         g.into()
     }
 }
 
+impl<'a, N, E, Ty, Ix> From<&'a Csr<N, E, Ty, Ix>>
+    for DenseGraph<(&'a N, Vec<&'a E>), &'a E, Ty>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+    N: Ord,
+    E: Hash + Ord,
+{
+    fn from(g: &'a Csr<N, E, Ty, Ix>) -> Self {
+        let g: RawGraphData<_, _, _> = g.into();
+        g.into()
+    }
+}
+
+impl<N, E, Ty> DenseGraph<(N, Vec<E>), E, Ty>
+where
+    Ty: EdgeType,
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+{
+    // build from any graph with compact node indices, cloning the
+    // weights
+    pub(crate) fn from_visit<G>(g: G) -> Self
+    where
+        G: IntoNodeReferences
+            + IntoEdgeReferences
+            + NodeCompactIndexable
+            + GraphProp<EdgeType = Ty>
+            + Data<NodeWeight = N, EdgeWeight = E>,
+    {
+        let mut nodes = Vec::from_iter((0..g.node_bound()).map(|_| None));
+        for n in g.node_references() {
+            nodes[g.to_index(n.id())] = Some(n.weight().clone());
+        }
+        let nodes = Vec::from_iter(nodes.into_iter().map(|n| n.unwrap()));
+        let edges = g.edge_references().map(|e| {
+            let edge = (g.to_index(e.source()), g.to_index(e.target()));
+            (edge, e.weight().clone())
+        });
+        RawGraphData::from_parts(nodes, edges).into()
+    }
+}
+
+impl<N, E, Ty> DenseGraph<N, E, Ty> {
+    // replace the adjacency matrix by that of the complement, without
+    // self-loops
+    //
+    // The edge weights are not updated, so this is only meaningful
+    // for analysing the automorphisms of graphs without auxiliary
+    // vertices for edge weights.
+    pub(crate) fn complement(&mut self) {
+        debug_assert_eq!(self.n, self.nodes.weights.len());
+        for v in 0..self.n {
+            let row = GRAPHROW(&mut self.g, v, self.m);
+            for w in (0..self.n).filter(|&w| w != v) {
+                row[SETWD(w)] ^= bit[SETBT(w)];
+            }
+        }
+    }
+}
+
+/// Copy of the structure of `g` with references to its weights
+///
+/// This allows analysing a borrowed graph without cloning its
+/// weights. References compare and hash like the referenced weights,
+/// so the results are the same as for `g` itself.
+pub(crate) fn borrowed<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Graph<&N, &E, Ty, Ix>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    g.map(|_, w| w, |_, w| w)
+}
+
 fn inv_perm(perm: &[c_int]) -> Vec<usize> {
     let mut relabel = vec![0; perm.len()];
     for (new, &old) in perm.iter().enumerate() {
@@ -442,6 +689,33 @@ where
     }
 }
 
+impl<N, E: Ord, Ty: EdgeType> DenseGraph<(N, Vec<E>), E, Ty> {
+    /// Node weights and edges in the order of the canonical graph,
+    /// without building it
+    ///
+    /// Must agree with the conversion to a petgraph `Graph`.
+    pub(crate) fn canon_parts(&self) -> (Vec<&N>, Vec<(usize, usize, &E)>) {
+        let relabel = inv_perm(&self.nodes.lab);
+        let mut nodes = vec![None; relabel.len()];
+        let mut edges = Vec::new();
+        for (&pos, (w, loops)) in izip!(&relabel, &self.nodes.weights) {
+            nodes[pos] = Some(w);
+            edges.extend(loops.iter().map(|w| (pos, pos, w)));
+        }
+        for (&(source, target), weights) in &self.edges {
+            let mut source = relabel[source];
+            let mut target = relabel[target];
+            if !Ty::is_directed() && source > target {
+                std::mem::swap(&mut source, &mut target);
+            }
+            edges.extend(weights.iter().map(|w| (source, target, w)));
+        }
+        sort(&mut edges);
+        let nodes = Vec::from_iter(nodes.into_iter().map(Option::unwrap));
+        (nodes, edges)
+    }
+}
+
 impl<N, E, Ty, Ix> From<SparseGraph<(N, Vec<E>), E, Ty>> for Graph<N, E, Ty, Ix>
 where
     Ty: EdgeType,
@@ -586,4 +860,37 @@ mod tests {
         tst_conv_sparse(g.clone());
         tst_conv_dense(g);
     }
+
+    #[test]
+    fn self_loops() {
+        log_init();
+
+        // loops are folded into the node weights, so nauty never sees
+        // them
+        let g = UnGraph::<u8, u8>::from_edges([
+            (0, 0, 2),
+            (0, 0, 1),
+            (0, 1, 0),
+            (1, 1, 0),
+            (1, 2, 0),
+        ]);
+        let raw = RawGraphData::from(g.clone());
+        for (v, adj) in raw.adj.iter().enumerate() {
+            assert!(!adj.contains(&(v as c_int)));
+        }
+        assert_eq!(raw.edges.len(), 2);
+        let loops = Vec::from_iter(raw.nodes.weights.iter().map(|w| &w.1));
+        assert!(loops.contains(&&vec![1, 2]));
+        assert!(loops.contains(&&vec![0]));
+        assert!(loops.contains(&&vec![]));
+        tst_conv_sparse(g.clone());
+        tst_conv_dense(g);
+
+        let g = DiGraph::<(), ()>::from_edges([(0, 0), (0, 0), (1, 1)]);
+        let raw = RawGraphData::from(g.clone());
+        assert!(raw.adj.iter().all(|adj| adj.is_empty()));
+        assert_eq!(raw.num_nauty_edges, 0);
+        tst_conv_sparse(g.clone());
+        tst_conv_dense(g);
+    }
 }
@@ -0,0 +1,277 @@
+//! Canonical forms of graphs with real-valued weights
+//!
+//! Floating-point weights cannot be compared for exact equality in a
+//! meaningful way. Instead, weights are clustered into classes of
+//! values that agree within a given tolerance, and the class indices
+//! are used as colours. Since the classification can be sensitive to
+//! small perturbations, potential instabilities are reported as
+//! [ClusterWarning]s.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nauty_pet::numeric::canon_weighted_matrix;
+//! use petgraph::Undirected;
+//!
+//! // two triangles with slightly perturbed weights
+//! let a = [vec![0.0, 1.0, 2.0], vec![1.0, 0.0, 1.0], vec![2.0, 1.0, 0.0]];
+//! let b = [
+//!     vec![0.0, 1.0001, 0.9999],
+//!     vec![1.0001, 0.0, 2.0002],
+//!     vec![0.9999, 2.0002, 0.0],
+//! ];
+//! let a = canon_weighted_matrix::<Undirected>(&a, 1e-3);
+//! let b = canon_weighted_matrix::<Undirected>(&b, 1e-3);
+//! assert_eq!(a.graph, b.graph);
+//! assert!(a.warnings.is_empty());
+//! ```
+use crate::cancel;
+use crate::canon::try_canon_with_labelling;
+use crate::graph::CanonGraph;
+
+use petgraph::{
+    graph::{Graph, NodeIndex},
+    EdgeType,
+};
+
+/// A potential instability in the clustering of values
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ClusterWarning {
+    /// The values in a class span more than the tolerance
+    ///
+    /// This happens when a chain of values, each within the tolerance
+    /// of the next, connects values that are further apart.
+    Chained {
+        /// Index of the class
+        class: usize,
+        /// Difference between the largest and the smallest value
+        spread: f64,
+    },
+    /// The gap between two adjacent classes is less than twice the
+    /// tolerance, so perturbing values by less than the tolerance
+    /// could merge the classes
+    NearBoundary {
+        /// Index of the lower class; the upper class has the next
+        /// index
+        class: usize,
+        /// Difference between the smallest value of the upper class
+        /// and the largest value of the lower class
+        gap: f64,
+    },
+}
+
+/// Values grouped into classes
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Clusters {
+    /// The class of each value
+    ///
+    /// Classes are numbered in increasing order of their values.
+    pub classes: Vec<usize>,
+    /// The mean value of each class
+    pub means: Vec<f64>,
+    /// Potential instabilities
+    pub warnings: Vec<ClusterWarning>,
+}
+
+/// Cluster values that agree within `tolerance`
+///
+/// Two values are in the same class if they are connected by a
+/// chain of values where each difference between neighbours is at
+/// most `tolerance`.
+///
+/// # Panics
+///
+/// Panics if any value is NaN or if `tolerance` is negative or NaN.
+pub fn cluster(values: &[f64], tolerance: f64) -> Clusters {
+    assert!(tolerance >= 0., "Invalid tolerance {tolerance}");
+    assert!(values.iter().all(|v| !v.is_nan()), "NaN value");
+    let mut order = Vec::from_iter(0..values.len());
+    order.sort_unstable_by(|&i, &j| values[i].total_cmp(&values[j]));
+
+    let mut res = Clusters {
+        classes: vec![0; values.len()],
+        ..Default::default()
+    };
+    // smallest value, largest value, sum, and size of each class
+    let mut bounds: Vec<(f64, f64, f64, usize)> = Vec::new();
+    for i in order {
+        let value = values[i];
+        match bounds.last_mut() {
+            Some((_, max, sum, size)) if value - *max <= tolerance => {
+                *max = value;
+                *sum += value;
+                *size += 1;
+            }
+            _ => bounds.push((value, value, value, 1)),
+        }
+        res.classes[i] = bounds.len() - 1;
+    }
+    for (class, &(min, max, sum, size)) in bounds.iter().enumerate() {
+        res.means.push(sum / size as f64);
+        let spread = max - min;
+        if spread > tolerance {
+            res.warnings.push(ClusterWarning::Chained { class, spread });
+        }
+        if let Some(&(next_min, ..)) = bounds.get(class + 1) {
+            let gap = next_min - max;
+            if gap <= 2. * tolerance {
+                res.warnings
+                    .push(ClusterWarning::NearBoundary { class, gap });
+            }
+        }
+    }
+    res
+}
+
+/// Canonical form of a graph with a real-valued adjacency matrix
+#[derive(Clone, Debug)]
+pub struct WeightedCanon<Ty: EdgeType> {
+    /// Canonical graph
+    ///
+    /// Vertex weights are the classes of the diagonal matrix entries,
+    /// edge weights the classes of the off-diagonal entries. Entries
+    /// in the same class as zero correspond to absent edges.
+    pub graph: CanonGraph<usize, usize, Ty>,
+    /// Mean value of each class
+    pub means: Vec<f64>,
+    /// Canonical labelling
+    ///
+    /// The `i`th row of the matrix corresponds to the vertex with
+    /// index `labelling[i]` in `graph`.
+    pub labelling: Vec<usize>,
+    /// Potential instabilities in the classification of the matrix
+    /// entries
+    pub warnings: Vec<ClusterWarning>,
+}
+
+/// Canonical form of the graph with the given adjacency matrix
+///
+/// All matrix entries, including an implicit zero, are clustered
+/// with [cluster]. Two matrices have the same canonical
+/// [graph](WeightedCanon::graph) if they are related by a simultaneous
+/// permutation of rows and columns after replacing each entry by its
+/// class. For undirected graphs, only the upper triangle is used.
+///
+/// # Panics
+///
+/// Panics if the matrix is not square, under the same conditions as
+/// [cluster], or if the graph is undirected and the classes of the
+/// matrix entries are not symmetric.
+pub fn canon_weighted_matrix<Ty: EdgeType>(
+    matrix: &[Vec<f64>],
+    tolerance: f64,
+) -> WeightedCanon<Ty> {
+    let n = matrix.len();
+    let mut values = Vec::with_capacity(n * n + 1);
+    for row in matrix {
+        assert_eq!(row.len(), n, "Matrix is not square");
+        values.extend_from_slice(row);
+    }
+    values.push(0.);
+    let Clusters {
+        classes,
+        means,
+        warnings,
+    } = cluster(&values, tolerance);
+    let zero = classes[n * n];
+    let class = |i: usize, j: usize| classes[i * n + j];
+
+    let mut g = Graph::with_capacity(n, 0);
+    for i in 0..n {
+        g.add_node(class(i, i));
+    }
+    for i in 0..n {
+        let start = if Ty::is_directed() { 0 } else { i + 1 };
+        for j in start..n {
+            if !Ty::is_directed() {
+                assert_eq!(class(i, j), class(j, i), "Asymmetric matrix");
+            }
+            if i != j && class(i, j) != zero {
+                g.add_edge(NodeIndex::new(i), NodeIndex::new(j), class(i, j));
+            }
+        }
+    }
+    let (canon, labelling) = cancel::unwrap(try_canon_with_labelling(g));
+    WeightedCanon {
+        graph: CanonGraph::from_canon_unchecked(canon),
+        means,
+        labelling,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::{Directed, Undirected};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn clustering() {
+        log_init();
+
+        let clusters = cluster(&[1.0, 0.0, 1.05, 3.0, 0.02], 0.1);
+        assert_eq!(clusters.classes, [1, 0, 1, 2, 0]);
+        assert_eq!(clusters.means.len(), 3);
+        assert!((clusters.means[1] - 1.025).abs() < 1e-12);
+        assert!(clusters.warnings.is_empty());
+
+        // chain of close values
+        let clusters = cluster(&[0.0, 0.1, 0.2, 0.3], 0.15);
+        assert_eq!(clusters.classes, [0, 0, 0, 0]);
+        assert!(matches!(
+            clusters.warnings[..],
+            [ClusterWarning::Chained { class: 0, .. }]
+        ));
+
+        // classes that are barely separated
+        let clusters = cluster(&[0.0, 0.25], 0.2);
+        assert_eq!(clusters.classes, [0, 1]);
+        assert!(matches!(
+            clusters.warnings[..],
+            [ClusterWarning::NearBoundary { class: 0, .. }]
+        ));
+    }
+
+    #[test]
+    fn matrices() {
+        log_init();
+
+        // directed path with a loop at the start
+        let a = [
+            vec![0.5, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+            vec![0.0, 0.0, 0.0],
+        ];
+        let b = [
+            vec![0.0, 0.0, 0.0],
+            vec![0.0, 0.0, 1e-9],
+            vec![1.0, 0.0, 0.5],
+        ];
+        let canon_a = canon_weighted_matrix::<Directed>(&a, 1e-6);
+        let canon_b = canon_weighted_matrix::<Directed>(&b, 1e-6);
+        assert_eq!(canon_a.graph.edge_count(), 2);
+        assert_ne!(canon_a.graph, canon_b.graph);
+        let b = [
+            vec![0.0, 1.0, 0.0],
+            vec![0.0, 0.0, 1e-9],
+            vec![1.0, 0.0, 0.5],
+        ];
+        let canon_b = canon_weighted_matrix::<Directed>(&b, 1e-6);
+        assert_eq!(canon_a.graph, canon_b.graph);
+        assert_eq!(canon_a.labelling[0], canon_b.labelling[2]);
+
+        // the same weights as undirected graphs
+        let sym = [
+            vec![0.5, 1.0, 0.0],
+            vec![1.0, 0.0, 1.0],
+            vec![0.0, 1.0, 0.0],
+        ];
+        let canon = canon_weighted_matrix::<Undirected>(&sym, 1e-6);
+        assert_eq!(canon.graph.edge_count(), 2);
+        assert_eq!(canon.means, [0.0, 0.5, 1.0]);
+    }
+}
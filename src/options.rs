@@ -0,0 +1,1538 @@
+//! Tuning options for nauty
+//!
+//! nauty's default options work well for most graphs. For hard
+//! instances like strongly regular graphs or graphs derived from
+//! designs, a stronger vertex invariant can drastically reduce the
+//! size of the search tree. See the nauty user guide for a
+//! description of the individual invariants and the meaning of their
+//! levels and arguments.
+//!
+//! Canonical forms depend on the options, so only canonical forms
+//! computed with the same options should be compared.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::options::{Invariant, NautyOptions};
+//!
+//! // the Petersen graph
+//! let outer = (0..5).map(|i| (i, (i + 1) % 5));
+//! let spokes = (0..5).map(|i| (i, i + 5));
+//! let inner = (0..5).map(|i| (i + 5, (i + 2) % 5 + 5));
+//! let g = UnGraph::<(), ()>::from_edges(outer.chain(spokes).chain(inner));
+//!
+//! let options = NautyOptions {
+//!     invariant: Some(Invariant::Distances),
+//!     ..Default::default()
+//! };
+//! let autom = options.autom_dense(&g).unwrap();
+//! assert_eq!(autom.grpsize(), 120.);
+//! ```
+use std::cell::Cell;
+use std::hash::Hash;
+use std::ops::ControlFlow;
+use std::os::raw::c_int;
+use std::time::Duration;
+
+use crate::autom::{dense_autom_in, sparse_autom_with, Autom, Backend};
+use crate::canon::{dense_graph_canon_in, sparse_graph_canon};
+use crate::error::NautyError;
+use crate::hooks::with_automproc;
+use crate::ignore::map_owned;
+use crate::nauty_graph::{borrowed, DenseGraph, SparseGraph};
+use crate::search::{with_search_hooks, SearchHooks};
+use crate::stats::NautyStats;
+#[cfg(feature = "traces")]
+use crate::stats::TracesSearchStats;
+use crate::timeout::with_timeout;
+use crate::workspace::NautyWorkspace;
+#[cfg(feature = "traces")]
+use crate::{autom::traces_autom, canon::traces_graph_canon};
+
+#[cfg(feature = "traces")]
+use nauty_Traces_sys::TracesOptions;
+use nauty_Traces_sys::{boolean, graph, optionblk, CONSOLWIDTH, FALSE, TRUE};
+#[cfg(feature = "traces")]
+use petgraph::graph::UnGraph;
+use petgraph::{
+    graph::{Graph, IndexType},
+    EdgeType,
+};
+
+macro_rules! invarprocs {
+    ($($name:ident),*) => {
+        extern "C" {
+            $(
+                fn $name(
+                    g: *mut graph,
+                    lab: *mut c_int,
+                    ptn: *mut c_int,
+                    level: c_int,
+                    numcells: c_int,
+                    tvpos: c_int,
+                    invar: *mut c_int,
+                    invararg: c_int,
+                    digraph: boolean,
+                    m: c_int,
+                    n: c_int,
+                );
+            )*
+        }
+    };
+}
+
+invarprocs!(
+    adjacencies,
+    adjtriang,
+    cellcliq,
+    cellfano,
+    cellfano2,
+    cellind,
+    cellquads,
+    cellquins,
+    celltrips,
+    cliques,
+    distances,
+    indsets,
+    quadruples,
+    refinvar,
+    triples,
+    twopaths,
+    adjacencies_sg,
+    distances_sg
+);
+
+extern "C" {
+    fn schreier_fails(nfails: c_int) -> c_int;
+}
+
+/// Vertex invariants provided by nauty
+///
+/// Only [Adjacencies](Invariant::Adjacencies) and
+/// [Distances](Invariant::Distances) are available for sparse nauty.
+/// Many invariants are designed for undirected graphs; consult the
+/// nauty user guide before using them with directed graphs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Invariant {
+    /// Colours of the neighbours
+    Adjacencies,
+    /// Common neighbours of pairs of vertices
+    Adjtriang,
+    /// Cliques within the largest non-singleton cells
+    Cellcliq,
+    /// Fano-like configurations within cells
+    Cellfano,
+    /// Fano-like configurations within cells, faster variant
+    Cellfano2,
+    /// Independent sets within the largest non-singleton cells
+    Cellind,
+    /// Quadruples within cells
+    Cellquads,
+    /// Quintuples within cells
+    Cellquins,
+    /// Triples within cells
+    Celltrips,
+    /// Cliques of the size given by the argument
+    Cliques,
+    /// Numbers of vertices at each distance
+    Distances,
+    /// Independent sets of the size given by the argument
+    Indsets,
+    /// Quadruples of vertices
+    Quadruples,
+    /// Refinement with one vertex individualised
+    Refinvar,
+    /// Triples of vertices
+    Triples,
+    /// Paths of length two
+    Twopaths,
+}
+
+/// Options for dense and sparse nauty
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NautyOptions {
+    /// Vertex invariant used to refine the partitions, if any
+    pub invariant: Option<Invariant>,
+    /// Least level of the search tree at which the invariant is
+    /// applied
+    pub min_invar_level: i32,
+    /// Greatest level of the search tree at which the invariant is
+    /// applied
+    pub max_invar_level: i32,
+    /// Argument passed to the invariant
+    pub invar_arg: i32,
+    /// Greatest level of the search tree at which nauty looks for a
+    /// target cell with many non-trivial joins to other cells,
+    /// instead of taking the first largest cell
+    ///
+    /// This can speed up hard instances, but is wasted effort on
+    /// most graphs.
+    pub tc_level: i32,
+    /// Maximum line length of the diagnostic output enabled with
+    /// the `log` feature
+    pub line_length: i32,
+    /// Write automorphisms in diagnostic output as lists of images
+    /// instead of in cycle notation
+    pub cartesian: bool,
+    /// Use the random Schreier method to prune the search tree
+    ///
+    /// For graphs with very large automorphism groups, this can save
+    /// a lot of memory. The reported group size remains exact.
+    pub schreier: bool,
+    /// Number of consecutive failures after which the random
+    /// Schreier method stops filtering group elements
+    ///
+    /// Only used if [schreier](Self::schreier) is set. Larger
+    /// values prune more at the cost of more time per node of the
+    /// search tree. Values less than one select nauty's default.
+    /// The value is stored by nauty for each thread.
+    pub schreier_fails: i32,
+}
+
+impl Default for NautyOptions {
+    fn default() -> Self {
+        Self {
+            invariant: None,
+            min_invar_level: 0,
+            max_invar_level: 1,
+            invar_arg: 0,
+            tc_level: 100,
+            line_length: CONSOLWIDTH,
+            cartesian: false,
+            schreier: false,
+            schreier_fails: 10,
+        }
+    }
+}
+
+impl NautyOptions {
+    /// Analyse the automorphism group of `g` with dense nauty
+    pub fn autom_dense<N, E, Ty, Ix>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+    ) -> Result<Autom, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        self.autom_dense_with_stats(g).map(|s| s.autom)
+    }
+
+    /// Analyse the automorphism group of `g` with dense nauty,
+    /// including statistics on the search
+    pub fn autom_dense_with_stats<N, E, Ty, Ix>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+    ) -> Result<NautyStats, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        self.autom_dense_in(g, &mut NautyWorkspace::default())
+    }
+
+    // dense nauty with the buffers of `ws`
+    fn autom_dense_in<N, E, Ty, Ix>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+        ws: &mut NautyWorkspace,
+    ) -> Result<NautyStats, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let buf = std::mem::take(&mut ws.g);
+        let mut dg = DenseGraph::from_graph_in(borrowed(g), buf);
+        let options = self.dense_options();
+        let _fails = self.set_schreier_fails();
+        let stats = dense_autom_in(&mut dg, g.is_directed(), options, ws);
+        ws.g = std::mem::take(&mut dg.g);
+        stats
+    }
+
+    /// Analyse the automorphism group of `g` with sparse nauty
+    pub fn autom_sparse<N, E, Ty, Ix>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+    ) -> Result<Autom, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        self.autom_sparse_with_stats(g).map(|s| s.autom)
+    }
+
+    /// Analyse the automorphism group of `g` with sparse nauty,
+    /// including statistics on the search
+    pub fn autom_sparse_with_stats<N, E, Ty, Ix>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+    ) -> Result<NautyStats, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let options = self.sparse_options()?;
+        let _fails = self.set_schreier_fails();
+        let sg = SparseGraph::from(borrowed(g));
+        sparse_autom_with(sg, g.is_directed(), options)
+    }
+
+    /// Analyse the automorphism group of `g` with dense nauty,
+    /// passing each generator to `f` as soon as it is found
+    ///
+    /// Each generator maps the `i`th vertex to the vertex with index
+    /// `generator[i]`. Returning [ControlFlow::Break] from `f` stops
+    /// the search, in which case the result is
+    /// [NautyError::Aborted]. A panic in `f` also stops the search
+    /// and is resumed afterwards. `f` must not call nauty or Traces;
+    /// doing so panics.
+    ///
+    /// nauty's mechanism for stopping a search is shared by all
+    /// threads. Searches stopped in other threads are repeated, so
+    /// `f` may receive the same generator more than once.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::ops::ControlFlow;
+    /// use petgraph::graph::UnGraph;
+    /// use nauty_pet::error::NautyError;
+    /// use nauty_pet::options::NautyOptions;
+    ///
+    /// // stop at the first non-trivial automorphism
+    /// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    /// let mut found = None;
+    /// let res = NautyOptions::default().autom_dense_with_generators(&g, |gen| {
+    ///     found = Some(gen.to_vec());
+    ///     ControlFlow::Break(())
+    /// });
+    /// assert!(matches!(res, Err(NautyError::Aborted)));
+    /// assert_eq!(found, Some(vec![2, 1, 0]));
+    /// ```
+    pub fn autom_dense_with_generators<N, E, Ty, Ix, F>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+        f: F,
+    ) -> Result<Autom, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+        F: FnMut(&[usize]) -> ControlFlow<()>,
+    {
+        let mut dg = DenseGraph::from(borrowed(g));
+        let options = self.dense_options();
+        let _fails = self.set_schreier_fails();
+        let mut automproc = original_generators(&dg.nodes.relabel, dg.n, f);
+        let mut ws = NautyWorkspace::default();
+        let stats = with_automproc(options, &mut automproc, |options| {
+            dense_autom_in(&mut dg, g.is_directed(), options, &mut ws)
+        });
+        stats.ok_or(NautyError::Aborted)?.map(|s| s.autom)
+    }
+
+    /// Analyse the automorphism group of `g` with sparse nauty,
+    /// passing each generator to `f` as soon as it is found
+    ///
+    /// See
+    /// [autom_dense_with_generators](Self::autom_dense_with_generators)
+    /// for details.
+    pub fn autom_sparse_with_generators<N, E, Ty, Ix, F>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+        f: F,
+    ) -> Result<Autom, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+        F: FnMut(&[usize]) -> ControlFlow<()>,
+    {
+        let options = self.sparse_options()?;
+        let _fails = self.set_schreier_fails();
+        let sg = SparseGraph::from(borrowed(g));
+        let n = sg.g.v.len();
+        let mut automproc = original_generators(&sg.nodes.relabel, n, f);
+        let stats = with_automproc(options, &mut automproc, |options| {
+            sparse_autom_with(sg, g.is_directed(), options)
+        });
+        stats.ok_or(NautyError::Aborted)?.map(|s| s.autom)
+    }
+
+    /// Analyse the automorphism group of `g` with dense nauty,
+    /// calling the given hooks during the search
+    ///
+    /// See the [search](crate::search) module for details.
+    pub fn autom_dense_with_hooks<N, E, Ty, Ix>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+        hooks: SearchHooks<'_>,
+    ) -> Result<Autom, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let mut dg = DenseGraph::from(borrowed(g));
+        let options = self.dense_options();
+        let _fails = self.set_schreier_fails();
+        let orig = original_vertices(&dg.nodes.relabel, dg.n);
+        let mut ws = NautyWorkspace::default();
+        let stats = with_search_hooks(options, hooks, &orig, |options| {
+            dense_autom_in(&mut dg, g.is_directed(), options, &mut ws)
+        });
+        stats.ok_or(NautyError::Aborted)?.map(|s| s.autom)
+    }
+
+    /// Analyse the automorphism group of `g` with sparse nauty,
+    /// calling the given hooks during the search
+    ///
+    /// See the [search](crate::search) module for details.
+    pub fn autom_sparse_with_hooks<N, E, Ty, Ix>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+        hooks: SearchHooks<'_>,
+    ) -> Result<Autom, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let options = self.sparse_options()?;
+        let _fails = self.set_schreier_fails();
+        let sg = SparseGraph::from(borrowed(g));
+        let orig = original_vertices(&sg.nodes.relabel, sg.g.v.len());
+        let stats = with_search_hooks(options, hooks, &orig, |options| {
+            sparse_autom_with(sg, g.is_directed(), options)
+        });
+        stats.ok_or(NautyError::Aborted)?.map(|s| s.autom)
+    }
+
+    /// The canonical form of `g` computed with dense nauty, calling
+    /// the given hooks during the search
+    ///
+    /// See the [search](crate::search) module for details.
+    pub fn canon_dense_with_hooks<N, E, Ty, Ix>(
+        &self,
+        g: Graph<N, E, Ty, Ix>,
+        hooks: SearchHooks<'_>,
+    ) -> Result<Graph<N, E, Ty, Ix>, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let dg = DenseGraph::from(g);
+        let options = self.dense_options();
+        let _fails = self.set_schreier_fails();
+        let orig = original_vertices(&dg.nodes.relabel, dg.n);
+        let mut ws = NautyWorkspace::default();
+        let res = with_search_hooks(options, hooks, &orig, |options| {
+            dense_graph_canon_in(dg, options, &mut ws)
+        });
+        let (dg, _) = res.ok_or(NautyError::Aborted)??;
+        Ok(dg.into())
+    }
+
+    /// The canonical form of `g` computed with dense nauty
+    pub fn canon_dense<N, E, Ty, Ix>(
+        &self,
+        g: Graph<N, E, Ty, Ix>,
+    ) -> Result<Graph<N, E, Ty, Ix>, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        self.canon_dense_with_stats(g).map(|(g, _)| g)
+    }
+
+    /// The canonical form of `g` computed with dense nauty, together
+    /// with statistics on the search
+    pub fn canon_dense_with_stats<N, E, Ty, Ix>(
+        &self,
+        g: Graph<N, E, Ty, Ix>,
+    ) -> Result<(Graph<N, E, Ty, Ix>, NautyStats), NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        self.canon_dense_in(g, &mut NautyWorkspace::default())
+    }
+
+    // dense nauty with the buffers of `ws`
+    fn canon_dense_in<N, E, Ty, Ix>(
+        &self,
+        g: Graph<N, E, Ty, Ix>,
+        ws: &mut NautyWorkspace,
+    ) -> Result<(Graph<N, E, Ty, Ix>, NautyStats), NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let buf = std::mem::take(&mut ws.g);
+        let dg = DenseGraph::from_graph_in(g, buf);
+        let options = self.dense_options();
+        let _fails = self.set_schreier_fails();
+        let (mut dg, stats) = dense_graph_canon_in(dg, options, ws)?;
+        ws.g = std::mem::take(&mut dg.g);
+        Ok((dg.into(), stats))
+    }
+
+    /// The canonical form of `g` computed with sparse nauty
+    pub fn canon_sparse<N, E, Ty, Ix>(
+        &self,
+        g: Graph<N, E, Ty, Ix>,
+    ) -> Result<Graph<N, E, Ty, Ix>, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        self.canon_sparse_with_stats(g).map(|(g, _)| g)
+    }
+
+    /// The canonical form of `g` computed with sparse nauty, together
+    /// with statistics on the search
+    ///
+    /// For the graph without vertices, nauty is not run and the
+    /// statistics are those of the trivial group.
+    pub fn canon_sparse_with_stats<N, E, Ty, Ix>(
+        &self,
+        g: Graph<N, E, Ty, Ix>,
+    ) -> Result<(Graph<N, E, Ty, Ix>, NautyStats), NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let options = self.sparse_options()?;
+        let _fails = self.set_schreier_fails();
+        if g.node_count() == 0 {
+            return Ok((g, NautyStats::trivial()));
+        }
+        let (sg, stats) = sparse_graph_canon(SparseGraph::from(g), options)?;
+        Ok((sg.into(), stats))
+    }
+
+    pub(crate) fn dense_options(&self) -> optionblk {
+        use Invariant::*;
+        let mut options = optionblk::default();
+        options.invarproc = match self.invariant {
+            None => None,
+            Some(Adjacencies) => Some(adjacencies),
+            Some(Adjtriang) => Some(adjtriang),
+            Some(Cellcliq) => Some(cellcliq),
+            Some(Cellfano) => Some(cellfano),
+            Some(Cellfano2) => Some(cellfano2),
+            Some(Cellind) => Some(cellind),
+            Some(Cellquads) => Some(cellquads),
+            Some(Cellquins) => Some(cellquins),
+            Some(Celltrips) => Some(celltrips),
+            Some(Cliques) => Some(cliques),
+            Some(Distances) => Some(distances),
+            Some(Indsets) => Some(indsets),
+            Some(Quadruples) => Some(quadruples),
+            Some(Refinvar) => Some(refinvar),
+            Some(Triples) => Some(triples),
+            Some(Twopaths) => Some(twopaths),
+        };
+        self.apply(&mut options);
+        options
+    }
+
+    pub(crate) fn sparse_options(&self) -> Result<optionblk, NautyError> {
+        use Invariant::*;
+        let mut options = optionblk::default_sparse();
+        options.invarproc = match self.invariant {
+            None => None,
+            Some(Adjacencies) => Some(adjacencies_sg),
+            Some(Distances) => Some(distances_sg),
+            Some(i) => return Err(NautyError::SparseInvariantUnsupported(i)),
+        };
+        self.apply(&mut options);
+        Ok(options)
+    }
+
+    // set everything except the vertex invariant
+    fn apply(&self, options: &mut optionblk) {
+        options.mininvarlevel = self.min_invar_level;
+        options.maxinvarlevel = self.max_invar_level;
+        options.invararg = self.invar_arg;
+        options.tc_level = self.tc_level;
+        options.linelength = self.line_length;
+        options.cartesian = if self.cartesian { TRUE } else { FALSE };
+        if self.schreier {
+            options.schreier = TRUE;
+        }
+    }
+
+    // set the thread-local number of failures for the Schreier method
+    // until the result is dropped
+    fn set_schreier_fails(&self) -> SchreierFails {
+        SchreierFails(self.schreier.then(|| unsafe {
+            schreier_fails(self.schreier_fails)
+        }))
+    }
+}
+
+// restores the previous number of failures for the Schreier method
+// when dropped
+struct SchreierFails(Option<c_int>);
+
+impl Drop for SchreierFails {
+    fn drop(&mut self) {
+        if let Some(prev) = self.0 {
+            unsafe {
+                schreier_fails(prev);
+            }
+        }
+    }
+}
+
+// The original vertex of each of nauty's `n` vertices, `usize::MAX`
+// for auxiliary vertices
+fn original_vertices(relabel: &[usize], n: usize) -> Vec<usize> {
+    let mut orig = vec![usize::MAX; n];
+    for (v, &nauty_idx) in relabel.iter().enumerate() {
+        orig[nauty_idx] = v;
+    }
+    orig
+}
+
+// Translate generators found by nauty back to the original vertices,
+// skipping generators that only permute auxiliary vertices, and pass
+// them on to `f`
+fn original_generators<F>(
+    relabel: &[usize],
+    n: usize,
+    mut f: F,
+) -> impl FnMut(&[c_int]) -> ControlFlow<()>
+where
+    F: FnMut(&[usize]) -> ControlFlow<()>,
+{
+    let relabel = relabel.to_vec();
+    let orig = original_vertices(&relabel, n);
+    let mut generator = Vec::with_capacity(relabel.len());
+    move |perm| {
+        generator.clear();
+        generator.extend(
+            relabel
+                .iter()
+                .map(|&nauty_idx| orig[perm[nauty_idx] as usize]),
+        );
+        if generator.iter().enumerate().all(|(v, &w)| v == w) {
+            return ControlFlow::Continue(());
+        }
+        f(&generator)
+    }
+}
+
+/// Search strategy of Traces
+#[cfg(feature = "traces")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TracesStrategy {
+    /// Breadth-first search, the only strategy currently supported
+    /// by Traces
+    #[default]
+    BreadthFirst,
+}
+
+/// Options for Traces
+///
+/// The diagnostic output of Traces is controlled by the `traces`
+/// level of the verbosity settings enabled with the `log` feature.
+/// Edge weights are always encoded by auxiliary vertices, so
+/// Traces' own support for weighted edges is not used.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::options::TracesConfig;
+///
+/// let g = UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+/// let autom = TracesConfig::default().autom(&g).unwrap();
+/// assert_eq!(autom.grpsize(), 12.);
+/// ```
+#[cfg(feature = "traces")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TracesConfig {
+    /// Search strategy
+    pub strategy: TracesStrategy,
+    /// Maximum line length of the diagnostic output, where zero
+    /// means no limit
+    pub line_length: i32,
+    /// Write automorphisms in diagnostic output as lists of images
+    /// instead of in cycle notation
+    pub cartesian: bool,
+}
+
+#[cfg(feature = "traces")]
+impl TracesConfig {
+    /// Analyse the automorphism group of `g` with Traces
+    ///
+    /// The result is the same as for
+    /// [TryIntoAutomTraces](crate::autom::TryIntoAutomTraces).
+    pub fn autom<N, E, Ix>(
+        &self,
+        g: &UnGraph<N, E, Ix>,
+    ) -> Result<Autom, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ix: IndexType,
+    {
+        self.autom_with_stats(g).map(|s| s.autom)
+    }
+
+    /// Analyse the automorphism group of `g` with Traces, including
+    /// statistics on the search
+    pub fn autom_with_stats<N, E, Ix>(
+        &self,
+        g: &UnGraph<N, E, Ix>,
+    ) -> Result<TracesSearchStats, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ix: IndexType,
+    {
+        let sg = SparseGraph::from(borrowed(g));
+        traces_autom(sg, self.traces_options())
+    }
+
+    /// The canonical form of `g` computed with Traces
+    ///
+    /// The result is the same as for
+    /// [TryIntoCanonTraces](crate::canon::TryIntoCanonTraces).
+    pub fn canon<N, E, Ix>(
+        &self,
+        g: UnGraph<N, E, Ix>,
+    ) -> Result<UnGraph<N, E, Ix>, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ix: IndexType,
+    {
+        self.canon_with_stats(g).map(|(g, _)| g)
+    }
+
+    /// The canonical form of `g` computed with Traces, together with
+    /// statistics on the search
+    ///
+    /// For the graph without vertices, Traces is not run and the
+    /// statistics are those of the trivial group.
+    pub fn canon_with_stats<N, E, Ix>(
+        &self,
+        g: UnGraph<N, E, Ix>,
+    ) -> Result<(UnGraph<N, E, Ix>, TracesSearchStats), NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ix: IndexType,
+    {
+        if g.node_count() == 0 {
+            return Ok((g, TracesSearchStats::trivial()));
+        }
+        let sg = SparseGraph::from(g);
+        let (sg, stats) = traces_graph_canon(sg, self.traces_options())?;
+        Ok((sg.into(), stats))
+    }
+
+    pub(crate) fn traces_options(&self) -> TracesOptions {
+        TracesOptions {
+            strategy: match self.strategy {
+                TracesStrategy::BreadthFirst => 0,
+            },
+            linelength: self.line_length,
+            cartesian: if self.cartesian { TRUE } else { FALSE },
+            ..Default::default()
+        }
+    }
+}
+
+/// Options accepted by [TryIntoAutomWithOptions] and
+/// [TryIntoCanonWithOptions]
+///
+/// Use [CanonOptionsBuilder] to construct options. As for the
+/// individual options, canonical forms should only be compared if
+/// they were computed with the same options.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CanonOptions {
+    backend: Backend,
+    nauty: NautyOptions,
+    #[cfg(feature = "traces")]
+    traces: TracesConfig,
+    fixed: Vec<usize>,
+    colours: Vec<usize>,
+    timeout: Option<Duration>,
+    reuse_workspace: bool,
+}
+
+thread_local! {
+    // buffers reused by the dense nauty runs of this thread
+    static WORKSPACE: Cell<NautyWorkspace> = Cell::new(NautyWorkspace::new());
+}
+
+impl CanonOptions {
+    /// Start building options
+    pub fn builder() -> CanonOptionsBuilder {
+        CanonOptionsBuilder::default()
+    }
+
+    /// The backend, which is dense nauty unless chosen otherwise
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// The time limit, if any
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    // run `f` within the time limit
+    fn run<T>(
+        &self,
+        f: impl FnOnce() -> Result<T, NautyError>,
+    ) -> Result<T, NautyError> {
+        match self.timeout {
+            Some(timeout) => with_timeout(timeout, f)?,
+            None => f(),
+        }
+    }
+
+    // run `f` with the workspace of the current thread if it is to be
+    // reused, otherwise with fresh buffers
+    fn with_workspace<T>(&self, f: impl FnOnce(&mut NautyWorkspace) -> T) -> T {
+        if !self.reuse_workspace {
+            return f(&mut NautyWorkspace::new());
+        }
+        let mut ws = WORKSPACE.with(Cell::take);
+        let res = f(&mut ws);
+        WORKSPACE.with(|w| w.set(ws));
+        res
+    }
+
+    // the colour of each vertex, ordered before the vertex weight
+    fn vertex_colours(&self, n: usize) -> Vec<(usize, usize)> {
+        let mut colours = vec![(self.fixed.len(), 0); n];
+        for (v, &c) in self.colours.iter().enumerate().take(n) {
+            colours[v].1 = c;
+        }
+        for (i, &v) in self.fixed.iter().enumerate() {
+            assert!(v < n, "fixed vertex {v} is not in the graph");
+            colours[v].0 = i;
+        }
+        colours
+    }
+}
+
+/// Builder for [CanonOptions]
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::options::{
+///     CanonOptionsBuilder, Invariant, TryIntoAutomWithOptions,
+/// };
+/// use nauty_pet::Backend;
+///
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+/// let options = CanonOptionsBuilder::new()
+///     .backend(Backend::NautySparse)
+///     .invariant(Invariant::Distances)
+///     .fixed_vertices([1])
+///     .build();
+/// let autom = g.try_into_autom_with_options(&options).unwrap();
+/// assert_eq!(autom.grpsize(), 1.);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CanonOptionsBuilder {
+    options: CanonOptions,
+}
+
+impl CanonOptionsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run the given backend
+    ///
+    /// Traces only supports undirected graphs.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.options.backend = backend;
+        self
+    }
+
+    /// Options for dense and sparse nauty
+    pub fn nauty(mut self, nauty: NautyOptions) -> Self {
+        self.options.nauty = nauty;
+        self
+    }
+
+    /// Vertex invariant for dense and sparse nauty, see
+    /// [NautyOptions::invariant]
+    pub fn invariant(mut self, invariant: Invariant) -> Self {
+        self.options.nauty.invariant = Some(invariant);
+        self
+    }
+
+    /// Options for Traces
+    #[cfg(feature = "traces")]
+    pub fn traces(mut self, traces: TracesConfig) -> Self {
+        self.options.traces = traces;
+        self
+    }
+
+    /// Individualise the vertices with the given indices
+    ///
+    /// As for [with_fixed_vertices](crate::canon::with_fixed_vertices),
+    /// the `i`th fixed vertex becomes the vertex with index `i` in
+    /// the canonical form, and automorphisms fix each of the
+    /// vertices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a vertex is fixed more than once. Running nauty
+    /// panics if a fixed vertex is not in the graph.
+    pub fn fixed_vertices(
+        mut self,
+        fixed: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        for v in fixed {
+            assert!(
+                !self.options.fixed.contains(&v),
+                "vertex {v} is fixed more than once"
+            );
+            self.options.fixed.push(v);
+        }
+        self
+    }
+
+    /// Colour the vertices in addition to their weights
+    ///
+    /// The `i`th entry is the colour of the vertex with index `i`.
+    /// Vertices without an entry have colour zero. Colours take
+    /// precedence over weights in the ordering of the vertices of
+    /// the canonical form.
+    pub fn colouring(mut self, colours: Vec<usize>) -> Self {
+        self.options.colours = colours;
+        self
+    }
+
+    /// Stop nauty or Traces once `timeout` has passed
+    ///
+    /// Computations exceeding the limit return
+    /// [NautyError::Timeout], see [timeout](crate::timeout).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// Reuse the buffers of dense nauty between calls in the same
+    /// thread
+    ///
+    /// See [NautyWorkspace] for the buffers that are reused. The
+    /// results are the same as without reusing buffers.
+    pub fn reuse_workspace(mut self, reuse: bool) -> Self {
+        self.options.reuse_workspace = reuse;
+        self
+    }
+
+    pub fn build(self) -> CanonOptions {
+        self.options
+    }
+}
+
+/// Analyse a graph's automorphism group with the given options
+pub trait TryIntoAutomWithOptions {
+    type Error;
+
+    fn try_into_autom_with_options(
+        self,
+        options: &CanonOptions,
+    ) -> Result<Autom, Self::Error>;
+}
+
+/// Find the canonical form of a graph with the given options
+pub trait TryIntoCanonWithOptions: Sized {
+    type Error;
+
+    fn try_into_canon_with_options(
+        self,
+        options: &CanonOptions,
+    ) -> Result<Self, Self::Error>;
+}
+
+impl<N, E, Ty, Ix> TryIntoAutomWithOptions for Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_autom_with_options(
+        self,
+        options: &CanonOptions,
+    ) -> Result<Autom, Self::Error> {
+        let mut colours = options.vertex_colours(self.node_count()).into_iter();
+        let g = map_owned(self, |w| (colours.next().unwrap(), w), |w| w);
+        options.run(|| match options.backend() {
+            Backend::NautyDense => options
+                .with_workspace(|ws| options.nauty.autom_dense_in(&g, ws))
+                .map(|s| s.autom),
+            Backend::NautySparse => options.nauty.autom_sparse(&g),
+            #[cfg(feature = "traces")]
+            Backend::Traces => {
+                if g.is_directed() {
+                    return Err(NautyError::TracesDigraphUnsupported);
+                }
+                let g = g.into_edge_type();
+                options.traces.autom(&g)
+            }
+        })
+    }
+}
+
+impl<N, E, Ty, Ix> TryIntoCanonWithOptions for Graph<N, E, Ty, Ix>
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = NautyError;
+
+    fn try_into_canon_with_options(
+        self,
+        options: &CanonOptions,
+    ) -> Result<Self, Self::Error> {
+        let mut colours = options.vertex_colours(self.node_count()).into_iter();
+        let g = map_owned(self, |w| (colours.next().unwrap(), w), |w| w);
+        let canon = options.run(|| match options.backend() {
+            Backend::NautyDense => options
+                .with_workspace(|ws| options.nauty.canon_dense_in(g, ws))
+                .map(|(g, _)| g),
+            Backend::NautySparse => options.nauty.canon_sparse(g),
+            #[cfg(feature = "traces")]
+            Backend::Traces => {
+                if g.is_directed() {
+                    return Err(NautyError::TracesDigraphUnsupported);
+                }
+                let canon = options.traces.canon(g.into_edge_type())?;
+                Ok(canon.into_edge_type())
+            }
+        })?;
+        Ok(map_owned(canon, |(_, w)| w, |w| w))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autom::TryIntoAutom;
+    use crate::canon::{IntoCanonNautyDense, IntoCanonNautySparse};
+    use crate::cmp::IsIdentical;
+    use petgraph::{
+        graph::{NodeIndex, UnGraph},
+        Directed,
+    };
+    use rand::prelude::*;
+    use rand_xoshiro::Xoshiro256Plus;
+    use testing::{randomize_labels, GraphIter};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    const INVARIANTS: [Invariant; 16] = {
+        use Invariant::*;
+        [
+            Adjacencies,
+            Adjtriang,
+            Cellcliq,
+            Cellfano,
+            Cellfano2,
+            Cellind,
+            Cellquads,
+            Cellquins,
+            Celltrips,
+            Cliques,
+            Distances,
+            Indsets,
+            Quadruples,
+            Refinvar,
+            Triples,
+            Twopaths,
+        ]
+    };
+
+    // Cayley graph of Z_4 x Z_4 with the given connection set
+    fn cayley(gens: &[(usize, usize)]) -> UnGraph<(), ()> {
+        let mut edges = Vec::new();
+        for v in 0..16 {
+            let (x, y) = (v / 4, v % 4);
+            for (dx, dy) in gens {
+                let w = 4 * ((x + dx) % 4) + (y + dy) % 4;
+                if v < w {
+                    edges.push((v as u32, w as u32));
+                }
+            }
+        }
+        UnGraph::from_edges(edges)
+    }
+
+    #[test]
+    fn strongly_regular() {
+        log_init();
+
+        // both are strongly regular with parameters (16, 6, 2, 2)
+        let rook = cayley(&[(1, 0), (2, 0), (3, 0), (0, 1), (0, 2), (0, 3)]);
+        let shrikhande =
+            cayley(&[(1, 0), (3, 0), (0, 1), (0, 3), (1, 1), (3, 3)]);
+        assert_eq!(rook.clone().try_into_autom().unwrap().grpsize(), 1152.);
+        let expected = shrikhande.clone().try_into_autom().unwrap().grpsize();
+        assert_eq!(expected, 192.);
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for invariant in INVARIANTS {
+            let options = NautyOptions {
+                invariant: Some(invariant),
+                max_invar_level: 2,
+                invar_arg: 3,
+                ..Default::default()
+            };
+            let autom = options.autom_dense(&shrikhande).unwrap();
+            assert_eq!(autom.grpsize(), expected);
+            let canon = options.canon_dense(shrikhande.clone()).unwrap();
+            let relabelled = randomize_labels(shrikhande.clone(), &mut rng);
+            let other = options.canon_dense(relabelled).unwrap();
+            assert!(canon.is_identical(&other));
+            let rook = options.canon_dense(rook.clone()).unwrap();
+            assert!(!canon.is_identical(&rook));
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        log_init();
+
+        let options = NautyOptions::default();
+        for g in GraphIter::<Directed>::default().take(100) {
+            let dense = options.canon_dense(g.clone()).unwrap();
+            assert!(dense.is_identical(&g.clone().into_canon_nauty_dense()));
+            let sparse = options.canon_sparse(g.clone()).unwrap();
+            assert!(sparse.is_identical(&g.clone().into_canon_nauty_sparse()));
+            let expected = (&g).try_into_autom().unwrap();
+            assert_eq!(options.autom_dense(&g).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn target_cells() {
+        log_init();
+
+        let g = cayley(&[(1, 0), (3, 0), (0, 1), (0, 3), (1, 1), (3, 3)]);
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for tc_level in [0, 1, 100] {
+            let options = NautyOptions {
+                tc_level,
+                ..Default::default()
+            };
+            assert_eq!(options.autom_dense(&g).unwrap().grpsize(), 192.);
+            assert_eq!(options.autom_sparse(&g).unwrap().grpsize(), 192.);
+            let canon = options.canon_dense(g.clone()).unwrap();
+            let relabelled = randomize_labels(g.clone(), &mut rng);
+            let other = options.canon_dense(relabelled).unwrap();
+            assert!(canon.is_identical(&other));
+        }
+    }
+
+    #[test]
+    fn schreier() {
+        log_init();
+
+        // the complete graph on 12 vertices has 12! automorphisms
+        let complete = UnGraph::<(), ()>::from_edges(
+            (0..12u32).flat_map(|i| (0..i).map(move |j| (i, j))),
+        );
+        let shrikhande =
+            cayley(&[(1, 0), (3, 0), (0, 1), (0, 3), (1, 1), (3, 3)]);
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for schreier_fails in [0, 1, 10, 100] {
+            let options = NautyOptions {
+                schreier: true,
+                schreier_fails,
+                ..Default::default()
+            };
+            let autom = options.autom_dense(&complete).unwrap();
+            assert_eq!(autom.grpsize(), 479001600.);
+            let autom = options.autom_sparse(&complete).unwrap();
+            assert_eq!(autom.grpsize(), 479001600.);
+            let autom = options.autom_dense(&shrikhande).unwrap();
+            assert_eq!(autom.grpsize(), 192.);
+            let canon = options.canon_dense(shrikhande.clone()).unwrap();
+            let relabelled = randomize_labels(shrikhande.clone(), &mut rng);
+            let other = options.canon_dense(relabelled).unwrap();
+            assert!(canon.is_identical(&other));
+        }
+
+        // the number of failures is restored after each call
+        let prev = unsafe { schreier_fails(7) };
+        let options = NautyOptions {
+            schreier: true,
+            schreier_fails: 100,
+            ..Default::default()
+        };
+        options.autom_sparse(&complete).unwrap();
+        assert_eq!(unsafe { schreier_fails(prev) }, 7);
+
+        for g in GraphIter::<Directed>::default().take(100) {
+            let options = NautyOptions {
+                schreier: true,
+                ..Default::default()
+            };
+            let expected = (&g).try_into_autom().unwrap();
+            let autom = options.autom_dense(&g).unwrap();
+            assert_eq!(autom.grpsize(), expected.grpsize());
+            assert_eq!(autom.num_orbits, expected.num_orbits);
+        }
+    }
+
+    #[cfg(feature = "traces")]
+    #[test]
+    fn traces() {
+        use crate::canon::IntoCanonTraces;
+        use petgraph::Undirected;
+
+        log_init();
+
+        let config = TracesConfig {
+            cartesian: true,
+            line_length: 40,
+            ..Default::default()
+        };
+        for g in GraphIter::<Undirected>::default().take(100) {
+            let canon = config.canon(g.clone()).unwrap();
+            assert!(canon.is_identical(&g.clone().into_canon_traces()));
+            let autom = config.autom(&g).unwrap();
+            let expected = g.try_into_autom().unwrap();
+            assert_eq!(autom.grpsize(), expected.grpsize());
+            assert_eq!(autom.num_orbits, expected.num_orbits);
+        }
+        let g = cayley(&[(1, 0), (3, 0), (0, 1), (0, 3), (1, 1), (3, 3)]);
+        assert_eq!(config.autom(&g).unwrap().grpsize(), 192.);
+    }
+
+    #[test]
+    fn canon_options() {
+        log_init();
+
+        let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        let mut backends = vec![Backend::NautyDense, Backend::NautySparse];
+        #[cfg(feature = "traces")]
+        backends.push(Backend::Traces);
+        for backend in backends {
+            let options = CanonOptions::builder().backend(backend).build();
+            let autom =
+                path.clone().try_into_autom_with_options(&options).unwrap();
+            assert_eq!(autom.grpsize(), 2.);
+            let canon =
+                path.clone().try_into_canon_with_options(&options).unwrap();
+            assert_eq!(canon.edge_count(), 3);
+
+            // fixing an end point or colouring removes the symmetry
+            let end = CanonOptions::builder()
+                .backend(backend)
+                .fixed_vertices([0])
+                .build();
+            let autom = path.clone().try_into_autom_with_options(&end).unwrap();
+            assert_eq!(autom.grpsize(), 1.);
+            let other_end = CanonOptions::builder()
+                .backend(backend)
+                .fixed_vertices([3])
+                .build();
+            let canon = path.clone().try_into_canon_with_options(&end);
+            let other = path.clone().try_into_canon_with_options(&other_end);
+            assert!(canon.unwrap().is_identical(&other.unwrap()));
+            let coloured = CanonOptions::builder()
+                .backend(backend)
+                .colouring(vec![1, 0, 0, 0])
+                .build();
+            let autom =
+                path.clone().try_into_autom_with_options(&coloured).unwrap();
+            assert_eq!(autom.grpsize(), 1.);
+        }
+
+        // defaults agree with the plain canonical form
+        let options = CanonOptions::default();
+        for g in GraphIter::<Directed>::default().take(100) {
+            let canon = g.clone().try_into_canon_with_options(&options);
+            assert!(canon.unwrap().is_identical(&g.into_canon_nauty_dense()));
+        }
+
+        // so do the results with a time limit and reused buffers
+        let options = CanonOptions::builder()
+            .timeout(Duration::from_secs(100))
+            .reuse_workspace(true)
+            .build();
+        assert_eq!(options.timeout(), Some(Duration::from_secs(100)));
+        for g in GraphIter::<Directed>::default().take(100) {
+            let autom = g.clone().try_into_autom_with_options(&options);
+            assert_eq!(autom.unwrap(), g.clone().try_into_autom().unwrap());
+            let canon = g.clone().try_into_canon_with_options(&options);
+            assert!(canon.unwrap().is_identical(&g.into_canon_nauty_dense()));
+        }
+    }
+
+    #[cfg(feature = "traces")]
+    #[test]
+    fn canon_options_traces_digraph() {
+        log_init();
+
+        let g = petgraph::graph::DiGraph::<(), ()>::from_edges([(0, 1)]);
+        let options = CanonOptions::builder().backend(Backend::Traces).build();
+        assert!(matches!(
+            g.try_into_canon_with_options(&options),
+            Err(NautyError::TracesDigraphUnsupported)
+        ));
+    }
+
+    #[test]
+    fn sparse() {
+        log_init();
+
+        let g = cayley(&[(1, 0), (3, 0), (0, 1), (0, 3), (1, 1), (3, 3)]);
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for invariant in [Invariant::Adjacencies, Invariant::Distances] {
+            let options = NautyOptions {
+                invariant: Some(invariant),
+                ..Default::default()
+            };
+            assert_eq!(options.autom_sparse(&g).unwrap().grpsize(), 192.);
+            let canon = options.canon_sparse(g.clone()).unwrap();
+            let relabelled = randomize_labels(g.clone(), &mut rng);
+            let other = options.canon_sparse(relabelled).unwrap();
+            assert!(canon.is_identical(&other));
+        }
+        let options = NautyOptions {
+            invariant: Some(Invariant::Cellquads),
+            ..Default::default()
+        };
+        assert!(matches!(
+            options.autom_sparse(&g),
+            Err(NautyError::SparseInvariantUnsupported(Invariant::Cellquads))
+        ));
+    }
+
+    #[test]
+    fn stats() {
+        log_init();
+
+        let g = cayley(&[(1, 0), (3, 0), (0, 1), (0, 3), (1, 1), (3, 3)]);
+        let options = NautyOptions::default();
+        let stats = options.autom_dense_with_stats(&g).unwrap();
+        assert_eq!(stats.autom, options.autom_dense(&g).unwrap());
+        assert!(stats.num_nodes > 0);
+        assert!(stats.max_level > 0);
+        assert_eq!(stats.invar_applications, 0);
+        let (canon, stats) =
+            options.canon_sparse_with_stats(g.clone()).unwrap();
+        assert!(canon.is_identical(&options.canon_sparse(g.clone()).unwrap()));
+        assert_eq!(stats.autom.grpsize(), 192.);
+
+        let options = NautyOptions {
+            invariant: Some(Invariant::Distances),
+            max_invar_level: 2,
+            ..Default::default()
+        };
+        let (_, stats) = options.canon_dense_with_stats(g.clone()).unwrap();
+        assert_eq!(stats.autom.grpsize(), 192.);
+        assert!(stats.invar_applications > 0);
+        let stats = options.autom_sparse_with_stats(&g).unwrap();
+        assert!(stats.invar_applications > 0);
+
+        let (_, stats) = options
+            .canon_dense_with_stats(UnGraph::<(), ()>::default())
+            .unwrap();
+        assert!(stats.autom.is_asymmetric());
+        assert_eq!(stats.num_nodes, 0);
+
+        #[cfg(feature = "traces")]
+        {
+            let config = TracesConfig::default();
+            let stats = config.autom_with_stats(&g).unwrap();
+            assert_eq!(stats.autom.grpsize(), 192.);
+            assert!(stats.num_nodes > 0);
+            let (canon, stats) = config.canon_with_stats(g.clone()).unwrap();
+            assert!(canon.is_identical(&config.canon(g).unwrap()));
+            assert!(stats.tree_depth > 0);
+        }
+    }
+
+    // check that `perm` maps edges of `g` to edges
+    fn is_automorphism(g: &UnGraph<(), u8>, perm: &[usize]) -> bool {
+        use petgraph::visit::EdgeRef;
+        g.edge_references().all(|e| {
+            let source = NodeIndex::new(perm[e.source().index()]);
+            let target = NodeIndex::new(perm[e.target().index()]);
+            g.find_edge(source, target)
+                .map(|f| g[f] == *e.weight())
+                .unwrap_or(false)
+        })
+    }
+
+    #[test]
+    fn generators() {
+        log_init();
+
+        // hexagon with alternating edge weights
+        let g = UnGraph::<(), u8>::from_edges(
+            (0..6).map(|i| (i, (i + 1) % 6, (i % 2) as u8)),
+        );
+        let options = NautyOptions::default();
+        let mut gens = Vec::new();
+        let autom = options
+            .autom_dense_with_generators(&g, |gen| {
+                gens.push(gen.to_vec());
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(autom, options.autom_dense(&g).unwrap());
+        assert_eq!(autom.grpsize(), 6.);
+        assert!(!gens.is_empty());
+        for gen in gens {
+            assert_eq!(gen.len(), 6);
+            assert!(is_automorphism(&g, &gen));
+        }
+
+        let mut gens = Vec::new();
+        let autom = options
+            .autom_sparse_with_generators(&g, |gen| {
+                gens.push(gen.to_vec());
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(autom.grpsize(), 6.);
+        assert!(gens.iter().all(|gen| is_automorphism(&g, gen)));
+    }
+
+    #[test]
+    fn generators_abort() {
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let complete = UnGraph::<(), u8>::from_edges(
+            (0..10u32).flat_map(|i| (0..i).map(move |j| (i, j, 0))),
+        );
+        let options = NautyOptions::default();
+        let mut count = 0;
+        let res = options.autom_dense_with_generators(&complete, |gen| {
+            assert!(is_automorphism(&complete, gen));
+            count += 1;
+            ControlFlow::Break(())
+        });
+        assert!(matches!(res, Err(NautyError::Aborted)));
+        assert_eq!(count, 1);
+        let mut count = 0;
+        let res = options.autom_sparse_with_generators(&complete, |_| {
+            count += 1;
+            ControlFlow::Break(())
+        });
+        assert!(matches!(res, Err(NautyError::Aborted)));
+        assert_eq!(count, 1);
+
+        let res = std::panic::catch_unwind(|| {
+            options.autom_dense_with_generators(&complete, |_| panic!("gen"))
+        });
+        assert_eq!(*res.unwrap_err().downcast::<&str>().unwrap(), "gen");
+
+        // later searches are unaffected
+        let autom = options.autom_dense(&complete).unwrap();
+        assert_eq!(autom.grpsize(), 3628800.);
+        let autom = options
+            .autom_sparse_with_generators(&complete, |_| {
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(autom.grpsize(), 3628800.);
+    }
+
+    #[test]
+    fn generators_reentrant() {
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let options = NautyOptions::default();
+        let res = std::panic::catch_unwind(|| {
+            options.autom_dense_with_generators(&g, |_| {
+                let _ = (&g).try_into_autom();
+                ControlFlow::Continue(())
+            })
+        });
+        let msg = res.unwrap_err().downcast::<&str>().unwrap();
+        assert_eq!(*msg, "reentrant call to nauty or Traces");
+
+        // later searches are unaffected
+        let autom = options.autom_dense(&g).unwrap();
+        assert_eq!(autom.grpsize(), 2.);
+    }
+
+    #[test]
+    fn generators_abort_threads() {
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let g = cayley(&[(1, 0), (3, 0), (0, 1), (0, 3), (1, 1), (3, 3)]);
+        let options = NautyOptions::default();
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for _ in 0..100 {
+                    let res = options.autom_sparse_with_generators(&g, |_| {
+                        ControlFlow::Break(())
+                    });
+                    assert!(matches!(res, Err(NautyError::Aborted)));
+                }
+            });
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        let autom = options.autom_dense(&g).unwrap();
+                        assert_eq!(autom.grpsize(), 192.);
+                        let canon = options.canon_sparse(g.clone()).unwrap();
+                        assert_eq!(canon.edge_count(), 48);
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,238 @@
+//! Symmetry information for orbital branching and orbital fixing
+//!
+//! Integer programming and constraint programming solvers can exploit
+//! the symmetries of a problem by branching on whole orbits of
+//! variables instead of single variables, and by fixing variables
+//! that are equivalent under the symmetries that remain after
+//! branching. The symmetries are usually obtained from a graph that
+//! encodes the problem, where some vertices correspond to the
+//! variables. This module computes the relevant orbits and
+//! generators in terms of variable indices, independent of any
+//! particular solver.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::{NodeIndex, UnGraph};
+//! use nauty_pet::orbital::{orbital_symmetry, stabilizer_chain};
+//!
+//! // constraint x0 + x1 + x2 + x3 <= 2 encoded as a star with the
+//! // constraint vertex 4 in the centre
+//! let mut g = UnGraph::<u8, ()>::from_edges([(4, 0), (4, 1), (4, 2), (4, 3)]);
+//! g[NodeIndex::new(4)] = 1;
+//! let variables = Vec::from_iter((0..4).map(NodeIndex::new));
+//!
+//! let symmetry = orbital_symmetry(&g, &variables);
+//! assert_eq!(symmetry.orbits, [0, 0, 0, 0]);
+//!
+//! // after branching on x1, the other variables are still equivalent
+//! let chain = stabilizer_chain(&g, &variables, &[1]);
+//! assert_eq!(chain[1].orbits, [0, 1, 0, 0]);
+//! ```
+use std::hash::Hash;
+
+use crate::autom::autom_generators;
+
+use petgraph::{
+    graph::{Graph, IndexType, NodeIndex},
+    EdgeType,
+};
+
+/// Symmetries acting on the variables of a problem
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct OrbitalSymmetry {
+    /// Generators of the symmetry group
+    ///
+    /// Each generator maps the `i`th variable to the variable with
+    /// index `generator[i]`.
+    pub generators: Vec<Vec<usize>>,
+    /// Orbit of each variable, given by the smallest variable index
+    /// in the orbit
+    pub orbits: Vec<usize>,
+}
+
+impl OrbitalSymmetry {
+    /// All variables in the same orbit as `var`
+    pub fn orbit(&self, var: usize) -> Vec<usize> {
+        let rep = self.orbits[var];
+        Vec::from_iter(
+            (0..self.orbits.len()).filter(|&v| self.orbits[v] == rep),
+        )
+    }
+
+    /// The number of orbits
+    pub fn num_orbits(&self) -> usize {
+        self.orbits
+            .iter()
+            .enumerate()
+            .filter(|&(var, &rep)| var == rep)
+            .count()
+    }
+
+    /// Check whether there are no symmetries
+    pub fn is_trivial(&self) -> bool {
+        self.generators.is_empty()
+    }
+}
+
+/// Symmetries of a graph restricted to the vertices representing
+/// variables
+///
+/// The `i`th variable corresponds to the vertex `variables[i]`. Only
+/// automorphisms that map variables to variables are taken into
+/// account.
+///
+/// # Panics
+///
+/// Panics if a vertex appears more than once in `variables`.
+pub fn orbital_symmetry<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    variables: &[NodeIndex<Ix>],
+) -> OrbitalSymmetry
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    fixed_symmetry(g, variables, &[])
+}
+
+/// Symmetries remaining after successively fixing variables
+///
+/// The `i`th entry of the result describes the symmetries that leave
+/// each of the first `i` variables in `fixed` in place, so the first
+/// entry is the same as the result of [orbital_symmetry]. The last
+/// entry corresponds to all variables in `fixed` being fixed.
+///
+/// # Panics
+///
+/// Panics if a vertex appears more than once in `variables` or if a
+/// variable index in `fixed` is out of range.
+pub fn stabilizer_chain<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    variables: &[NodeIndex<Ix>],
+    fixed: &[usize],
+) -> Vec<OrbitalSymmetry>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut chain = Vec::with_capacity(fixed.len() + 1);
+    chain.push(fixed_symmetry(g, variables, &[]));
+    for i in 1..=fixed.len() {
+        // once the group is trivial, it stays trivial
+        let last = chain.last().unwrap();
+        let next = if last.is_trivial() {
+            last.clone()
+        } else {
+            fixed_symmetry(g, variables, &fixed[..i])
+        };
+        chain.push(next);
+    }
+    chain
+}
+
+fn fixed_symmetry<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    variables: &[NodeIndex<Ix>],
+    fixed: &[usize],
+) -> OrbitalSymmetry
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    // vertex colours: other vertices, free variables, and each fixed
+    // variable on its own
+    const OTHER: usize = 0;
+    const FREE: usize = 1;
+    let mut colour = vec![OTHER; g.node_count()];
+    let mut var_idx = vec![usize::MAX; g.node_count()];
+    for (i, v) in variables.iter().enumerate() {
+        assert_eq!(var_idx[v.index()], usize::MAX, "Duplicate variable {v:?}");
+        var_idx[v.index()] = i;
+        colour[v.index()] = FREE;
+    }
+    for (i, &var) in fixed.iter().enumerate() {
+        colour[variables[var].index()] = FREE + 1 + i;
+    }
+    let coloured =
+        g.map(|v, w| (colour[v.index()], w.clone()), |_, w| w.clone());
+    let (generators, orbits) = autom_generators(coloured);
+
+    let generators = generators
+        .into_iter()
+        .map(|gen| {
+            Vec::from_iter(variables.iter().map(|v| var_idx[gen[v.index()]]))
+        })
+        .filter(|gen| gen.iter().enumerate().any(|(i, &j)| i != j))
+        .collect();
+    let mut reps = vec![usize::MAX; g.node_count()];
+    let orbits = Vec::from_iter(variables.iter().enumerate().map(|(i, v)| {
+        let rep = &mut reps[orbits[v.index()]];
+        if *rep == usize::MAX {
+            *rep = i;
+        }
+        *rep
+    }));
+    OrbitalSymmetry { generators, orbits }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::UnGraph;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn cycle() {
+        log_init();
+
+        // variables on a six-cycle, in reverse order
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (3, 4),
+            (4, 5),
+            (5, 0),
+        ]);
+        let variables = Vec::from_iter((0..6).rev().map(NodeIndex::new));
+        let chain = stabilizer_chain(&g, &variables, &[0, 1]);
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[0].num_orbits(), 1);
+        assert_eq!(chain[0].orbit(3), [0, 1, 2, 3, 4, 5]);
+        // fixing one vertex leaves a reflection
+        assert_eq!(chain[1].orbits, [0, 1, 2, 3, 2, 1]);
+        assert_eq!(chain[1].generators, [[0, 5, 4, 3, 2, 1]]);
+        // fixing a neighbour as well leaves nothing
+        assert_eq!(chain[2].orbits, [0, 1, 2, 3, 4, 5]);
+        assert!(chain[2].is_trivial());
+    }
+
+    #[test]
+    fn subset() {
+        log_init();
+
+        // path 0 - 1 - 2, only the end points are variables
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let variables = [NodeIndex::new(2), NodeIndex::new(0)];
+        let symmetry = orbital_symmetry(&g, &variables);
+        assert_eq!(symmetry.orbits, [0, 0]);
+        assert_eq!(symmetry.generators, [[1, 0]]);
+
+        // variables are never mapped to other vertices
+        let variables = [NodeIndex::new(1), NodeIndex::new(0)];
+        let symmetry = orbital_symmetry(&g, &variables);
+        assert_eq!(symmetry.orbits, [0, 1]);
+        assert!(symmetry.is_trivial());
+    }
+}
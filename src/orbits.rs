@@ -0,0 +1,1029 @@
+//! Orbits of the automorphism group on combinatorial objects
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::{NodeIndex, UnGraph};
+//! use nauty_pet::orbits::pair_orbits;
+//!
+//! // path with three edges
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+//! let orbits = pair_orbits(&g);
+//!
+//! // outer edges, inner edge, pairs at distance two, end points
+//! assert_eq!(orbits.len(), 4);
+//! let [v0, v1, v2, v3] = [0, 1, 2, 3].map(NodeIndex::new);
+//! assert_eq!(orbits[0], [(v0, v1), (v2, v3)]);
+//! ```
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use crate::autom::{autom_generators, vertex_orbits};
+use crate::cancel;
+use crate::error::TooManySubsets;
+use crate::group::AutomorphismGroup;
+
+use itertools::{structs::Combinations, Itertools};
+
+use petgraph::{
+    graph::{DiGraph, EdgeIndex, Graph, IndexType, NodeIndex},
+    visit::EdgeRef,
+    EdgeType,
+};
+
+/// Orbits of unordered pairs of distinct vertices
+///
+/// Both adjacent and non-adjacent pairs are included. Each pair is
+/// given with the smaller vertex index first. The pairs in each
+/// orbit are sorted, and the orbits are sorted by their first pair.
+pub fn pair_orbits<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Vec<Vec<(NodeIndex<Ix>, NodeIndex<Ix>)>>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let idx = |v: usize, w: usize| {
+        let (v, w) = if v < w { (v, w) } else { (w, v) };
+        // number of pairs with a smaller first vertex, plus offset
+        v * (2 * n - v - 1) / 2 + (w - v - 1)
+    };
+    let (generators, _) = autom_generators(g.clone());
+    let mut orbits = UnionFind::new(n * n.saturating_sub(1) / 2);
+    for gen in &generators {
+        for v in 0..n {
+            for w in (v + 1)..n {
+                orbits.union(idx(v, w), idx(gen[v], gen[w]));
+            }
+        }
+    }
+    let pairs = (0..n).flat_map(|v| ((v + 1)..n).map(move |w| (v, w)));
+    let pairs = pairs.map(|(v, w)| (NodeIndex::new(v), NodeIndex::new(w)));
+    orbits.into_classes(pairs)
+}
+
+/// Orbits of ordered pairs of vertices
+///
+/// All pairs `(v, w)` are included, whether or not there is an edge
+/// from `v` to `w`, and also the pairs with `v = w`. For a graph with
+/// a vertex-transitive automorphism group, the number of orbits is
+/// the rank of the group. The pairs in each orbit are sorted, and
+/// the orbits are sorted by their first pair.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::orbits::arc_orbits;
+///
+/// // the five-cycle has rank three: equal vertices, adjacent
+/// // vertices, and vertices at distance two
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+/// let orbits = arc_orbits(&g);
+/// assert_eq!(orbits.len(), 3);
+/// assert_eq!(orbits[0].len(), 5);
+/// ```
+pub fn arc_orbits<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Vec<Vec<(NodeIndex<Ix>, NodeIndex<Ix>)>>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let (generators, _) = autom_generators(g.clone());
+    let mut orbits = UnionFind::new(n * n);
+    for gen in &generators {
+        for v in 0..n {
+            for w in 0..n {
+                orbits.union(v * n + w, gen[v] * n + gen[w]);
+            }
+        }
+    }
+    let pairs = (0..n).flat_map(|v| (0..n).map(move |w| (v, w)));
+    let pairs = pairs.map(|(v, w)| (NodeIndex::new(v), NodeIndex::new(w)));
+    orbits.into_classes(pairs)
+}
+
+/// The orbital graphs of the automorphism group
+///
+/// For each orbit of ordered pairs `(v, w)` of distinct vertices, the
+/// orbital graph has the vertices of `g` with their weights and an
+/// arc from `v` to `w` for each pair in the orbit. The orbits of pairs
+/// `(v, v)` are left out. The graphs are in the same order as the
+/// orbits returned by [arc_orbits], and the arcs of each graph are
+/// sorted.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::orbits::orbital_graphs;
+///
+/// // the five-cycle itself and its complement
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+/// let orbitals = orbital_graphs(&g);
+/// assert_eq!(orbitals.len(), 2);
+/// assert!(orbitals.iter().all(|h| h.edge_count() == 10));
+/// ```
+pub fn orbital_graphs<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Vec<DiGraph<N, (), Ix>>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut vertices = DiGraph::with_capacity(g.node_count(), 0);
+    for weight in g.node_weights() {
+        vertices.add_node(weight.clone());
+    }
+    arc_orbits(g)
+        .into_iter()
+        .filter(|orbit| orbit[0].0 != orbit[0].1)
+        .map(|orbit| {
+            let mut orbital = vertices.clone();
+            for (v, w) in orbit {
+                orbital.add_edge(v, w, ());
+            }
+            orbital
+        })
+        .collect()
+}
+
+/// Orbits of edges
+///
+/// Two edges are in the same orbit if an automorphism maps the
+/// endpoints of one to the endpoints of the other, in the same order
+/// for directed graphs, and the edges have the same weight. Parallel
+/// edges with the same weight are always in the same orbit. The edges
+/// in each orbit are sorted, and the orbits are sorted by their first
+/// edge.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{EdgeIndex, UnGraph};
+/// use nauty_pet::orbits::edge_orbits;
+///
+/// // triangle with a pendant edge
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0), (2, 3)]);
+/// let orbits = edge_orbits(&g);
+/// assert_eq!(orbits.len(), 3);
+/// assert_eq!(orbits[0], [EdgeIndex::new(0)]);
+/// assert_eq!(orbits[1], [EdgeIndex::new(1), EdgeIndex::new(2)]);
+/// ```
+pub fn edge_orbits<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Vec<Vec<EdgeIndex<Ix>>>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let endpoints = |v: usize, w: usize| {
+        if g.is_directed() || v <= w {
+            (v, w)
+        } else {
+            (w, v)
+        }
+    };
+    let mut first_edge = BTreeMap::new();
+    let mut orbits = UnionFind::new(g.edge_count());
+    for e in g.edge_references() {
+        let key = (
+            endpoints(e.source().index(), e.target().index()),
+            e.weight(),
+        );
+        let first = *first_edge.entry(key).or_insert(e.id().index());
+        orbits.union(first, e.id().index());
+    }
+    let (generators, _) = autom_generators(g.clone());
+    for gen in &generators {
+        for e in g.edge_references() {
+            let image =
+                endpoints(gen[e.source().index()], gen[e.target().index()]);
+            orbits.union(e.id().index(), first_edge[&(image, e.weight())]);
+        }
+    }
+    orbits.into_classes(g.edge_indices())
+}
+
+/// Default limit on the number of subsets for [subset_orbits]
+pub const DEFAULT_MAX_SUBSETS: usize = 1 << 24;
+
+/// Orbits of `k`-element vertex subsets
+///
+/// Each subset is sorted by vertex index. The subsets in each orbit
+/// are sorted lexicographically, so that the first subset is the
+/// smallest orbit representative, and the orbits are sorted by their
+/// first subset.
+///
+/// Since the number of subsets grows exponentially with `k`, this
+/// fails if there are more than [DEFAULT_MAX_SUBSETS] subsets. Use
+/// [subset_orbits_with_limit] for a different limit.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::orbits::subset_orbits;
+///
+/// // in the five-cycle, three vertices either form a path or not
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+/// let orbits = subset_orbits(&g, 3).unwrap();
+/// assert_eq!(orbits.len(), 2);
+/// assert_eq!(orbits[0].len(), 5);
+/// ```
+pub fn subset_orbits<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    k: usize,
+) -> Result<Vec<Vec<Vec<NodeIndex<Ix>>>>, TooManySubsets>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    subset_orbits_with_limit(g, k, DEFAULT_MAX_SUBSETS)
+}
+
+/// Orbits of `k`-element vertex subsets, considering at most
+/// `max_subsets` subsets
+///
+/// See [subset_orbits].
+pub fn subset_orbits_with_limit<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    k: usize,
+    max_subsets: usize,
+) -> Result<Vec<Vec<Vec<NodeIndex<Ix>>>>, TooManySubsets>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let binomial = binomials(n, k);
+    let num_subsets = binomial[n][k];
+    if num_subsets > max_subsets as u128 {
+        return Err(TooManySubsets {
+            num_subsets,
+            max_subsets,
+        });
+    }
+    // index of a sorted subset in the colexicographic order
+    let colex = |subset: &[usize]| -> usize {
+        subset
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| binomial[v][i + 1] as usize)
+            .sum()
+    };
+    let subsets = Vec::from_iter((0..n).combinations(k));
+    let mut pos = vec![0; subsets.len()];
+    for (i, subset) in subsets.iter().enumerate() {
+        pos[colex(subset)] = i;
+    }
+    let (generators, _) = autom_generators(g.clone());
+    let mut orbits = UnionFind::new(subsets.len());
+    let mut image = Vec::with_capacity(k);
+    for gen in &generators {
+        for (i, subset) in subsets.iter().enumerate() {
+            image.clear();
+            image.extend(subset.iter().map(|&v| gen[v]));
+            image.sort_unstable();
+            orbits.union(i, pos[colex(&image)]);
+        }
+    }
+    let subsets = subsets
+        .into_iter()
+        .map(|subset| Vec::from_iter(subset.into_iter().map(NodeIndex::new)));
+    Ok(orbits.into_classes(subsets))
+}
+
+/// Representatives of the orbits of `k`-element vertex subsets
+///
+/// The iterator yields the smallest subset of each orbit together
+/// with the orbit size, in the same order as [subset_orbits]. Unlike
+/// [subset_orbits], it only stores a single orbit at a time, so it is
+/// not limited by the total number of subsets. However, each subset
+/// is still visited, and the orbits of those that are not the
+/// smallest in their orbit are explored until a smaller image is
+/// found.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::orbits::subset_orbit_representatives;
+///
+/// // in the five-cycle, three vertices either form a path or not
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+/// let reps = Vec::from_iter(subset_orbit_representatives(&g, 3));
+/// let [v0, v1, v2, v3] = [0, 1, 2, 3].map(NodeIndex::new);
+/// assert_eq!(reps, [(vec![v0, v1, v2], 5), (vec![v0, v1, v3], 5)]);
+/// ```
+pub fn subset_orbit_representatives<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    k: usize,
+) -> SubsetOrbitRepresentatives<Ix>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let (generators, _) = autom_generators(g.clone());
+    SubsetOrbitRepresentatives {
+        generators,
+        subsets: (0..g.node_count()).combinations(k),
+        ix: PhantomData,
+    }
+}
+
+/// Iterator over representatives of the orbits of vertex subsets
+///
+/// Created by [subset_orbit_representatives].
+#[derive(Clone, Debug)]
+pub struct SubsetOrbitRepresentatives<Ix> {
+    generators: Vec<Vec<usize>>,
+    subsets: Combinations<Range<usize>>,
+    ix: PhantomData<Ix>,
+}
+
+// size of the orbit of `subset`, `None` if there is a smaller subset
+// in the orbit
+fn subset_orbit_size(
+    generators: &[Vec<usize>],
+    subset: &[usize],
+) -> Option<usize> {
+    let mut orbit = BTreeSet::from([subset.to_vec()]);
+    let mut todo = vec![subset.to_vec()];
+    while let Some(set) = todo.pop() {
+        for gen in generators {
+            let mut image = Vec::from_iter(set.iter().map(|&v| gen[v]));
+            image.sort_unstable();
+            if image.as_slice() < subset {
+                return None;
+            }
+            if orbit.insert(image.clone()) {
+                todo.push(image);
+            }
+        }
+    }
+    Some(orbit.len())
+}
+
+impl<Ix: IndexType> Iterator for SubsetOrbitRepresentatives<Ix> {
+    type Item = (Vec<NodeIndex<Ix>>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for subset in self.subsets.by_ref() {
+            if let Some(size) = subset_orbit_size(&self.generators, &subset) {
+                let subset = subset.into_iter().map(NodeIndex::new);
+                return Some((Vec::from_iter(subset), size));
+            }
+        }
+        None
+    }
+}
+
+/// The orbit of a vertex
+///
+/// The orbit consists of all images of `v` under the automorphisms of
+/// `g`, in increasing order. It is found by applying the generators
+/// until no new images appear.
+///
+/// # Panics
+///
+/// Panics if `v` is not a vertex of `g`.
+pub fn orbit_of<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    v: NodeIndex<Ix>,
+) -> Vec<NodeIndex<Ix>>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    assert!(v.index() < g.node_count(), "Vertex {v:?} out of range");
+    let (generators, _) = autom_generators(g.clone());
+    let mut orbit = BTreeSet::from([v.index()]);
+    let mut todo = vec![v.index()];
+    while let Some(v) = todo.pop() {
+        for gen in &generators {
+            if orbit.insert(gen[v]) {
+                todo.push(gen[v]);
+            }
+        }
+    }
+    Vec::from_iter(orbit.into_iter().map(NodeIndex::new))
+}
+
+/// The orbit of a vertex set
+///
+/// The orbit consists of all images of `set` under the automorphisms
+/// of `g`. Each image is sorted by vertex index, and the images are
+/// sorted lexicographically. Repeated vertices in `set` are ignored.
+///
+/// In contrast to [subset_orbits], only the subsets in a single orbit
+/// are visited, so this is also feasible for large subsets as long as
+/// the orbit is small.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::orbits::orbit_of_set;
+///
+/// // the five-cycle
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+/// let edge = [NodeIndex::new(0), NodeIndex::new(1)];
+/// let orbit = orbit_of_set(&g, &edge);
+/// assert_eq!(orbit.len(), 5);
+/// assert_eq!(orbit[0], edge);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a vertex in `set` is not a vertex of `g`.
+pub fn orbit_of_set<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    set: &[NodeIndex<Ix>],
+) -> Vec<Vec<NodeIndex<Ix>>>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    for v in set {
+        assert!(v.index() < g.node_count(), "Vertex {v:?} out of range");
+    }
+    let (generators, _) = autom_generators(g.clone());
+    let mut set = Vec::from_iter(set.iter().map(|v| v.index()));
+    set.sort_unstable();
+    set.dedup();
+    let mut orbit = BTreeSet::from([set.clone()]);
+    let mut todo = vec![set];
+    while let Some(set) = todo.pop() {
+        for gen in &generators {
+            let mut image = Vec::from_iter(set.iter().map(|&v| gen[v]));
+            image.sort_unstable();
+            if orbit.insert(image.clone()) {
+                todo.push(image);
+            }
+        }
+    }
+    Vec::from_iter(
+        orbit
+            .into_iter()
+            .map(|set| Vec::from_iter(set.into_iter().map(NodeIndex::new))),
+    )
+}
+
+/// The quotient of a graph by its vertex orbits
+///
+/// Each node of the quotient holds the vertices of one orbit, sorted
+/// by index, and the nodes are sorted by their first vertex. There is
+/// an edge between two orbits if `g` has edges between them, with the
+/// number of these edges as its weight. Edges within an orbit become
+/// self-loops. For directed graphs, edges from one orbit to another
+/// are counted separately from edges in the opposite direction. The
+/// edge weights of `g` are only used to find the orbits.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::{NodeIndex, UnGraph};
+/// use nauty_pet::orbits::orbit_quotient;
+///
+/// // star with three leaves
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+/// let quotient = orbit_quotient(&g);
+/// assert_eq!(quotient.node_count(), 2);
+/// let [v0, v1, v2, v3] = [0, 1, 2, 3].map(NodeIndex::new);
+/// assert_eq!(quotient[v1], [v1, v2, v3]);
+/// let e = quotient.find_edge(v0, v1).unwrap();
+/// assert_eq!(quotient[e], 3);
+/// ```
+pub fn orbit_quotient<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Graph<Vec<NodeIndex<Ix>>, usize, Ty, Ix>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let reps = cancel::unwrap(vertex_orbits(g.clone()));
+    let mut quotient = Graph::default();
+    // representatives are the smallest vertices of their orbits, so
+    // they are encountered first
+    let mut orbit_idx = vec![NodeIndex::end(); reps.len()];
+    for (v, &rep) in reps.iter().enumerate() {
+        if rep == v {
+            orbit_idx[rep] = quotient.add_node(Vec::new());
+        }
+        quotient[orbit_idx[rep]].push(NodeIndex::new(v));
+    }
+    let mut multiplicities = BTreeMap::new();
+    for e in g.edge_references() {
+        let source = orbit_idx[reps[e.source().index()]];
+        let target = orbit_idx[reps[e.target().index()]];
+        let key = if g.is_directed() || source <= target {
+            (source, target)
+        } else {
+            (target, source)
+        };
+        *multiplicities.entry(key).or_insert(0) += 1;
+    }
+    for ((source, target), multiplicity) in multiplicities {
+        quotient.add_edge(source, target, multiplicity);
+    }
+    quotient
+}
+
+/// Orbits of the automorphism group acting on the vertices
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::orbits::Orbits;
+///
+/// // star with three leaves and a pendant path of length two
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3), (0, 4), (4, 5)]);
+/// let orbits = Orbits::from(g);
+/// assert_eq!(orbits.len(), 4);
+/// assert_eq!(orbits.largest(), 3);
+/// assert_eq!(orbits.num_fixed_points(), 3);
+/// assert_eq!(orbits.size_histogram(), [(1, 3), (3, 1)].into());
+/// ```
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Orbits {
+    representatives: Vec<usize>,
+}
+
+impl Orbits {
+    /// Orbit of each vertex, given by the smallest vertex index in the
+    /// orbit
+    pub fn representatives(&self) -> &[usize] {
+        &self.representatives
+    }
+
+    /// The number of orbits
+    pub fn len(&self) -> usize {
+        self.representatives
+            .iter()
+            .enumerate()
+            .filter(|&(v, &rep)| v == rep)
+            .count()
+    }
+
+    /// Check whether there are no orbits, i.e. no vertices
+    pub fn is_empty(&self) -> bool {
+        self.representatives.is_empty()
+    }
+
+    /// The size of each orbit, in the order of the representatives
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.representatives.len()];
+        for &rep in &self.representatives {
+            sizes[rep] += 1;
+        }
+        sizes.retain(|&size| size > 0);
+        sizes
+    }
+
+    /// The number of orbits of each size
+    pub fn size_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for size in self.sizes() {
+            *histogram.entry(size).or_default() += 1;
+        }
+        histogram
+    }
+
+    /// The size of the largest orbit, or zero if there are no vertices
+    pub fn largest(&self) -> usize {
+        self.sizes().into_iter().max().unwrap_or(0)
+    }
+
+    /// The number of vertices fixed by all automorphisms
+    ///
+    /// This is the number of orbits with a single vertex.
+    pub fn num_fixed_points(&self) -> usize {
+        self.sizes().into_iter().filter(|&size| size == 1).count()
+    }
+}
+
+/// The vertex orbits of the automorphism group of a graph
+impl<N, E, Ty, Ix> From<Graph<N, E, Ty, Ix>> for Orbits
+where
+    N: Ord,
+    E: Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    fn from(g: Graph<N, E, Ty, Ix>) -> Self {
+        Self {
+            representatives: cancel::unwrap(vertex_orbits(g)),
+        }
+    }
+}
+
+impl From<&AutomorphismGroup> for Orbits {
+    fn from(group: &AutomorphismGroup) -> Self {
+        Self {
+            representatives: group.orbits(),
+        }
+    }
+}
+
+// binomial coefficients `binomial[n][k]` up to the given values,
+// saturating at `u128::MAX`
+fn binomials(n: usize, k: usize) -> Vec<Vec<u128>> {
+    let mut binomial = vec![vec![0u128; k + 1]; n + 1];
+    for m in 0..=n {
+        binomial[m][0] = 1;
+        for j in 1..=k.min(m) {
+            binomial[m][j] =
+                binomial[m - 1][j - 1].saturating_add(binomial[m - 1][j]);
+        }
+    }
+    binomial
+}
+
+// disjoint-set forest over 0..n
+#[derive(Clone, Debug)]
+pub(crate) struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    pub(crate) fn new(n: usize) -> Self {
+        Self {
+            parent: Vec::from_iter(0..n),
+        }
+    }
+
+    pub(crate) fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    // the smaller representative becomes the root, so that each
+    // class is represented by its smallest element
+    pub(crate) fn union(&mut self, i: usize, j: usize) {
+        let (i, j) = (self.find(i), self.find(j));
+        if i < j {
+            self.parent[j] = i;
+        } else {
+            self.parent[i] = j;
+        }
+    }
+
+    // group elements into classes, where the `i`th element
+    // corresponds to `i`
+    pub(crate) fn into_classes<T>(
+        mut self,
+        elements: impl IntoIterator<Item = T>,
+    ) -> Vec<Vec<T>> {
+        let mut class_idx = vec![usize::MAX; self.parent.len()];
+        let mut classes: Vec<Vec<T>> = Vec::new();
+        for (i, element) in elements.into_iter().enumerate() {
+            let root = self.find(i);
+            if class_idx[root] == usize::MAX {
+                class_idx[root] = classes.len();
+                classes.push(Vec::new());
+            }
+            classes[class_idx[root]].push(element);
+        }
+        classes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::{DiGraph, UnGraph};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn pairs() {
+        log_init();
+
+        // the Petersen graph is distance-transitive with diameter two
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (3, 4),
+            (4, 0),
+            (0, 5),
+            (1, 6),
+            (2, 7),
+            (3, 8),
+            (4, 9),
+            (5, 7),
+            (7, 9),
+            (9, 6),
+            (6, 8),
+            (8, 5),
+        ]);
+        let orbits = pair_orbits(&g);
+        assert_eq!(orbits.len(), 2);
+        assert_eq!(orbits[0].len(), 15);
+        assert_eq!(orbits[1].len(), 30);
+        for (v, w) in &orbits[0] {
+            assert!(g.contains_edge(*v, *w));
+        }
+
+        // directed three-cycle: all pairs are equivalent
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(pair_orbits(&g).len(), 1);
+
+        let g = UnGraph::<(), ()>::default();
+        assert!(pair_orbits(&g).is_empty());
+    }
+
+    #[test]
+    fn arcs() {
+        log_init();
+
+        // the Petersen graph has rank three
+        #[rustfmt::skip]
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1), (1, 2), (2, 3), (3, 4), (4, 0),
+            (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+            (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+        ]);
+        let orbits = arc_orbits(&g);
+        assert_eq!(orbits.len(), 3);
+        assert!(orbits[0].iter().all(|(v, w)| v == w));
+        assert_eq!(orbits[1].len(), 30);
+        assert!(orbits[1].iter().all(|&(v, w)| g.contains_edge(v, w)));
+
+        // directed three-cycle: forward and backward pairs differ
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        let orbits = arc_orbits(&g);
+        assert_eq!(orbits.len(), 3);
+        let [v0, v1, v2] = [0, 1, 2].map(NodeIndex::new);
+        assert_eq!(orbits[1], [(v0, v1), (v1, v2), (v2, v0)]);
+
+        assert!(arc_orbits(&UnGraph::<(), ()>::default()).is_empty());
+    }
+
+    #[test]
+    fn orbitals() {
+        log_init();
+
+        // directed three-cycle and its reverse
+        let g = DiGraph::<u8, ()>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        let orbitals = orbital_graphs(&g);
+        assert_eq!(orbitals.len(), 2);
+        for orbital in &orbitals {
+            assert_eq!(orbital.node_count(), 3);
+            assert_eq!(orbital.edge_count(), 3);
+        }
+        for e in g.edge_references() {
+            assert!(orbitals[0].contains_edge(e.source(), e.target()));
+            assert!(orbitals[1].contains_edge(e.target(), e.source()));
+        }
+
+        // in the path, the end vertices can be swapped, giving three
+        // orbits of pairs of distinct vertices
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let orbitals = orbital_graphs(&g);
+        let sizes = Vec::from_iter(orbitals.iter().map(|h| h.edge_count()));
+        assert_eq!(sizes, [2, 2, 2]);
+        assert!(orbital_graphs(&UnGraph::<(), ()>::default()).is_empty());
+    }
+
+    #[test]
+    fn edges() {
+        log_init();
+
+        // the edges of the cube are equivalent
+        let g = UnGraph::<(), ()>::from_edges(
+            (0..8u32)
+                .flat_map(|v| [1, 2, 4].map(|b| (v, v ^ b)))
+                .filter(|(v, w)| v < w),
+        );
+        assert_eq!(edge_orbits(&g).len(), 1);
+
+        // directed path with a double arc and a weighted arc
+        let g = DiGraph::<(), u8>::from_edges([
+            (0, 1, 0),
+            (0, 1, 0),
+            (1, 2, 0),
+            (3, 2, 0),
+            (3, 4, 1),
+        ]);
+        let orbits = edge_orbits(&g);
+        let orbits = Vec::from_iter(
+            orbits
+                .iter()
+                .map(|o| Vec::from_iter(o.iter().map(|e| e.index()))),
+        );
+        assert_eq!(orbits, [vec![0, 1], vec![2], vec![3], vec![4]]);
+
+        // the two arcs into the sink are exchanged
+        let g = DiGraph::<(), ()>::from_edges([(0, 2), (1, 2), (2, 2)]);
+        let orbits = edge_orbits(&g);
+        assert_eq!(orbits.len(), 2);
+        assert_eq!(orbits[0].len(), 2);
+
+        assert!(edge_orbits(&UnGraph::<(), ()>::default()).is_empty());
+    }
+
+    #[test]
+    fn subsets() {
+        log_init();
+
+        // cube graph
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 3),
+            (3, 2),
+            (2, 0),
+            (4, 5),
+            (5, 7),
+            (7, 6),
+            (6, 4),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ]);
+        // orbits of k-subsets of cube vertices, OEIS A000616
+        for (k, num_orbits) in
+            [1, 1, 3, 3, 6, 3, 3, 1, 1].into_iter().enumerate()
+        {
+            let orbits = subset_orbits(&g, k).unwrap();
+            assert_eq!(orbits.len(), num_orbits);
+            let num_subsets: usize = orbits.iter().map(|o| o.len()).sum();
+            assert_eq!(num_subsets as u128, binomials(8, k)[8][k]);
+        }
+        let pairs = subset_orbits(&g, 2).unwrap();
+        let expected = pair_orbits(&g);
+        for (orbit, expected) in pairs.iter().zip(&expected) {
+            assert_eq!(orbit.len(), expected.len());
+            assert_eq!(orbit[0], [expected[0].0, expected[0].1]);
+        }
+
+        let err = subset_orbits_with_limit(&g, 4, 69).unwrap_err();
+        assert_eq!(err.num_subsets, 70);
+        assert!(subset_orbits(&g, 9).unwrap().is_empty());
+    }
+
+    #[test]
+    fn representatives() {
+        log_init();
+
+        // cube graph
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 3),
+            (3, 2),
+            (2, 0),
+            (4, 5),
+            (5, 7),
+            (7, 6),
+            (6, 4),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ]);
+        for k in 0..=9 {
+            let expected = subset_orbits(&g, k).unwrap();
+            let reps = Vec::from_iter(subset_orbit_representatives(&g, k));
+            assert_eq!(reps.len(), expected.len());
+            for ((rep, size), orbit) in reps.into_iter().zip(expected) {
+                assert_eq!(rep, orbit[0]);
+                assert_eq!(size, orbit.len());
+            }
+        }
+    }
+
+    #[test]
+    fn orbit() {
+        log_init();
+
+        // star with three leaves and a pendant path of length two
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (4, 5),
+        ]);
+        let v = NodeIndex::new;
+        assert_eq!(orbit_of(&g, v(2)), [v(1), v(2), v(3)]);
+        assert_eq!(orbit_of(&g, v(4)), [v(4)]);
+        let orbit = orbit_of_set(&g, &[v(2), v(0), v(2)]);
+        assert_eq!(orbit, [[v(0), v(1)], [v(0), v(2)], [v(0), v(3)]]);
+        let orbit = orbit_of_set(&g, &[v(1), v(2)]);
+        assert_eq!(orbit.len(), 3);
+        assert_eq!(orbit_of_set(&g, &[]), [Vec::<NodeIndex>::new()]);
+        // consistent with the orbits of all subsets
+        let subsets = subset_orbits(&g, 2).unwrap();
+        for subset_orbit in &subsets {
+            assert_eq!(&orbit_of_set(&g, &subset_orbit[0]), subset_orbit);
+        }
+    }
+
+    #[test]
+    fn quotient() {
+        log_init();
+
+        // star with three leaves and a pendant path of length two
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (4, 5),
+        ]);
+        let quotient = orbit_quotient(&g);
+        let v = NodeIndex::new;
+        let orbits = Vec::from_iter(quotient.node_weights().cloned());
+        assert_eq!(
+            orbits,
+            [vec![v(0)], vec![v(1), v(2), v(3)], vec![v(4)], vec![v(5)]]
+        );
+        let edges =
+            Vec::from_iter(quotient.edge_references().map(|e| {
+                (e.source().index(), e.target().index(), *e.weight())
+            }));
+        assert_eq!(edges, [(0, 1, 3), (0, 2, 1), (2, 3, 1)]);
+
+        // cycles collapse to a single vertex with a loop
+        let cycle =
+            UnGraph::<(), ()>::from_edges((0..5).map(|i| (i, (i + 1) % 5)));
+        let quotient = orbit_quotient(&cycle);
+        assert_eq!(quotient.node_count(), 1);
+        assert_eq!(quotient[quotient.find_edge(v(0), v(0)).unwrap()], 5);
+
+        // directed edges between orbits in both directions
+        let g = DiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 0), (2, 0)]);
+        let quotient = orbit_quotient(&g);
+        assert_eq!(quotient.edge_count(), 2);
+        assert_eq!(quotient[quotient.find_edge(v(1), v(0)).unwrap()], 2);
+
+        assert_eq!(
+            orbit_quotient(&UnGraph::<(), ()>::default()).node_count(),
+            0
+        );
+    }
+
+    #[test]
+    fn statistics() {
+        log_init();
+
+        // the Petersen graph is vertex transitive
+        #[rustfmt::skip]
+        let g = UnGraph::<(), ()>::from_edges([
+            (0, 1), (1, 2), (2, 3), (3, 4), (4, 0),
+            (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+            (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+        ]);
+        let orbits = Orbits::from(g.clone());
+        assert_eq!(orbits.sizes(), [10]);
+        assert_eq!(orbits.num_fixed_points(), 0);
+        assert_eq!(orbits, Orbits::from(&AutomorphismGroup::from(g)));
+
+        // path with four vertices
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        let orbits = Orbits::from(g);
+        assert_eq!(orbits.representatives(), [0, 1, 1, 0]);
+        assert_eq!(orbits.sizes(), [2, 2]);
+        assert_eq!(orbits.size_histogram(), [(2, 2)].into());
+
+        let orbits = Orbits::from(UnGraph::<(), ()>::default());
+        assert!(orbits.is_empty());
+        assert_eq!(orbits.len(), 0);
+        assert_eq!(orbits.largest(), 0);
+        assert!(orbits.size_histogram().is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! Canonical forms of periodic graphs
+//!
+//! A periodic structure such as a crystal is described by its
+//! labelled quotient graph: one vertex for each vertex in the unit
+//! cell, and an edge `(v, w, t)` for each bond between `v` in the
+//! reference cell and `w` in the cell translated by the integer vector
+//! `t`.
+//!
+//! # Normalisation
+//!
+//! The edge `(v, w, t)` describes the same bond as `(w, v, -t)`. To
+//! make the canonical form independent of the orientation in which
+//! edges are stored, each edge is encoded as a pair of opposite arcs
+//! `v -> w` with colour `t` and `w -> v` with colour `-t`. Loops
+//! `(v, v, t)` with `t != 0` describe bonds between translated copies
+//! of the same vertex and become a pair of loops with colours `t` and
+//! `-t`.
+//!
+//! Translation vectors are compared as they are, so the canonical
+//! form depends on the choice of the unit cell basis and on the cells
+//! in which the vertices are placed. Shifting a vertex `v` by `s`
+//! changes the translation vectors of its edges, and structures that
+//! only differ in this way get different canonical forms.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::periodic::periodic_canon;
+//!
+//! // the primitive cubic net has one vertex with a bond along each
+//! // axis
+//! let cubic = UnGraph::<(), [i64; 3]>::from_edges([
+//!     (0, 0, [1, 0, 0]),
+//!     (0, 0, [0, 1, 0]),
+//!     (0, 0, [0, 0, 1]),
+//! ]);
+//! // the same bonds in the opposite direction
+//! let reversed = UnGraph::<(), [i64; 3]>::from_edges([
+//!     (0, 0, [0, -1, 0]),
+//!     (0, 0, [-1, 0, 0]),
+//!     (0, 0, [0, 0, -1]),
+//! ]);
+//! assert_eq!(periodic_canon(&cubic).graph, periodic_canon(&reversed).graph);
+//! ```
+use crate::cancel;
+use crate::canon::try_canon_with_labelling;
+use crate::graph::CanonGraph;
+
+use petgraph::{
+    graph::{Graph, IndexType, UnGraph},
+    visit::EdgeRef,
+    Directed,
+};
+
+/// Canonical form of a labelled quotient graph
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PeriodicCanon<N, const D: usize, Ix: IndexType> {
+    /// Canonical graph
+    ///
+    /// Each edge of the quotient graph corresponds to two opposite
+    /// arcs, with the translation vector and its negative as weights.
+    pub graph: CanonGraph<N, [i64; D], Directed, Ix>,
+    /// Canonical labelling
+    ///
+    /// The `i`th vertex of the quotient graph corresponds to the
+    /// vertex with index `labelling[i]` in `graph`.
+    pub labelling: Vec<usize>,
+}
+
+/// Canonical form of a labelled quotient graph
+///
+/// Two quotient graphs have the same canonical
+/// [graph](PeriodicCanon::graph) if and only if they are isomorphic
+/// with an isomorphism that preserves the vertex weights and the
+/// translation vectors, up to the orientation of each edge. See the
+/// [module documentation](self) for details.
+pub fn periodic_canon<N, const D: usize, Ix>(
+    g: &UnGraph<N, [i64; D], Ix>,
+) -> PeriodicCanon<N, D, Ix>
+where
+    N: Clone + Ord,
+    Ix: IndexType,
+{
+    let mut arcs = Graph::with_capacity(g.node_count(), 2 * g.edge_count());
+    for w in g.node_weights() {
+        arcs.add_node(w.clone());
+    }
+    for e in g.edge_references() {
+        let t = *e.weight();
+        let (v, w) = (e.source(), e.target());
+        arcs.add_edge(v, w, t);
+        arcs.add_edge(w, v, t.map(|x| -x));
+    }
+    let (canon, labelling) = cancel::unwrap(try_canon_with_labelling(arcs));
+    PeriodicCanon {
+        graph: CanonGraph::from_canon_unchecked(canon),
+        labelling,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // honeycomb net: two vertices per cell
+    fn honeycomb() -> UnGraph<u8, [i64; 2]> {
+        UnGraph::from_edges([(0, 1, [0, 0]), (0, 1, [-1, 0]), (0, 1, [0, -1])])
+    }
+
+    #[test]
+    fn relabelling() {
+        log_init();
+
+        let g = honeycomb();
+        // swap the vertices, and reverse the orientation of one edge
+        let h = UnGraph::<u8, [i64; 2]>::from_edges([
+            (1, 0, [0, 0]),
+            (0, 1, [1, 0]),
+            (1, 0, [0, -1]),
+        ]);
+        let g = periodic_canon(&g);
+        let h = periodic_canon(&h);
+        assert_eq!(g.graph, h.graph);
+        assert_eq!(g.graph.edge_count(), 6);
+    }
+
+    #[test]
+    fn distinct() {
+        log_init();
+
+        // different translation vectors
+        let g = honeycomb();
+        let h = UnGraph::<u8, [i64; 2]>::from_edges([
+            (0, 1, [0, 0]),
+            (0, 1, [-1, 0]),
+            (0, 1, [1, -1]),
+        ]);
+        assert_ne!(periodic_canon(&g).graph, periodic_canon(&h).graph);
+
+        // different vertex weights
+        let mut h = honeycomb();
+        h[petgraph::graph::NodeIndex::new(1)] = 1;
+        let g = periodic_canon(&g);
+        let h = periodic_canon(&h);
+        assert_ne!(g.graph, h.graph);
+        assert_eq!(h.graph[petgraph::graph::NodeIndex::new(h.labelling[1])], 1);
+    }
+}
@@ -0,0 +1,223 @@
+//! Permutations of vertex indices
+//!
+//! # Example
+//!
+//! ```rust
+//! use nauty_pet::perm::Permutation;
+//!
+//! let p = Permutation::new(vec![1, 2, 0]);
+//! let q = Permutation::new(vec![1, 0, 2]);
+//! // `p * q` first applies `p`, then `q`
+//! assert_eq!((&p * &q).image(0), 0);
+//! assert_eq!(&p * &p.inverse(), Permutation::identity(3));
+//! assert_eq!(p.cycles(), [vec![0, 1, 2]]);
+//! ```
+use std::ops::Mul;
+
+use petgraph::{
+    graph::{Graph, IndexType, NodeIndex},
+    visit::EdgeRef,
+    EdgeType,
+};
+
+/// A permutation of `0..n`
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Permutation(Vec<usize>);
+
+impl Permutation {
+    /// The permutation mapping each `i` to `images[i]`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `images` is not a permutation of `0..images.len()`.
+    pub fn new(images: Vec<usize>) -> Self {
+        let mut seen = vec![false; images.len()];
+        for &i in &images {
+            assert!(i < images.len(), "Image {i} out of range");
+            assert!(!seen[i], "Duplicate image {i}");
+            seen[i] = true;
+        }
+        Self(images)
+    }
+
+    /// The identity permutation of `0..n`
+    pub fn identity(n: usize) -> Self {
+        Self(Vec::from_iter(0..n))
+    }
+
+    /// The number of permuted points
+    pub fn degree(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The image of `i`
+    pub fn image(&self, i: usize) -> usize {
+        self.0[i]
+    }
+
+    /// The image of the node with index `v`
+    pub fn node_image<Ix: IndexType>(&self, v: NodeIndex<Ix>) -> NodeIndex<Ix> {
+        NodeIndex::new(self.0[v.index()])
+    }
+
+    /// The images of all points
+    pub fn images(&self) -> &[usize] {
+        &self.0
+    }
+
+    /// Check whether this is the identity
+    pub fn is_identity(&self) -> bool {
+        self.0.iter().enumerate().all(|(i, &j)| i == j)
+    }
+
+    /// The inverse permutation
+    pub fn inverse(&self) -> Self {
+        let mut inv = vec![0; self.degree()];
+        for (i, &j) in self.0.iter().enumerate() {
+            inv[j] = i;
+        }
+        Self(inv)
+    }
+
+    /// Relabel the nodes of `g`
+    ///
+    /// The node with index `i` becomes the node with index
+    /// `self.image(i)`. Node and edge weights are kept, and the edges
+    /// stay in the same order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the degree differs from the number of nodes.
+    pub fn apply<N, E, Ty, Ix>(
+        &self,
+        g: &Graph<N, E, Ty, Ix>,
+    ) -> Graph<N, E, Ty, Ix>
+    where
+        N: Clone,
+        E: Clone,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        assert_eq!(
+            self.degree(),
+            g.node_count(),
+            "Permutation of wrong degree"
+        );
+        let mut res = Graph::with_capacity(g.node_count(), g.edge_count());
+        for &v in self.inverse().images() {
+            res.add_node(g[NodeIndex::<Ix>::new(v)].clone());
+        }
+        for e in g.edge_references() {
+            res.add_edge(
+                self.node_image(e.source()),
+                self.node_image(e.target()),
+                e.weight().clone(),
+            );
+        }
+        res
+    }
+
+    /// The cycles of length at least two
+    ///
+    /// Each cycle starts with its smallest point, and cycles are
+    /// sorted by their first point.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut seen = vec![false; self.degree()];
+        let mut cycles = Vec::new();
+        for start in 0..self.degree() {
+            if seen[start] || self.0[start] == start {
+                continue;
+            }
+            let mut cycle = Vec::new();
+            let mut i = start;
+            while !seen[i] {
+                seen[i] = true;
+                cycle.push(i);
+                i = self.0[i];
+            }
+            cycles.push(cycle);
+        }
+        cycles
+    }
+}
+
+impl From<Permutation> for Vec<usize> {
+    fn from(p: Permutation) -> Self {
+        p.0
+    }
+}
+
+/// Composition: `p * q` maps `i` to `q.image(p.image(i))`
+///
+/// # Panics
+///
+/// Panics if the permutations have different degrees.
+impl Mul for &Permutation {
+    type Output = Permutation;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        assert_eq!(self.degree(), rhs.degree());
+        Permutation(Vec::from_iter(self.0.iter().map(|&i| rhs.0[i])))
+    }
+}
+
+impl Mul for Permutation {
+    type Output = Permutation;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn group_laws() {
+        log_init();
+
+        let p = Permutation::new(vec![3, 0, 1, 2]);
+        let q = Permutation::new(vec![1, 0, 3, 2]);
+        let r = Permutation::new(vec![0, 2, 1, 3]);
+        assert_eq!(&(&p * &q) * &r, &p * &(&q * &r));
+        assert_eq!(&p * &Permutation::identity(4), p);
+        assert!((&q * &q.inverse()).is_identity());
+        assert_eq!(q.cycles(), [vec![0, 1], vec![2, 3]]);
+        assert!(Permutation::identity(0).cycles().is_empty());
+    }
+
+    #[test]
+    fn relabel() {
+        log_init();
+
+        let g = petgraph::graph::DiGraph::<char, u8>::from_edges([
+            (0, 1, 1),
+            (1, 2, 2),
+        ]);
+        let g = g.map(|v, _| ['a', 'b', 'c'][v.index()], |_, &w| w);
+        let p = Permutation::new(vec![2, 0, 1]);
+        let h = p.apply(&g);
+        assert_eq!(Vec::from_iter(h.node_weights().copied()), ['b', 'c', 'a']);
+        let edges =
+            Vec::from_iter(h.edge_references().map(|e| {
+                (e.source().index(), e.target().index(), *e.weight())
+            }));
+        assert_eq!(edges, [(2, 0, 1), (0, 1, 2)]);
+        let g2 = p.inverse().apply(&h);
+        assert!(g2.node_weights().eq(g.node_weights()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid() {
+        log_init();
+
+        Permutation::new(vec![0, 0]);
+    }
+}
@@ -0,0 +1,172 @@
+//! Canonical forms and symmetries of Petri nets
+//!
+//! A place/transition net is a bipartite directed graph, where arcs
+//! connect places to transitions and transitions to places. Places
+//! and transitions are distinguished by their node weights, and arc
+//! weights become edge colours. The symmetries of a net can be used
+//! to reduce its state space.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nauty_pet::petri::{canonical_net, PetriNet, PetriNode::*};
+//!
+//! // two processes competing for a shared resource
+//! let mut net = PetriNet::<u32, ()>::new();
+//! let resource = net.add_node(Place(1));
+//! for _ in 0..2 {
+//!     let idle = net.add_node(Place(1));
+//!     let busy = net.add_node(Place(0));
+//!     let acquire = net.add_node(Transition(()));
+//!     let release = net.add_node(Transition(()));
+//!     net.add_edge(idle, acquire, 1);
+//!     net.add_edge(resource, acquire, 1);
+//!     net.add_edge(acquire, busy, 1);
+//!     net.add_edge(busy, release, 1);
+//!     net.add_edge(release, idle, 1);
+//!     net.add_edge(release, resource, 1);
+//! }
+//! let canon = canonical_net(&net).unwrap();
+//! // the processes can be exchanged
+//! assert_eq!(canon.generators.len(), 1);
+//! ```
+use crate::autom::autom_generators;
+use crate::cancel;
+use crate::canon::try_canon_with_labelling;
+use crate::error::InvalidArc;
+use crate::graph::CanonGraph;
+
+use petgraph::{graph::DiGraph, visit::EdgeRef, Directed};
+
+/// A node in a [PetriNet]
+///
+/// All places come before all transitions in the canonical form.
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PetriNode<P, T> {
+    Place(P),
+    Transition(T),
+}
+
+impl<P, T> PetriNode<P, T> {
+    /// Check whether this is a place
+    pub fn is_place(&self) -> bool {
+        matches!(self, PetriNode::Place(_))
+    }
+
+    /// Check whether this is a transition
+    pub fn is_transition(&self) -> bool {
+        matches!(self, PetriNode::Transition(_))
+    }
+}
+
+/// A place/transition net
+///
+/// Places carry weights of type `P`, for example their initial
+/// marking, transitions carry weights of type `T`. Edge weights are
+/// arc multiplicities.
+pub type PetriNet<P, T> = DiGraph<PetriNode<P, T>, u32>;
+
+/// Canonical form and symmetries of a [PetriNet]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CanonPetriNet<P, T> {
+    /// Canonically labelled net
+    pub net: CanonGraph<PetriNode<P, T>, u32, Directed>,
+    /// Canonical labelling
+    ///
+    /// The `i`th node of the original net corresponds to the node
+    /// with index `labelling[i]` in `net`.
+    pub labelling: Vec<usize>,
+    /// Generators of the symmetry group of the original net
+    ///
+    /// Each generator maps the `i`th node to the node with index
+    /// `generator[i]`.
+    pub generators: Vec<Vec<usize>>,
+}
+
+/// Canonical form and symmetries of a Petri net
+///
+/// Two nets have the same canonical form if and only if they are
+/// isomorphic, with an isomorphism that preserves node weights and
+/// arc multiplicities.
+///
+/// # Errors
+///
+/// Fails if an arc connects two places or two transitions.
+pub fn canonical_net<P, T>(
+    net: &PetriNet<P, T>,
+) -> Result<CanonPetriNet<P, T>, InvalidArc>
+where
+    P: Clone + Ord,
+    T: Clone + Ord,
+{
+    for e in net.edge_references() {
+        let (from, to) = (e.source(), e.target());
+        if net[from].is_place() == net[to].is_place() {
+            return Err(InvalidArc {
+                from: from.index(),
+                to: to.index(),
+            });
+        }
+    }
+    let (generators, _) = autom_generators(net.clone());
+    let res = try_canon_with_labelling(net.clone());
+    let (canon, labelling) = cancel::unwrap(res);
+    Ok(CanonPetriNet {
+        net: CanonGraph::from_canon_unchecked(canon),
+        labelling,
+        generators,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PetriNode::*;
+    use super::*;
+    use petgraph::graph::NodeIndex;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn cycle() {
+        log_init();
+
+        // a token circulating between three places
+        let mut net = PetriNet::<u32, ()>::new();
+        let places = [1, 0, 0].map(|m| net.add_node(Place(m)));
+        for i in 0..3 {
+            let t = net.add_node(Transition(()));
+            net.add_edge(places[i], t, 1);
+            net.add_edge(t, places[(i + 1) % 3], 1);
+        }
+        let canon = canonical_net(&net).unwrap();
+        // the marking breaks the rotation symmetry
+        assert!(canon.generators.is_empty());
+        assert!(canon.net[NodeIndex::new(0)].is_place());
+
+        let e = net.find_edge(places[0], NodeIndex::new(3)).unwrap();
+        net[places[0]] = Place(0);
+        let symmetric = canonical_net(&net).unwrap();
+        assert_eq!(symmetric.generators.len(), 1);
+        // arc weights break it again
+        net[e] = 2;
+        let weighted = canonical_net(&net).unwrap();
+        assert!(weighted.generators.is_empty());
+        assert_ne!(weighted.net, symmetric.net);
+    }
+
+    #[test]
+    fn invalid() {
+        log_init();
+
+        let mut net = PetriNet::<(), ()>::new();
+        let p = net.add_node(Place(()));
+        let q = net.add_node(Place(()));
+        net.add_edge(p, q, 1);
+        let err = canonical_net(&net).unwrap_err();
+        assert_eq!((err.from, err.to), (0, 1));
+    }
+}
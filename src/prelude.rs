@@ -1,4 +1,7 @@
-pub use crate::autom::TryIntoAutom;
-pub use crate::canon::{IntoCanon, TryIntoCanon};
+pub use crate::autom::{TryIntoAutom, TryIntoAutomWithGenerators};
+pub use crate::canon::{
+    IntoCanon, TryIntoCanon, TryIntoCanonRooted, TryIntoCanonWithAutom,
+    TryIntoCanonWithLabelling,
+};
 pub use crate::cmp::IsIdentical;
 pub use crate::graph::{CanonDiGraph, CanonGraph, CanonUnGraph};
@@ -0,0 +1,331 @@
+//! Cartesian, tensor, and strong products of undirected graphs
+//!
+//! The automorphism group of a product can often be obtained from the
+//! automorphism groups of the factors, without running nauty on the
+//! much larger product graph. This is the case if the factors are
+//! connected and relatively prime, see R. Hammack, W. Imrich, and
+//! S. Klavžar, Handbook of Product Graphs, CRC Press (2011). The tensor
+//! product additionally requires non-bipartite factors without
+//! vertices with the same neighbourhood, and the strong product
+//! factors without vertices with the same closed neighbourhood.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::prelude::*;
+//! use nauty_pet::product::Product;
+//!
+//! let edge = CanonGraph::from(UnGraph::<(), ()>::from_edges([(0, 1)]));
+//! let triangle = CanonGraph::from(UnGraph::<(), ()>::from_edges([
+//!     (0, 1),
+//!     (1, 2),
+//!     (2, 0),
+//! ]));
+//!
+//! // the triangular prism
+//! let prism = Product::Cartesian.graph(&edge, &triangle);
+//! assert_eq!(prism.node_count(), 6);
+//! assert_eq!(prism.edge_count(), 9);
+//!
+//! let group = Product::Cartesian.automorphism_group(&edge, &triangle);
+//! assert_eq!(group.order(), Some(12));
+//! ```
+use std::collections::VecDeque;
+
+use crate::group::AutomorphismGroup;
+use crate::perm::Permutation;
+use crate::subgraph::undirected_neighbours;
+
+use petgraph::graph::{IndexType, NodeIndex, UnGraph};
+
+/// Graph product
+///
+/// The vertices of a product of `g` and `h` are pairs `(v, w)` of
+/// vertices `v` of `g` and `w` of `h`.
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Product {
+    /// `(v, w)` and `(v', w')` are adjacent if `v = v'` and `w` is
+    /// adjacent to `w'`, or if `v` is adjacent to `v'` and `w = w'`
+    Cartesian,
+    /// `(v, w)` and `(v', w')` are adjacent if `v` is adjacent to
+    /// `v'` and `w` is adjacent to `w'`
+    Tensor,
+    /// `(v, w)` and `(v', w')` are adjacent if they are adjacent in
+    /// the Cartesian or in the tensor product
+    Strong,
+}
+
+impl Product {
+    /// The product of `g` and `h`
+    ///
+    /// The vertex `(v, w)` has index `v * h.node_count() + w` and the
+    /// pair of node weights as its weight. Edge weights, loops, and
+    /// multiple edges of the factors are ignored.
+    pub fn graph<N, E, Ix>(
+        self,
+        g: &UnGraph<N, E, Ix>,
+        h: &UnGraph<N, E, Ix>,
+    ) -> UnGraph<(N, N), (), Ix>
+    where
+        N: Clone,
+        Ix: IndexType,
+    {
+        let (adj_g, adj_h) =
+            (undirected_neighbours(g), undirected_neighbours(h));
+        let n_h = h.node_count();
+        let mut product = UnGraph::default();
+        for v in g.node_weights() {
+            for w in h.node_weights() {
+                product.add_node((v.clone(), w.clone()));
+            }
+        }
+        let idx = |v, w| NodeIndex::new(v * n_h + w);
+        // each edge is added from its endpoint with the smaller index
+        for (v, nbrs_g) in adj_g.iter().enumerate() {
+            let larger_g = nbrs_g.iter().filter(|&&v2| v2 > v);
+            for (w, nbrs_h) in adj_h.iter().enumerate() {
+                if self != Product::Tensor {
+                    for &w2 in nbrs_h.iter().filter(|&&w2| w2 > w) {
+                        product.add_edge(idx(v, w), idx(v, w2), ());
+                    }
+                    for &v2 in larger_g.clone() {
+                        product.add_edge(idx(v, w), idx(v2, w), ());
+                    }
+                }
+                if self != Product::Cartesian {
+                    for &v2 in larger_g.clone() {
+                        for &w2 in nbrs_h {
+                            product.add_edge(idx(v, w), idx(v2, w2), ());
+                        }
+                    }
+                }
+            }
+        }
+        product
+    }
+
+    /// The automorphism group of the product of `g` and `h`
+    ///
+    /// Vertices are numbered as in [graph](Self::graph). If the
+    /// factorisation theorems apply, the group is generated by the
+    /// automorphisms of `g` and `h` acting on the respective
+    /// coordinate. Otherwise, the automorphisms are computed from the
+    /// product graph.
+    pub fn automorphism_group<N, E, Ix>(
+        self,
+        g: &UnGraph<N, E, Ix>,
+        h: &UnGraph<N, E, Ix>,
+    ) -> AutomorphismGroup
+    where
+        N: Clone + Ord,
+        Ix: IndexType,
+    {
+        let (adj_g, adj_h) =
+            (undirected_neighbours(g), undirected_neighbours(h));
+        if !self.is_factorisable(&adj_g, &adj_h) {
+            return AutomorphismGroup::from(self.graph(g, h));
+        }
+        let (n_g, n_h) = (g.node_count(), h.node_count());
+        let aut_g = AutomorphismGroup::from(simple_graph(g, &adj_g));
+        let aut_h = AutomorphismGroup::from(simple_graph(h, &adj_h));
+        let pairs = || (0..n_g).flat_map(|v| (0..n_h).map(move |w| (v, w)));
+        let mut generators = Vec::new();
+        for gen in aut_g.generators() {
+            generators.push(Permutation::new(Vec::from_iter(
+                pairs().map(|(v, w)| gen.image(v) * n_h + w),
+            )));
+        }
+        for gen in aut_h.generators() {
+            generators.push(Permutation::new(Vec::from_iter(
+                pairs().map(|(v, w)| v * n_h + gen.image(w)),
+            )));
+        }
+        AutomorphismGroup::new(n_g * n_h, generators)
+    }
+
+    // check whether the automorphisms of the product are exactly the
+    // products of the automorphisms of the factors
+    fn is_factorisable(
+        self,
+        adj_g: &[Vec<usize>],
+        adj_h: &[Vec<usize>],
+    ) -> bool {
+        // a common factor would have at least two vertices and
+        // divide both vertex numbers
+        let relatively_prime = gcd(adj_g.len(), adj_h.len()) == 1;
+        relatively_prime
+            && [adj_g, adj_h].into_iter().all(|adj| {
+                is_connected(adj)
+                    && match self {
+                        Product::Cartesian => true,
+                        Product::Tensor => {
+                            !is_bipartite(adj) && is_thin(adj, false)
+                        }
+                        Product::Strong => is_thin(adj, true),
+                    }
+            })
+    }
+}
+
+// node weights and one edge between each pair of adjacent vertices
+fn simple_graph<N: Clone, E, Ix: IndexType>(
+    g: &UnGraph<N, E, Ix>,
+    adj: &[Vec<usize>],
+) -> UnGraph<N, (), Ix> {
+    let mut res = g.filter_map(|_, w| Some(w.clone()), |_, _| None);
+    for (v, nbrs) in adj.iter().enumerate() {
+        for &w in nbrs.iter().filter(|&&w| w > v) {
+            res.add_edge(NodeIndex::new(v), NodeIndex::new(w), ());
+        }
+    }
+    res
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// breadth-first colouring with two colours, `None` for vertices that
+// are not reachable from vertex 0
+fn two_colouring(adj: &[Vec<usize>]) -> Vec<Option<bool>> {
+    let mut colour = vec![None; adj.len()];
+    if adj.is_empty() {
+        return colour;
+    }
+    colour[0] = Some(false);
+    let mut todo = VecDeque::from([0]);
+    while let Some(v) = todo.pop_front() {
+        for &w in &adj[v] {
+            if colour[w].is_none() {
+                colour[w] = colour[v].map(|c| !c);
+                todo.push_back(w);
+            }
+        }
+    }
+    colour
+}
+
+fn is_connected(adj: &[Vec<usize>]) -> bool {
+    !adj.is_empty() && two_colouring(adj).iter().all(|c| c.is_some())
+}
+
+// assumes a connected graph
+fn is_bipartite(adj: &[Vec<usize>]) -> bool {
+    let colour = two_colouring(adj);
+    adj.iter()
+        .enumerate()
+        .all(|(v, nbrs)| nbrs.iter().all(|&w| colour[v] != colour[w]))
+}
+
+// check whether no two vertices have the same (closed) neighbourhood
+fn is_thin(adj: &[Vec<usize>], closed: bool) -> bool {
+    let mut neighbourhoods =
+        Vec::from_iter(adj.iter().enumerate().map(|(v, nbrs)| {
+            let mut nbrs = nbrs.clone();
+            if closed {
+                nbrs.push(v);
+                nbrs.sort_unstable();
+            }
+            nbrs
+        }));
+    neighbourhoods.sort_unstable();
+    neighbourhoods.windows(2).all(|w| w[0] != w[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn cycle(n: u32) -> UnGraph<(), ()> {
+        UnGraph::from_edges((0..n).map(|i| (i, (i + 1) % n)))
+    }
+
+    fn path(n: u32) -> UnGraph<(), ()> {
+        UnGraph::from_edges((1..n).map(|i| (i - 1, i)))
+    }
+
+    // compare with the automorphisms of the product graph
+    fn check_order(product: Product, g: &UnGraph<(), ()>, h: &UnGraph<(), ()>) {
+        let group = product.automorphism_group(g, h);
+        let expected = AutomorphismGroup::from(product.graph(g, h));
+        assert_eq!(group.degree(), expected.degree());
+        assert_eq!(group.order(), expected.order());
+    }
+
+    #[test]
+    fn graphs() {
+        log_init();
+
+        let (g, h) = (cycle(5), path(3));
+        let (e_g, e_h) = (g.edge_count(), h.edge_count());
+        let cartesian = Product::Cartesian.graph(&g, &h);
+        assert_eq!(cartesian.node_count(), 15);
+        assert_eq!(cartesian.edge_count(), 5 * e_h + 3 * e_g);
+        let tensor = Product::Tensor.graph(&g, &h);
+        assert_eq!(tensor.edge_count(), 2 * e_g * e_h);
+        let strong = Product::Strong.graph(&g, &h);
+        assert_eq!(
+            strong.edge_count(),
+            cartesian.edge_count() + tensor.edge_count()
+        );
+    }
+
+    #[test]
+    fn factorisable() {
+        log_init();
+
+        let triangle = cycle(3);
+        assert!(Product::Cartesian.is_factorisable(
+            &undirected_neighbours(&path(2)),
+            &undirected_neighbours(&triangle)
+        ));
+        check_order(Product::Cartesian, &path(2), &triangle);
+        check_order(Product::Cartesian, &path(3), &path(2));
+        check_order(Product::Tensor, &triangle, &cycle(5));
+        check_order(Product::Strong, &path(3), &cycle(5));
+        assert_eq!(
+            Product::Tensor
+                .automorphism_group(&triangle, &cycle(5))
+                .order(),
+            Some(60)
+        );
+    }
+
+    #[test]
+    fn not_factorisable() {
+        log_init();
+
+        // the four-dimensional hypercube
+        let square = cycle(4);
+        assert!(!Product::Cartesian.is_factorisable(
+            &undirected_neighbours(&square),
+            &undirected_neighbours(&square)
+        ));
+        let group = Product::Cartesian.automorphism_group(&square, &square);
+        assert_eq!(group.order(), Some(384));
+
+        // bipartite factor
+        let (g, h) = (path(2), cycle(3));
+        assert!(!Product::Tensor.is_factorisable(
+            &undirected_neighbours(&g),
+            &undirected_neighbours(&h)
+        ));
+        check_order(Product::Tensor, &path(3), &cycle(5));
+        // vertices with the same closed neighbourhood
+        assert!(!Product::Strong.is_factorisable(
+            &undirected_neighbours(&g),
+            &undirected_neighbours(&h)
+        ));
+        check_order(Product::Strong, &g, &h);
+    }
+}
@@ -0,0 +1,438 @@
+//! Equitable partitions
+//!
+//! A partition of the vertices into cells is equitable if any two
+//! vertices in the same cell have the same number of neighbours in
+//! each cell. Refining an initial colouring to the coarsest equitable
+//! partition is the first step of nauty's search, and it can be
+//! useful on its own, for example to detect symmetries heuristically.
+//! Every orbit of the automorphism group preserving the initial
+//! colouring lies within a single cell.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::{NodeIndex, UnGraph};
+//! use nauty_pet::refine::equitable_partition;
+//!
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+//! let cells = equitable_partition(&g, &[0; 4]);
+//! let [v0, v1, v2, v3] = [0, 1, 2, 3].map(NodeIndex::new);
+//! assert_eq!(cells, [vec![v0, v3], vec![v1, v2]]);
+//! ```
+//!
+//! # Two-dimensional Weisfeiler-Leman refinement
+//!
+//! With the `wl2` feature, [wl2_partition] refines colourings of
+//! pairs of vertices instead. This distinguishes more vertices, for
+//! example in many regular graphs, at the cost of `O(n^3)` time per
+//! round and memory. [try_canon_wl2] uses the result to seed
+//! nauty's search, which can make it much smaller for graphs that
+//! are hard for equitable refinement.
+#[cfg(feature = "wl2")]
+use std::hash::Hash;
+
+#[cfg(feature = "wl2")]
+use crate::{canon::TryIntoCanon, error::NautyError};
+
+use petgraph::{
+    graph::{Graph, IndexType, NodeIndex},
+    visit::EdgeRef,
+    EdgeType,
+};
+
+/// The coarsest equitable partition refining the given colouring
+///
+/// The `i`th entry of `colouring` is the colour of the `i`th vertex.
+/// Neighbours are counted with the multiplicity of the connecting
+/// edges, and separately for each edge weight and, in directed
+/// graphs, each direction.
+///
+/// Each cell is sorted. The order of the cells only depends on the
+/// colours and the structure of the graph, but not on the vertex
+/// numbering. In particular, cells with smaller initial colours come
+/// first.
+///
+/// # Panics
+///
+/// Panics if the length of `colouring` is not the number of vertices.
+pub fn equitable_partition<N, E, Ty, Ix, C>(
+    g: &Graph<N, E, Ty, Ix>,
+    colouring: &[C],
+) -> Vec<Vec<NodeIndex<Ix>>>
+where
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+    C: Ord,
+{
+    assert_eq!(
+        colouring.len(),
+        g.node_count(),
+        "colouring does not match the number of vertices"
+    );
+    let mut cells = ranks(colouring);
+    let mut num_cells = num_ranks(&cells);
+    // for undirected graphs, both endpoints see an outgoing edge
+    let incoming = u8::from(g.is_directed());
+    loop {
+        let mut incident = vec![Vec::new(); g.node_count()];
+        for e in g.edge_references() {
+            let (source, target) = (e.source().index(), e.target().index());
+            incident[source].push((0, e.weight(), cells[target]));
+            incident[target].push((incoming, e.weight(), cells[source]));
+        }
+        // the old cell comes first, so the new cells are ordered
+        // consistently with the old ones
+        let signatures = Vec::from_iter(incident.into_iter().enumerate().map(
+            |(v, mut incident)| {
+                incident.sort_unstable();
+                (cells[v], incident)
+            },
+        ));
+        cells = ranks(&signatures);
+        let num_refined = num_ranks(&cells);
+        if num_refined == num_cells {
+            break;
+        }
+        num_cells = num_refined;
+    }
+    let mut partition = vec![Vec::new(); num_cells];
+    for (v, cell) in cells.into_iter().enumerate() {
+        partition[cell].push(NodeIndex::new(v));
+    }
+    partition
+}
+
+/// Vertex partition from two-dimensional Weisfeiler-Leman refinement
+///
+/// Each pair of vertices starts with a colour given by whether the
+/// vertices are equal, the initial colour of the vertex for equal
+/// pairs, and the weights of the edges between them in both
+/// directions. In each round, the new colour of a pair `(u, v)` is
+/// determined by its old colour and the multiset of colour pairs of
+/// `(u, w)` and `(w, v)` for all vertices `w`. Once the colouring is
+/// stable, vertices are partitioned by the colours of the pairs
+/// `(v, v)`.
+///
+/// The partition refines the [equitable_partition]. As there, each
+/// cell is sorted and the order of the cells does not depend on the
+/// vertex numbering. Requires the `wl2` feature.
+///
+/// # Panics
+///
+/// Panics if the length of `colouring` is not the number of vertices.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::refine::{equitable_partition, wl2_partition};
+///
+/// // a triangle and a square
+/// let g = UnGraph::<(), ()>::from_edges([
+///     (0, 1),
+///     (1, 2),
+///     (2, 0),
+///     (3, 4),
+///     (4, 5),
+///     (5, 6),
+///     (6, 3),
+/// ]);
+/// assert_eq!(equitable_partition(&g, &[(); 7]).len(), 1);
+/// assert_eq!(wl2_partition(&g, &[(); 7]).len(), 2);
+/// ```
+#[cfg(feature = "wl2")]
+pub fn wl2_partition<N, E, Ty, Ix, C>(
+    g: &Graph<N, E, Ty, Ix>,
+    colouring: &[C],
+) -> Vec<Vec<NodeIndex<Ix>>>
+where
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+    C: Ord,
+{
+    let cells = wl2_cells(g, colouring);
+    let mut partition = vec![Vec::new(); num_ranks(&cells)];
+    for (v, cell) in cells.into_iter().enumerate() {
+        partition[cell].push(NodeIndex::new(v));
+    }
+    partition
+}
+
+/// Canonical form with nauty's search seeded by
+/// [two-dimensional Weisfeiler-Leman refinement](wl2_partition)
+///
+/// The node weights are used as initial colouring. The result is a
+/// canonical form, i.e. two graphs give identical results if and
+/// only if they are isomorphic. However, it is in general different
+/// from the canonical form computed with [TryIntoCanon], so it must
+/// only be compared to other results of this function. Requires the
+/// `wl2` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use petgraph::graph::UnGraph;
+/// use nauty_pet::prelude::*;
+/// use nauty_pet::refine::try_canon_wl2;
+///
+/// let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+/// let h = UnGraph::<(), ()>::from_edges([(2, 0), (0, 1)]);
+/// let g = try_canon_wl2(&g).unwrap();
+/// assert!(g.is_identical(&try_canon_wl2(&h).unwrap()));
+/// ```
+#[cfg(feature = "wl2")]
+pub fn try_canon_wl2<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Result<Graph<N, E, Ty, Ix>, NautyError>
+where
+    N: Clone + Ord,
+    E: Clone + Hash + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let colouring = Vec::from_iter(g.node_weights());
+    let cells = wl2_cells(g, &colouring);
+    // the cells are ordered consistently with the node weights, so
+    // they come first
+    let seeded = g.map(|v, w| (cells[v.index()], w.clone()), |_, w| w.clone());
+    let canon = seeded.try_into_canon()?;
+    Ok(canon.map(|_, w| w.1.clone(), |_, w| w.clone()))
+}
+
+// vertex cells from two-dimensional Weisfeiler-Leman refinement
+#[cfg(feature = "wl2")]
+fn wl2_cells<N, E, Ty, Ix, C>(
+    g: &Graph<N, E, Ty, Ix>,
+    colouring: &[C],
+) -> Vec<usize>
+where
+    E: Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+    C: Ord,
+{
+    let n = g.node_count();
+    assert_eq!(
+        colouring.len(),
+        n,
+        "colouring does not match the number of vertices"
+    );
+    let vertex_colours = ranks(colouring);
+    // edge weights from the first to the second vertex of each pair
+    let mut weights = vec![Vec::new(); n * n];
+    for e in g.edge_references() {
+        let (source, target) = (e.source().index(), e.target().index());
+        weights[source * n + target].push(e.weight());
+        if !g.is_directed() && source != target {
+            weights[target * n + source].push(e.weight());
+        }
+    }
+    for weights in &mut weights {
+        weights.sort_unstable();
+    }
+    let initial = Vec::from_iter((0..n * n).map(|i| {
+        let (u, v) = (i / n, i % n);
+        let vertex_colour = (u == v).then_some(vertex_colours[u]);
+        (vertex_colour, &weights[i], &weights[v * n + u])
+    }));
+    let mut colours = ranks(&initial);
+    let mut num_colours = num_ranks(&colours);
+    loop {
+        let signatures = Vec::from_iter((0..n * n).map(|i| {
+            let (u, v) = (i / n, i % n);
+            let mut paths = Vec::from_iter(
+                (0..n).map(|w| (colours[u * n + w], colours[w * n + v])),
+            );
+            paths.sort_unstable();
+            (colours[i], paths)
+        }));
+        colours = ranks(&signatures);
+        let num_refined = num_ranks(&colours);
+        if num_refined == num_colours {
+            break;
+        }
+        num_colours = num_refined;
+    }
+    let diagonal = Vec::from_iter((0..n).map(|v| colours[v * n + v]));
+    ranks(&diagonal)
+}
+
+// position of each value among the sorted distinct values
+fn ranks<T: Ord>(values: &[T]) -> Vec<usize> {
+    let mut distinct = Vec::from_iter(values);
+    distinct.sort_unstable();
+    distinct.dedup();
+    Vec::from_iter(
+        values
+            .iter()
+            .map(|value| distinct.binary_search(&value).unwrap()),
+    )
+}
+
+fn num_ranks(ranks: &[usize]) -> usize {
+    ranks.iter().max().map_or(0, |r| r + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::AutomorphismGroup;
+    use itertools::Itertools;
+    use petgraph::graph::{DiGraph, UnGraph};
+    use petgraph::{Directed, Undirected};
+    use rand::SeedableRng;
+    use testing::{randomize_labels, GraphIter};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // check that the partition is equitable and refines the colouring
+    fn check_equitable<Ty: EdgeType>(
+        g: &Graph<u8, u8, Ty>,
+        cells: &[Vec<NodeIndex>],
+    ) {
+        let mut cell_of = vec![usize::MAX; g.node_count()];
+        for (i, cell) in cells.iter().enumerate() {
+            for v in cell {
+                cell_of[v.index()] = i;
+            }
+        }
+        assert!(cell_of.iter().all(|&c| c != usize::MAX));
+        let mut counts = vec![Vec::new(); g.node_count()];
+        for e in g.edge_references() {
+            let (v, w) = (e.source().index(), e.target().index());
+            counts[v].push((true, e.weight(), cell_of[w]));
+            counts[w].push((!g.is_directed(), e.weight(), cell_of[v]));
+        }
+        for counts in &mut counts {
+            counts.sort_unstable();
+        }
+        for cell in cells {
+            let first = cell[0];
+            assert!(cell.iter().all(|&v| g[v] == g[first]));
+            assert!(cell
+                .iter()
+                .all(|&v| counts[v.index()] == counts[first.index()]));
+        }
+    }
+
+    #[test]
+    fn equitable() {
+        log_init();
+
+        let mut rng = rand_xoshiro::Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Directed>::default().take(200) {
+            let colouring = Vec::from_iter(g.node_weights().copied());
+            let cells = equitable_partition(&g, &colouring);
+            check_equitable(&g, &cells);
+
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let colouring = Vec::from_iter(gg.node_weights().copied());
+            let other = equitable_partition(&gg, &colouring);
+            assert!(cells.iter().map(Vec::len).eq(other.iter().map(Vec::len)));
+        }
+        for g in GraphIter::<Undirected>::default().take(200) {
+            let colouring = Vec::from_iter(g.node_weights().copied());
+            let cells = equitable_partition(&g, &colouring);
+            check_equitable(&g, &cells);
+            // orbits lie within cells
+            let group = AutomorphismGroup::from(g.clone());
+            for cell in &cells {
+                for (gen, v) in
+                    group.generators().iter().cartesian_product(cell)
+                {
+                    let image = NodeIndex::new(gen.image(v.index()));
+                    assert!(cell.contains(&image));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cells() {
+        log_init();
+
+        // regular graphs cannot be refined
+        let cycle =
+            UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+        assert_eq!(equitable_partition(&cycle, &[(); 6]).len(), 1);
+
+        // individualising a vertex of the Petersen graph splits the
+        // remaining vertices by distance
+        #[rustfmt::skip]
+        let petersen = UnGraph::<(), ()>::from_edges([
+            (0, 1), (1, 2), (2, 3), (3, 4), (4, 0),
+            (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+            (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+        ]);
+        let mut colouring = [1; 10];
+        colouring[0] = 0;
+        let cells = equitable_partition(&petersen, &colouring);
+        assert_eq!(cells.len(), 3);
+        assert_eq!(cells[0], [NodeIndex::new(0)]);
+        assert_eq!(cells.iter().map(Vec::len).sum::<usize>(), 10);
+
+        // directions and edge weights are taken into account
+        let d = DiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        assert_eq!(equitable_partition(&d, &[(); 3]).len(), 3);
+        let g = UnGraph::<(), u8>::from_edges([(0, 1, 0), (1, 2, 1)]);
+        assert_eq!(equitable_partition(&g, &[(); 3]).len(), 3);
+
+        let empty = UnGraph::<(), ()>::default();
+        assert!(equitable_partition(&empty, &[(); 0]).is_empty());
+    }
+
+    #[cfg(feature = "wl2")]
+    #[test]
+    fn wl2() {
+        use crate::cmp::IsIdentical;
+
+        log_init();
+
+        let mut rng = rand_xoshiro::Xoshiro256Plus::seed_from_u64(0);
+        for g in GraphIter::<Undirected>::default().take(100) {
+            let colouring = Vec::from_iter(g.node_weights().copied());
+            let cells = wl2_partition(&g, &colouring);
+            check_equitable(&g, &cells);
+            // finer than the equitable partition
+            let coarse = equitable_partition(&g, &colouring);
+            assert!(cells.len() >= coarse.len());
+            for cell in &cells {
+                assert!(coarse
+                    .iter()
+                    .any(|c| cell.iter().all(|v| c.contains(v))));
+            }
+
+            let canon = try_canon_wl2(&g).unwrap();
+            assert!(petgraph::algo::is_isomorphic(&g, &canon));
+            let gg = randomize_labels(g, &mut rng);
+            assert!(canon.is_identical(&try_canon_wl2(&gg).unwrap()));
+        }
+        for g in GraphIter::<Directed>::default().take(100) {
+            let colouring = Vec::from_iter(g.node_weights().copied());
+            check_equitable(&g, &wl2_partition(&g, &colouring));
+            let gg = randomize_labels(g.clone(), &mut rng);
+            let canon = try_canon_wl2(&g).unwrap();
+            assert!(canon.is_identical(&try_canon_wl2(&gg).unwrap()));
+        }
+
+        // the six-cycle and two triangles
+        let cycle =
+            UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+        let triangles = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (3, 4),
+            (4, 5),
+            (5, 3),
+        ]);
+        assert!(!try_canon_wl2(&cycle)
+            .unwrap()
+            .is_identical(&try_canon_wl2(&triangles).unwrap()));
+        assert_eq!(wl2_partition(&cycle, &[(); 6]).len(), 1);
+    }
+}
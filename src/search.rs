@@ -0,0 +1,660 @@
+//! Observing nauty's search tree
+//!
+//! nauty explores a tree of ordered partitions of the vertices,
+//! where each child is obtained by individualising a vertex of the
+//! target cell and refining. [SearchHooks] register closures that
+//! are called for the nodes and levels of this tree, for example to
+//! collect statistics, and for the candidates for the canonical
+//! labelling found at its leaves. Returning [ControlFlow::Break] from a closure
+//! stops the search, in which case the result is
+//! [NautyError::Aborted](crate::error::NautyError::Aborted). A panic
+//! in a closure also stops the search and is resumed afterwards.
+//! The closures must not call nauty or Traces; doing so panics.
+//!
+//! nauty's mechanism for stopping a search is shared by all threads.
+//! Searches stopped in other threads are repeated, so the closures
+//! may see the same part of the tree more than once. Counts like the
+//! ones in the following example are therefore only exact if no
+//! search in another thread is stopped at the same time, e.g. by a
+//! [cancellation](crate::cancel) or a closure.
+//!
+//! Vertices are given by their indices in the original graph.
+//! Auxiliary vertices used internally to represent edge weights
+//! are omitted.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::ops::ControlFlow;
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::options::NautyOptions;
+//! use nauty_pet::search::SearchHooks;
+//!
+//! // the Petersen graph
+//! let outer = (0..5).map(|i| (i, (i + 1) % 5));
+//! let spokes = (0..5).map(|i| (i, i + 5));
+//! let inner = (0..5).map(|i| (i + 5, (i + 2) % 5 + 5));
+//! let g = UnGraph::<(), ()>::from_edges(outer.chain(spokes).chain(inner));
+//!
+//! let mut nodes = 0;
+//! let mut indices = Vec::new();
+//! let hooks = SearchHooks::new()
+//!     .on_node(|_| {
+//!         nodes += 1;
+//!         ControlFlow::Continue(())
+//!     })
+//!     .on_level(|level| {
+//!         indices.push(level.index);
+//!         ControlFlow::Continue(())
+//!     });
+//! let autom = NautyOptions::default().autom_dense_with_hooks(&g, hooks).unwrap();
+//! assert!(nodes > 0);
+//! // the group size is the product of the indices
+//! assert_eq!(indices.iter().product::<usize>() as f64, autom.grpsize());
+//! ```
+use std::cell::Cell;
+use std::ops::ControlFlow;
+use std::os::raw::{c_int, c_ulong};
+use std::ptr;
+use std::slice;
+
+use nauty_Traces_sys::{graph, optionblk, statsblk};
+
+use crate::hooks::{call, finish, try_call};
+
+/// A level of the leftmost path of nauty's search tree
+///
+/// Levels are reported once nauty has finished exploring them,
+/// starting with the leaf of the leftmost path and ending with the
+/// root at level one.
+#[derive(Copy, Clone, Debug)]
+pub struct SearchLevel<'a> {
+    /// Depth in the search tree, where the root is at level one
+    pub level: usize,
+    /// The vertex individualised to obtain the leftmost child
+    ///
+    /// This is `None` for the leaf and if the vertex is auxiliary.
+    pub fixed_vertex: Option<usize>,
+    /// Size of the orbit of the fixed vertex under the stabiliser of
+    /// the vertices fixed at lower levels
+    pub index: usize,
+    /// Size of the target cell
+    pub target_cell_size: usize,
+    /// Number of cells of the partition
+    pub num_cells: usize,
+    /// Number of children that were explored
+    pub num_children: usize,
+    /// Number of generators of the automorphism group found so far
+    pub num_generators: usize,
+    partition: Partition<'a>,
+}
+
+impl SearchLevel<'_> {
+    /// The cells of the partition at this level
+    pub fn cells(&self) -> Vec<Vec<usize>> {
+        self.partition.cells()
+    }
+}
+
+/// A node of nauty's search tree
+#[derive(Copy, Clone, Debug)]
+pub struct SearchNode<'a> {
+    /// Depth in the search tree, where the root is at level one
+    pub level: usize,
+    /// Number of cells of the partition
+    pub num_cells: usize,
+    /// Whether the partition is discrete
+    pub is_leaf: bool,
+    /// Code of the refinement leading to this node
+    ///
+    /// Nodes with different codes cannot be equivalent.
+    pub code: i32,
+    target_cell: Option<usize>,
+    partition: Partition<'a>,
+}
+
+impl SearchNode<'_> {
+    /// The cells of the partition at this node
+    pub fn cells(&self) -> Vec<Vec<usize>> {
+        self.partition.cells()
+    }
+
+    /// The target cell, whose vertices are individualised to obtain
+    /// the children of this node
+    ///
+    /// This is `None` if the children are not explored.
+    pub fn target_cell(&self) -> Option<Vec<usize>> {
+        self.target_cell.map(|start| self.partition.cell_at(start))
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Partition<'a> {
+    lab: &'a [c_int],
+    ptn: &'a [c_int],
+    level: c_int,
+    orig: &'a [usize],
+}
+
+impl Partition<'_> {
+    fn cells(&self) -> Vec<Vec<usize>> {
+        let mut cells = Vec::new();
+        let mut start = 0;
+        while start < self.lab.len() {
+            let end = self.cell_end(start);
+            let cell = self.original(start, end);
+            if !cell.is_empty() {
+                cells.push(cell);
+            }
+            start = end;
+        }
+        cells
+    }
+
+    fn cell_at(&self, start: usize) -> Vec<usize> {
+        self.original(start, self.cell_end(start))
+    }
+
+    // one past the last position of the cell starting at `start`
+    fn cell_end(&self, start: usize) -> usize {
+        let len = self.ptn[start..]
+            .iter()
+            .position(|&p| p <= self.level)
+            .unwrap_or(self.ptn.len() - start - 1);
+        start + len + 1
+    }
+
+    fn original(&self, start: usize, end: usize) -> Vec<usize> {
+        Vec::from_iter(
+            self.lab[start..end]
+                .iter()
+                .map(|&v| self.orig[v as usize])
+                .filter(|&v| v != usize::MAX),
+        )
+    }
+}
+
+/// A candidate for the canonical labelling
+///
+/// nauty keeps the best labelling found so far. A candidate is
+/// reported whenever this labelling changes, so the last candidate
+/// is the canonical labelling.
+#[derive(Copy, Clone, Debug)]
+pub struct CanonCandidate<'a> {
+    /// Number of candidates so far, including this one
+    pub count: u64,
+    /// Code of the refinement leading to the leaf of the labelling
+    pub code: i32,
+    lab: &'a [c_int],
+    orig: &'a [usize],
+}
+
+impl CanonCandidate<'_> {
+    /// The vertices in canonical order
+    ///
+    /// The `i`th vertex of the candidate canonical form is the
+    /// vertex with index `labelling()[i]` in the original graph.
+    pub fn labelling(&self) -> Vec<usize> {
+        Vec::from_iter(
+            self.lab
+                .iter()
+                .map(|&v| self.orig[v as usize])
+                .filter(|&v| v != usize::MAX),
+        )
+    }
+}
+
+type LevelHook<'a> = Box<dyn FnMut(&SearchLevel) -> ControlFlow<()> + 'a>;
+type NodeHook<'a> = Box<dyn FnMut(&SearchNode) -> ControlFlow<()> + 'a>;
+type CanonHook<'a> = Box<dyn FnMut(&CanonCandidate) -> ControlFlow<()> + 'a>;
+
+/// Closures called during nauty's search
+///
+/// See the [module documentation](self) for an example.
+#[derive(Default)]
+pub struct SearchHooks<'a> {
+    level: Option<LevelHook<'a>>,
+    node: Option<NodeHook<'a>>,
+    canon: Option<CanonHook<'a>>,
+}
+
+impl<'a> SearchHooks<'a> {
+    /// No hooks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `f` for each level of the leftmost path
+    pub fn on_level<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&SearchLevel) -> ControlFlow<()> + 'a,
+    {
+        self.level = Some(Box::new(f));
+        self
+    }
+
+    /// Call `f` for each node of the search tree
+    ///
+    /// The closure is called once nauty has refined the partition
+    /// of the node and chosen the target cell.
+    pub fn on_node<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&SearchNode) -> ControlFlow<()> + 'a,
+    {
+        self.node = Some(Box::new(f));
+        self
+    }
+
+    /// Call `f` for each candidate for the canonical labelling
+    ///
+    /// Only called when computing canonical forms. Unlike for the
+    /// other hooks, stopping the search from `f` does not affect
+    /// nauty in other threads.
+    ///
+    /// `f` can observe the candidates, but not choose between them.
+    /// nauty only lets `usercanonproc` continue or abort the search:
+    /// it has already replaced its best labelling when the procedure
+    /// is called, and a non-zero return value ends the search with
+    /// [NautyError::Aborted](crate::error::NautyError::Aborted)
+    /// rather than rejecting the candidate. Overriding nauty's
+    /// choice of leaf would also make the result depend on the
+    /// closure, so it would no longer be a canonical form.
+    pub fn on_canon_candidate<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&CanonCandidate) -> ControlFlow<()> + 'a,
+    {
+        self.canon = Some(Box::new(f));
+        self
+    }
+}
+
+// the hooks of the running search together with the original vertex
+// of each of nauty's vertices
+struct Active<'a, 'b> {
+    hooks: &'b mut SearchHooks<'a>,
+    orig: &'b [usize],
+}
+
+thread_local! {
+    // the `Active` hooks, with their type erased
+    static ACTIVE: Cell<*mut ()> = Cell::new(ptr::null_mut());
+}
+
+/// Run nauty with the given hooks
+///
+/// `orig` maps each of nauty's vertices to the original vertex, or
+/// to `usize::MAX` for auxiliary vertices. Returns `None` if a hook
+/// stopped the search.
+pub(crate) fn with_search_hooks<R>(
+    mut options: optionblk,
+    mut hooks: SearchHooks<'_>,
+    orig: &[usize],
+    run: impl FnOnce(optionblk) -> R,
+) -> Option<R> {
+    if hooks.level.is_some() {
+        options.userlevelproc = Some(levelproc);
+    }
+    if hooks.node.is_some() {
+        options.usernodeproc = Some(nodeproc);
+    }
+    if hooks.canon.is_some() {
+        options.usercanonproc = Some(canonproc);
+    }
+    let mut active = Active {
+        hooks: &mut hooks,
+        orig,
+    };
+    let ptr = &mut active as *mut Active as *mut ();
+    let prev = ACTIVE.with(|a| a.replace(ptr));
+    let res = run(options);
+    ACTIVE.with(|a| a.set(prev));
+    finish(res)
+}
+
+// SAFETY: must only be called by nauty during `with_search_hooks`
+unsafe fn active<'b>() -> &'b mut Active<'static, 'b> {
+    &mut *(ACTIVE.with(Cell::get) as *mut Active)
+}
+
+#[allow(clippy::too_many_arguments)]
+extern "C" fn levelproc(
+    lab: *mut c_int,
+    ptn: *mut c_int,
+    level: c_int,
+    _orbits: *mut c_int,
+    stats: *mut statsblk,
+    tv: c_int,
+    index: c_int,
+    tcellsize: c_int,
+    numcells: c_int,
+    childcount: c_int,
+    n: c_int,
+) {
+    call(|| {
+        // SAFETY: nauty passes its partition of the `n` vertices and
+        // its statistics
+        let (active, lab, ptn, stats) = unsafe {
+            (
+                active(),
+                slice::from_raw_parts(lab, n as usize),
+                slice::from_raw_parts(ptn, n as usize),
+                &*stats,
+            )
+        };
+        let fixed_vertex = if numcells == n {
+            None
+        } else {
+            Some(active.orig[tv as usize]).filter(|&v| v != usize::MAX)
+        };
+        let level = SearchLevel {
+            level: level as usize,
+            fixed_vertex,
+            index: index as usize,
+            target_cell_size: tcellsize as usize,
+            num_cells: numcells as usize,
+            num_children: childcount as usize,
+            num_generators: stats.numgenerators as usize,
+            partition: Partition {
+                lab,
+                ptn,
+                level,
+                orig: active.orig,
+            },
+        };
+        match &mut active.hooks.level {
+            Some(f) => f(&level),
+            None => ControlFlow::Continue(()),
+        }
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+extern "C" fn nodeproc(
+    _g: *mut graph,
+    lab: *mut c_int,
+    ptn: *mut c_int,
+    level: c_int,
+    numcells: c_int,
+    tc: c_int,
+    code: c_int,
+    _m: c_int,
+    n: c_int,
+) {
+    call(|| {
+        // SAFETY: nauty passes its partition of the `n` vertices
+        let (active, lab, ptn) = unsafe {
+            (
+                active(),
+                slice::from_raw_parts(lab, n as usize),
+                slice::from_raw_parts(ptn, n as usize),
+            )
+        };
+        let node = SearchNode {
+            level: level as usize,
+            num_cells: numcells as usize,
+            is_leaf: numcells == n,
+            code,
+            target_cell: (tc >= 0).then_some(tc as usize),
+            partition: Partition {
+                lab,
+                ptn,
+                level,
+                orig: active.orig,
+            },
+        };
+        match &mut active.hooks.node {
+            Some(f) => f(&node),
+            None => ControlFlow::Continue(()),
+        }
+    })
+}
+
+// returning a non-zero value makes nauty stop with `NAUABORTED`
+extern "C" fn canonproc(
+    _g: *mut graph,
+    canonlab: *mut c_int,
+    _canong: *mut graph,
+    count: c_ulong,
+    code: c_int,
+    _m: c_int,
+    n: c_int,
+) -> c_int {
+    let flow = try_call(|| {
+        // SAFETY: nauty passes its best labelling of the `n` vertices
+        let (active, lab) =
+            unsafe { (active(), slice::from_raw_parts(canonlab, n as usize)) };
+        let candidate = CanonCandidate {
+            count: u64::from(count),
+            code,
+            lab,
+            orig: active.orig,
+        };
+        match &mut active.hooks.canon {
+            Some(f) => f(&candidate),
+            None => ControlFlow::Continue(()),
+        }
+    });
+    c_int::from(flow.is_break())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmp::IsIdentical;
+    use crate::error::NautyError;
+    use crate::options::NautyOptions;
+    use petgraph::graph::{Graph, UnGraph};
+    use petgraph::Undirected;
+    use testing::GraphIter;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn petersen() -> UnGraph<(), u8> {
+        let outer = (0..5).map(|i| (i, (i + 1) % 5, 0));
+        let spokes = (0..5).map(|i| (i, i + 5, 1));
+        let inner = (0..5).map(|i| (i + 5, (i + 2) % 5 + 5, 0));
+        UnGraph::from_edges(outer.chain(spokes).chain(inner))
+    }
+
+    fn is_partition(cells: &[Vec<usize>], n: usize) -> bool {
+        let mut vertices = Vec::from_iter(cells.iter().flatten().copied());
+        vertices.sort_unstable();
+        vertices == Vec::from_iter(0..n)
+    }
+
+    #[test]
+    fn nodes() {
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let options = NautyOptions::default();
+        for g in GraphIter::<Undirected>::default().take(200) {
+            let n = g.node_count();
+            let mut num_nodes = 0;
+            let hooks = SearchHooks::new().on_node(|node| {
+                num_nodes += 1;
+                assert!(is_partition(&node.cells(), n));
+                if node.is_leaf {
+                    assert!(node.cells().iter().all(|c| c.len() == 1));
+                }
+                if let Some(cell) = node.target_cell() {
+                    assert!(cell.is_empty() || node.cells().contains(&cell));
+                }
+                ControlFlow::Continue(())
+            });
+            let autom = options.autom_dense_with_hooks(&g, hooks).unwrap();
+            let stats = options.autom_dense_with_stats(&g).unwrap();
+            assert_eq!(autom, stats.autom);
+            if n > 0 {
+                assert_eq!(num_nodes, stats.num_nodes);
+            }
+
+            let mut sparse_nodes = 0;
+            let hooks = SearchHooks::new().on_node(|_| {
+                sparse_nodes += 1;
+                ControlFlow::Continue(())
+            });
+            options.autom_sparse_with_hooks(&g, hooks).unwrap();
+            let stats = options.autom_sparse_with_stats(&g).unwrap();
+            if n > 0 {
+                assert_eq!(sparse_nodes, stats.num_nodes);
+            }
+        }
+    }
+
+    #[test]
+    fn levels() {
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let g = petersen();
+        let options = NautyOptions::default();
+        let mut levels = Vec::new();
+        let hooks = SearchHooks::new().on_level(|level| {
+            assert!(is_partition(&level.cells(), 10));
+            levels.push((level.level, level.index, level.fixed_vertex));
+            ControlFlow::Continue(())
+        });
+        let autom = options.autom_dense_with_hooks(&g, hooks).unwrap();
+        // the leaf is reported first and the root last
+        assert_eq!(levels.first().unwrap().2, None);
+        assert_eq!(levels.last().unwrap().0, 1);
+        assert!(levels.windows(2).all(|w| w[0].0 > w[1].0));
+        let grpsize: usize = levels.iter().map(|l| l.1).product();
+        assert_eq!(grpsize as f64, autom.grpsize());
+        assert_eq!(autom.grpsize(), 20.);
+    }
+
+    #[test]
+    fn canon() {
+        log_init();
+
+        let options = NautyOptions::default();
+        for g in GraphIter::<Undirected>::default().take(100) {
+            let mut leaves = 0;
+            let hooks = SearchHooks::new().on_node(|node| {
+                leaves += usize::from(node.is_leaf);
+                ControlFlow::Continue(())
+            });
+            let canon = options.canon_dense_with_hooks(g.clone(), hooks);
+            let expected = options.canon_dense(g.clone()).unwrap();
+            assert!(canon.unwrap().is_identical(&expected));
+            if g.node_count() > 0 {
+                assert!(leaves > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn abort() {
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let g: Graph<(), u8, Undirected> = petersen();
+        let options = NautyOptions::default();
+        let mut num_nodes = 0;
+        let hooks = SearchHooks::new().on_node(|_| {
+            num_nodes += 1;
+            if num_nodes < 3 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+        let res = options.autom_dense_with_hooks(&g, hooks);
+        assert!(matches!(res, Err(NautyError::Aborted)));
+        assert_eq!(num_nodes, 3);
+
+        let hooks = SearchHooks::new().on_level(|_| ControlFlow::Break(()));
+        let res = options.canon_dense_with_hooks(g.clone(), hooks);
+        assert!(matches!(res, Err(NautyError::Aborted)));
+
+        let res = std::panic::catch_unwind(|| {
+            let hooks = SearchHooks::new().on_node(|_| panic!("node"));
+            options.autom_sparse_with_hooks(&g, hooks)
+        });
+        assert!(res.is_err());
+        let autom = options.autom_sparse(&g).unwrap();
+        assert_eq!(autom.grpsize(), 20.);
+    }
+
+    #[test]
+    fn reentrant() {
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let g = petersen();
+        let options = NautyOptions::default();
+        let res = std::panic::catch_unwind(|| {
+            let hooks = SearchHooks::new().on_level(|_| {
+                let _ = options.canon_dense(g.clone());
+                ControlFlow::Continue(())
+            });
+            options.autom_dense_with_hooks(&g, hooks)
+        });
+        let msg = res.unwrap_err().downcast::<&str>().unwrap();
+        assert_eq!(*msg, "reentrant call to nauty or Traces");
+        let autom = options.autom_dense(&g).unwrap();
+        assert_eq!(autom.grpsize(), 20.);
+    }
+
+    #[test]
+    fn canon_candidates() {
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let options = NautyOptions::default();
+        for g in GraphIter::<Undirected>::default().take(200) {
+            let g = g.map(|_, _| (), |_, _| ());
+            let mut counts = Vec::new();
+            let mut last = Vec::new();
+            let hooks = SearchHooks::new().on_canon_candidate(|candidate| {
+                counts.push(candidate.count);
+                last = candidate.labelling();
+                ControlFlow::Continue(())
+            });
+            let canon = options.canon_dense_with_hooks(g.clone(), hooks);
+            let canon = canon.unwrap();
+            let (_, stats) = options.canon_dense_with_stats(g.clone()).unwrap();
+            let n = g.node_count();
+            if n == 0 {
+                assert!(counts.is_empty());
+                continue;
+            }
+            assert_eq!(counts, Vec::from_iter(1..=stats.can_updates));
+
+            // the last candidate is the canonical labelling
+            let mut pos = vec![0; n];
+            for (i, &v) in last.iter().enumerate() {
+                pos[v] = i;
+            }
+            let mut edges = Vec::from_iter(g.edge_indices().map(|e| {
+                let (s, t) = g.edge_endpoints(e).unwrap();
+                let (s, t) = (pos[s.index()], pos[t.index()]);
+                (s.min(t), s.max(t))
+            }));
+            let mut canon_edges =
+                Vec::from_iter(canon.edge_indices().map(|e| {
+                    let (s, t) = canon.edge_endpoints(e).unwrap();
+                    (s.index().min(t.index()), s.index().max(t.index()))
+                }));
+            edges.sort_unstable();
+            canon_edges.sort_unstable();
+            assert_eq!(edges, canon_edges);
+        }
+
+        let g = petersen();
+        let mut count = 0;
+        let hooks = SearchHooks::new().on_canon_candidate(|_| {
+            count += 1;
+            ControlFlow::Break(())
+        });
+        let res = options.canon_dense_with_hooks(g.clone(), hooks);
+        assert!(matches!(res, Err(NautyError::Aborted)));
+        assert_eq!(count, 1);
+        let canon = options.canon_dense(g.clone()).unwrap();
+        assert_eq!(canon.edge_count(), 15);
+    }
+}
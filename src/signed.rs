@@ -0,0 +1,339 @@
+//! Signed graphs and switching equivalence
+//!
+//! A signed graph is an undirected graph where each edge is either
+//! positive or negative. Since [Sign] is an ordinary edge weight,
+//! signed graphs can be canonicalised like any other graph. In
+//! addition, this module provides canonical forms up to *switching*,
+//! i.e. flipping the signs of all edges between a set of vertices and
+//! its complement.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::NodeIndex;
+//! use nauty_pet::signed::{switch, switching_canon, Sign, SignedGraph};
+//!
+//! // triangle with a single negative edge
+//! let g = SignedGraph::<()>::from_edges([
+//!     (0, 1, Sign::Positive),
+//!     (1, 2, Sign::Positive),
+//!     (2, 0, Sign::Negative),
+//! ]);
+//! let mut h = g.clone();
+//! switch(&mut h, &[NodeIndex::new(1)]);
+//! assert_ne!(
+//!     Vec::from_iter(g.edge_weights()),
+//!     Vec::from_iter(h.edge_weights())
+//! );
+//! assert_eq!(switching_canon(&g), switching_canon(&h));
+//! ```
+use std::hash::Hash;
+use std::ops::{Mul, Neg};
+
+use crate::cancel;
+use crate::canon::try_canon_with_labelling;
+use crate::graph::CanonGraph;
+
+use petgraph::{
+    graph::{DefaultIx, Graph, IndexType, NodeIndex, UnGraph},
+    visit::EdgeRef,
+    Undirected,
+};
+
+/// Sign of an edge
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sign {
+    #[default]
+    Positive,
+    Negative,
+}
+
+impl Neg for Sign {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Sign::Positive => Sign::Negative,
+            Sign::Negative => Sign::Positive,
+        }
+    }
+}
+
+impl Mul for Sign {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        if self == rhs {
+            Sign::Positive
+        } else {
+            Sign::Negative
+        }
+    }
+}
+
+/// Undirected graph with signed edges
+pub type SignedGraph<N, Ix = DefaultIx> = Graph<N, Sign, Undirected, Ix>;
+
+/// Switch a signed graph at a set of vertices
+///
+/// The signs of all edges with exactly one end point in `vertices`
+/// are flipped.
+pub fn switch<N, Ix: IndexType>(
+    g: &mut SignedGraph<N, Ix>,
+    vertices: &[NodeIndex<Ix>],
+) {
+    let mut switched = vec![false; g.node_count()];
+    for v in vertices {
+        switched[v.index()] = true;
+    }
+    for e in g.edge_indices() {
+        let (v, w) = g.edge_endpoints(e).unwrap();
+        if switched[v.index()] != switched[w.index()] {
+            g[e] = -g[e];
+        }
+    }
+}
+
+/// Check whether a signed graph is balanced
+///
+/// A signed graph is balanced if every cycle has an even number of
+/// negative edges, or equivalently if it can be switched to a graph
+/// with only positive edges.
+pub fn is_balanced<N, Ix: IndexType>(g: &SignedGraph<N, Ix>) -> bool {
+    // try to assign a sign to each vertex such that each edge sign is
+    // the product of its end point signs
+    let mut vertex_sign = vec![None; g.node_count()];
+    let mut stack = Vec::new();
+    for start in g.node_indices() {
+        if vertex_sign[start.index()].is_some() {
+            continue;
+        }
+        vertex_sign[start.index()] = Some(Sign::Positive);
+        stack.push(start);
+        while let Some(v) = stack.pop() {
+            let sign = vertex_sign[v.index()].unwrap();
+            for e in g.edges(v) {
+                let w = e.target();
+                let expected = sign * *e.weight();
+                match vertex_sign[w.index()] {
+                    None => {
+                        vertex_sign[w.index()] = Some(expected);
+                        stack.push(w);
+                    }
+                    Some(s) if s != expected => return false,
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Canonical form up to switching
+///
+/// Two signed graphs have the same canonical form if and only if one
+/// can be obtained from the other by switching and relabelling
+/// vertices. The result is a canonical member of the switching
+/// class.
+pub fn switching_canon<N, Ix>(
+    g: &SignedGraph<N, Ix>,
+) -> CanonGraph<N, Sign, Undirected, Ix>
+where
+    N: Clone + Ord,
+    Ix: IndexType,
+{
+    // the switching class is encoded in the double cover: each vertex
+    // v is split into v+ and v-, joined by a marked edge, and a
+    // positive edge {v, w} becomes {v+, w+}, {v-, w-}, whereas a
+    // negative edge becomes {v+, w-}, {v-, w+}. Switching at v
+    // amounts to exchanging v+ and v-.
+    const EDGE: u8 = 0;
+    const PAIR: u8 = 1;
+    let n = g.node_count();
+    let mut cover =
+        UnGraph::<N, u8>::with_capacity(2 * n, 2 * g.edge_count() + n);
+    for w in g.node_weights().chain(g.node_weights()) {
+        cover.add_node(w.clone());
+    }
+    let plus = NodeIndex::new;
+    let minus = |v: usize| NodeIndex::new(v + n);
+    for v in 0..n {
+        cover.add_edge(plus(v), minus(v), PAIR);
+    }
+    for e in g.edge_references() {
+        let (v, w) = (e.source().index(), e.target().index());
+        match e.weight() {
+            Sign::Positive => {
+                cover.add_edge(plus(v), plus(w), EDGE);
+                cover.add_edge(minus(v), minus(w), EDGE);
+            }
+            Sign::Negative => {
+                cover.add_edge(plus(v), minus(w), EDGE);
+                cover.add_edge(minus(v), plus(w), EDGE);
+            }
+        }
+    }
+    let (_, labelling) = cancel::unwrap(try_canon_with_labelling(cover));
+
+    // choose the copy that comes first in the canonical cover as the
+    // positive one and order vertices accordingly
+    let pos = |v: usize| labelling[v].min(labelling[v + n]);
+    let mut order = Vec::from_iter(0..n);
+    order.sort_unstable_by_key(|&v| pos(v));
+    let mut new_idx = vec![0; n];
+    for (i, &v) in order.iter().enumerate() {
+        new_idx[v] = i;
+    }
+    let vertex_sign = |v: usize| {
+        if labelling[v] < labelling[v + n] {
+            Sign::Positive
+        } else {
+            Sign::Negative
+        }
+    };
+    let mut res = SignedGraph::with_capacity(n, g.edge_count());
+    for &v in &order {
+        res.add_node(g[NodeIndex::new(v)].clone());
+    }
+    for e in g.edge_references() {
+        let (v, w) = (e.source().index(), e.target().index());
+        let sign = vertex_sign(v) * *e.weight() * vertex_sign(w);
+        res.add_edge(
+            NodeIndex::new(new_idx[v]),
+            NodeIndex::new(new_idx[w]),
+            sign,
+        );
+    }
+    CanonGraph::from(res)
+}
+
+/// Check whether two signed graphs are isomorphic up to switching
+pub fn is_switching_equivalent<N, Ix>(
+    g: &SignedGraph<N, Ix>,
+    h: &SignedGraph<N, Ix>,
+) -> bool
+where
+    N: Clone + Ord,
+    Ix: IndexType,
+{
+    g.node_count() == h.node_count()
+        && g.edge_count() == h.edge_count()
+        && switching_canon(g) == switching_canon(h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{seq::SliceRandom, Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn random_signed_graph(n: usize, rng: &mut impl Rng) -> SignedGraph<u8> {
+        let mut g = SignedGraph::default();
+        for _ in 0..n {
+            g.add_node(rng.gen_range(0..2));
+        }
+        for v in 0..n {
+            for w in (v + 1)..n {
+                if rng.gen_bool(0.5) {
+                    let sign = if rng.gen() {
+                        Sign::Positive
+                    } else {
+                        Sign::Negative
+                    };
+                    g.add_edge(NodeIndex::new(v), NodeIndex::new(w), sign);
+                }
+            }
+        }
+        g
+    }
+
+    #[test]
+    fn balance() {
+        log_init();
+
+        let mut g = SignedGraph::<()>::from_edges([
+            (0, 1, Sign::Negative),
+            (1, 2, Sign::Negative),
+            (2, 0, Sign::Positive),
+            (3, 4, Sign::Negative),
+        ]);
+        assert!(is_balanced(&g));
+        let e = g.find_edge(NodeIndex::new(2), NodeIndex::new(0)).unwrap();
+        g[e] = Sign::Negative;
+        assert!(!is_balanced(&g));
+    }
+
+    #[test]
+    fn switching_invariance() {
+        log_init();
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(221);
+        for n in 0..8 {
+            for _ in 0..20 {
+                let g = random_signed_graph(n, &mut rng);
+                let canon = switching_canon(&g);
+                assert_eq!(is_balanced(&g), is_balanced(&canon));
+
+                // random switching and relabelling
+                let mut switched = g.clone();
+                let vertices = Vec::from_iter(
+                    g.node_indices().filter(|_| rng.gen_bool(0.5)),
+                );
+                switch(&mut switched, &vertices);
+                let mut perm = Vec::from_iter(0..n);
+                perm.shuffle(&mut rng);
+                let mut h = SignedGraph::default();
+                for &v in &perm {
+                    h.add_node(g[NodeIndex::new(v)]);
+                }
+                let mut new_idx = vec![0; n];
+                for (i, &v) in perm.iter().enumerate() {
+                    new_idx[v] = i;
+                }
+                for e in switched.edge_references() {
+                    h.add_edge(
+                        NodeIndex::new(new_idx[e.source().index()]),
+                        NodeIndex::new(new_idx[e.target().index()]),
+                        *e.weight(),
+                    );
+                }
+                assert_eq!(switching_canon(&h), canon);
+                assert!(is_switching_equivalent(&g, &h));
+            }
+        }
+    }
+
+    #[test]
+    fn switching_classes() {
+        log_init();
+
+        // all eight signings of a triangle fall into two classes:
+        // balanced and unbalanced
+        let mut classes = std::collections::BTreeSet::new();
+        let mut plain = std::collections::BTreeSet::new();
+        for mask in 0..8 {
+            let sign = |i: u32| {
+                if mask & (1 << i) == 0 {
+                    Sign::Positive
+                } else {
+                    Sign::Negative
+                }
+            };
+            let g = SignedGraph::<()>::from_edges([
+                (0, 1, sign(0)),
+                (1, 2, sign(1)),
+                (2, 0, sign(2)),
+            ]);
+            classes.insert(switching_canon(&g));
+            plain.insert(CanonGraph::from(g));
+        }
+        assert_eq!(classes.len(), 2);
+        assert_eq!(plain.len(), 4);
+    }
+}
@@ -0,0 +1,127 @@
+//! Strongly regular graphs
+//!
+//! A graph with `n` vertices is strongly regular with parameters
+//! `(n, k, λ, μ)` if each vertex has `k` neighbours, any two adjacent
+//! vertices have `λ` common neighbours, and any two distinct
+//! non-adjacent vertices have `μ` common neighbours.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::srg::srg_parameters;
+//!
+//! // the five-cycle
+//! let g = UnGraph::<(), ()>::from_edges((0..5).map(|i| (i, (i + 1) % 5)));
+//! assert_eq!(srg_parameters(&g), Some((5, 2, 0, 1)));
+//!
+//! let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+//! assert_eq!(srg_parameters(&path), None);
+//! ```
+use crate::subgraph::undirected_neighbours;
+
+use petgraph::graph::{IndexType, UnGraph};
+
+/// The parameters `(n, k, λ, μ)` of a strongly regular graph
+///
+/// Returns `None` if the graph is not strongly regular. Complete
+/// graphs and graphs without edges are not considered strongly
+/// regular. Edge multiplicities and loops are ignored.
+pub fn srg_parameters<N, E, Ix>(
+    g: &UnGraph<N, E, Ix>,
+) -> Option<(usize, usize, usize, usize)>
+where
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let neighbours = undirected_neighbours(g);
+    let k = neighbours.first()?.len();
+    if k == 0 || k + 1 == n || neighbours.iter().any(|nbrs| nbrs.len() != k) {
+        return None;
+    }
+    let mut adjacent = vec![false; n * n];
+    for (v, nbrs) in neighbours.iter().enumerate() {
+        for &w in nbrs {
+            adjacent[v * n + w] = true;
+        }
+    }
+    let (mut lambda, mut mu) = (None, None);
+    for (v, nbrs) in neighbours.iter().enumerate() {
+        for w in (v + 1)..n {
+            let common = nbrs.iter().filter(|&&u| adjacent[w * n + u]).count();
+            let expected = if adjacent[v * n + w] {
+                &mut lambda
+            } else {
+                &mut mu
+            };
+            if *expected.get_or_insert(common) != common {
+                return None;
+            }
+        }
+    }
+    Some((n, k, lambda?, mu?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn parameters() {
+        log_init();
+
+        #[rustfmt::skip]
+        let petersen = UnGraph::<(), ()>::from_edges([
+            (0, 1), (1, 2), (2, 3), (3, 4), (4, 0),
+            (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+            (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+        ]);
+        assert_eq!(srg_parameters(&petersen), Some((10, 3, 0, 1)));
+
+        // Cayley graph of Z_4 x Z_4
+        let idx = |a: u32, b: u32| 4 * (a % 4) + b % 4;
+        let shrikhande = UnGraph::<(), ()>::from_edges((0..4).flat_map(|a| {
+            (0..4).flat_map(move |b| {
+                [(1, 0), (0, 1), (1, 1)]
+                    .map(|(da, db)| (idx(a, b), idx(a + da, b + db)))
+            })
+        }));
+        assert_eq!(srg_parameters(&shrikhande), Some((16, 6, 2, 2)));
+
+        // disjoint triangles
+        let triangles = UnGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (3, 4),
+            (4, 5),
+            (5, 3),
+        ]);
+        assert_eq!(srg_parameters(&triangles), Some((6, 2, 1, 0)));
+    }
+
+    #[test]
+    fn not_strongly_regular() {
+        log_init();
+
+        let cube = UnGraph::<(), ()>::from_edges(
+            (0..8u32)
+                .flat_map(|v| [1, 2, 4].map(|b| (v, v ^ b)))
+                .filter(|(v, w)| v < w),
+        );
+        assert_eq!(srg_parameters(&cube), None);
+        let complete = UnGraph::<(), ()>::from_edges(
+            (0..4).flat_map(|i| ((i + 1)..4).map(move |j| (i, j))),
+        );
+        assert_eq!(srg_parameters(&complete), None);
+        let mut edgeless = UnGraph::<(), ()>::default();
+        edgeless.add_node(());
+        edgeless.add_node(());
+        assert_eq!(srg_parameters(&edgeless), None);
+        assert_eq!(srg_parameters(&UnGraph::<(), ()>::default()), None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Statistics on the search performed by nauty and Traces
+//!
+//! Besides the automorphism group, nauty and Traces report how much
+//! work the search took. These numbers are helpful for choosing
+//! [options](crate::options) for hard instances. They are returned
+//! by the `*_with_stats` methods of
+//! [NautyOptions](crate::options::NautyOptions) and
+//! [TracesConfig](crate::options::TracesConfig).
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::options::NautyOptions;
+//!
+//! let g = UnGraph::<(), ()>::from_edges((0..6).map(|i| (i, (i + 1) % 6)));
+//! let stats = NautyOptions::default().autom_dense_with_stats(&g).unwrap();
+//! assert_eq!(stats.autom.grpsize(), 12.);
+//! assert!(stats.num_nodes > 0);
+//! ```
+use crate::autom::Autom;
+
+use nauty_Traces_sys::statsblk;
+#[cfg(feature = "traces")]
+use nauty_Traces_sys::TracesStats;
+
+/// Statistics of a nauty run
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default)]
+pub struct NautyStats {
+    /// Information on the automorphism group
+    pub autom: Autom,
+    /// Number of nodes in the search tree
+    pub num_nodes: u64,
+    /// Number of leaves of the search tree that gave neither an
+    /// automorphism nor a better canonical labelling
+    pub num_bad_leaves: u64,
+    /// Maximum depth of the search tree
+    pub max_level: u32,
+    /// Total size of all target cells
+    pub tc_total: u64,
+    /// Number of updates of the best canonical labelling
+    pub can_updates: u64,
+    /// Number of applications of the vertex invariant
+    pub invar_applications: u64,
+    /// Number of applications of the vertex invariant that split a
+    /// cell
+    pub invar_successes: u64,
+    /// Least level at which the vertex invariant split a cell
+    pub invar_success_level: u32,
+}
+
+impl NautyStats {
+    // the statistics for graphs on which nauty is not run
+    pub(crate) fn trivial() -> Self {
+        let autom = Autom {
+            grpsize_base: 1.,
+            ..Default::default()
+        };
+        Self {
+            autom,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<statsblk> for NautyStats {
+    fn from(o: statsblk) -> Self {
+        Self {
+            autom: o.into(),
+            num_nodes: u64::from(o.numnodes),
+            num_bad_leaves: u64::from(o.numbadleaves),
+            max_level: o.maxlevel as u32,
+            tc_total: u64::from(o.tctotal),
+            can_updates: u64::from(o.canupdates),
+            invar_applications: u64::from(o.invapplics),
+            invar_successes: u64::from(o.invsuccesses),
+            invar_success_level: o.invarsuclevel as u32,
+        }
+    }
+}
+
+/// Statistics of a Traces run
+#[cfg(feature = "traces")]
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default)]
+pub struct TracesSearchStats {
+    /// Information on the automorphism group
+    pub autom: Autom,
+    /// Maximum depth of the search tree
+    pub tree_depth: u32,
+    /// Number of updates of the best canonical labelling
+    pub can_updates: u32,
+    /// Number of nodes in the search tree
+    pub num_nodes: u64,
+    /// Number of nodes at which the search was interrupted
+    pub interrupted: u64,
+    /// Maximum number of nodes stored at the same time
+    pub peak_nodes: u64,
+}
+
+#[cfg(feature = "traces")]
+impl TracesSearchStats {
+    // the statistics for graphs on which Traces is not run
+    pub(crate) fn trivial() -> Self {
+        Self {
+            autom: NautyStats::trivial().autom,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(feature = "traces")]
+impl From<TracesStats> for TracesSearchStats {
+    fn from(o: TracesStats) -> Self {
+        Self {
+            autom: o.into(),
+            tree_depth: o.treedepth as u32,
+            can_updates: o.canupdates as u32,
+            num_nodes: u64::from(o.numnodes),
+            interrupted: u64::from(o.interrupted),
+            peak_nodes: u64::from(o.peaknodes),
+        }
+    }
+}
@@ -0,0 +1,614 @@
+//! Persistent store of canonical certificates
+//!
+//! A [CertStore] keeps a set of certificates, i.e. byte strings
+//! uniquely identifying isomorphism classes, in memory-mapped files.
+//! This allows deduplicating collections of graphs that are too
+//! large to keep in memory. The store consists of two files: an
+//! append-only data file with the certificates and a hash index
+//! pointing into the data file, stored next to it with an additional
+//! `.idx` extension. Both files use a fixed, platform-independent
+//! layout.
+//!
+//! A store can be opened for reading by any number of processes, but
+//! there must be at most one writer at a time. Readers see the
+//! certificates that were stored when they opened the store or last
+//! called [refresh](CertStore::refresh). When the index is full, the
+//! writer builds a larger one in a new file that replaces the old
+//! index, so readers are not affected until they refresh. Files that
+//! are not valid stores result in errors of kind
+//! [InvalidData](io::ErrorKind::InvalidData).
+//!
+//! Requires the `cert-store` feature and a Unix-like operating system.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nauty_pet::store::CertStore;
+//!
+//! let path = std::env::temp_dir().join("nauty_pet_store_doc_example");
+//! let mut store = CertStore::create(&path).unwrap();
+//! assert!(store.insert(b"first certificate").unwrap());
+//! assert!(store.insert(b"second certificate").unwrap());
+//! assert!(!store.insert(b"first certificate").unwrap());
+//! drop(store);
+//!
+//! let store = CertStore::open_read_only(&path).unwrap();
+//! assert_eq!(store.len(), 2);
+//! assert!(store.contains(b"second certificate").unwrap());
+//! # std::fs::remove_file(&path).unwrap();
+//! # std::fs::remove_file(path.with_extension("idx")).unwrap();
+//! ```
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+const INDEX_MAGIC: &[u8; 8] = b"NPCSIDX1";
+const HEADER_LEN: usize = 32;
+const SLOT_LEN: usize = 8;
+const MIN_CAPACITY: u64 = 1024;
+const MIN_DATA_LEN: u64 = 4096;
+const RECORD_HEADER_LEN: usize = 4;
+
+/// Set of certificates stored in memory-mapped files
+///
+/// See the [module documentation](self).
+#[derive(Debug)]
+pub struct CertStore {
+    data_file: File,
+    data: Mapping,
+    index_path: PathBuf,
+    index_file: File,
+    index: Mapping,
+    // for readers, the state of the store at the last refresh
+    snapshot: Option<Snapshot>,
+    writable: bool,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Snapshot {
+    count: u64,
+    data_len: u64,
+}
+
+impl CertStore {
+    /// Create a new, empty store, replacing any existing one
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let data_file = open_rw(path, true)?;
+        data_file.set_len(MIN_DATA_LEN)?;
+        let index_path = index_path(path);
+        let index_file = open_rw(&index_path, true)?;
+        init_index(&index_file, MIN_CAPACITY, 0, 0)?;
+        Self::map(data_file, index_file, index_path, true)
+    }
+
+    /// Open an existing store for reading and writing
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let data_file = open_rw(path, false)?;
+        let index_path = index_path(path);
+        let index_file = open_rw(&index_path, false)?;
+        Self::map(data_file, index_file, index_path, true)
+    }
+
+    /// Open an existing store for reading
+    pub fn open_read_only(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let data_file = File::open(path)?;
+        let index_path = index_path(path);
+        let index_file = File::open(&index_path)?;
+        Self::map(data_file, index_file, index_path, false)
+    }
+
+    /// Update the mappings to include changes made by a writer
+    pub fn refresh(&mut self) -> io::Result<()> {
+        // the index file is replaced when the writer enlarges it
+        self.index_file = if self.writable {
+            open_rw(&self.index_path, false)?
+        } else {
+            File::open(&self.index_path)?
+        };
+        self.index = Mapping::new(&self.index_file, self.writable)?;
+        self.data = Mapping::new(&self.data_file, self.writable)?;
+        self.load_header()
+    }
+
+    /// The number of stored certificates
+    pub fn len(&self) -> usize {
+        self.count() as usize
+    }
+
+    /// Check whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check whether the store contains a certificate
+    pub fn contains(&self, cert: &[u8]) -> io::Result<bool> {
+        Ok(matches!(self.find(cert)?, Slot::Occupied))
+    }
+
+    /// Add a certificate to the store
+    ///
+    /// Returns `true` if the certificate was not yet in the store.
+    pub fn insert(&mut self, cert: &[u8]) -> io::Result<bool> {
+        if !self.writable {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Certificate store was opened read-only",
+            ));
+        }
+        let cert_len = u32::try_from(cert.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Certificate too long")
+        })?;
+        if self.contains(cert)? {
+            return Ok(false);
+        }
+        if 2 * (self.count() + 1) > self.capacity() {
+            self.grow_index()?;
+        }
+        let offset = self.data_len();
+        let end = offset + (RECORD_HEADER_LEN + cert.len()) as u64;
+        if end > self.data.len() as u64 {
+            let new_len = end.max(2 * self.data.len() as u64);
+            self.data_file.set_len(new_len)?;
+            self.data = Mapping::new(&self.data_file, true)?;
+        }
+        let data = self.data.as_mut_slice();
+        let offset_usize = offset as usize;
+        data[offset_usize..offset_usize + RECORD_HEADER_LEN]
+            .copy_from_slice(&cert_len.to_le_bytes());
+        data[offset_usize + RECORD_HEADER_LEN..end as usize]
+            .copy_from_slice(cert);
+        let Slot::Vacant(slot) = self.find(cert)? else {
+            unreachable!()
+        };
+        let index = self.index.as_mut_slice();
+        write_u64(index, slot_pos(slot), offset + 1);
+        write_u64(index, 24, end);
+        let count = self.count();
+        write_u64(self.index.as_mut_slice(), 16, count + 1);
+        Ok(true)
+    }
+
+    /// Iterate over all certificates in insertion order
+    ///
+    /// The iteration ends after the first error.
+    pub fn iter(&self) -> impl Iterator<Item = io::Result<&[u8]>> + '_ {
+        let mut offset = 0;
+        std::iter::from_fn(move || {
+            if offset >= self.data_len() {
+                return None;
+            }
+            let res = self.record(offset);
+            offset = match &res {
+                Ok(cert) => offset + (RECORD_HEADER_LEN + cert.len()) as u64,
+                Err(_) => u64::MAX,
+            };
+            Some(res)
+        })
+    }
+
+    /// Write all changes to disk
+    pub fn flush(&self) -> io::Result<()> {
+        self.data.flush()?;
+        self.index.flush()
+    }
+
+    fn map(
+        data_file: File,
+        index_file: File,
+        index_path: PathBuf,
+        writable: bool,
+    ) -> io::Result<Self> {
+        // the data file is mapped last, so that it contains all records
+        // the index points to
+        let index = Mapping::new(&index_file, writable)?;
+        let data = Mapping::new(&data_file, writable)?;
+        let mut store = Self {
+            data_file,
+            data,
+            index_path,
+            index_file,
+            index,
+            snapshot: None,
+            writable,
+        };
+        store.load_header()?;
+        Ok(store)
+    }
+
+    // check the header of the index and take a snapshot for readers
+    fn load_header(&mut self) -> io::Result<()> {
+        let index = self.index.as_slice();
+        let is_valid = index.len() >= HEADER_LEN
+            && &index[..8] == INDEX_MAGIC
+            && read_u64(index, 8).is_power_of_two()
+            && index_len(read_u64(index, 8)) == index.len() as u64
+            && read_u64(index, 24) <= self.data.len() as u64;
+        if !is_valid {
+            return Err(invalid_data());
+        }
+        if !self.writable {
+            self.snapshot = Some(Snapshot {
+                count: read_u64(index, 16),
+                data_len: read_u64(index, 24),
+            });
+        }
+        Ok(())
+    }
+
+    fn capacity(&self) -> u64 {
+        // checked against the header in `load_header`
+        ((self.index.len() - HEADER_LEN) / SLOT_LEN) as u64
+    }
+
+    fn count(&self) -> u64 {
+        match self.snapshot {
+            Some(snapshot) => snapshot.count,
+            None => read_u64(self.index.as_slice(), 16),
+        }
+    }
+
+    fn data_len(&self) -> u64 {
+        match self.snapshot {
+            Some(snapshot) => snapshot.data_len,
+            None => read_u64(self.index.as_slice(), 24),
+        }
+    }
+
+    fn record(&self, offset: u64) -> io::Result<&[u8]> {
+        let data = self.data.as_slice();
+        let data = &data[..self.data_len().min(data.len() as u64) as usize];
+        let start = (offset as usize)
+            .checked_add(RECORD_HEADER_LEN)
+            .ok_or_else(invalid_data)?;
+        let len_bytes =
+            data.get(offset as usize..start).ok_or_else(invalid_data)?;
+        let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        start
+            .checked_add(len)
+            .and_then(|end| data.get(start..end))
+            .ok_or_else(invalid_data)
+    }
+
+    // linear probing
+    fn find(&self, cert: &[u8]) -> io::Result<Slot> {
+        let index = self.index.as_slice();
+        let capacity = self.capacity();
+        let mask = capacity - 1;
+        let mut slot = fnv1a(cert) & mask;
+        for _ in 0..capacity {
+            match read_u64(index, slot_pos(slot)) {
+                0 => return Ok(Slot::Vacant(slot)),
+                // stored after the snapshot of a reader
+                offset
+                    if self.snapshot.is_some() && offset > self.data_len() => {}
+                offset if self.record(offset - 1)? == cert => {
+                    return Ok(Slot::Occupied)
+                }
+                _ => {}
+            }
+            slot = (slot + 1) & mask;
+        }
+        Err(invalid_data())
+    }
+
+    fn grow_index(&mut self) -> io::Result<()> {
+        // build the new index in a separate file that replaces the old
+        // one, so that readers can continue to use the old index
+        let capacity = 2 * self.capacity();
+        let mut tmp_path = self.index_path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let index_file = open_rw(Path::new(&tmp_path), true)?;
+        init_index(&index_file, capacity, self.count(), self.data_len())?;
+        let mut index = Mapping::new(&index_file, true)?;
+        let mask = capacity - 1;
+        let mut offset = 0;
+        while offset < self.data_len() {
+            let cert = self.record(offset)?;
+            let slots = index.as_mut_slice();
+            let mut slot = fnv1a(cert) & mask;
+            while read_u64(slots, slot_pos(slot)) != 0 {
+                slot = (slot + 1) & mask;
+            }
+            write_u64(slots, slot_pos(slot), offset + 1);
+            offset += (RECORD_HEADER_LEN + cert.len()) as u64;
+        }
+        index.flush()?;
+        std::fs::rename(&tmp_path, &self.index_path)?;
+        self.index_file = index_file;
+        self.index = index;
+        Ok(())
+    }
+}
+
+enum Slot {
+    Occupied,
+    Vacant(u64),
+}
+
+fn invalid_data() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "Invalid certificate store")
+}
+
+fn open_rw(path: &Path, truncate: bool) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(truncate)
+        .truncate(truncate)
+        .open(path)
+}
+
+// write the header of an index without any certificates in its slots
+fn init_index(
+    file: &File,
+    capacity: u64,
+    count: u64,
+    data_len: u64,
+) -> io::Result<()> {
+    file.set_len(index_len(capacity))?;
+    let mut header = [0; HEADER_LEN];
+    header[..8].copy_from_slice(INDEX_MAGIC);
+    write_u64(&mut header, 8, capacity);
+    write_u64(&mut header, 16, count);
+    write_u64(&mut header, 24, data_len);
+    file.write_all_at(&header, 0)
+}
+
+fn index_path(path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".idx");
+    path.into()
+}
+
+fn index_len(capacity: u64) -> u64 {
+    HEADER_LEN as u64 + capacity * SLOT_LEN as u64
+}
+
+fn slot_pos(slot: u64) -> usize {
+    HEADER_LEN + slot as usize * SLOT_LEN
+}
+
+fn read_u64(bytes: &[u8], pos: usize) -> u64 {
+    u64::from_le_bytes(bytes[pos..pos + 8].try_into().unwrap())
+}
+
+fn write_u64(bytes: &mut [u8], pos: usize, val: u64) {
+    bytes[pos..pos + 8].copy_from_slice(&val.to_le_bytes())
+}
+
+// 64-bit FNV-1a hash, which is fixed across platforms
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(PRIME)
+    })
+}
+
+// memory mapping of a whole file
+#[derive(Debug)]
+struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// SAFETY: the mapping is only mutated through `&mut self`
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    fn new(file: &File, writable: bool) -> io::Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Ok(Self {
+                ptr: std::ptr::null_mut(),
+                len,
+            });
+        }
+        let prot = if writable {
+            libc::PROT_READ | libc::PROT_WRITE
+        } else {
+            libc::PROT_READ
+        };
+        // SAFETY: we map a file we own with its current length
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                prot,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: the mapping is valid for `len` bytes
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        if self.len == 0 {
+            return &mut [];
+        }
+        // SAFETY: the mapping is valid for `len` bytes and writable
+        // whenever we call this
+        unsafe { std::slice::from_raw_parts_mut(self.ptr as *mut u8, self.len) }
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        // SAFETY: the mapping is valid for `len` bytes
+        if unsafe { libc::msync(self.ptr, self.len, libc::MS_SYNC) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: the mapping was created by `mmap` with this length
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn tmp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("nauty_pet_{name}_{}", std::process::id()))
+    }
+
+    fn cleanup(path: &Path) {
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(index_path(path));
+    }
+
+    #[test]
+    fn many() {
+        log_init();
+
+        let path = tmp_path("store_many");
+        let certs = Vec::from_iter(
+            (0..5000u32)
+                .map(|i| Vec::from_iter((0..i % 37).map(|j| (i + j) as u8))),
+        );
+        let mut store = CertStore::create(&path).unwrap();
+        for cert in &certs {
+            store.insert(cert).unwrap();
+        }
+        let num_distinct = {
+            let mut certs = certs.clone();
+            certs.sort();
+            certs.dedup();
+            certs.len()
+        };
+        assert_eq!(store.len(), num_distinct);
+        store.flush().unwrap();
+        drop(store);
+
+        let mut store = CertStore::open(&path).unwrap();
+        assert_eq!(store.len(), num_distinct);
+        for cert in &certs {
+            assert!(store.contains(cert).unwrap());
+            assert!(!store.insert(cert).unwrap());
+        }
+        assert!(!store.contains(&[255; 40]).unwrap());
+        assert!(store.insert(&[255; 40]).unwrap());
+
+        let reader = CertStore::open_read_only(&path).unwrap();
+        assert_eq!(reader.len(), num_distinct + 1);
+        assert_eq!(reader.iter().count(), num_distinct + 1);
+        let last = reader.iter().last().unwrap().unwrap();
+        assert_eq!(last, [255; 40].as_slice());
+        cleanup(&path);
+    }
+
+    #[test]
+    fn read_only() {
+        log_init();
+
+        let path = tmp_path("store_read_only");
+        let mut writer = CertStore::create(&path).unwrap();
+        writer.insert(b"").unwrap();
+        let mut reader = CertStore::open_read_only(&path).unwrap();
+        assert!(reader.insert(b"a").is_err());
+        assert_eq!(reader.len(), 1);
+        assert!(reader.contains(b"").unwrap());
+        writer.insert(b"a").unwrap();
+        // not visible before refreshing
+        assert!(!reader.contains(b"a").unwrap());
+        assert_eq!(reader.len(), 1);
+        reader.refresh().unwrap();
+        assert!(reader.contains(b"a").unwrap());
+        cleanup(&path);
+    }
+
+    #[test]
+    fn grow_with_reader() {
+        log_init();
+
+        let path = tmp_path("store_grow_with_reader");
+        let mut writer = CertStore::create(&path).unwrap();
+        writer.insert(b"first").unwrap();
+        let mut reader = CertStore::open_read_only(&path).unwrap();
+        let certs = Vec::from_iter((0..5000u32).map(|i| i.to_le_bytes()));
+        for cert in &certs {
+            writer.insert(cert).unwrap();
+        }
+        // the reader keeps using the old index
+        assert_eq!(reader.len(), 1);
+        assert!(reader.contains(b"first").unwrap());
+        assert!(!reader.contains(&certs[0]).unwrap());
+        assert_eq!(reader.iter().count(), 1);
+        reader.refresh().unwrap();
+        assert_eq!(reader.len(), certs.len() + 1);
+        for cert in &certs {
+            assert!(reader.contains(cert).unwrap());
+        }
+        cleanup(&path);
+    }
+
+    #[test]
+    fn corrupt() {
+        log_init();
+
+        let path = tmp_path("store_corrupt");
+        let mut store = CertStore::create(&path).unwrap();
+        store.insert(b"certificate").unwrap();
+        store.flush().unwrap();
+        drop(store);
+        // record length beyond the end of the data
+        let data = File::options().write(true).open(&path).unwrap();
+        data.write_all_at(&u32::MAX.to_le_bytes(), 0).unwrap();
+        let store = CertStore::open_read_only(&path).unwrap();
+        let err = store.contains(b"certificate").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = store.iter().next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(store.iter().count(), 1);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn invalid() {
+        log_init();
+
+        let path = tmp_path("store_invalid");
+        std::fs::write(&path, b"").unwrap();
+        std::fs::write(index_path(&path), b"not an index").unwrap();
+        assert!(CertStore::open(&path).is_err());
+        cleanup(&path);
+    }
+}
@@ -0,0 +1,193 @@
+use std::cmp::Reverse;
+
+use petgraph::{
+    graph::{EdgeIndex, Graph, IndexType},
+    visit::{EdgeRef, NodeIndexable},
+    EdgeType,
+};
+
+/// Sorted neighbours of each vertex, ignoring edge direction,
+/// self-loops, and edge multiplicities
+pub(crate) fn undirected_neighbours<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+) -> Vec<Vec<usize>>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut adj = vec![vec![]; g.node_count()];
+    for e in g.edge_references() {
+        let source = g.to_index(e.source());
+        let target = g.to_index(e.target());
+        if source != target {
+            adj[source].push(target);
+            adj[target].push(source);
+        }
+    }
+    for adj in &mut adj {
+        adj.sort_unstable();
+        adj.dedup();
+    }
+    adj
+}
+
+/// Subgraph induced by the given vertices
+///
+/// The `i`th vertex of the subgraph corresponds to `vertices[i]`.
+/// All edges between the vertices are kept, including self-loops and
+/// multiple edges.
+pub(crate) fn induced_subgraph<N, E, Ty, Ix>(
+    g: &Graph<N, E, Ty, Ix>,
+    vertices: &[usize],
+) -> Graph<N, E, Ty, Ix>
+where
+    N: Clone,
+    E: Clone,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let mut edges = Vec::new();
+    for &v in vertices {
+        edges.extend(g.edges(g.from_index(v)).map(|e| e.id()));
+    }
+    edges.sort_unstable();
+    edges.dedup();
+
+    let mut res = Graph::with_capacity(vertices.len(), edges.len());
+    for &v in vertices {
+        res.add_node(g[g.from_index(v)].clone());
+    }
+    let mut positions =
+        Vec::from_iter(vertices.iter().enumerate().map(|(i, &v)| (v, i)));
+    positions.sort_unstable();
+    let pos = |v| {
+        let v = g.to_index(v);
+        positions
+            .binary_search_by_key(&v, |&(w, _)| w)
+            .ok()
+            .map(|i| positions[i].1)
+    };
+    for e in edges {
+        let (source, target) = g.edge_endpoints(e).unwrap();
+        if let (Some(source), Some(target)) = (pos(source), pos(target)) {
+            res.add_edge(
+                res.from_index(source),
+                res.from_index(target),
+                g[e].clone(),
+            );
+        }
+    }
+    res
+}
+
+/// Number of injective maps from the vertices of `pattern` to the
+/// vertices of `host` that preserve node weights and edges
+///
+/// For each edge from `a` to `b` in `pattern` there has to be an edge
+/// with the same weight from the image of `a` to the image of `b` in
+/// `host`. Further edges in `host` are allowed.
+pub(crate) fn count_embeddings<N, E, Ty, Ix>(
+    pattern: &Graph<N, E, Ty, Ix>,
+    host: &Graph<N, E, Ty, Ix>,
+) -> usize
+where
+    N: PartialEq,
+    E: PartialEq,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    let matcher = Matcher::new(pattern, host);
+    let mut image = vec![usize::MAX; pattern.node_count()];
+    let mut used = vec![false; host.node_count()];
+    matcher.count_extensions(0, &mut image, &mut used)
+}
+
+struct Matcher<'a, N, E, Ty: EdgeType, Ix: IndexType> {
+    pattern: &'a Graph<N, E, Ty, Ix>,
+    host: &'a Graph<N, E, Ty, Ix>,
+    // order in which pattern vertices are mapped
+    order: Vec<usize>,
+    // pattern edges to check after mapping the vertex at each position
+    checks: Vec<Vec<EdgeIndex<Ix>>>,
+}
+
+impl<'a, N, E, Ty, Ix> Matcher<'a, N, E, Ty, Ix>
+where
+    N: PartialEq,
+    E: PartialEq,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    fn new(
+        pattern: &'a Graph<N, E, Ty, Ix>,
+        host: &'a Graph<N, E, Ty, Ix>,
+    ) -> Self {
+        // map vertices with many mapped neighbours first to detect
+        // mismatches early
+        let n = pattern.node_count();
+        let adj = undirected_neighbours(pattern);
+        let mut pos = vec![usize::MAX; n];
+        let mut order = Vec::with_capacity(n);
+        while order.len() < n {
+            let next = (0..n)
+                .filter(|&v| pos[v] == usize::MAX)
+                .max_by_key(|&v| {
+                    let mapped =
+                        adj[v].iter().filter(|&&w| pos[w] != usize::MAX);
+                    (mapped.count(), Reverse(v))
+                })
+                .unwrap();
+            pos[next] = order.len();
+            order.push(next);
+        }
+        let mut checks = vec![Vec::new(); n];
+        for e in pattern.edge_references() {
+            let source = pos[pattern.to_index(e.source())];
+            let target = pos[pattern.to_index(e.target())];
+            checks[source.max(target)].push(e.id());
+        }
+        Self {
+            pattern,
+            host,
+            order,
+            checks,
+        }
+    }
+
+    fn count_extensions(
+        &self,
+        depth: usize,
+        image: &mut [usize],
+        used: &mut [bool],
+    ) -> usize {
+        let Some(&v) = self.order.get(depth) else {
+            return 1;
+        };
+        let mut count = 0;
+        for w in self.host.node_indices() {
+            if used[w.index()]
+                || self.host[w] != self.pattern[self.pattern.from_index(v)]
+            {
+                continue;
+            }
+            image[v] = w.index();
+            if self.checks[depth].iter().all(|&e| self.has_image(e, image)) {
+                used[w.index()] = true;
+                count += self.count_extensions(depth + 1, image, used);
+                used[w.index()] = false;
+            }
+        }
+        image[v] = usize::MAX;
+        count
+    }
+
+    // check whether the image of a pattern edge is in the host
+    fn has_image(&self, e: EdgeIndex<Ix>, image: &[usize]) -> bool {
+        let (source, target) = self.pattern.edge_endpoints(e).unwrap();
+        let source = self.host.from_index(image[self.pattern.to_index(source)]);
+        let target = self.host.from_index(image[self.pattern.to_index(target)]);
+        self.host
+            .edges_connecting(source, target)
+            .any(|h| h.weight() == &self.pattern[e])
+    }
+}
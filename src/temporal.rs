@@ -0,0 +1,170 @@
+//! Canonical forms of temporal graphs
+//!
+//! In a temporal graph, each edge is active during a time interval,
+//! or at a single point in time. The times are quantised and the
+//! distinct quantised times are numbered in increasing order, so that
+//! each edge is assigned a layer interval. The canonical form only
+//! depends on the order of the quantised times, not on their values,
+//! which is what is needed to identify temporal motifs.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::DiGraph;
+//! use nauty_pet::temporal::temporal_canon;
+//!
+//! // two messages relayed along a path, with time stamps in seconds
+//! let g = DiGraph::<(), u64>::from_edges([(0, 1, 10), (1, 2, 70)]);
+//! let h = DiGraph::<(), u64>::from_edges([(2, 0, 250), (1, 2, 130)]);
+//!
+//! // quantise to minutes
+//! let minute = |t: &u64| (t / 60, t / 60);
+//! let g = temporal_canon(&g, minute);
+//! let h = temporal_canon(&h, minute);
+//! assert_eq!(g.graph, h.graph);
+//! assert_eq!(g.times, [0, 1]);
+//! assert_eq!(h.times, [2, 4]);
+//! ```
+use std::hash::Hash;
+
+use crate::cancel;
+use crate::canon::try_canon_with_labelling;
+use crate::graph::CanonGraph;
+
+use petgraph::{
+    graph::{Graph, IndexType},
+    EdgeType,
+};
+
+/// Canonical form of a temporal graph
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TemporalCanon<N, T, Ty: EdgeType, Ix: IndexType> {
+    /// Canonical graph
+    ///
+    /// Each edge weight is the pair of the first and the last layer
+    /// during which the edge is active.
+    pub graph: CanonGraph<N, (usize, usize), Ty, Ix>,
+    /// The quantised time corresponding to each layer, in increasing
+    /// order
+    pub times: Vec<T>,
+    /// Canonical labelling
+    ///
+    /// The `i`th vertex of the original graph corresponds to the
+    /// vertex with index `labelling[i]` in `graph`.
+    pub labelling: Vec<usize>,
+}
+
+/// Canonical form of a temporal graph
+///
+/// `time` maps each edge weight to the first and last quantised time
+/// during which the edge is active. For time stamps, both are the
+/// same. Two temporal graphs have the same canonical
+/// [graph](TemporalCanon::graph) if and only if they are isomorphic
+/// with an isomorphism that preserves the order of all quantised
+/// times. If the actual times also have to agree, additionally
+/// compare the [times](TemporalCanon::times).
+///
+/// # Panics
+///
+/// Panics if the first time for some edge is later than the last
+/// time.
+pub fn temporal_canon<N, E, Ty, Ix, T, F>(
+    g: &Graph<N, E, Ty, Ix>,
+    time: F,
+) -> TemporalCanon<N, T, Ty, Ix>
+where
+    N: Clone + Ord,
+    Ty: EdgeType,
+    Ix: IndexType,
+    T: Clone + Ord,
+    F: Fn(&E) -> (T, T),
+{
+    let spans = Vec::from_iter(g.edge_weights().map(time));
+    let mut times = Vec::with_capacity(2 * spans.len());
+    for (start, end) in &spans {
+        assert!(start <= end, "Edge interval ends before it starts");
+        times.extend([start, end]);
+    }
+    times.sort_unstable();
+    times.dedup();
+    let layer = |t: &&T| times.binary_search(t).unwrap();
+    let layered = g.map(
+        |_, w| w.clone(),
+        |e, _| {
+            let (start, end) = &spans[e.index()];
+            (layer(&start), layer(&end))
+        },
+    );
+    let (canon, labelling) = cancel::unwrap(try_canon_with_labelling(layered));
+    TemporalCanon {
+        graph: CanonGraph::from_canon_unchecked(canon),
+        times: Vec::from_iter(times.into_iter().cloned()),
+        labelling,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::{DiGraph, NodeIndex, UnGraph};
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn time_order() {
+        log_init();
+
+        // a path traversed forward in time and one traversed backward
+        let forward = UnGraph::<(), u32>::from_edges([(0, 1, 1), (1, 2, 2)]);
+        let backward = UnGraph::<(), u32>::from_edges([(0, 1, 2), (1, 2, 1)]);
+        let stamp = |t: &u32| (*t, *t);
+        let forward = temporal_canon(&forward, stamp);
+        let backward = temporal_canon(&backward, stamp);
+        // undirected paths can be reversed
+        assert_eq!(forward.graph, backward.graph);
+
+        // the same for directed paths, where edges cannot be reversed
+        let forward = DiGraph::<(), u32>::from_edges([(0, 1, 1), (1, 2, 2)]);
+        let backward = DiGraph::<(), u32>::from_edges([(0, 1, 2), (1, 2, 1)]);
+        let forward = temporal_canon(&forward, stamp);
+        let backward = temporal_canon(&backward, stamp);
+        assert_ne!(forward.graph, backward.graph);
+
+        // quantisation can merge layers
+        let coarse = DiGraph::<(), u32>::from_edges([(0, 1, 1), (1, 2, 2)]);
+        let coarse = temporal_canon(&coarse, |t| (t / 4, t / 4));
+        assert_eq!(coarse.times, [0]);
+        assert_ne!(coarse.graph, forward.graph);
+    }
+
+    #[test]
+    fn intervals() {
+        log_init();
+
+        let g = DiGraph::<u8, (u32, u32)>::from_edges([
+            (0, 1, (0, 5)),
+            (1, 2, (3, 4)),
+            (1, 2, (7, 9)),
+        ]);
+        let canon = temporal_canon(&g, |&span| span);
+        assert_eq!(canon.times, [0, 3, 4, 5, 7, 9]);
+        assert_eq!(canon.graph.edge_count(), 3);
+        for e in g.edge_indices() {
+            let (v, w) = g.edge_endpoints(e).unwrap();
+            let v = NodeIndex::new(canon.labelling[v.index()]);
+            let w = NodeIndex::new(canon.labelling[w.index()]);
+            let (start, end) = g[e];
+            let layers = (
+                canon.times.binary_search(&start).unwrap(),
+                canon.times.binary_search(&end).unwrap(),
+            );
+            assert!(canon
+                .graph
+                .edges_connecting(v, w)
+                .any(|e| *e.weight() == layers));
+        }
+    }
+}
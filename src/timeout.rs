@@ -0,0 +1,236 @@
+//! Time limits for computations
+//!
+//! A few graphs take nauty and Traces a very long time. With the
+//! functions in this module, the calls to nauty and Traces are
+//! stopped once a time limit is exceeded, and the error
+//! [NautyError::Timeout] is returned instead of the result.
+//!
+//! [with_timeout] is the single entry point to limit the time of
+//! any computation, and works for all functions of this crate, e.g. the
+//! methods of [NautyOptions](crate::options::NautyOptions). Only the
+//! most common computations have shorthands, the extension traits
+//! [TryIntoCanonWithTimeout] and [TryIntoAutomWithTimeout].
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::prelude::*;
+//! use nauty_pet::options::NautyOptions;
+//! use nauty_pet::timeout::{with_timeout, TryIntoAutomWithTimeout};
+//!
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+//! let limit = Duration::from_secs(10);
+//! let autom = (&g).try_into_autom_with_timeout(limit).unwrap();
+//! assert_eq!(autom.grpsize(), 2.);
+//!
+//! let options = NautyOptions::default();
+//! let res = with_timeout(limit, || options.canon_sparse(g.clone()));
+//! // the outer error is a timeout, the inner one comes from nauty
+//! let canon = res.unwrap().unwrap();
+//! assert!(canon.is_identical(&g.into_canon()));
+//! ```
+use std::collections::BTreeMap;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::autom::{Autom, TryIntoAutom};
+use crate::cancel::CancellationToken;
+use crate::canon::TryIntoCanon;
+use crate::error::NautyError;
+
+// The deadlines of all running computations, shared by a single
+// timer thread. The id tells apart computations with equal deadlines.
+struct Timers {
+    deadlines: BTreeMap<(Instant, u64), CancellationToken>,
+    next_id: u64,
+    started: bool,
+}
+
+static TIMERS: Mutex<Timers> = Mutex::new(Timers {
+    deadlines: BTreeMap::new(),
+    next_id: 0,
+    started: false,
+});
+
+// notified whenever a new deadline is added
+static ADDED: Condvar = Condvar::new();
+
+fn timers() -> MutexGuard<'static, Timers> {
+    TIMERS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+// cancel the computations that have run out of time
+fn run_timers() {
+    let mut timers = timers();
+    loop {
+        let now = Instant::now();
+        while let Some(entry) = timers.deadlines.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            entry.remove().cancel();
+        }
+        timers = match timers.deadlines.keys().next() {
+            Some(&(deadline, _)) => {
+                let wait = ADDED.wait_timeout(timers, deadline - now);
+                wait.unwrap_or_else(|err| err.into_inner()).0
+            }
+            None => ADDED.wait(timers).unwrap_or_else(|err| err.into_inner()),
+        };
+    }
+}
+
+/// Run `f`, stopping the calls to nauty and Traces it makes in the
+/// current thread once `timeout` has passed
+///
+/// This is the single entry point for limiting the time of any
+/// computation in this crate; there are no separate
+/// `*_with_timeout` variants apart from the shorthands
+/// [TryIntoCanonWithTimeout] and [TryIntoAutomWithTimeout].
+///
+/// Returns [NautyError::Timeout] if `f` did not finish in time. As
+/// with [CancellationToken::run], functions that cannot return an
+/// error unwind to the end of `f` when they are stopped.
+///
+/// All time limits are watched by one shared background thread, so
+/// no thread is started per call.
+pub fn with_timeout<T>(
+    timeout: Duration,
+    f: impl FnOnce() -> T,
+) -> Result<T, NautyError> {
+    let token = CancellationToken::new();
+    // a deadline that cannot be represented is never reached
+    let key = Instant::now().checked_add(timeout).map(|deadline| {
+        let mut timers = timers();
+        if !timers.started {
+            thread::spawn(run_timers);
+            timers.started = true;
+        }
+        let key = (deadline, timers.next_id);
+        timers.next_id += 1;
+        timers.deadlines.insert(key, token.clone());
+        ADDED.notify_one();
+        key
+    });
+    let res = token.run(f);
+    if let Some(key) = key {
+        timers().deadlines.remove(&key);
+    }
+    res.map_err(|err| match err {
+        NautyError::Cancelled => NautyError::Timeout,
+        err => err,
+    })
+}
+
+/// Try to find the canonical labelling for a graph within a time
+/// limit
+pub trait TryIntoCanonWithTimeout {
+    fn try_into_canon_with_timeout(
+        self,
+        timeout: Duration,
+    ) -> Result<Self, NautyError>
+    where
+        Self: Sized;
+}
+
+impl<G> TryIntoCanonWithTimeout for G
+where
+    G: TryIntoCanon,
+    G::Error: Into<NautyError>,
+{
+    fn try_into_canon_with_timeout(
+        self,
+        timeout: Duration,
+    ) -> Result<Self, NautyError> {
+        with_timeout(timeout, || self.try_into_canon())?.map_err(Into::into)
+    }
+}
+
+/// Try to analyse a graph's automorphism group within a time limit
+pub trait TryIntoAutomWithTimeout {
+    fn try_into_autom_with_timeout(
+        self,
+        timeout: Duration,
+    ) -> Result<Autom, NautyError>;
+}
+
+impl<G> TryIntoAutomWithTimeout for G
+where
+    G: TryIntoAutom,
+    G::Error: Into<NautyError>,
+{
+    fn try_into_autom_with_timeout(
+        self,
+        timeout: Duration,
+    ) -> Result<Autom, NautyError> {
+        with_timeout(timeout, || self.try_into_autom())?.map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canon::IntoCanon;
+    use crate::cmp::IsIdentical;
+    use petgraph::{graph::UnGraph, Undirected};
+    use rand::prelude::*;
+    use std::time::Instant;
+    use testing::{randomize_labels, GraphIter};
+
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn in_time() {
+        log_init();
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let limit = Duration::from_secs(100);
+        let graphs = GraphIter::<Undirected>::default();
+        for g in graphs.take(200) {
+            let canon = g.clone().try_into_canon().unwrap();
+            let autom = (&g).try_into_autom().unwrap();
+            let g = randomize_labels(g, &mut rng);
+            let autom_timeout = (&g).try_into_autom_with_timeout(limit);
+            assert_eq!(autom_timeout.unwrap().grpsize(), autom.grpsize());
+            let g = g.try_into_canon_with_timeout(limit).unwrap();
+            assert!(g.is_identical(&canon));
+        }
+    }
+
+    #[test]
+    fn timeout() {
+        log_init();
+        let _lock = crate::kill::test_lock();
+
+        let g =
+            UnGraph::<(), ()>::from_edges((0..200).map(|i| (i, (i + 1) % 200)));
+        let limit = Duration::from_millis(1);
+        let start = Instant::now();
+        let res = with_timeout(limit, || {
+            for _ in 0..100_000 {
+                let _ = (&g).try_into_autom();
+            }
+        });
+        assert!(matches!(res, Err(NautyError::Timeout)));
+        assert!(start.elapsed() < Duration::from_secs(60));
+
+        // functions without errors are stopped as well
+        let res = with_timeout(limit, || {
+            for _ in 0..100_000 {
+                let _ = g.clone().into_canon();
+            }
+        });
+        assert!(matches!(res, Err(NautyError::Timeout)));
+
+        // later computations are unaffected
+        let autom = (&g).try_into_autom_with_timeout(Duration::from_secs(100));
+        assert_eq!(autom.unwrap().grpsize(), 400.);
+    }
+}
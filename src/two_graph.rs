@@ -0,0 +1,183 @@
+//! Seidel switching and two-graphs
+//!
+//! Switching a graph with respect to a set of vertices `S` exchanges
+//! edges and non-edges between `S` and its complement. The switching
+//! classes of graphs on a given vertex set are in one-to-one
+//! correspondence with two-graphs, and play an important role in the
+//! study of equiangular lines.
+//!
+//! A graph corresponds to a signed complete graph, where edges are
+//! negative and non-edges positive. Seidel switching is then the same
+//! as switching the signed graph, so the canonical forms are computed
+//! with [switching_canon].
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::two_graph::is_switching_equivalent;
+//!
+//! // the four-cycle, switched at one vertex, becomes a star
+//! let square = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 0)]);
+//! let star = UnGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3)]);
+//! assert!(is_switching_equivalent(&square, &star));
+//!
+//! // but not a path
+//! let path = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+//! assert!(!is_switching_equivalent(&square, &path));
+//! ```
+use crate::graph::CanonGraph;
+use crate::signed::{switching_canon, Sign, SignedGraph};
+
+use petgraph::{
+    graph::{IndexType, NodeIndex, UnGraph},
+    Undirected,
+};
+
+/// Switch a graph with respect to a set of vertices
+///
+/// Pairs of vertices with exactly one vertex in `vertices` become
+/// adjacent if they were not adjacent before and vice versa. New edges
+/// have the default weight, self-loops and the weights of existing
+/// edges are discarded.
+pub fn seidel_switch<N, E, Ix>(
+    g: &UnGraph<N, E, Ix>,
+    vertices: &[NodeIndex<Ix>],
+) -> UnGraph<N, E, Ix>
+where
+    N: Clone,
+    E: Default,
+    Ix: IndexType,
+{
+    let mut switched = vec![false; g.node_count()];
+    for v in vertices {
+        switched[v.index()] = true;
+    }
+    let signed = to_signed(g);
+    let mut res = UnGraph::with_capacity(g.node_count(), 0);
+    for w in g.node_weights() {
+        res.add_node(w.clone());
+    }
+    for e in signed.edge_indices() {
+        let (v, w) = signed.edge_endpoints(e).unwrap();
+        let flip = switched[v.index()] != switched[w.index()];
+        if (signed[e] == Sign::Negative) != flip {
+            res.add_edge(v, w, E::default());
+        }
+    }
+    res
+}
+
+/// Canonical representative of the switching class of a graph
+///
+/// Two graphs have the same canonical representative if and only if
+/// one is isomorphic to a graph obtained from the other by Seidel
+/// switching. The edges of the representative have the default
+/// weight, and self-loops are ignored.
+pub fn switching_class_canon<N, E, Ix>(
+    g: &UnGraph<N, E, Ix>,
+) -> CanonGraph<N, E, Undirected, Ix>
+where
+    N: Clone + Ord,
+    E: Default + std::hash::Hash + Ord,
+    Ix: IndexType,
+{
+    let canon = switching_canon(&to_signed(g));
+    let mut res = UnGraph::with_capacity(canon.node_count(), 0);
+    for w in canon.node_weights() {
+        res.add_node(w.clone());
+    }
+    for e in canon.edge_indices() {
+        if canon[e] == Sign::Negative {
+            let (v, w) = canon.edge_endpoints(e).unwrap();
+            res.add_edge(v, w, E::default());
+        }
+    }
+    CanonGraph::from(res)
+}
+
+/// Check whether two graphs are isomorphic up to Seidel switching
+pub fn is_switching_equivalent<N, E, Ix>(
+    g: &UnGraph<N, E, Ix>,
+    h: &UnGraph<N, E, Ix>,
+) -> bool
+where
+    N: Clone + Ord,
+    Ix: IndexType,
+{
+    g.node_count() == h.node_count()
+        && switching_canon(&to_signed(g)) == switching_canon(&to_signed(h))
+}
+
+// the complete graph with negative edges between adjacent vertices
+fn to_signed<N, E, Ix>(g: &UnGraph<N, E, Ix>) -> SignedGraph<N, Ix>
+where
+    N: Clone,
+    Ix: IndexType,
+{
+    let n = g.node_count();
+    let mut adjacent = vec![false; n * n];
+    for e in g.edge_indices() {
+        let (v, w) = g.edge_endpoints(e).unwrap();
+        adjacent[v.index() * n + w.index()] = true;
+        adjacent[w.index() * n + v.index()] = true;
+    }
+    let mut signed = SignedGraph::with_capacity(n, n * n.saturating_sub(1) / 2);
+    for w in g.node_weights() {
+        signed.add_node(w.clone());
+    }
+    for v in 0..n {
+        for w in (v + 1)..n {
+            let sign = if adjacent[v * n + w] {
+                Sign::Negative
+            } else {
+                Sign::Positive
+            };
+            signed.add_edge(NodeIndex::new(v), NodeIndex::new(w), sign);
+        }
+    }
+    signed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::GraphGenerator;
+
+    use std::collections::BTreeSet;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn switching() {
+        log_init();
+
+        let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3)]);
+        let switched =
+            seidel_switch(&g, &[NodeIndex::new(0), NodeIndex::new(3)]);
+        assert_eq!(switched.edge_count(), 3);
+        assert!(is_switching_equivalent(&g, &switched));
+        assert_eq!(switching_class_canon(&g), switching_class_canon(&switched));
+        let back =
+            seidel_switch(&switched, &[NodeIndex::new(1), NodeIndex::new(2)]);
+        assert_eq!(CanonGraph::from(back), CanonGraph::from(g.clone()));
+    }
+
+    #[test]
+    fn count() {
+        log_init();
+
+        // number of switching classes (two-graphs) on n vertices,
+        // OEIS A002854
+        for (n, expected) in [1, 1, 2, 3, 7, 16, 54].into_iter().enumerate() {
+            let classes = BTreeSet::from_iter(
+                GraphGenerator::new(n + 1)
+                    .into_iter()
+                    .map(|g| switching_class_canon(&g)),
+            );
+            assert_eq!(classes.len(), expected, "n = {}", n + 1);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Diagnostic output of nauty and Traces
+//!
+//! nauty can report the automorphism group generators and the level
+//! markers of its search tree, and Traces has several verbosity
+//! levels. By default, this output is disabled. When enabled with
+//! [set_verbosity], the output is captured and forwarded line by line
+//! to the [log](https://crates.io/crates/log) crate instead of being
+//! printed to standard output. Applications using
+//! [tracing](https://crates.io/crates/tracing) can receive the
+//! messages via `tracing-log`. Alternatively, the output can be
+//! passed on unchanged to any [Write] implementation with
+//! [set_writer].
+//!
+//! Requires the `log` feature and a Unix-like system.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::prelude::*;
+//! use nauty_pet::verbosity::{set_verbosity, Verbosity};
+//!
+//! set_verbosity(Verbosity {
+//!     nauty_automorphisms: true,
+//!     level: log::Level::Info,
+//!     ..Default::default()
+//! });
+//! // the automorphisms found by nauty are logged
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+//! let autom = g.try_into_autom().unwrap();
+//! assert_eq!(autom.grpsize(), 2.);
+//! set_verbosity(Verbosity::default());
+//! ```
+use std::io::Write;
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::{Mutex, RwLock};
+
+use log::{log, warn, Level};
+#[cfg(feature = "traces")]
+use nauty_Traces_sys::TracesOptions;
+use nauty_Traces_sys::{optionblk, FALSE, TRUE};
+
+/// Which diagnostic output to produce
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Verbosity {
+    /// Report the automorphism group generators found by nauty
+    pub nauty_automorphisms: bool,
+    /// Report the level markers of nauty's search tree
+    pub nauty_markers: bool,
+    /// Verbosity level of Traces, where zero means no output
+    pub traces: u8,
+    /// Log level of the forwarded messages
+    pub level: Level,
+}
+
+impl Verbosity {
+    const QUIET: Self = Self {
+        nauty_automorphisms: false,
+        nauty_markers: false,
+        traces: 0,
+        level: Level::Debug,
+    };
+}
+
+impl Default for Verbosity {
+    fn default() -> Self {
+        Self::QUIET
+    }
+}
+
+static VERBOSITY: RwLock<Verbosity> = RwLock::new(Verbosity::QUIET);
+
+/// Set the diagnostic output for all subsequent calls to nauty and
+/// Traces in any thread
+pub fn set_verbosity(verbosity: Verbosity) {
+    *VERBOSITY.write().unwrap() = verbosity;
+}
+
+/// The current diagnostic output settings
+pub fn verbosity() -> Verbosity {
+    *VERBOSITY.read().unwrap()
+}
+
+type Writer = Box<dyn Write + Send>;
+
+static WRITER: Mutex<Option<Writer>> = Mutex::new(None);
+
+/// Write the diagnostic output to `writer` instead of the logger
+///
+/// nauty and Traces insist on writing to a C stream. Their output
+/// is collected in memory and written to `writer` after each call.
+/// Returns the previous writer, if any.
+pub fn set_writer(writer: Writer) -> Option<Writer> {
+    WRITER.lock().unwrap().replace(writer)
+}
+
+/// Forward the diagnostic output to the logger again
+///
+/// Returns the writer set with [set_writer], if any.
+pub fn take_writer() -> Option<Writer> {
+    WRITER.lock().unwrap().take()
+}
+
+/// Captured output of a single call to nauty or Traces
+pub(crate) struct Output(Option<(MemStream, Level)>);
+
+impl Output {
+    /// Pass the captured output on to the writer or the logger
+    pub(crate) fn finish(self) {
+        let Some((stream, level)) = self.0 else {
+            return;
+        };
+        let output = stream.into_bytes();
+        if let Some(writer) = WRITER.lock().unwrap().as_mut() {
+            if let Err(err) = writer.write_all(&output) {
+                warn!("Failed to write nauty output: {err}");
+            }
+            return;
+        }
+        for line in String::from_utf8_lossy(&output).lines() {
+            log!(level, "{line}");
+        }
+    }
+}
+
+/// Redirect the output of nauty according to the current settings
+pub(crate) fn capture_nauty(options: &mut optionblk) -> Output {
+    let verbosity = verbosity();
+    if !verbosity.nauty_automorphisms && !verbosity.nauty_markers {
+        return Output(None);
+    }
+    let Some(stream) = MemStream::open() else {
+        return Output(None);
+    };
+    options.writeautoms = to_boolean(verbosity.nauty_automorphisms);
+    options.writemarkers = to_boolean(verbosity.nauty_markers);
+    options.outfile = stream.file.cast();
+    Output(Some((stream, verbosity.level)))
+}
+
+/// Redirect the output of Traces according to the current settings
+#[cfg(feature = "traces")]
+pub(crate) fn capture_traces(options: &mut TracesOptions) -> Output {
+    let verbosity = verbosity();
+    if verbosity.traces == 0 {
+        return Output(None);
+    }
+    let Some(stream) = MemStream::open() else {
+        return Output(None);
+    };
+    options.verbosity = verbosity.traces.into();
+    options.outfile = stream.file.cast();
+    Output(Some((stream, verbosity.level)))
+}
+
+fn to_boolean(b: bool) -> nauty_Traces_sys::boolean {
+    if b {
+        TRUE
+    } else {
+        FALSE
+    }
+}
+
+// C stream writing into a growing memory buffer
+struct MemStream {
+    file: *mut libc::FILE,
+    // buffer and size, updated by the C library until the stream is
+    // closed, so they must not move
+    buf: Box<(*mut c_char, libc::size_t)>,
+}
+
+impl MemStream {
+    fn open() -> Option<Self> {
+        let mut buf = Box::new((ptr::null_mut(), 0));
+        let file = unsafe { libc::open_memstream(&mut buf.0, &mut buf.1) };
+        if file.is_null() {
+            None
+        } else {
+            Some(Self { file, buf })
+        }
+    }
+
+    fn close(&mut self) {
+        if !self.file.is_null() {
+            unsafe {
+                libc::fclose(self.file);
+            }
+            self.file = ptr::null_mut();
+        }
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.close();
+        let (data, len) = *self.buf;
+        if data.is_null() {
+            return Vec::new();
+        }
+        unsafe { std::slice::from_raw_parts(data.cast(), len) }.to_vec()
+    }
+}
+
+impl Drop for MemStream {
+    fn drop(&mut self) {
+        self.close();
+        unsafe { libc::free(self.buf.0.cast()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mem_stream() {
+        let stream = MemStream::open().unwrap();
+        let text = b"first line\nsecond line\n\0";
+        unsafe {
+            libc::fputs(text.as_ptr().cast(), stream.file);
+        }
+        assert_eq!(stream.into_bytes(), b"first line\nsecond line\n");
+    }
+
+    // serialises the tests changing the global settings
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    fn lock() -> std::sync::MutexGuard<'static, ()> {
+        LOCK.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(std::sync::Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writer() {
+        let _lock = lock();
+        let buf = SharedBuf::default();
+        assert!(set_writer(Box::new(buf.clone())).is_none());
+        let stream = MemStream::open().unwrap();
+        unsafe {
+            libc::fputs(b"automorphism\n\0".as_ptr().cast(), stream.file);
+        }
+        Output(Some((stream, Level::Info))).finish();
+        assert!(take_writer().is_some());
+        assert_eq!(&*buf.0.lock().unwrap(), b"automorphism\n");
+    }
+
+    // restores the default verbosity when dropped, even if a test fails
+    struct ResetVerbosity;
+
+    impl Drop for ResetVerbosity {
+        fn drop(&mut self) {
+            set_verbosity(Verbosity::default());
+        }
+    }
+
+    #[test]
+    fn capture() {
+        let _lock = lock();
+        let _reset = ResetVerbosity;
+        let mut options = optionblk::default();
+        set_verbosity(Verbosity {
+            nauty_markers: true,
+            ..Default::default()
+        });
+        let output = capture_nauty(&mut options);
+        assert_eq!(options.writemarkers, TRUE);
+        assert_eq!(options.writeautoms, FALSE);
+        assert!(!options.outfile.is_null());
+        output.finish();
+
+        #[cfg(feature = "traces")]
+        {
+            let mut options = TracesOptions::default();
+            set_verbosity(Verbosity::default());
+            let output = capture_traces(&mut options);
+            assert_eq!(options.verbosity, 0);
+            assert!(options.outfile.is_null());
+            output.finish();
+        }
+    }
+}
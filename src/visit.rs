@@ -0,0 +1,120 @@
+//! Canonical forms and automorphisms of arbitrary graph types
+//!
+//! The functions in this module accept any graph implementing the
+//! [petgraph visit traits](petgraph::visit) with compact node
+//! indices, including user-defined graph types. The node and edge
+//! weights are cloned. For the graph types of petgraph, the trait
+//! implementations in the rest of this crate avoid cloning and
+//! should be preferred.
+//!
+//! For undirected graphs, each edge has to be reported exactly once
+//! by `edge_references`. This is the case for [Graph] and
+//! [GraphMap](petgraph::graphmap::GraphMap), but not for
+//! [Csr](petgraph::csr::Csr), which reports undirected edges once
+//! in each direction.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::prelude::*;
+//! use nauty_pet::visit::{try_autom, try_canon};
+//!
+//! let g = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+//! assert_eq!(try_autom(&g).unwrap().grpsize(), 2.);
+//! assert_eq!(try_canon(&g).unwrap(), CanonGraph::from(g));
+//! ```
+use std::hash::Hash;
+
+use crate::autom::{dense_autom, Autom};
+use crate::canon::dense_graph_canon;
+use crate::error::NautyError;
+use crate::graph::CanonGraph;
+use crate::nauty_graph::DenseGraph;
+
+use petgraph::{
+    graph::Graph,
+    visit::{
+        Data, GraphProp, IntoEdgeReferences, IntoNodeReferences,
+        NodeCompactIndexable,
+    },
+    EdgeType,
+};
+
+type Canon<G> = CanonGraph<
+    <G as Data>::NodeWeight,
+    <G as Data>::EdgeWeight,
+    <G as GraphProp>::EdgeType,
+>;
+
+/// Analyse the automorphism group of `g` with dense nauty
+///
+/// The result is the same as for
+/// [TryIntoAutom](crate::autom::TryIntoAutom) applied to the
+/// corresponding [Graph].
+pub fn try_autom<G>(g: G) -> Result<Autom, NautyError>
+where
+    G: IntoNodeReferences
+        + IntoEdgeReferences
+        + NodeCompactIndexable
+        + GraphProp,
+    G::NodeWeight: Clone + Ord,
+    G::EdgeWeight: Clone + Hash + Ord,
+{
+    let is_directed = G::EdgeType::is_directed();
+    dense_autom(DenseGraph::from_visit(g), is_directed)
+}
+
+/// The canonical form of `g`
+///
+/// The result is the same as for [CanonGraph::from] applied to the
+/// corresponding [Graph].
+pub fn try_canon<G>(g: G) -> Result<Canon<G>, NautyError>
+where
+    G: IntoNodeReferences
+        + IntoEdgeReferences
+        + NodeCompactIndexable
+        + GraphProp,
+    G::NodeWeight: Clone + Ord,
+    G::EdgeWeight: Clone + Hash + Ord,
+{
+    let (dg, _) = dense_graph_canon(DenseGraph::from_visit(g))?;
+    let canon: Graph<_, _, _> = dg.into();
+    Ok(CanonGraph::from_canon_unchecked(canon))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autom::TryIntoAutom;
+    use petgraph::graphmap::UnGraphMap;
+    use petgraph::{Directed, Undirected};
+    use testing::GraphIter;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn graph() {
+        log_init();
+
+        for g in GraphIter::<Directed>::default().take(100) {
+            let autom = try_autom(&g).unwrap();
+            assert_eq!(autom, g.clone().try_into_autom().unwrap());
+            assert_eq!(try_canon(&g).unwrap(), CanonGraph::from(g));
+        }
+        for g in GraphIter::<Undirected>::default().take(100) {
+            assert_eq!(try_canon(&g).unwrap(), CanonGraph::from(g));
+        }
+    }
+
+    #[test]
+    fn graph_map() {
+        log_init();
+
+        let g = UnGraphMap::<u8, ()>::from_edges([(3, 1), (1, 2)]);
+        assert_eq!(try_autom(&g).unwrap().grpsize(), 1.);
+        assert_eq!(try_canon(&g).unwrap(), CanonGraph::from(g));
+    }
+}
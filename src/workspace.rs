@@ -0,0 +1,135 @@
+//! Reusable buffers for many runs of nauty
+//!
+//! Each canonical form or automorphism group computed through the
+//! traits of this crate allocates fresh buffers for nauty. When
+//! processing many small graphs, these allocations can dominate the
+//! run time. A [NautyWorkspace] keeps the buffers for the adjacency
+//! matrix, the canonical adjacency matrix, and the orbits around
+//! between calls, so that they only grow to the size required by
+//! the largest graph.
+//!
+//! Only these three buffers of dense nauty are reused. Converting a
+//! graph into nauty's format still allocates the initial labelling
+//! and partition (`lab` and `ptn`) as well as the relabelling of the
+//! vertices, and [NautyWorkspace::canon] allocates the canonically
+//! labelled result. Sparse nauty and Traces are not supported.
+//!
+//! # Example
+//!
+//! ```rust
+//! use petgraph::graph::UnGraph;
+//! use nauty_pet::prelude::*;
+//! use nauty_pet::workspace::NautyWorkspace;
+//!
+//! let mut ws = NautyWorkspace::new();
+//! for n in 1..10 {
+//!     let path = UnGraph::<(), ()>::from_edges((1..n).map(|i| (i - 1, i)));
+//!     let canon = ws.canon(&path).unwrap();
+//!     assert_eq!(canon, CanonGraph::from(path.clone()));
+//!     let autom = ws.autom(&path).unwrap();
+//!     assert_eq!(autom.grpsize(), if n > 1 { 2. } else { 1. });
+//! }
+//! ```
+use std::hash::Hash;
+use std::os::raw::c_int;
+
+use crate::autom::{dense_autom_in, Autom};
+use crate::canon::{canonical_positions, dense_graph_canon_in, relabel_cloned};
+use crate::error::NautyError;
+use crate::graph::CanonGraph;
+use crate::nauty_graph::{borrowed, DenseGraph};
+
+use nauty_Traces_sys::{graph, optionblk};
+use petgraph::{
+    graph::{Graph, IndexType},
+    EdgeType,
+};
+
+/// Buffers for dense nauty that are reused between calls
+///
+/// See the [module documentation](self) for the buffers that are
+/// reused. The results are the same as for [CanonGraph::from] and
+/// [TryIntoAutom](crate::autom::TryIntoAutom).
+#[derive(Clone, Debug, Default)]
+pub struct NautyWorkspace {
+    // adjacency matrix of the input graph
+    pub(crate) g: Vec<graph>,
+    // adjacency matrix of the canonical form
+    pub(crate) cg: Vec<graph>,
+    pub(crate) orbits: Vec<c_int>,
+}
+
+impl NautyWorkspace {
+    /// Create a new workspace with empty buffers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The canonical form of `g`, with cloned weights
+    pub fn canon<N, E, Ty, Ix>(
+        &mut self,
+        g: &Graph<N, E, Ty, Ix>,
+    ) -> Result<CanonGraph<N, E, Ty, Ix>, NautyError>
+    where
+        N: Clone + Ord,
+        E: Clone + Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let buf = std::mem::take(&mut self.g);
+        let dg = DenseGraph::from_graph_in(borrowed(g), buf);
+        let options = optionblk::default();
+        let (mut dg, _) = dense_graph_canon_in(dg, options, self)?;
+        self.g = std::mem::take(&mut dg.g);
+        let labelling = canonical_positions(&dg);
+        Ok(CanonGraph::from_canon_unchecked(relabel_cloned(
+            g, &labelling,
+        )))
+    }
+
+    /// Analyse the automorphism group of `g`
+    pub fn autom<N, E, Ty, Ix>(
+        &mut self,
+        g: &Graph<N, E, Ty, Ix>,
+    ) -> Result<Autom, NautyError>
+    where
+        N: Ord,
+        E: Hash + Ord,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let buf = std::mem::take(&mut self.g);
+        let mut dg = DenseGraph::from_graph_in(borrowed(g), buf);
+        let options = optionblk::default();
+        let stats = dense_autom_in(&mut dg, g.is_directed(), options, self);
+        self.g = std::mem::take(&mut dg.g);
+        stats.map(|s| s.autom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autom::TryIntoAutom;
+    use petgraph::{Directed, Undirected};
+    use testing::GraphIter;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn reuse() {
+        log_init();
+
+        let mut ws = NautyWorkspace::new();
+        for g in GraphIter::<Directed>::default().take(200) {
+            assert_eq!(ws.canon(&g).unwrap(), CanonGraph::from(g.clone()));
+            assert_eq!(ws.autom(&g).unwrap(), g.try_into_autom().unwrap());
+        }
+        for g in GraphIter::<Undirected>::default().take(200) {
+            assert_eq!(ws.canon(&g).unwrap(), CanonGraph::from(g.clone()));
+            assert_eq!(ws.autom(&g).unwrap(), g.try_into_autom().unwrap());
+        }
+    }
+}